@@ -1,4 +1,6 @@
 use csml_engine::{Client};
+use csml_engine::auth::{self, Principal, Scope};
+use csml_engine::data::{EngineError, RunRequest};
 
 pub fn format_response(status_code: i32, body: serde_json::Value) -> serde_json::Value {
   serde_json::json!(
@@ -27,3 +29,63 @@ pub fn format_csml_client(query_params: &serde_json::Value) -> Result<Client, se
     _ => return Err(format_response(400, serde_json::json!("Missing query params client info (user_id, bot_id, channel_id)")))
   }
 }
+
+/**
+ * API Gateway does not normalize the case of the headers
+ */
+fn get_header<'a>(headers: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+  match headers.as_object() {
+    Some(headers) => headers
+      .iter()
+      .find(|(key, _)| key.eq_ignore_ascii_case(name))
+      .and_then(|(_, val)| val.as_str()),
+    None => None,
+  }
+}
+
+/**
+ * W3C traceparent header of the caller, the spans of the request continue its trace
+ */
+pub fn get_traceparent(headers: &serde_json::Value) -> Option<String> {
+  get_header(headers, "traceparent").map(|val| val.to_owned())
+}
+
+fn auth_error_response(err: EngineError) -> serde_json::Value {
+  match err {
+    EngineError::Unauthorized(err) => {
+      eprintln!("AuthError: {}", err);
+      format_response(401, serde_json::json!("Unauthorized"))
+    }
+    EngineError::Forbidden(err) => {
+      eprintln!("AuthError: {}", err);
+      format_response(403, serde_json::json!("Forbidden"))
+    }
+    err => {
+      eprintln!("AuthError: {:?}", err);
+      format_response(500, serde_json::json!("Internal server error"))
+    }
+  }
+}
+
+fn authenticate(headers: &serde_json::Value) -> Result<Principal, serde_json::Value> {
+  auth::authenticate(
+    get_header(headers, "X-Api-Key"),
+    get_header(headers, "Authorization"),
+  ).map_err(auth_error_response)
+}
+
+/**
+ * Same checks as the routes of the server: the credentials of the request must have
+ * the scope of the route and access to `bot_id` (None if the route is not bound to a bot)
+ */
+pub fn authorize(headers: &serde_json::Value, scope: Scope, bot_id: Option<&str>) -> Result<(), serde_json::Value> {
+  authenticate(headers)?
+    .authorize(scope, bot_id)
+    .map_err(auth_error_response)
+}
+
+pub fn authorize_run(headers: &serde_json::Value, request: &RunRequest) -> Result<(), serde_json::Value> {
+  authenticate(headers)?
+    .authorize_run(request)
+    .map_err(auth_error_response)
+}
@@ -7,20 +7,31 @@ use routes::{
         get_bot_latest_versions, get_bot_version,
     },
     bots::{delete_bot_data, fold_bot},
-    clean_data::delete_expired_data,
+    clean_data::{delete_expired_data, process_due_timeouts},
     clients::delete_client_data,
-    conversations::{close_user_conversations, get_client_conversations, get_open},
+    conversations::{
+        close_user_conversations, get_client_conversations, get_open, resume_conversation,
+        send_agent_message,
+    },
     memories::{create_client_memory, delete_memories, delete_memory, get_memories, get_memory},
     messages::get_client_messages,
     migrations::make_migrations,
-    run, sns,
+    outbox::{delete_dead_letter, get_dead_letters, process_outbox, replay_dead_letters},
+    run,
+    schedules::{cancel_schedule, create_schedule, get_client_schedules, process_due_schedules},
+    sns,
     state::get_client_current_state,
-    validate, BotIdPath, BotIdVersionIdPath, GetVersionsRequest, MemoryBody, MemoryKeyPath,
+    validate, BotIdPath, BotIdVersionIdPath, DeadLetterIdPath, GetVersionsRequest, MemoryBody,
+    MemoryKeyPath, ReplayDeadLettersRequest, ScheduleIdPath,
 };
 
-use csml_engine::{data::RunRequest, Client};
+use csml_engine::{
+    auth::Scope,
+    data::{AgentMessageRequest, ResumeRequest, RunRequest, ScheduleRequest},
+    Client,
+};
 use csml_interpreter::data::csml_bot::CsmlBot;
-use helpers::{format_csml_client, format_response};
+use helpers::{authorize, authorize_run, format_csml_client, format_response, get_traceparent};
 
 use lambda_runtime::{service_fn, LambdaEvent};
 use serde::{Deserialize, Serialize};
@@ -54,6 +65,9 @@ struct LambdaRequest {
 }
 
 async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_json::Value, Error> {
+    // each route checks that the credentials of the request have its scope
+    let headers = request.payload.headers.clone();
+
     match request.payload {
         /*
          * RUN
@@ -64,14 +78,23 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
             body: Some(body),
             ..
         } if path.ends_with("/run") && http_method == "POST" => {
-            let body: RunRequest = match serde_json::from_str(&body) {
+            let mut body: RunRequest = match serde_json::from_str(&body) {
                 Ok(body) => body,
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize_run(&headers, &body) {
+                return Ok(err);
+            }
+
+            if let Some(traceparent) = get_traceparent(&headers) {
+                body.event.traceparent = Some(traceparent);
+            }
+
             run::handler(body)
         }
 
+        // public route: SNS can not send the X-Api-Key header
         LambdaRequest {
             path,
             http_method,
@@ -94,6 +117,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&body.bot_id)) {
+                return Ok(err);
+            }
+
             get_open(body)
         }
 
@@ -108,9 +135,49 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&body.bot_id)) {
+                return Ok(err);
+            }
+
             close_user_conversations(body)
         }
 
+        LambdaRequest {
+            path,
+            http_method,
+            body: Some(body),
+            ..
+        } if path.ends_with("/conversations/handoff/messages") && http_method == "POST" => {
+            let body: AgentMessageRequest = match serde_json::from_str(&body) {
+                Ok(body) => body,
+                Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&body.client.bot_id)) {
+                return Ok(err);
+            }
+
+            send_agent_message(body)
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            body: Some(body),
+            ..
+        } if path.ends_with("/conversations/handoff/resume") && http_method == "POST" => {
+            let body: ResumeRequest = match serde_json::from_str(&body) {
+                Ok(body) => body,
+                Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&body.client.bot_id)) {
+                return Ok(err);
+            }
+
+            resume_conversation(body)
+        }
+
         LambdaRequest {
             path,
             http_method,
@@ -132,6 +199,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 _ => None,
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             get_client_conversations(client, limit, pagination_key)
         }
 
@@ -155,6 +226,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             create_client_memory(client, body.key, body.value)
         }
 
@@ -169,6 +244,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(err) => return Ok(err),
             };
 
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             delete_memories(client)
         }
 
@@ -194,6 +273,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 }
             };
 
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             delete_memory(client, &memory.key)
         }
 
@@ -208,6 +291,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(err) => return Ok(err),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             get_memories(client)
         }
 
@@ -233,9 +320,169 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 }
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             get_memory(client, &memory.key)
         }
 
+        /*
+         * SCHEDULES
+         */
+        LambdaRequest {
+            path, http_method, ..
+        } if path.ends_with("/schedules/run") && http_method == "POST" => {
+            if let Err(err) = authorize(&headers, Scope::Run, None) {
+                return Ok(err);
+            }
+
+            process_due_schedules()
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            body: Some(body),
+            ..
+        } if path.ends_with("/schedules") && http_method == "POST" => {
+            let body: ScheduleRequest = match serde_json::from_str(&body) {
+                Ok(body) => body,
+                Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&body.client.bot_id)) {
+                return Ok(err);
+            }
+
+            create_schedule(body)
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            query_string_parameters: Some(query_params),
+            ..
+        } if path.ends_with("/schedules") && http_method == "GET" => {
+            let client = match format_csml_client(&query_params) {
+                Ok(client) => client,
+                Err(err) => return Ok(err),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
+            get_client_schedules(client)
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            query_string_parameters: Some(query_params),
+            path_parameters: Some(path_params),
+            ..
+        } if path.ends_with("/schedules/{id}") && http_method == "DELETE" => {
+            let client = match format_csml_client(&query_params) {
+                Ok(client) => client,
+                Err(err) => return Ok(err),
+            };
+
+            let schedule: ScheduleIdPath = match serde_json::from_value(path_params) {
+                Ok(path_params) => path_params,
+                Err(_err) => {
+                    return Ok(format_response(
+                        400,
+                        serde_json::json!("Path parameters bad format"),
+                    ))
+                }
+            };
+
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
+            cancel_schedule(client, &schedule.id)
+        }
+
+        /*
+         * OUTBOX
+         */
+        LambdaRequest {
+            path, http_method, ..
+        } if path.ends_with("/outbox/run") && http_method == "POST" => {
+            if let Err(err) = authorize(&headers, Scope::Run, None) {
+                return Ok(err);
+            }
+
+            process_outbox()
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            query_string_parameters: Some(query_params),
+            ..
+        } if path.ends_with("/outbox/dead-letters") && http_method == "GET" => {
+            let client = match format_csml_client(&query_params) {
+                Ok(client) => client,
+                Err(err) => return Ok(err),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
+            get_dead_letters(client)
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            body: Some(body),
+            ..
+        } if path.ends_with("/outbox/dead-letters/replay") && http_method == "POST" => {
+            let body: ReplayDeadLettersRequest = match serde_json::from_str(&body) {
+                Ok(body) => body,
+                Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
+            };
+
+            if let Err(err) = authorize(&headers, Scope::Run, Some(&body.client.bot_id)) {
+                return Ok(err);
+            }
+
+            replay_dead_letters(body.client, body.ids)
+        }
+
+        LambdaRequest {
+            path,
+            http_method,
+            query_string_parameters: Some(query_params),
+            path_parameters: Some(path_params),
+            ..
+        } if path.ends_with("/outbox/dead-letters/{id}") && http_method == "DELETE" => {
+            let client = match format_csml_client(&query_params) {
+                Ok(client) => client,
+                Err(err) => return Ok(err),
+            };
+
+            let dead_letter: DeadLetterIdPath = match serde_json::from_value(path_params) {
+                Ok(path_params) => path_params,
+                Err(_err) => {
+                    return Ok(format_response(
+                        400,
+                        serde_json::json!("Path parameters bad format"),
+                    ))
+                }
+            };
+
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
+            delete_dead_letter(client, &dead_letter.id)
+        }
+
         /*
          * STATE
          */
@@ -250,6 +497,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(err) => return Ok(err),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             get_client_current_state(client)
         }
 
@@ -287,6 +538,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 _ => None,
             };
 
+            if let Err(err) = authorize(&headers, Scope::ReadData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             get_client_messages(client, limit, pagination_key, from_date, to_date)
         }
 
@@ -304,6 +559,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(err) => return Ok(err),
             };
 
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&client.bot_id)) {
+                return Ok(err);
+            }
+
             delete_client_data(client)
         }
 
@@ -318,6 +577,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::DeleteData, Some(&bot.bot_id)) {
+                return Ok(err);
+            }
+
             delete_bot_data(&bot.bot_id)
         }
 
@@ -335,6 +598,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&body.id)) {
+                return Ok(err);
+            }
+
             validate::handler(body)
         }
 
@@ -352,6 +619,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&body.id)) {
+                return Ok(err);
+            }
+
             add_bot_version(body)
         }
 
@@ -366,6 +637,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&path_params.bot_id)) {
+                return Ok(err);
+            }
+
             get_bot_latest_version(path_params.bot_id)
         }
 
@@ -384,6 +659,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                     ))
                 }
             };
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&path_params.bot_id)) {
+                return Ok(err);
+            }
+
             delete_bot_versions(path_params.bot_id)
         }
 
@@ -415,6 +694,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 params.pagination_key = Some(pagination_key.to_owned());
             }
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&params.bot_id)) {
+                return Ok(err);
+            }
+
             get_bot_latest_versions(params)
         }
 
@@ -429,6 +712,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&path_parameters.bot_id)) {
+                return Ok(err);
+            }
+
             get_bot_version(path_parameters)
         }
 
@@ -448,6 +735,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 }
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&path_params.bot_id)) {
+                return Ok(err);
+            }
+
             delete_bot_version(path_params.bot_id, path_params.version_id)
         }
 
@@ -462,6 +753,10 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
                 Err(_err) => return Ok(format_response(400, serde_json::json!("Body bad format"))),
             };
 
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, Some(&bot.id)) {
+                return Ok(err);
+            }
+
             fold_bot(bot)
         }
 
@@ -470,14 +765,40 @@ async fn lambda_handler(request: LambdaEvent<LambdaRequest>) -> Result<serde_jso
          */
         LambdaRequest {
             path, http_method, ..
-        } if path.ends_with("/data/cleanup") && http_method == "POST" => delete_expired_data(),
+        } if path.ends_with("/data/cleanup") && http_method == "POST" => {
+            if let Err(err) = authorize(&headers, Scope::DeleteData, None) {
+                return Ok(err);
+            }
+
+            delete_expired_data()
+        }
+
+        /*
+         * Send the timeout events of expired holds,
+         * this route is meant to be called by a scheduled event (ex: every minute)
+         */
+        LambdaRequest {
+            path, http_method, ..
+        } if path.ends_with("/data/timeouts") && http_method == "POST" => {
+            if let Err(err) = authorize(&headers, Scope::Run, None) {
+                return Ok(err);
+            }
+
+            process_due_timeouts()
+        }
 
         /*
          * make migrations
          */
         LambdaRequest {
             path, http_method, ..
-        } if path.ends_with("/migrations") && http_method == "POST" => make_migrations(),
+        } if path.ends_with("/migrations") && http_method == "POST" => {
+            if let Err(err) = authorize(&headers, Scope::ManageVersions, None) {
+                return Ok(err);
+            }
+
+            make_migrations()
+        }
 
         /*
          * CATCHALL
@@ -7,6 +7,8 @@ pub mod bots;
 pub mod clients;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod schedules;
 pub mod state;
 pub mod migrations;
 
@@ -26,6 +28,22 @@ pub struct BotIdPath {
     pub bot_id: String,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleIdPath {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadLetterIdPath {
+    pub id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayDeadLettersRequest {
+    pub client: csml_engine::Client,
+    pub ids: Option<Vec<String>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct MemoryKeyPath {
     pub key: String,
@@ -13,3 +13,13 @@ pub fn delete_expired_data() -> Result<serde_json::Value, Error> {
         }
     }
 }
+
+pub fn process_due_timeouts() -> Result<serde_json::Value, Error> {
+    match csml_engine::process_due_timeouts() {
+        Ok(results) => Ok(format_response(200, serde_json::json!(results))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
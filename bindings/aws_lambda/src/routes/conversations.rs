@@ -1,6 +1,9 @@
-use csml_engine::{get_open_conversation, user_close_all_conversations, Client};
+use csml_engine::{
+    data::{AgentMessageRequest, EngineError, ResumeRequest},
+    get_open_conversation, user_close_all_conversations, Client,
+};
 
-use crate::Error;
+use crate::{format_response, Error};
 
 pub fn get_open(body: Client) -> Result<serde_json::Value, Error> {
     let res = get_open_conversation(&body);
@@ -73,3 +76,27 @@ pub fn get_client_conversations(
         }
     }
 }
+
+pub fn send_agent_message(body: AgentMessageRequest) -> Result<serde_json::Value, Error> {
+    match csml_engine::send_agent_message(body) {
+        Ok(data) => Ok(format_response(200, serde_json::json!(data))),
+        Err(EngineError::Format(err)) => Ok(format_response(400, serde_json::json!(err))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            eprintln!("{}", error);
+            Err(Error::from(error.as_str()))
+        }
+    }
+}
+
+pub fn resume_conversation(body: ResumeRequest) -> Result<serde_json::Value, Error> {
+    match csml_engine::resume_conversation(body) {
+        Ok(data) => Ok(format_response(200, serde_json::json!(data))),
+        Err(EngineError::Format(err)) => Ok(format_response(400, serde_json::json!(err))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            eprintln!("{}", error);
+            Err(Error::from(error.as_str()))
+        }
+    }
+}
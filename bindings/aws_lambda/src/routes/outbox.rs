@@ -0,0 +1,53 @@
+use csml_engine::Client;
+
+use crate::{format_response, Error};
+
+pub fn process_outbox() -> Result<serde_json::Value, Error> {
+    match csml_engine::process_outbox() {
+        Ok(results) => Ok(format_response(200, results)),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn get_dead_letters(client: Client) -> Result<serde_json::Value, Error> {
+    match csml_engine::get_dead_letters(&client) {
+        Ok(messages) => Ok(format_response(200, serde_json::json!(messages))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn replay_dead_letters(
+    client: Client,
+    ids: Option<Vec<String>>,
+) -> Result<serde_json::Value, Error> {
+    match csml_engine::replay_dead_letters(&client, ids) {
+        Ok(count) => Ok(format_response(
+            200,
+            serde_json::json!({ "replayed": count }),
+        )),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn delete_dead_letter(client: Client, id: &str) -> Result<serde_json::Value, Error> {
+    match csml_engine::delete_dead_letter(&client, id) {
+        Ok(_) => Ok(serde_json::json!(
+            {
+                "statusCode": 204,
+            }
+        )),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::format_response;
+use csml_engine::data::EngineError;
 use csml_engine::start_conversation;
 use serde_json::{json, Value};
 
@@ -32,6 +33,8 @@ pub fn handler(body: RunRequest) -> Result<serde_json::Value, Error> {
                 "body": serde_json::json!(data).to_string()
             }
         )),
+        // ENGINE_LOCK_MODE=reject, or the lock timed out
+        Err(EngineError::Locked(err)) => Ok(format_response(409, serde_json::json!(err))),
         Err(err) => {
             let error = format!("EngineError: {:?}", err);
             return Ok(format_response(400, serde_json::json!(error)));
@@ -0,0 +1,48 @@
+use csml_engine::{data::ScheduleRequest, Client};
+
+use crate::{format_response, Error};
+
+pub fn create_schedule(body: ScheduleRequest) -> Result<serde_json::Value, Error> {
+    match csml_engine::create_schedule(body) {
+        Ok(schedule) => Ok(format_response(201, serde_json::json!(schedule))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn get_client_schedules(client: Client) -> Result<serde_json::Value, Error> {
+    match csml_engine::get_client_schedules(&client) {
+        Ok(schedules) => Ok(format_response(200, serde_json::json!(schedules))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn cancel_schedule(client: Client, id: &str) -> Result<serde_json::Value, Error> {
+    match csml_engine::cancel_schedule(&client, id) {
+        Ok(true) => Ok(serde_json::json!(
+            {
+                "statusCode": 204,
+            }
+        )),
+        Ok(false) => Ok(format_response(404, serde_json::json!("Not found"))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
+
+pub fn process_due_schedules() -> Result<serde_json::Value, Error> {
+    match csml_engine::process_due_schedules() {
+        Ok(results) => Ok(format_response(200, serde_json::json!(results))),
+        Err(err) => {
+            let error = format!("EngineError: {:?}", err);
+            return Ok(format_response(400, serde_json::json!(error)));
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use crate::format_response;
-use csml_engine::data::RunRequest;
+use csml_engine::data::{EngineError, RunRequest};
 use csml_engine::start_conversation;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -80,6 +80,8 @@ fn handle_notification(body: &str) -> serde_json::Value {
 
     match res {
         Ok(data) => format_response(200, serde_json::json!(data)),
+        // SNS retries the delivery of the message
+        Err(EngineError::Locked(err)) => format_response(409, serde_json::json!(err)),
         Err(err) => {
             eprintln!("EngineError: {:?}", err);
             return format_response(400, serde_json::json!(format!("EngineError: {:?}", err)));
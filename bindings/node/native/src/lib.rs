@@ -641,6 +641,26 @@ fn delete_client_memories(mut cx: FunctionContext) -> JsResult<JsValue> {
     }
 }
 
+/*
+* Export all data associated with a given Client: conversations, messages, memories and state
+*/
+fn export_client_data(mut cx: FunctionContext) -> JsResult<JsValue> {
+    let jsclient = cx.argument::<JsObject>(0)?;
+    let client: Client = get_client(&mut cx, jsclient)?;
+
+    let obj = cx.empty_object();
+
+    match csml_engine::export_client_data(&client) {
+        Ok(value) => Ok(to_js_value(&mut cx, value)?),
+        Err(err) => {
+            let error = cx.string(format!("{:?}", err));
+            obj.set(&mut cx, "error", error)?;
+
+            Ok(obj.upcast())
+        }
+    }
+}
+
 /*
 * Remove all data associated with a given Client
 */
@@ -883,6 +903,7 @@ register_module!(mut cx, {
     cx.export_function("getClientConversations", get_client_conversations)?;
     cx.export_function("deleteMemory", delete_client_memory)?;
     cx.export_function("deleteMemories", delete_client_memories)?;
+    cx.export_function("exportClientData", export_client_data)?;
     cx.export_function("deleteClientData", delete_client_data)?;
     cx.export_function("deleteBotData", delete_bot_data)?;
 
@@ -31,6 +31,8 @@ pub fn init_request(string: &str, metadata: Option<serde_json::Value>) -> CsmlRe
         ttl_duration: None,
         step_limit: None,
         low_data_mode: None,
+        renderer: None,
+        traceparent: None,
     }
 }
 
@@ -54,6 +56,8 @@ pub fn init_request_flow_trigger(flow_id: &str, step_id: Option<&str>) -> CsmlRe
         ttl_duration: None,
         step_limit: None,
         low_data_mode: None,
+        renderer: None,
+        traceparent: None,
     }
 }
 
@@ -22,7 +22,7 @@ fn get_flow(name: &str) -> Result<String, ::std::io::Error> {
 
 fn init_request(string: &str) -> CsmlRequest {
     CsmlRequest {
-        request_id: "tmp".to_owned(),
+        request_id: uuid::Uuid::new_v4().to_string(),
         client: Client {
             user_id: "alexis".to_owned(),
             bot_id: "botid".to_owned(),
@@ -37,6 +37,8 @@ fn init_request(string: &str) -> CsmlRequest {
         ttl_duration: None,
         step_limit: None,
         low_data_mode: None,
+        renderer: None,
+        traceparent: None,
     }
 }
 
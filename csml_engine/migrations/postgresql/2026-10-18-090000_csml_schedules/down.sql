@@ -0,0 +1,4 @@
+DROP INDEX schedule_next_run_at;
+DROP INDEX schedule_client;
+
+DROP TABLE csml_schedules;
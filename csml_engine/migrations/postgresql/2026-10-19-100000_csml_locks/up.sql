@@ -0,0 +1,13 @@
+CREATE TABLE csml_locks (
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  owner VARCHAR NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (bot_id, channel_id, user_id)
+);
@@ -0,0 +1,8 @@
+CREATE TABLE csml_api_keys (
+  key_hash VARCHAR PRIMARY KEY,
+
+  bot_ids TEXT,
+  scopes TEXT NOT NULL,
+
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
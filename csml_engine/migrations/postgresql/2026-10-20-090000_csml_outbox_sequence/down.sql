@@ -0,0 +1,3 @@
+DROP INDEX outbox_conversation_position;
+
+CREATE INDEX outbox_status_position ON csml_outbox (status, position);
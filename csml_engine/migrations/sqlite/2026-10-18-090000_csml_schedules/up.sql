@@ -0,0 +1,16 @@
+CREATE TABLE csml_schedules (
+  id BINARY(128) PRIMARY KEY NOT NULL,
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  cron VARCHAR DEFAULT NULL,
+  job VARCHAR NOT NULL,
+  next_run_at TIMESTAMP NOT NULL,
+
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX schedule_client ON csml_schedules (bot_id, channel_id, user_id);
+CREATE INDEX schedule_next_run_at ON csml_schedules (next_run_at);
@@ -0,0 +1,4 @@
+DROP INDEX outbox_status_position;
+DROP INDEX outbox_client;
+
+DROP TABLE csml_outbox;
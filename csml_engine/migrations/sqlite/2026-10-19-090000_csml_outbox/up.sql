@@ -0,0 +1,21 @@
+CREATE TABLE csml_outbox (
+  id BINARY(128) PRIMARY KEY NOT NULL,
+  bot_id VARCHAR NOT NULL,
+  channel_id VARCHAR NOT NULL,
+  user_id VARCHAR NOT NULL,
+
+  conversation_id VARCHAR NOT NULL,
+  callback_url VARCHAR NOT NULL,
+  payload VARCHAR NOT NULL,
+  status VARCHAR NOT NULL,
+  attempts INTEGER NOT NULL DEFAULT 0,
+  error VARCHAR DEFAULT NULL,
+  position BIGINT NOT NULL,
+  next_attempt_at TIMESTAMP NOT NULL,
+
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX outbox_client ON csml_outbox (bot_id, channel_id, user_id);
+CREATE INDEX outbox_status_position ON csml_outbox (status, position);
@@ -0,0 +1 @@
+DROP TABLE csml_rate_limits;
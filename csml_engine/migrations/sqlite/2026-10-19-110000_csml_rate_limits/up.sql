@@ -0,0 +1,12 @@
+CREATE TABLE csml_rate_limits (
+  scope VARCHAR NOT NULL,
+  name VARCHAR NOT NULL,
+
+  value BIGINT NOT NULL,
+  expires_at TIMESTAMP NOT NULL,
+
+  updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+  created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+  PRIMARY KEY (scope, name)
+);
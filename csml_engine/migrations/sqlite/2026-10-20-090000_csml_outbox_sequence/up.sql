@@ -0,0 +1,3 @@
+DROP INDEX outbox_status_position;
+
+CREATE INDEX outbox_conversation_position ON csml_outbox (conversation_id, position);
@@ -0,0 +1,475 @@
+/**
+ * Step and funnel analytics of a bot, computed from the messages stored in db.
+ *
+ * The messages of each conversation are ordered, and consecutive messages of the same
+ * flow and step are a visit of this step. The time spent in a step is the time between
+ * the start of a visit and the start of the next one, so the last step of a
+ * conversation has no time spent. Steps that do not send or receive any message are
+ * not visible in the analytics.
+ *
+ * At most ENGINE_ANALYTICS_MAX_MESSAGES messages (100000 by default) are loaded,
+ * beyond that the request fails and the date range must be narrowed.
+ */
+use crate::data::{Database, EngineError};
+use crate::db_connectors::{analytics, DbMessageStep};
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+const DEFAULT_MAX_MESSAGES: i64 = 100_000;
+// 9999-12-31T23:59:59Z, the dates are compared as strings in some databases
+const MAX_TIMESTAMP: i64 = 253_402_300_799;
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StepStats {
+    pub flow_id: String,
+    pub step_id: String,
+    // number of conversations that visited the step
+    pub conversations: u64,
+    pub visits: u64,
+    // number of conversations whose last step is this one
+    pub ended: u64,
+    pub drop_off_rate: f64,
+    // in seconds
+    pub median_time_spent: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StepTransition {
+    pub from_flow_id: String,
+    pub from_step_id: String,
+    pub to_flow_id: String,
+    pub to_step_id: String,
+    pub conversations: u64,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct StepAnalytics {
+    pub conversations: u64,
+    pub steps: Vec<StepStats>,
+    pub transitions: Vec<StepTransition>,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct FunnelStep {
+    pub flow_id: String,
+    pub step_id: String,
+    // number of conversations that visited all the previous steps of the funnel, then this one
+    pub conversations: u64,
+    // share of the conversations of the previous step (or of all the conversations
+    // for the first step) that did not reach this step
+    pub drop_off_rate: f64,
+}
+
+type StepKey = (String, String);
+
+struct Visit {
+    step: StepKey,
+    // unix timestamp in milliseconds
+    start: i64,
+}
+
+/**
+ * Visits of the steps of each conversation, in order
+ */
+fn get_visits(mut messages: Vec<DbMessageStep>) -> HashMap<String, Vec<Visit>> {
+    messages.sort_by(|a, b| {
+        (a.created_at, a.interaction_order, a.message_order).cmp(&(
+            b.created_at,
+            b.interaction_order,
+            b.message_order,
+        ))
+    });
+
+    let mut conversations: HashMap<String, Vec<Visit>> = HashMap::new();
+    for message in messages {
+        let visits = conversations.entry(message.conversation_id).or_default();
+
+        match visits.last() {
+            Some(visit) if visit.step.0 == message.flow_id && visit.step.1 == message.step_id => {}
+            _ => visits.push(Visit {
+                step: (message.flow_id, message.step_id),
+                start: message.created_at,
+            }),
+        }
+    }
+
+    conversations
+}
+
+fn rate(count: u64, total: u64) -> f64 {
+    match total {
+        0 => 0.0,
+        total => count as f64 / total as f64,
+    }
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+    let middle = values.len() / 2;
+    match values.len() % 2 {
+        0 => Some((values[middle - 1] + values[middle]) / 2.0),
+        _ => Some(values[middle]),
+    }
+}
+
+#[derive(Default)]
+struct StepAccumulator {
+    conversations: HashSet<String>,
+    visits: u64,
+    ended: u64,
+    durations: Vec<f64>,
+}
+
+fn compute_step_analytics(messages: Vec<DbMessageStep>) -> StepAnalytics {
+    let conversations = get_visits(messages);
+
+    let mut steps: HashMap<StepKey, StepAccumulator> = HashMap::new();
+    let mut transitions: HashMap<(StepKey, StepKey), HashSet<String>> = HashMap::new();
+
+    for (conversation_id, visits) in conversations.iter() {
+        for (index, visit) in visits.iter().enumerate() {
+            let step = steps.entry(visit.step.clone()).or_default();
+            step.conversations.insert(conversation_id.to_owned());
+            step.visits += 1;
+
+            match visits.get(index + 1) {
+                Some(next) => {
+                    step.durations
+                        .push((next.start - visit.start) as f64 / 1000.0);
+
+                    transitions
+                        .entry((visit.step.clone(), next.step.clone()))
+                        .or_default()
+                        .insert(conversation_id.to_owned());
+                }
+                None => step.ended += 1,
+            }
+        }
+    }
+
+    let mut steps: Vec<StepStats> = steps
+        .into_iter()
+        .map(|((flow_id, step_id), step)| {
+            let conversations = step.conversations.len() as u64;
+
+            StepStats {
+                flow_id,
+                step_id,
+                conversations,
+                visits: step.visits,
+                ended: step.ended,
+                drop_off_rate: rate(step.ended, conversations),
+                median_time_spent: median(step.durations),
+            }
+        })
+        .collect();
+    steps.sort_by(|a, b| {
+        b.conversations
+            .cmp(&a.conversations)
+            .then_with(|| (&a.flow_id, &a.step_id).cmp(&(&b.flow_id, &b.step_id)))
+    });
+
+    let mut transitions: Vec<StepTransition> = transitions
+        .into_iter()
+        .map(|((from, to), conversations)| StepTransition {
+            from_flow_id: from.0,
+            from_step_id: from.1,
+            to_flow_id: to.0,
+            to_step_id: to.1,
+            conversations: conversations.len() as u64,
+        })
+        .collect();
+    transitions.sort_by(|a, b| {
+        b.conversations.cmp(&a.conversations).then_with(|| {
+            (
+                &a.from_flow_id,
+                &a.from_step_id,
+                &a.to_flow_id,
+                &a.to_step_id,
+            )
+                .cmp(&(
+                    &b.from_flow_id,
+                    &b.from_step_id,
+                    &b.to_flow_id,
+                    &b.to_step_id,
+                ))
+        })
+    });
+
+    StepAnalytics {
+        conversations: conversations.len() as u64,
+        steps,
+        transitions,
+    }
+}
+
+/**
+ * Number of steps of the funnel visited in order by a conversation
+ */
+fn get_funnel_depth(visits: &[Visit], funnel: &[StepKey]) -> usize {
+    let mut depth = 0;
+
+    for visit in visits {
+        match funnel.get(depth) {
+            Some(step) if *step == visit.step => depth += 1,
+            Some(_) => {}
+            None => break,
+        }
+    }
+
+    depth
+}
+
+fn compute_funnel(messages: Vec<DbMessageStep>, funnel: &[StepKey]) -> Vec<FunnelStep> {
+    let conversations = get_visits(messages);
+
+    let mut counts = vec![0u64; funnel.len()];
+    for visits in conversations.values() {
+        for count in counts.iter_mut().take(get_funnel_depth(visits, funnel)) {
+            *count += 1;
+        }
+    }
+
+    let mut previous = conversations.len() as u64;
+    funnel
+        .iter()
+        .zip(counts)
+        .map(|((flow_id, step_id), conversations)| {
+            let step = FunnelStep {
+                flow_id: flow_id.to_owned(),
+                step_id: step_id.to_owned(),
+                conversations,
+                drop_off_rate: rate(previous - conversations, previous),
+            };
+            previous = conversations;
+
+            step
+        })
+        .collect()
+}
+
+/**
+ * Parse the steps of a funnel written `flow:step,flow:step`
+ */
+pub fn parse_funnel(value: &str) -> Option<Vec<StepKey>> {
+    let steps = value
+        .split(',')
+        .map(|step| {
+            let (flow_id, step_id) = step.trim().split_once(':')?;
+
+            match (flow_id.trim(), step_id.trim()) {
+                ("", _) | (_, "") => None,
+                (flow_id, step_id) => Some((flow_id.to_owned(), step_id.to_owned())),
+            }
+        })
+        .collect::<Option<Vec<StepKey>>>()?;
+
+    match steps.is_empty() {
+        true => None,
+        false => Some(steps),
+    }
+}
+
+/**
+ * Parse the dates of the analytics, unix timestamps in seconds. from_date defaults to
+ * the epoch and to_date to now. Returns None if a date is out of range or from_date
+ * is after to_date.
+ */
+pub fn parse_date_range(
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let parse = |timestamp: i64| match timestamp {
+        0..=MAX_TIMESTAMP => Utc.timestamp_opt(timestamp, 0).single(),
+        _ => None,
+    };
+
+    let from_date = parse(from_date.unwrap_or(0))?;
+    let to_date = match to_date {
+        Some(to_date) => parse(to_date)?,
+        None => Utc::now(),
+    };
+
+    match from_date <= to_date {
+        true => Some((from_date, to_date)),
+        false => None,
+    }
+}
+
+fn get_max_messages() -> i64 {
+    match std::env::var("ENGINE_ANALYTICS_MAX_MESSAGES") {
+        Ok(value) => value.parse::<i64>().unwrap_or(DEFAULT_MAX_MESSAGES),
+        Err(_) => DEFAULT_MAX_MESSAGES,
+    }
+}
+
+fn get_message_steps(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+    db: &mut Database,
+) -> Result<Vec<DbMessageStep>, EngineError> {
+    let (from_date, to_date) = parse_date_range(from_date, to_date).ok_or_else(|| {
+        EngineError::Format("from_date and to_date must be an ordered range of dates".to_owned())
+    })?;
+    let limit = get_max_messages();
+
+    // one more message than the limit is loaded to know whether it is exceeded
+    let messages =
+        analytics::get_bot_message_steps(bot_id, channel_id, from_date, to_date, limit + 1, db)?;
+
+    match messages.len() as i64 > limit {
+        true => Err(EngineError::Format(format!(
+            "more than {} messages between from_date and to_date, narrow the date range",
+            limit
+        ))),
+        false => Ok(messages),
+    }
+}
+
+pub fn get_step_analytics(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+    db: &mut Database,
+) -> Result<StepAnalytics, EngineError> {
+    let messages = get_message_steps(bot_id, channel_id, from_date, to_date, db)?;
+
+    Ok(compute_step_analytics(messages))
+}
+
+pub fn get_funnel(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+    funnel: &[StepKey],
+    db: &mut Database,
+) -> Result<Vec<FunnelStep>, EngineError> {
+    let messages = get_message_steps(bot_id, channel_id, from_date, to_date, db)?;
+
+    Ok(compute_funnel(messages, funnel))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(conversation_id: &str, flow_id: &str, step_id: &str, seconds: i64) -> DbMessageStep {
+        DbMessageStep {
+            conversation_id: conversation_id.to_owned(),
+            flow_id: flow_id.to_owned(),
+            step_id: step_id.to_owned(),
+            interaction_order: 0,
+            message_order: 0,
+            created_at: seconds * 1000,
+        }
+    }
+
+    fn messages() -> Vec<DbMessageStep> {
+        vec![
+            // conversation a: start -> name -> end
+            message("a", "Default", "start", 0),
+            message("a", "Default", "start", 1),
+            message("a", "Default", "name", 10),
+            message("a", "Default", "end", 30),
+            // conversation b: start -> name, abandoned
+            message("b", "Default", "name", 20),
+            message("b", "Default", "start", 0),
+            // conversation c: start, abandoned
+            message("c", "Default", "start", 5),
+        ]
+    }
+
+    fn get_step<'a>(analytics: &'a StepAnalytics, step_id: &str) -> &'a StepStats {
+        analytics
+            .steps
+            .iter()
+            .find(|step| step.step_id == step_id)
+            .unwrap()
+    }
+
+    #[test]
+    fn ok_step_analytics() {
+        let analytics = compute_step_analytics(messages());
+
+        assert_eq!(analytics.conversations, 3);
+
+        let start = get_step(&analytics, "start");
+        assert_eq!(start.conversations, 3);
+        assert_eq!(start.visits, 3);
+        assert_eq!(start.ended, 1);
+        assert_eq!(start.median_time_spent, Some(15.0));
+
+        let name = get_step(&analytics, "name");
+        assert_eq!(name.conversations, 2);
+        assert_eq!(name.ended, 1);
+        assert_eq!(name.drop_off_rate, 0.5);
+        assert_eq!(name.median_time_spent, Some(20.0));
+
+        let end = get_step(&analytics, "end");
+        assert_eq!(end.drop_off_rate, 1.0);
+        assert_eq!(end.median_time_spent, None);
+
+        assert_eq!(analytics.transitions[0].from_step_id, "start");
+        assert_eq!(analytics.transitions[0].to_step_id, "name");
+        assert_eq!(analytics.transitions[0].conversations, 2);
+        assert_eq!(analytics.transitions.len(), 2);
+    }
+
+    #[test]
+    fn ok_funnel() {
+        let funnel = parse_funnel("Default:start, Default:name,Default:end").unwrap();
+        let steps = compute_funnel(messages(), &funnel);
+
+        let counts: Vec<u64> = steps.iter().map(|step| step.conversations).collect();
+        assert_eq!(counts, vec![3, 2, 1]);
+        assert_eq!(steps[0].drop_off_rate, 0.0);
+        assert_eq!(steps[1].drop_off_rate, 1.0 / 3.0);
+        assert_eq!(steps[2].drop_off_rate, 0.5);
+    }
+
+    #[test]
+    fn ok_funnel_out_of_order() {
+        let funnel = parse_funnel("Default:name,Default:start").unwrap();
+        let steps = compute_funnel(messages(), &funnel);
+
+        let counts: Vec<u64> = steps.iter().map(|step| step.conversations).collect();
+        assert_eq!(counts, vec![2, 0]);
+    }
+
+    #[test]
+    fn ok_parse_date_range() {
+        let (from_date, to_date) = parse_date_range(Some(60), Some(120)).unwrap();
+        assert_eq!(from_date.timestamp(), 60);
+        assert_eq!(to_date.timestamp(), 120);
+
+        let (from_date, to_date) = parse_date_range(None, None).unwrap();
+        assert_eq!(from_date.timestamp(), 0);
+        assert!(to_date <= Utc::now());
+    }
+
+    #[test]
+    fn ko_parse_date_range() {
+        assert!(parse_date_range(Some(120), Some(60)).is_none());
+        assert!(parse_date_range(Some(-1), None).is_none());
+        assert!(parse_date_range(None, Some(i64::MAX)).is_none());
+        assert!(parse_date_range(Some(i64::MIN), Some(i64::MAX)).is_none());
+    }
+
+    #[test]
+    fn ko_parse_funnel() {
+        assert!(parse_funnel("").is_none());
+        assert!(parse_funnel("Default").is_none());
+        assert!(parse_funnel("Default:start,:name").is_none());
+    }
+}
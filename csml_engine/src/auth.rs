@@ -0,0 +1,477 @@
+/**
+ * Authentication and authorization of the requests sent to the engine.
+ *
+ * A request is authenticated either by an api key (X-Api-Key header) or by a JWT
+ * bearer token (Authorization header). Api keys are read, in this order, from:
+ * - ENGINE_SERVER_API_KEYS: comma-separated keys with access to every bot and scope
+ * - ENGINE_SERVER_API_KEYS_FILE: a json file `[{"key", "bot_ids"?, "scopes"}]`
+ * - the database, if ENGINE_SERVER_API_KEYS_DB=true (see create_api_key)
+ *
+ * Bearer tokens are verified with the keys of the JWKS file ENGINE_SERVER_JWKS_FILE,
+ * and optionally their issuer (ENGINE_SERVER_JWT_ISSUER) and audience
+ * (ENGINE_SERVER_JWT_AUDIENCE). Their scopes are read from the `scope` claim
+ * (space-separated) and their bots from the `bot_ids` claim.
+ *
+ * When none of these is configured, auth is disabled and every request is allowed.
+ */
+use crate::data::{EngineError, RunRequest};
+use crate::db_connectors::{api_keys, init_db};
+
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{AlgorithmParameters, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Scope {
+    // send requests to bots
+    Run,
+    // read conversations, messages, memories and state
+    ReadData,
+    // delete the data of clients and bots
+    DeleteData,
+    // create, fold, validate and delete bot versions
+    ManageVersions,
+}
+
+impl Scope {
+    pub fn all() -> Vec<Scope> {
+        vec![
+            Scope::Run,
+            Scope::ReadData,
+            Scope::DeleteData,
+            Scope::ManageVersions,
+        ]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::Run => "run",
+            Scope::ReadData => "read-data",
+            Scope::DeleteData => "delete-data",
+            Scope::ManageVersions => "manage-versions",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Scope::all()
+            .into_iter()
+            .find(|scope| scope.as_str() == name.trim())
+    }
+}
+
+/**
+ * Owner of the credentials of a request, with the bots and scopes it has access to.
+ * bot_ids is None when every bot can be accessed.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct Principal {
+    pub subject: String,
+    pub bot_ids: Option<Vec<String>>,
+    pub scopes: Vec<Scope>,
+}
+
+impl Principal {
+    fn unrestricted(subject: &str) -> Self {
+        Self {
+            subject: subject.to_owned(),
+            bot_ids: None,
+            scopes: Scope::all(),
+        }
+    }
+
+    pub fn has_scope(&self, scope: Scope) -> bool {
+        self.scopes.contains(&scope)
+    }
+
+    /**
+     * None is given for the requests that are not bound to a bot
+     * (e.g. the cleanup of expired data), which require access to every bot.
+     */
+    pub fn can_access_bot(&self, bot_id: Option<&str>) -> bool {
+        match (&self.bot_ids, bot_id) {
+            (None, _) => true,
+            (Some(bot_ids), Some(bot_id)) => bot_ids.iter().any(|id| id == bot_id),
+            (Some(_), None) => false,
+        }
+    }
+
+    pub fn authorize(&self, scope: Scope, bot_id: Option<&str>) -> Result<(), EngineError> {
+        if !self.has_scope(scope) {
+            return Err(EngineError::Forbidden(format!(
+                "{} is missing the scope [{}]",
+                self.subject,
+                scope.as_str()
+            )));
+        }
+
+        if !self.can_access_bot(bot_id) {
+            return Err(EngineError::Forbidden(match bot_id {
+                Some(bot_id) => format!("{} has no access to the bot [{}]", self.subject, bot_id),
+                None => format!("{} has no access to every bot", self.subject),
+            }));
+        }
+
+        Ok(())
+    }
+
+    /**
+     * A run request accesses the bot of its client, the bot that runs and the bots
+     * it can switch to
+     */
+    pub fn authorize_run(&self, request: &RunRequest) -> Result<(), EngineError> {
+        let mut bot_ids = vec![request.event.client.bot_id.as_str()];
+
+        if let Some(bot) = &request.bot {
+            bot_ids.push(&bot.id);
+        }
+        if let Some(bot_id) = &request.bot_id {
+            bot_ids.push(bot_id);
+        }
+        for bot in request.multibot.iter().flatten() {
+            bot_ids.push(&bot.id);
+        }
+
+        for bot_id in bot_ids {
+            self.authorize(Scope::Run, Some(bot_id))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiKeyConfig {
+    key: String,
+    bot_ids: Option<Vec<String>>,
+    scopes: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Claims {
+    sub: Option<String>,
+    scope: Option<String>,
+    bot_ids: Option<Vec<String>>,
+}
+
+static API_KEYS_FILE: OnceLock<Result<Vec<ApiKeyConfig>, String>> = OnceLock::new();
+static JWKS_FILE: OnceLock<Result<JwkSet, String>> = OnceLock::new();
+
+fn get_env(name: &str) -> Option<String> {
+    match std::env::var(name) {
+        Ok(val) if !val.is_empty() => Some(val),
+        _ => None,
+    }
+}
+
+/**
+ * Api keys are looked up in db when ENGINE_SERVER_API_KEYS_DB=true
+ */
+pub fn is_db_enabled() -> bool {
+    matches!(get_env("ENGINE_SERVER_API_KEYS_DB"), Some(val) if val == "true")
+}
+
+/**
+ * Whether the requests must be authenticated
+ */
+pub fn is_enabled() -> bool {
+    get_env("ENGINE_SERVER_API_KEYS").is_some()
+        || get_env("ENGINE_SERVER_API_KEYS_FILE").is_some()
+        || get_env("ENGINE_SERVER_JWKS_FILE").is_some()
+        || is_db_enabled()
+}
+
+fn read_json_file<T: serde::de::DeserializeOwned>(path: &str) -> Result<T, String> {
+    let content = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+
+    serde_json::from_str(&content).map_err(|err| format!("{}: {}", path, err))
+}
+
+fn parse_scopes<'a, I: Iterator<Item = &'a str>>(names: I) -> Vec<Scope> {
+    // unknown scopes are ignored, so that they can be shared with other services
+    names.filter_map(Scope::from_name).collect()
+}
+
+fn hash_api_key(api_key: &str) -> String {
+    hex::encode(openssl::sha::sha256(api_key.as_bytes()))
+}
+
+/**
+ * Constant time comparison of the hashes, so that the time taken does not reveal
+ * how much of a key was guessed, nor its length
+ */
+fn is_same_api_key(api_key: &str, other: &str) -> bool {
+    openssl::memcmp::eq(
+        &openssl::sha::sha256(api_key.as_bytes()),
+        &openssl::sha::sha256(other.as_bytes()),
+    )
+}
+
+fn short_api_key(api_key: &str) -> String {
+    format!(
+        "api key [{}...]",
+        api_key.chars().take(4).collect::<String>()
+    )
+}
+
+fn authenticate_api_key(api_key: &str) -> Result<Principal, EngineError> {
+    if let Some(api_keys) = get_env("ENGINE_SERVER_API_KEYS") {
+        if api_keys.split(',').any(|key| is_same_api_key(key, api_key)) {
+            return Ok(Principal::unrestricted(&short_api_key(api_key)));
+        }
+    }
+
+    if let Some(path) = get_env("ENGINE_SERVER_API_KEYS_FILE") {
+        let api_keys = API_KEYS_FILE
+            .get_or_init(|| read_json_file(&path))
+            .as_ref()
+            .map_err(|err| EngineError::Manager(format!("invalid api keys file {}", err)))?;
+
+        if let Some(config) = api_keys
+            .iter()
+            .find(|config| is_same_api_key(&config.key, api_key))
+        {
+            return Ok(Principal {
+                subject: short_api_key(api_key),
+                bot_ids: config.bot_ids.to_owned(),
+                scopes: parse_scopes(config.scopes.iter().map(|scope| scope.as_str())),
+            });
+        }
+    }
+
+    if is_db_enabled() {
+        let mut db = init_db()?;
+
+        if let Some(db_api_key) = api_keys::get_api_key(&hash_api_key(api_key), &mut db)? {
+            return Ok(Principal {
+                subject: short_api_key(api_key),
+                bot_ids: db_api_key.bot_ids,
+                scopes: parse_scopes(db_api_key.scopes.iter().map(|scope| scope.as_str())),
+            });
+        }
+    }
+
+    Err(EngineError::Unauthorized(format!(
+        "Invalid X-Api-Key, unknown {}",
+        short_api_key(api_key)
+    )))
+}
+
+fn authenticate_bearer(token: &str) -> Result<Principal, EngineError> {
+    let path = match get_env("ENGINE_SERVER_JWKS_FILE") {
+        Some(path) => path,
+        None => {
+            return Err(EngineError::Unauthorized(
+                "Bearer tokens are not accepted".to_owned(),
+            ))
+        }
+    };
+
+    let jwks = JWKS_FILE
+        .get_or_init(|| read_json_file(&path))
+        .as_ref()
+        .map_err(|err| EngineError::Manager(format!("invalid jwks file {}", err)))?;
+
+    let invalid_token = |err: jsonwebtoken::errors::Error| {
+        EngineError::Unauthorized(format!("Invalid bearer token: {}", err))
+    };
+
+    let header = decode_header(token).map_err(invalid_token)?;
+
+    // only asymmetric keys are published in a JWKS, other algorithms are refused
+    // to prevent a token signed with the public key as an HMAC secret
+    match header.alg {
+        Algorithm::RS256
+        | Algorithm::RS384
+        | Algorithm::RS512
+        | Algorithm::PS256
+        | Algorithm::PS384
+        | Algorithm::PS512 => {}
+        _ => {
+            return Err(EngineError::Unauthorized(format!(
+                "Unsupported bearer token algorithm {:?}",
+                header.alg
+            )))
+        }
+    }
+
+    let jwk = match &header.kid {
+        Some(kid) => jwks.find(kid),
+        None => jwks.keys.first(),
+    };
+
+    let key = match jwk.map(|jwk| &jwk.algorithm) {
+        Some(AlgorithmParameters::RSA(rsa)) => {
+            DecodingKey::from_rsa_components(&rsa.n, &rsa.e).map_err(invalid_token)?
+        }
+        _ => {
+            return Err(EngineError::Unauthorized(
+                "No RSA key of the JWKS matches the bearer token".to_owned(),
+            ))
+        }
+    };
+
+    let mut validation = Validation::new(header.alg);
+    if let Some(issuer) = get_env("ENGINE_SERVER_JWT_ISSUER") {
+        validation.set_issuer(&[issuer]);
+    }
+    if let Some(audience) = get_env("ENGINE_SERVER_JWT_AUDIENCE") {
+        validation.set_audience(&[audience]);
+    }
+
+    let claims = decode::<Claims>(token, &key, &validation)
+        .map_err(invalid_token)?
+        .claims;
+
+    Ok(Principal {
+        subject: format!("token of [{}]", claims.sub.unwrap_or_default()),
+        bot_ids: claims.bot_ids,
+        scopes: parse_scopes(claims.scope.unwrap_or_default().split_whitespace()),
+    })
+}
+
+/**
+ * Authenticate a request from its X-Api-Key and Authorization headers
+ */
+pub fn authenticate(
+    api_key: Option<&str>,
+    authorization: Option<&str>,
+) -> Result<Principal, EngineError> {
+    if !is_enabled() {
+        return Ok(Principal::unrestricted("anonymous"));
+    }
+
+    if let Some(authorization) = authorization {
+        if let Some(token) = authorization.strip_prefix("Bearer ") {
+            return authenticate_bearer(token.trim());
+        }
+    }
+
+    match api_key {
+        Some(api_key) if !api_key.is_empty() => authenticate_api_key(api_key),
+        _ => Err(EngineError::Unauthorized(
+            "Missing X-Api-Key or Authorization in header".to_owned(),
+        )),
+    }
+}
+
+/**
+ * Create an api key stored in db, restricted to `bot_ids` (every bot if None) and `scopes`.
+ * The key is returned once: only its hash is saved.
+ */
+pub fn create_api_key(
+    bot_ids: Option<Vec<String>>,
+    scopes: Vec<Scope>,
+) -> Result<String, EngineError> {
+    let mut db = init_db()?;
+
+    let api_key = hex::encode(rand::thread_rng().gen::<[u8; 32]>());
+    let scopes = scopes
+        .iter()
+        .map(|scope| scope.as_str().to_owned())
+        .collect::<Vec<String>>();
+
+    api_keys::create_api_key(&hash_api_key(&api_key), &bot_ids, &scopes, &mut db)?;
+
+    Ok(api_key)
+}
+
+/**
+ * Revoke an api key stored in db
+ */
+pub fn delete_api_key(api_key: &str) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+
+    api_keys::delete_api_key(&hash_api_key(api_key), &mut db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_scope_names() {
+        for scope in Scope::all() {
+            assert_eq!(Scope::from_name(scope.as_str()), Some(scope));
+            assert_eq!(
+                serde_json::to_value(scope).unwrap(),
+                serde_json::json!(scope.as_str())
+            );
+        }
+        assert_eq!(Scope::from_name("admin"), None);
+    }
+
+    #[test]
+    fn ok_same_api_key() {
+        assert!(is_same_api_key("secret", "secret"));
+        assert!(!is_same_api_key("secret", "secreT"));
+        assert!(!is_same_api_key("secret", "secret2"));
+        assert!(!is_same_api_key("secret", ""));
+    }
+
+    #[test]
+    fn ok_authorize() {
+        let principal = Principal {
+            subject: "test".to_owned(),
+            bot_ids: Some(vec!["bot_a".to_owned()]),
+            scopes: vec![Scope::Run, Scope::ReadData],
+        };
+
+        assert!(principal.authorize(Scope::Run, Some("bot_a")).is_ok());
+        assert!(principal.authorize(Scope::ReadData, Some("bot_a")).is_ok());
+        assert!(principal
+            .authorize(Scope::DeleteData, Some("bot_a"))
+            .is_err());
+        assert!(principal.authorize(Scope::Run, Some("bot_b")).is_err());
+        // restricted keys can't be used on routes shared by every bot
+        assert!(principal.authorize(Scope::Run, None).is_err());
+
+        let principal = Principal::unrestricted("test");
+        assert!(principal.authorize(Scope::ManageVersions, None).is_ok());
+        assert!(principal
+            .authorize(Scope::DeleteData, Some("bot_b"))
+            .is_ok());
+    }
+
+    #[test]
+    fn ok_authorize_run() {
+        let request: RunRequest = serde_json::from_value(serde_json::json!({
+            "bot_id": "bot_a",
+            "multibot": [{"id": "bot_b"}],
+            "event": {
+                "request_id": "request_id",
+                "client": {"bot_id": "bot_a", "channel_id": "channel", "user_id": "user"},
+                "payload": {"content_type": "text", "content": {"text": "hello"}},
+                "metadata": {}
+            }
+        }))
+        .unwrap();
+
+        let mut principal = Principal {
+            subject: "test".to_owned(),
+            bot_ids: Some(vec!["bot_a".to_owned()]),
+            scopes: vec![Scope::Run],
+        };
+        // the request can switch to bot_b
+        assert!(principal.authorize_run(&request).is_err());
+
+        principal.bot_ids = Some(vec!["bot_a".to_owned(), "bot_b".to_owned()]);
+        assert!(principal.authorize_run(&request).is_ok());
+
+        principal.scopes = vec![Scope::ReadData];
+        assert!(principal.authorize_run(&request).is_err());
+    }
+
+    #[test]
+    fn ok_parse_scopes() {
+        assert_eq!(
+            parse_scopes("run read-data openid".split_whitespace()),
+            vec![Scope::Run, Scope::ReadData]
+        );
+    }
+}
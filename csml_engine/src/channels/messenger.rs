@@ -0,0 +1,128 @@
+use super::{get_buttons, get_cards, get_str, ButtonInfo, Renderer};
+
+use csml_interpreter::data::Message;
+use serde_json::{json, Value};
+
+/**
+ * Messenger Send API: messages are rendered as the `message` object of the request.
+ */
+pub struct MessengerRenderer;
+
+// limits of the Messenger platform
+const MAX_QUICK_REPLIES: usize = 13;
+const MAX_BUTTONS: usize = 3;
+const MAX_ELEMENTS: usize = 10;
+
+fn postback_buttons(buttons: &[ButtonInfo]) -> Vec<Value> {
+    buttons
+        .iter()
+        .take(MAX_BUTTONS)
+        .map(|button| {
+            json!({
+                "type": "postback",
+                "title": button.title,
+                "payload": button.payload,
+            })
+        })
+        .collect()
+}
+
+fn generic_element(card: &Value) -> Value {
+    let mut element = json!({
+        "title": get_str(card, "title").unwrap_or_default(),
+    });
+
+    if let Some(subtitle) = get_str(card, "subtitle") {
+        element["subtitle"] = json!(subtitle);
+    }
+    if let Some(image_url) = get_str(card, "image_url") {
+        element["image_url"] = json!(image_url);
+    }
+
+    let buttons = get_buttons(card);
+    if !buttons.is_empty() {
+        element["buttons"] = json!(postback_buttons(&buttons));
+    }
+
+    element
+}
+
+fn generic_template(elements: Vec<Value>) -> Value {
+    json!({
+        "attachment": {
+            "type": "template",
+            "payload": {
+                "template_type": "generic",
+                "elements": elements,
+            }
+        }
+    })
+}
+
+impl Renderer for MessengerRenderer {
+    fn name(&self) -> &str {
+        "messenger"
+    }
+
+    fn render(&self, msg: &Message) -> Option<Value> {
+        let content = &msg.content;
+
+        match msg.content_type.as_str() {
+            "text" => Some(json!({ "text": get_str(content, "text")? })),
+            "question" => {
+                let quick_replies: Vec<Value> = get_buttons(content)
+                    .iter()
+                    .take(MAX_QUICK_REPLIES)
+                    .map(|button| {
+                        json!({
+                            "content_type": "text",
+                            "title": button.title,
+                            "payload": button.payload,
+                        })
+                    })
+                    .collect();
+
+                Some(json!({
+                    "text": get_str(content, "title").unwrap_or_default(),
+                    "quick_replies": quick_replies,
+                }))
+            }
+            "card" => Some(generic_template(vec![generic_element(content)])),
+            "carousel" => Some(generic_template(
+                get_cards(content)
+                    .into_iter()
+                    .take(MAX_ELEMENTS)
+                    .map(generic_element)
+                    .collect(),
+            )),
+            "image" | "video" | "audio" | "file" => Some(json!({
+                "attachment": {
+                    "type": msg.content_type,
+                    "payload": {
+                        "url": get_str(content, "url")?,
+                        "is_reusable": true,
+                    }
+                }
+            })),
+            "url" => {
+                let url = get_str(content, "url")?;
+
+                Some(json!({
+                    "attachment": {
+                        "type": "template",
+                        "payload": {
+                            "template_type": "button",
+                            "text": get_str(content, "text").unwrap_or(url),
+                            "buttons": [{
+                                "type": "web_url",
+                                "url": url,
+                                "title": get_str(content, "title").unwrap_or(url),
+                            }]
+                        }
+                    }
+                }))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,333 @@
+/**
+ * This module converts the generic CSML messages (`{content_type, content}`)
+ * to the formats expected by the messaging channels.
+ *
+ * A renderer is selected with the `renderer` option of the request or, when it
+ * is not set, with the `channel_id` of the client. Built-in renderers are:
+ *
+ * - `slack`: Slack Block Kit
+ * - `messenger`: Messenger Send API
+ * - `telegram`: Telegram Bot API
+ * - `whatsapp`: WhatsApp Cloud API
+ * - `teams`: Microsoft Teams (Bot Framework activities)
+ *
+ * When a component is not supported by a channel, the ENGINE_CHANNEL_FALLBACK
+ * env var defines what is sent instead:
+ *
+ * - `text` (default): a text version of the component
+ * - `raw`: the CSML message as is
+ * - `drop`: nothing, the rendered message is null
+ */
+mod messenger;
+mod slack;
+mod teams;
+mod telegram;
+mod whatsapp;
+
+pub use messenger::MessengerRenderer;
+pub use slack::SlackRenderer;
+pub use teams::TeamsRenderer;
+pub use telegram::TelegramRenderer;
+pub use whatsapp::WhatsAppRenderer;
+
+use csml_interpreter::data::{Client, Message};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, OnceLock, RwLock};
+
+pub trait Renderer: Send + Sync {
+    /**
+     * Name used to select the renderer, matched against the `renderer`
+     * request option or the `channel_id` of the client.
+     */
+    fn name(&self) -> &str;
+
+    /**
+     * Render a CSML message in the format of the channel,
+     * or return None if the component is not supported.
+     */
+    fn render(&self, message: &Message) -> Option<Value>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Fallback {
+    Text,
+    Raw,
+    Drop,
+}
+
+impl Fallback {
+    pub fn from_env() -> Self {
+        match env::var("ENGINE_CHANNEL_FALLBACK") {
+            Ok(value) if value.to_ascii_lowercase() == "raw" => Fallback::Raw,
+            Ok(value) if value.to_ascii_lowercase() == "drop" => Fallback::Drop,
+            _ => Fallback::Text,
+        }
+    }
+}
+
+static RENDERERS: OnceLock<RwLock<HashMap<String, Arc<dyn Renderer>>>> = OnceLock::new();
+
+fn renderers() -> &'static RwLock<HashMap<String, Arc<dyn Renderer>>> {
+    RENDERERS.get_or_init(|| {
+        let built_in: Vec<Arc<dyn Renderer>> = vec![
+            Arc::new(SlackRenderer),
+            Arc::new(MessengerRenderer),
+            Arc::new(TelegramRenderer),
+            Arc::new(WhatsAppRenderer),
+            Arc::new(TeamsRenderer),
+        ];
+
+        RwLock::new(
+            built_in
+                .into_iter()
+                .map(|renderer| (renderer.name().to_owned(), renderer))
+                .collect(),
+        )
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// COMPONENT HELPERS
+////////////////////////////////////////////////////////////////////////////////
+
+#[derive(Debug, Clone)]
+pub struct ButtonInfo {
+    pub title: String,
+    pub payload: String,
+}
+
+/**
+ * Read a string param of a component
+ */
+pub fn get_str<'a>(content: &'a Value, key: &str) -> Option<&'a str> {
+    content.get(key).and_then(|value| value.as_str())
+}
+
+/**
+ * Components nested in other components (buttons, cards) are either
+ * full messages `{content_type, content}` or their content only.
+ */
+fn get_component_content(component: &Value) -> &Value {
+    match component.get("content") {
+        Some(content) if content.is_object() => content,
+        _ => component,
+    }
+}
+
+pub fn get_buttons(content: &Value) -> Vec<ButtonInfo> {
+    let buttons = match content["buttons"].as_array() {
+        Some(buttons) => buttons,
+        None => return vec![],
+    };
+
+    buttons
+        .iter()
+        .filter_map(|button| {
+            let button = get_component_content(button);
+            let title = get_str(button, "title")?;
+
+            Some(ButtonInfo {
+                title: title.to_owned(),
+                payload: get_str(button, "payload").unwrap_or(title).to_owned(),
+            })
+        })
+        .collect()
+}
+
+pub fn get_cards(content: &Value) -> Vec<&Value> {
+    match content["cards"].as_array() {
+        Some(cards) => cards.iter().map(get_component_content).collect(),
+        None => vec![],
+    }
+}
+
+/**
+ * Text version of a component, used when a channel does not support it
+ */
+pub fn text_fallback(message: &Message) -> Message {
+    let content = &message.content;
+    let mut lines = vec![];
+
+    let describe_card = |card: &Value, lines: &mut Vec<String>| {
+        for key in ["title", "subtitle"].iter() {
+            if let Some(value) = get_str(card, key) {
+                lines.push(value.to_owned());
+            }
+        }
+        for button in get_buttons(card) {
+            lines.push(format!("- {}", button.title));
+        }
+    };
+
+    match message.content_type.as_str() {
+        "carousel" => {
+            for card in get_cards(content) {
+                describe_card(card, &mut lines);
+            }
+        }
+        "url" => {
+            match (get_str(content, "title"), get_str(content, "url")) {
+                (Some(title), Some(url)) if title != url => {
+                    lines.push(format!("{}: {}", title, url))
+                }
+                (_, Some(url)) => lines.push(url.to_owned()),
+                _ => {}
+            };
+        }
+        _ => {
+            if let Some(text) = get_str(content, "text") {
+                lines.push(text.to_owned());
+            }
+            describe_card(content, &mut lines);
+            if let Some(url) = get_str(content, "url") {
+                lines.push(url.to_owned());
+            }
+        }
+    }
+
+    Message {
+        content_type: "text".to_owned(),
+        content: json!({ "text": lines.join("\n") }),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Add a renderer, or replace the built-in renderer of the same name.
+ */
+pub fn register_renderer(renderer: Arc<dyn Renderer>) {
+    let mut renderers = match renderers().write() {
+        Ok(renderers) => renderers,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    renderers.insert(renderer.name().to_ascii_lowercase(), renderer);
+}
+
+pub fn get_renderer(name: &str) -> Option<Arc<dyn Renderer>> {
+    let renderers = match renderers().read() {
+        Ok(renderers) => renderers,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    renderers.get(&name.to_ascii_lowercase()).cloned()
+}
+
+/**
+ * Select the renderer of a request: the `renderer` option comes first,
+ * then the channel_id of the client. None if the messages must stay in the CSML format.
+ */
+pub fn select_renderer(renderer: &Option<String>, client: &Client) -> Option<Arc<dyn Renderer>> {
+    match renderer {
+        Some(name) => get_renderer(name),
+        None => get_renderer(&client.channel_id),
+    }
+}
+
+/**
+ * Render a message with the given renderer, applying the fallback
+ * if its component is not supported by the channel.
+ */
+pub fn render_message(renderer: &dyn Renderer, message: &Message, fallback: Fallback) -> Value {
+    if let Some(rendered) = renderer.render(message) {
+        return rendered;
+    }
+
+    match fallback {
+        Fallback::Text => {
+            let text = text_fallback(message);
+
+            // components without any text (typing, wait...) are not sent
+            match text.content["text"].as_str() {
+                Some(value) if !value.is_empty() => renderer.render(&text).unwrap_or(Value::Null),
+                _ => Value::Null,
+            }
+        }
+        Fallback::Raw => json!({
+            "content_type": message.content_type,
+            "content": message.content,
+        }),
+        Fallback::Drop => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn question() -> Message {
+        Message {
+            content_type: "question".to_owned(),
+            content: json!({
+                "title": "Pick one",
+                "buttons": [
+                    {"content_type": "button", "content": {"title": "b1", "payload": "p1"}},
+                    {"content_type": "button", "content": {"title": "b2"}},
+                ]
+            }),
+        }
+    }
+
+    fn carousel() -> Message {
+        Message {
+            content_type: "carousel".to_owned(),
+            content: json!({
+                "cards": [
+                    {"content_type": "card", "content": {"title": "c1", "buttons": []}},
+                    {"content_type": "card", "content": {"title": "c2", "buttons": []}},
+                ]
+            }),
+        }
+    }
+
+    #[test]
+    fn ok_get_buttons() {
+        let buttons = get_buttons(&question().content);
+
+        assert_eq!(buttons.len(), 2);
+        assert_eq!(buttons[0].payload, "p1");
+        assert_eq!(buttons[1].payload, "b2");
+    }
+
+    #[test]
+    fn ok_select_renderer() {
+        let client = Client::new("bot".to_owned(), "Slack".to_owned(), "user".to_owned());
+
+        assert_eq!(select_renderer(&None, &client).unwrap().name(), "slack");
+        assert_eq!(
+            select_renderer(&Some("teams".to_owned()), &client)
+                .unwrap()
+                .name(),
+            "teams"
+        );
+        assert!(select_renderer(&Some("unknown".to_owned()), &client).is_none());
+    }
+
+    #[test]
+    fn ok_render_question() {
+        let rendered = render_message(&TelegramRenderer, &question(), Fallback::Text);
+
+        assert_eq!(rendered["method"], "sendMessage");
+        assert_eq!(
+            rendered["reply_markup"]["inline_keyboard"][0][0]["callback_data"],
+            "p1"
+        );
+    }
+
+    #[test]
+    fn ok_fallbacks() {
+        let rendered = render_message(&TelegramRenderer, &carousel(), Fallback::Text);
+        assert_eq!(rendered["text"], "c1\nc2");
+
+        let rendered = render_message(&TelegramRenderer, &carousel(), Fallback::Raw);
+        assert_eq!(rendered["content_type"], "carousel");
+
+        let rendered = render_message(&TelegramRenderer, &carousel(), Fallback::Drop);
+        assert_eq!(rendered, Value::Null);
+    }
+}
@@ -0,0 +1,125 @@
+use super::{get_buttons, get_cards, get_str, ButtonInfo, Renderer};
+
+use csml_interpreter::data::Message;
+use serde_json::{json, Value};
+
+/**
+ * Slack Block Kit: messages are rendered as `{text, blocks}`,
+ * `text` being used by Slack in notifications.
+ */
+pub struct SlackRenderer;
+
+fn section(text: &str) -> Value {
+    json!({
+        "type": "section",
+        "text": {"type": "mrkdwn", "text": text}
+    })
+}
+
+fn actions(buttons: &[ButtonInfo]) -> Value {
+    let elements: Vec<Value> = buttons
+        .iter()
+        .map(|button| {
+            json!({
+                "type": "button",
+                "text": {"type": "plain_text", "text": button.title},
+                "value": button.payload,
+            })
+        })
+        .collect();
+
+    json!({"type": "actions", "elements": elements})
+}
+
+fn card_blocks(card: &Value) -> Vec<Value> {
+    let mut blocks = vec![];
+
+    let text = match (get_str(card, "title"), get_str(card, "subtitle")) {
+        (Some(title), Some(subtitle)) => format!("*{}*\n{}", title, subtitle),
+        (Some(title), None) => format!("*{}*", title),
+        (None, Some(subtitle)) => subtitle.to_owned(),
+        (None, None) => String::new(),
+    };
+
+    let mut block = section(&text);
+    if let Some(image_url) = get_str(card, "image_url") {
+        block["accessory"] = json!({
+            "type": "image",
+            "image_url": image_url,
+            "alt_text": get_str(card, "title").unwrap_or("image"),
+        });
+    }
+    blocks.push(block);
+
+    let buttons = get_buttons(card);
+    if !buttons.is_empty() {
+        blocks.push(actions(&buttons));
+    }
+
+    blocks
+}
+
+fn link(content: &Value) -> Option<String> {
+    let url = get_str(content, "url")?;
+
+    match get_str(content, "title").or_else(|| get_str(content, "text")) {
+        Some(title) if title != url => Some(format!("<{}|{}>", url, title)),
+        _ => Some(format!("<{}>", url)),
+    }
+}
+
+fn message(text: &str, blocks: Vec<Value>) -> Value {
+    json!({"text": text, "blocks": blocks})
+}
+
+impl Renderer for SlackRenderer {
+    fn name(&self) -> &str {
+        "slack"
+    }
+
+    fn render(&self, msg: &Message) -> Option<Value> {
+        let content = &msg.content;
+
+        match msg.content_type.as_str() {
+            "text" => {
+                let text = get_str(content, "text")?;
+                Some(message(text, vec![section(text)]))
+            }
+            "question" => {
+                let title = get_str(content, "title").unwrap_or_default();
+                Some(message(
+                    title,
+                    vec![section(title), actions(&get_buttons(content))],
+                ))
+            }
+            "card" => Some(message(
+                get_str(content, "title").unwrap_or_default(),
+                card_blocks(content),
+            )),
+            "carousel" => {
+                let mut blocks = vec![];
+
+                for (index, card) in get_cards(content).into_iter().enumerate() {
+                    if index > 0 {
+                        blocks.push(json!({"type": "divider"}));
+                    }
+                    blocks.append(&mut card_blocks(card));
+                }
+
+                Some(message("", blocks))
+            }
+            "image" => {
+                let url = get_str(content, "url")?;
+                Some(message(
+                    url,
+                    vec![json!({"type": "image", "image_url": url, "alt_text": "image"})],
+                ))
+            }
+            "video" | "audio" | "file" | "url" => {
+                let link = link(content)?;
+                Some(message(&link, vec![section(&link)]))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use super::{get_buttons, get_cards, get_str, Renderer};
+
+use csml_interpreter::data::Message;
+use serde_json::{json, Value};
+
+/**
+ * Microsoft Teams: messages are rendered as Bot Framework activities,
+ * components with buttons using hero cards.
+ */
+pub struct TeamsRenderer;
+
+fn hero_card(card: &Value) -> Value {
+    let mut content = json!({});
+
+    for key in ["title", "subtitle", "text"].iter() {
+        if let Some(value) = get_str(card, key) {
+            content[*key] = json!(value);
+        }
+    }
+    if let Some(image_url) = get_str(card, "image_url") {
+        content["images"] = json!([{ "url": image_url }]);
+    }
+
+    let buttons: Vec<Value> = get_buttons(card)
+        .iter()
+        .map(|button| {
+            json!({
+                "type": "imBack",
+                "title": button.title,
+                "value": button.payload,
+            })
+        })
+        .collect();
+    content["buttons"] = json!(buttons);
+
+    json!({
+        "contentType": "application/vnd.microsoft.card.hero",
+        "content": content,
+    })
+}
+
+fn get_mime_type(url: &str, default: &str) -> String {
+    let extension = url
+        .split(|c| c == '?' || c == '#')
+        .next()
+        .and_then(|path| path.rsplit('.').next())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+
+    let mime_type = match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "mp4" => "video/mp4",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "pdf" => "application/pdf",
+        _ => default,
+    };
+
+    mime_type.to_owned()
+}
+
+fn activity(attachments: Vec<Value>) -> Value {
+    json!({
+        "type": "message",
+        "attachments": attachments,
+    })
+}
+
+impl Renderer for TeamsRenderer {
+    fn name(&self) -> &str {
+        "teams"
+    }
+
+    fn render(&self, msg: &Message) -> Option<Value> {
+        let content = &msg.content;
+
+        match msg.content_type.as_str() {
+            "text" => Some(json!({
+                "type": "message",
+                "text": get_str(content, "text")?,
+            })),
+            "question" => {
+                let mut card = content.clone();
+                card["text"] = json!(get_str(content, "title").unwrap_or_default());
+                card["title"] = Value::Null;
+
+                Some(activity(vec![hero_card(&card)]))
+            }
+            "card" => Some(activity(vec![hero_card(content)])),
+            "carousel" => {
+                let mut activity =
+                    activity(get_cards(content).into_iter().map(hero_card).collect());
+                activity["attachmentLayout"] = json!("carousel");
+
+                Some(activity)
+            }
+            "image" | "video" | "audio" | "file" => {
+                let url = get_str(content, "url")?;
+                let default = match msg.content_type.as_str() {
+                    "image" => "image/png",
+                    "video" => "video/mp4",
+                    "audio" => "audio/mpeg",
+                    _ => "application/octet-stream",
+                };
+
+                Some(activity(vec![json!({
+                    "contentType": get_mime_type(url, default),
+                    "contentUrl": url,
+                })]))
+            }
+            "url" => {
+                let url = get_str(content, "url")?;
+
+                Some(activity(vec![json!({
+                    "contentType": "application/vnd.microsoft.card.hero",
+                    "content": {
+                        "text": get_str(content, "text").unwrap_or(url),
+                        "buttons": [{
+                            "type": "openUrl",
+                            "title": get_str(content, "title").unwrap_or(url),
+                            "value": url,
+                        }]
+                    }
+                })]))
+            }
+            _ => None,
+        }
+    }
+}
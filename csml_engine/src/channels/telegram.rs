@@ -0,0 +1,108 @@
+use super::{get_buttons, get_str, ButtonInfo, Renderer};
+
+use csml_interpreter::data::Message;
+use serde_json::{json, Value};
+
+/**
+ * Telegram Bot API: messages are rendered as `{method, ...params}`,
+ * the chat_id being added by the caller. Carousels are not supported.
+ */
+pub struct TelegramRenderer;
+
+fn inline_keyboard(buttons: &[ButtonInfo]) -> Value {
+    let rows: Vec<Value> = buttons
+        .iter()
+        .map(|button| {
+            json!([{
+                "text": button.title,
+                "callback_data": button.payload,
+            }])
+        })
+        .collect();
+
+    json!({ "inline_keyboard": rows })
+}
+
+fn with_keyboard(mut message: Value, buttons: &[ButtonInfo]) -> Value {
+    if !buttons.is_empty() {
+        message["reply_markup"] = inline_keyboard(buttons);
+    }
+
+    message
+}
+
+impl Renderer for TelegramRenderer {
+    fn name(&self) -> &str {
+        "telegram"
+    }
+
+    fn render(&self, msg: &Message) -> Option<Value> {
+        let content = &msg.content;
+
+        match msg.content_type.as_str() {
+            "text" => Some(json!({
+                "method": "sendMessage",
+                "text": get_str(content, "text")?,
+            })),
+            "question" => Some(with_keyboard(
+                json!({
+                    "method": "sendMessage",
+                    "text": get_str(content, "title").unwrap_or_default(),
+                }),
+                &get_buttons(content),
+            )),
+            "card" => {
+                let title = get_str(content, "title").unwrap_or_default();
+                let text = match get_str(content, "subtitle") {
+                    Some(subtitle) => format!("{}\n{}", title, subtitle),
+                    None => title.to_owned(),
+                };
+
+                let message = match get_str(content, "image_url") {
+                    Some(image_url) => json!({
+                        "method": "sendPhoto",
+                        "photo": image_url,
+                        "caption": text,
+                    }),
+                    None => json!({
+                        "method": "sendMessage",
+                        "text": text,
+                    }),
+                };
+
+                Some(with_keyboard(message, &get_buttons(content)))
+            }
+            "image" => Some(json!({
+                "method": "sendPhoto",
+                "photo": get_str(content, "url")?,
+            })),
+            "video" => Some(json!({
+                "method": "sendVideo",
+                "video": get_str(content, "url")?,
+            })),
+            "audio" => Some(json!({
+                "method": "sendAudio",
+                "audio": get_str(content, "url")?,
+            })),
+            "file" => Some(json!({
+                "method": "sendDocument",
+                "document": get_str(content, "url")?,
+            })),
+            "url" => {
+                let url = get_str(content, "url")?;
+
+                Some(json!({
+                    "method": "sendMessage",
+                    "text": get_str(content, "text").unwrap_or(url),
+                    "reply_markup": {
+                        "inline_keyboard": [[{
+                            "text": get_str(content, "title").unwrap_or(url),
+                            "url": url,
+                        }]]
+                    }
+                }))
+            }
+            _ => None,
+        }
+    }
+}
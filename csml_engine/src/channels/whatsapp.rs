@@ -0,0 +1,152 @@
+use super::{get_buttons, get_str, ButtonInfo, Renderer};
+
+use csml_interpreter::data::Message;
+use serde_json::{json, Value};
+
+/**
+ * WhatsApp Cloud API: messages are rendered as the body of the messages endpoint,
+ * the recipient (`to`) being added by the caller. Carousels are not supported.
+ */
+pub struct WhatsAppRenderer;
+
+// limits of the WhatsApp interactive messages
+const MAX_REPLY_BUTTONS: usize = 3;
+const MAX_LIST_ROWS: usize = 10;
+const MAX_TITLE_LEN: usize = 20;
+const MAX_ROW_TITLE_LEN: usize = 24;
+
+fn truncate(text: &str, len: usize) -> String {
+    text.chars().take(len).collect()
+}
+
+fn message(kind: &str, value: Value) -> Value {
+    let mut message = json!({
+        "messaging_product": "whatsapp",
+        "recipient_type": "individual",
+        "type": kind,
+    });
+    message[kind] = value;
+
+    message
+}
+
+fn interactive(body: &str, header: Option<Value>, buttons: &[ButtonInfo]) -> Value {
+    let mut interactive = if buttons.len() <= MAX_REPLY_BUTTONS {
+        let buttons: Vec<Value> = buttons
+            .iter()
+            .map(|button| {
+                json!({
+                    "type": "reply",
+                    "reply": {
+                        "id": button.payload,
+                        "title": truncate(&button.title, MAX_TITLE_LEN),
+                    }
+                })
+            })
+            .collect();
+
+        json!({
+            "type": "button",
+            "body": {"text": body},
+            "action": {"buttons": buttons},
+        })
+    } else {
+        let rows: Vec<Value> = buttons
+            .iter()
+            .take(MAX_LIST_ROWS)
+            .map(|button| {
+                json!({
+                    "id": button.payload,
+                    "title": truncate(&button.title, MAX_ROW_TITLE_LEN),
+                })
+            })
+            .collect();
+
+        json!({
+            "type": "list",
+            "body": {"text": body},
+            "action": {
+                "button": truncate(body, MAX_TITLE_LEN),
+                "sections": [{"rows": rows}],
+            },
+        })
+    };
+
+    if let Some(header) = header {
+        interactive["header"] = header;
+    }
+
+    message("interactive", interactive)
+}
+
+impl Renderer for WhatsAppRenderer {
+    fn name(&self) -> &str {
+        "whatsapp"
+    }
+
+    fn render(&self, msg: &Message) -> Option<Value> {
+        let content = &msg.content;
+
+        match msg.content_type.as_str() {
+            "text" => Some(message(
+                "text",
+                json!({ "body": get_str(content, "text")? }),
+            )),
+            "question" => {
+                let buttons = get_buttons(content);
+                if buttons.is_empty() {
+                    return None;
+                }
+
+                Some(interactive(
+                    get_str(content, "title").unwrap_or_default(),
+                    None,
+                    &buttons,
+                ))
+            }
+            "card" => {
+                let title = get_str(content, "title").unwrap_or_default();
+                let body = match get_str(content, "subtitle") {
+                    Some(subtitle) => format!("{}\n{}", title, subtitle),
+                    None => title.to_owned(),
+                };
+                let buttons = get_buttons(content);
+
+                // interactive messages require at least one button
+                if buttons.is_empty() {
+                    return Some(message("text", json!({ "body": body })));
+                }
+
+                let header = get_str(content, "image_url").map(|image_url| {
+                    json!({
+                        "type": "image",
+                        "image": {"link": image_url},
+                    })
+                });
+
+                Some(interactive(&body, header, &buttons))
+            }
+            "image" | "video" | "audio" => Some(message(
+                &msg.content_type,
+                json!({ "link": get_str(content, "url")? }),
+            )),
+            "file" => Some(message(
+                "document",
+                json!({ "link": get_str(content, "url")? }),
+            )),
+            "url" => {
+                let url = get_str(content, "url")?;
+                let body = match get_str(content, "title") {
+                    Some(title) if title != url => format!("{}\n{}", title, url),
+                    _ => url.to_owned(),
+                };
+
+                Some(message(
+                    "text",
+                    json!({ "body": body, "preview_url": true }),
+                ))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,344 @@
+/**
+ * Requests of the same client are processed one at a time, so that concurrent requests
+ * do not load the same hold and memories and then overwrite each other's state.
+ *
+ * Requests are serialized in the process with a lock per client, and across instances
+ * with a lease stored in db when ENGINE_LOCK_DISTRIBUTED=true. The lease expires after
+ * ENGINE_LOCK_TTL seconds (60 by default) in case the instance holding it stops,
+ * and is renewed every third of the TTL while the request is processed.
+ *
+ * ENGINE_LOCK_MODE sets what happens to a request while another one is processed:
+ * - `queue` (default): wait for the lock, up to ENGINE_LOCK_TIMEOUT seconds (30 by default)
+ * - `reject`: fail with EngineError::Locked
+ * - `drop`: ignore the request, the response has no messages and `dropped: true`
+ * - `off`: requests are not serialized
+ */
+use crate::data::{CsmlRequest, Database, EngineError};
+use crate::db_connectors::{init_db, locks};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+use csml_interpreter::data::Client;
+
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::collections::HashSet;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DEFAULT_LOCK_TTL: u64 = 60; // seconds
+const DEFAULT_LOCK_TIMEOUT: u64 = 30; // seconds
+const LEASE_RETRY_DELAY: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LockMode {
+    Queue,
+    Reject,
+    Drop,
+    Off,
+}
+
+impl LockMode {
+    pub fn from_env() -> Self {
+        match std::env::var("ENGINE_LOCK_MODE") {
+            Ok(value) if value.to_ascii_lowercase() == "reject" => LockMode::Reject,
+            Ok(value) if value.to_ascii_lowercase() == "drop" => LockMode::Drop,
+            Ok(value) if value.to_ascii_lowercase() == "off" => LockMode::Off,
+            _ => LockMode::Queue,
+        }
+    }
+}
+
+/**
+ * Held while a request of the client is processed, the lock and the lease are released on drop
+ */
+pub struct ClientLock {
+    client: Client,
+    key: String,
+    lease_owner: Option<String>,
+    // dropping the sender stops the renewal of the lease
+    lease_renewal: Option<(mpsc::Sender<()>, JoinHandle<()>)>,
+}
+
+static LOCKED_CLIENTS: OnceLock<(Mutex<HashSet<String>>, Condvar)> = OnceLock::new();
+
+fn locked_clients() -> &'static (Mutex<HashSet<String>>, Condvar) {
+    LOCKED_CLIENTS.get_or_init(|| (Mutex::new(HashSet::new()), Condvar::new()))
+}
+
+fn get_env_seconds(name: &str, default: u64) -> Duration {
+    match std::env::var(name) {
+        Ok(val) => Duration::from_secs(val.parse::<u64>().unwrap_or(default)),
+        Err(_) => Duration::from_secs(default),
+    }
+}
+
+fn get_client_key(client: &Client) -> String {
+    format!(
+        "bot_id:{}#channel_id:{}#user_id:{}",
+        client.bot_id, client.channel_id, client.user_id
+    )
+}
+
+fn locked_error(client: &Client) -> EngineError {
+    EngineError::Locked(format!(
+        "a request of the client is already being processed (user_id: {})",
+        client.user_id
+    ))
+}
+
+/**
+ * Take the in-process lock of a key. Returns false if the request must be dropped.
+ */
+fn lock_key(key: &str, mode: LockMode, deadline: Instant) -> Result<bool, ()> {
+    let (mutex, condvar) = locked_clients();
+    let mut locked = match mutex.lock() {
+        Ok(locked) => locked,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    while locked.contains(key) {
+        match mode {
+            LockMode::Drop => return Ok(false),
+            LockMode::Reject => return Err(()),
+            _ => {}
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(());
+        }
+
+        locked = match condvar.wait_timeout(locked, deadline - now) {
+            Ok((locked, _)) => locked,
+            Err(poisoned) => poisoned.into_inner().0,
+        };
+    }
+
+    locked.insert(key.to_owned());
+
+    Ok(true)
+}
+
+fn unlock_key(key: &str) {
+    let (mutex, condvar) = locked_clients();
+    let mut locked = match mutex.lock() {
+        Ok(locked) => locked,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    locked.remove(key);
+    condvar.notify_all();
+}
+
+/**
+ * Lock the client before processing one of its requests.
+ * Returns None if the request must be dropped because another one is being processed.
+ */
+pub fn lock_client(client: &Client, db: &mut Database) -> Result<Option<ClientLock>, EngineError> {
+    let mode = LockMode::from_env();
+    if mode == LockMode::Off {
+        return Ok(Some(ClientLock {
+            client: client.to_owned(),
+            key: String::new(),
+            lease_owner: None,
+            lease_renewal: None,
+        }));
+    }
+
+    let deadline = Instant::now() + get_env_seconds("ENGINE_LOCK_TIMEOUT", DEFAULT_LOCK_TIMEOUT);
+    let key = get_client_key(client);
+
+    match lock_key(&key, mode, deadline) {
+        Ok(true) => {}
+        Ok(false) => return Ok(None),
+        Err(_) => return Err(locked_error(client)),
+    }
+
+    let mut lock = ClientLock {
+        client: client.to_owned(),
+        key,
+        lease_owner: None,
+        lease_renewal: None,
+    };
+
+    match std::env::var("ENGINE_LOCK_DISTRIBUTED") {
+        Ok(val) if val == "true" => {}
+        _ => return Ok(Some(lock)),
+    }
+
+    let owner = uuid::Uuid::new_v4().to_string();
+    let ttl = chrono::Duration::from_std(get_env_seconds("ENGINE_LOCK_TTL", DEFAULT_LOCK_TTL))
+        .unwrap_or_else(|_| chrono::Duration::seconds(DEFAULT_LOCK_TTL as i64));
+
+    // another instance may be processing a request of the same client
+    while !locks::acquire_lease(client, &owner, chrono::Utc::now() + ttl, db)? {
+        match mode {
+            LockMode::Drop => return Ok(None),
+            LockMode::Queue if Instant::now() + LEASE_RETRY_DELAY < deadline => {
+                std::thread::sleep(LEASE_RETRY_DELAY)
+            }
+            _ => return Err(locked_error(client)),
+        }
+    }
+
+    let (stop, stopped) = mpsc::channel();
+    let (client, lease_owner) = (client.to_owned(), owner.clone());
+    let renewal = thread::spawn(move || renew_lease(&client, &lease_owner, ttl, stopped));
+
+    lock.lease_owner = Some(owner);
+    lock.lease_renewal = Some((stop, renewal));
+
+    Ok(Some(lock))
+}
+
+/**
+ * Extend the lease every third of its TTL, until the lock is dropped
+ */
+fn renew_lease(client: &Client, owner: &str, ttl: chrono::Duration, stopped: mpsc::Receiver<()>) {
+    let interval = (ttl / 3)
+        .to_std()
+        .unwrap_or_else(|_| Duration::from_secs(DEFAULT_LOCK_TTL / 3));
+
+    while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(interval) {
+        let res = init_db()
+            .and_then(|mut db| locks::acquire_lease(client, owner, Utc::now() + ttl, &mut db));
+
+        let (error, lost) = match res {
+            Ok(true) => continue,
+            Ok(false) => ("the lease was taken by another owner".to_owned(), true),
+            // the next renewal may succeed before the lease expires
+            Err(err) => (format!("{:?}", err), false),
+        };
+
+        csml_logger(
+            CsmlLog::new(
+                Some(client),
+                None,
+                None,
+                format!("failed to renew lease {}: {}", owner, error),
+            ),
+            LogLvl::Error,
+        );
+
+        if lost {
+            return;
+        }
+    }
+}
+
+/**
+ * Response of a request ignored in `drop` mode
+ */
+pub fn dropped_response(request: &CsmlRequest) -> Map<String, Value> {
+    let mut map = Map::new();
+
+    map.insert("messages".to_owned(), json!([]));
+    map.insert("conversation_end".to_owned(), json!(false));
+    map.insert("request_id".to_owned(), json!(request.request_id));
+    map.insert(
+        "received_at".to_owned(),
+        json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)),
+    );
+    map.insert("client".to_owned(), json!(request.client));
+    map.insert("dropped".to_owned(), json!(true));
+
+    map
+}
+
+impl Drop for ClientLock {
+    fn drop(&mut self) {
+        // the renewal must be over before the lease is released, or it could take it again
+        if let Some((stop, renewal)) = self.lease_renewal.take() {
+            drop(stop);
+            let _ = renewal.join();
+        }
+
+        if let Some(owner) = &self.lease_owner {
+            let res =
+                init_db().and_then(|mut db| locks::release_lease(&self.client, owner, &mut db));
+
+            if let Err(err) = res {
+                // the lease will expire after ENGINE_LOCK_TTL
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&self.client),
+                        None,
+                        None,
+                        format!("failed to release lease {}: {:?}", owner, err),
+                    ),
+                    LogLvl::Error,
+                );
+            }
+        }
+
+        if !self.key.is_empty() {
+            unlock_key(&self.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deadline(millis: u64) -> Instant {
+        Instant::now() + Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn ok_lock_key() {
+        let key = "bot_id:bot#channel_id:channel#user_id:ok_lock_key";
+
+        assert_eq!(lock_key(key, LockMode::Reject, deadline(0)), Ok(true));
+        assert_eq!(lock_key(key, LockMode::Reject, deadline(0)), Err(()));
+        assert_eq!(lock_key(key, LockMode::Drop, deadline(0)), Ok(false));
+        assert_eq!(lock_key(key, LockMode::Queue, deadline(50)), Err(()));
+
+        unlock_key(key);
+
+        assert_eq!(lock_key(key, LockMode::Reject, deadline(0)), Ok(true));
+        unlock_key(key);
+    }
+
+    #[test]
+    fn ok_queue() {
+        let key = "bot_id:bot#channel_id:channel#user_id:ok_queue";
+
+        assert_eq!(lock_key(key, LockMode::Queue, deadline(0)), Ok(true));
+
+        let waiting = std::thread::spawn(move || lock_key(key, LockMode::Queue, deadline(5000)));
+        std::thread::sleep(Duration::from_millis(50));
+        unlock_key(key);
+
+        assert_eq!(waiting.join().unwrap(), Ok(true));
+        unlock_key(key);
+    }
+
+    #[test]
+    fn ok_dropped_response() {
+        let request = CsmlRequest {
+            request_id: "request_id".to_owned(),
+            client: Client {
+                bot_id: "bot_id".to_owned(),
+                channel_id: "channel_id".to_owned(),
+                user_id: "user_id".to_owned(),
+            },
+            callback_url: None,
+            payload: json!({"content_type": "text", "content": {"text": "hello"}}),
+            metadata: json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+
+        let response = dropped_response(&request);
+
+        assert_eq!(response["request_id"], json!("request_id"));
+        assert_eq!(response["client"]["user_id"], json!("user_id"));
+        assert_eq!(response["messages"], json!([]));
+        assert_eq!(response["dropped"], json!(true));
+    }
+}
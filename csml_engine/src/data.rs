@@ -1,5 +1,5 @@
 use crate::{
-    db_connectors,
+    db_connectors::{self, unit_of_work::UnitOfWork},
     encrypt::{decrypt_data, encrypt_data},
     Client, Context,
 };
@@ -9,6 +9,8 @@ use serde_json::Value;
 
 pub const DEBUG: &str = "DEBUG";
 pub const DISABLE_SSL_VERIFY: &str = "DISABLE_SSL_VERIFY";
+// type of the schedules dispatched by `process_due_timeouts` instead of `process_due_schedules`
+pub const HOLD_TIMEOUT_JOB: &str = "hold_timeout";
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct FlowTrigger {
@@ -16,6 +18,42 @@ pub struct FlowTrigger {
     pub step_id: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleRequest {
+    pub client: Client,
+    pub flow_id: String,
+    pub step_id: Option<String>,
+    // RFC 3339 date of a single run
+    pub run_at: Option<String>,
+    // recurring runs, ex: "0 9 * * Mon-Fri"
+    pub cron: Option<String>,
+    pub callback_url: Option<String>,
+    #[serde(default)]
+    pub metadata: Value,
+    #[serde(alias = "fn_endpoint")]
+    pub apps_endpoint: Option<String>,
+    pub multibot: Option<Vec<MultiBot>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AgentMessageRequest {
+    pub client: Client,
+    // message sent by the human agent, ex: {"content_type": "text", "content": {"text": "Hi!"}}
+    pub payload: Value,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResumeRequest {
+    pub client: Client,
+    pub flow_id: String,
+    pub step_id: Option<String>,
+    pub callback_url: Option<String>,
+    #[serde(default)]
+    pub metadata: Value,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RunRequest {
     pub bot: Option<CsmlBot>,
@@ -346,6 +384,12 @@ pub struct CsmlRequest {
     pub step_limit: Option<usize>,
     pub ttl_duration: Option<serde_json::Value>,
     pub low_data_mode: Option<serde_json::Value>,
+    // channel format of the sent messages, defaults to the channel_id of the client
+    #[serde(default)]
+    pub renderer: Option<String>,
+    // W3C traceparent of the caller, the spans of the request are part of its trace
+    #[serde(default)]
+    pub traceparent: Option<String>,
 }
 
 pub enum Database {
@@ -387,12 +431,14 @@ impl PostgresqlClient {
 #[cfg(feature = "mongo")]
 pub struct MongoDbClient {
     pub client: mongodb::sync::Database,
+    // starts the sessions of the transactions, see db_connectors::unit_of_work
+    pub sessions: mongodb::sync::Client,
 }
 
 #[cfg(feature = "mongo")]
 impl MongoDbClient {
-    pub fn new(client: mongodb::sync::Database) -> Self {
-        Self { client }
+    pub fn new(client: mongodb::sync::Database, sessions: mongodb::sync::Client) -> Self {
+        Self { client, sessions }
     }
 }
 /**
@@ -428,6 +474,17 @@ pub struct ConversationInfo {
     pub messages: Vec<Message>,
     pub ttl: Option<chrono::Duration>,
     pub low_data: bool,
+    pub renderer: Option<String>,
+    // receives the messages as they are sent, see `start_conversation_stream`
+    pub stream: Option<std::sync::mpsc::Sender<Value>>,
+    // writes of the current step, committed at the end of `interpret_step`
+    pub unit_of_work: UnitOfWork,
+    // handoff webhook events of the current step, sent once its writes are committed
+    pub handoff_events: Vec<Value>,
+    // position of the last message of the conversation in the outbox, loaded on the first callback
+    pub outbox_position: Option<i64>,
+    // the next messages of the conversation must be queued behind the ones in the outbox
+    pub outbox_blocked: bool,
     pub db: Database,
 }
 
@@ -447,6 +504,12 @@ pub enum EngineError {
     Utf8(std::str::Utf8Error),
     Manager(String),
     Format(String),
+    // another request of the same client is being processed
+    Locked(String),
+    // missing or invalid credentials
+    Unauthorized(String),
+    // valid credentials without access to the bot or scope of the request
+    Forbidden(String),
     Interpreter(String),
     Parring(String),
     Time(std::time::SystemTimeError),
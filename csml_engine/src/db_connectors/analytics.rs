@@ -0,0 +1,89 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::db_connectors::DbMessageStep;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Database, EngineError};
+use chrono::{DateTime, Utc};
+
+/**
+ * Flow and step of the messages of a bot created between from_date and to_date,
+ * optionally filtered by channel, at most `limit` messages
+ */
+pub fn get_bot_message_steps(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: DateTime<Utc>,
+    to_date: DateTime<Utc>,
+    limit: i64,
+    db: &mut Database,
+) -> Result<Vec<DbMessageStep>, EngineError> {
+    let _db_call = db_call("get_bot_message_steps");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get message steps bot_id: {}", bot_id),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::analytics::get_bot_message_steps(
+            bot_id,
+            channel_id,
+            bson::DateTime::from_chrono(from_date),
+            bson::DateTime::from_chrono(to_date),
+            limit,
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::analytics::get_bot_message_steps(
+            bot_id, channel_id, from_date, to_date, limit, db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::analytics::get_bot_message_steps(
+            bot_id,
+            channel_id,
+            from_date.naive_utc(),
+            to_date.naive_utc(),
+            limit,
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::analytics::get_bot_message_steps(
+            bot_id,
+            channel_id,
+            from_date.naive_utc(),
+            to_date.naive_utc(),
+            limit,
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
@@ -0,0 +1,131 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::DbApiKey;
+use crate::db_connectors::db_call;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Database, EngineError};
+
+/**
+ * Store an api key, only the hash of the key is saved
+ */
+pub fn create_api_key(
+    key_hash: &str,
+    bot_ids: &Option<Vec<String>>,
+    scopes: &[String],
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let _db_call = db_call("create_api_key");
+    csml_logger(
+        CsmlLog::new(None, None, None, "db call create api key".to_owned()),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::api_keys::create_api_key(key_hash, bot_ids, scopes, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::api_keys::create_api_key(key_hash, bot_ids, scopes, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::api_keys::create_api_key(key_hash, bot_ids, scopes, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::api_keys::create_api_key(key_hash, bot_ids, scopes, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Api key whose hash is `key_hash`, None if it does not exist
+ */
+pub fn get_api_key(key_hash: &str, db: &mut Database) -> Result<Option<DbApiKey>, EngineError> {
+    let _db_call = db_call("get_api_key");
+    csml_logger(
+        CsmlLog::new(None, None, None, "db call get api key".to_owned()),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::api_keys::get_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::api_keys::get_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::api_keys::get_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::api_keys::get_api_key(key_hash, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Revoke an api key
+ */
+pub fn delete_api_key(key_hash: &str, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_api_key");
+    csml_logger(
+        CsmlLog::new(None, None, None, "db call delete api key".to_owned()),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::api_keys::delete_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::api_keys::delete_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::api_keys::delete_api_key(key_hash, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::api_keys::delete_api_key(key_hash, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
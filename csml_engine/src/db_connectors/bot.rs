@@ -7,6 +7,7 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 #[cfg(feature = "sqlite")]
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{BotVersion, CsmlBot, Database, EngineError};
 use csml_interpreter::data::csml_logs::*;
@@ -16,6 +17,7 @@ pub fn create_bot_version(
     csml_bot: CsmlBot,
     db: &mut Database,
 ) -> Result<String, EngineError> {
+    let _db_call = db_call("create_bot_version");
     csml_logger(
         CsmlLog::new(
             None,
@@ -106,6 +108,7 @@ pub fn get_last_bot_version(
     bot_id: &str,
     db: &mut Database,
 ) -> Result<Option<BotVersion>, EngineError> {
+    let _db_call = db_call("get_last_bot_version");
     csml_logger(
         CsmlLog::new(
             None,
@@ -148,6 +151,7 @@ pub fn get_by_version_id(
     _bot_id: &str,
     db: &mut Database,
 ) -> Result<Option<BotVersion>, EngineError> {
+    let _db_call = db_call("get_by_version_id");
     csml_logger(
         CsmlLog::new(
             None,
@@ -203,6 +207,7 @@ pub fn get_bot_versions(
     pagination_key: Option<String>,
     db: &mut Database,
 ) -> Result<serde_json::Value, EngineError> {
+    let _db_call = db_call("get_bot_versions");
     csml_logger(
         CsmlLog::new(
             None,
@@ -261,6 +266,7 @@ pub fn delete_bot_version(
     version_id: &str,
     db: &mut Database,
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_bot_version");
     csml_logger(
         CsmlLog::new(
             None,
@@ -308,6 +314,7 @@ pub fn delete_bot_version(
 }
 
 pub fn delete_bot_versions(bot_id: &str, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_bot_versions");
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call delete bot versions")),
         LogLvl::Info,
@@ -350,6 +357,7 @@ pub fn delete_bot_versions(bot_id: &str, db: &mut Database) -> Result<(), Engine
 }
 
 pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_all_bot_data");
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call delete all bot data")),
         LogLvl::Info,
@@ -375,6 +383,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         // mongodb_connector::bot::delete_all_bot_data(bot_id, "interaction", db)?;
         mongodb_connector::bot::delete_all_bot_data(bot_id, "conversation", db)?;
         mongodb_connector::bot::delete_all_bot_data(bot_id, "state", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "schedule", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "outbox", db)?;
+        mongodb_connector::bot::delete_all_bot_data(bot_id, "lock", db)?;
         mongodb_connector::bot::delete_all_bot_data(bot_id, "path", db)?;
 
         return Ok(());
@@ -391,6 +402,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         // dynamodb_connector::bot::delete_all_bot_data(bot_id, "interaction", db)?;
         dynamodb_connector::bot::delete_all_bot_data(bot_id, "conversation", db)?;
         dynamodb_connector::bot::delete_all_bot_data(bot_id, "state", db)?;
+        dynamodb_connector::bot::delete_all_bot_data(bot_id, "schedule", db)?;
+        dynamodb_connector::bot::delete_all_bot_data(bot_id, "outbox", db)?;
+        dynamodb_connector::bot::delete_all_bot_data(bot_id, "lock", db)?;
         return Ok(());
     }
 
@@ -403,6 +417,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         postgresql_connector::conversations::delete_all_bot_data(bot_id, db)?;
         postgresql_connector::memories::delete_all_bot_data(bot_id, db)?;
         postgresql_connector::state::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::schedules::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::outbox::delete_all_bot_data(bot_id, db)?;
+        postgresql_connector::locks::delete_all_bot_data(bot_id, db)?;
         return Ok(());
     }
 
@@ -415,6 +432,9 @@ pub fn delete_all_bot_data(bot_id: &str, db: &mut Database) -> Result<(), Engine
         sqlite_connector::conversations::delete_all_bot_data(bot_id, db)?;
         sqlite_connector::memories::delete_all_bot_data(bot_id, db)?;
         sqlite_connector::state::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::schedules::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::outbox::delete_all_bot_data(bot_id, db)?;
+        sqlite_connector::locks::delete_all_bot_data(bot_id, db)?;
         return Ok(());
     }
 
@@ -8,10 +8,12 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Database, EngineError};
 
 pub fn delete_expired_data(_db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_expired_data");
 
     #[cfg(feature = "mongo")]
     if is_mongodb() {
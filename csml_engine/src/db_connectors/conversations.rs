@@ -10,6 +10,7 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
 use crate::db_connectors::{state, utils::*};
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Client, ConversationInfo, Database, DbConversation, EngineError};
 
@@ -20,6 +21,7 @@ pub fn create_conversation(
     ttl: Option<chrono::Duration>,
     db: &mut Database,
 ) -> Result<String, EngineError> {
+    let _db_call = db_call("create_conversation");
     csml_logger(
         CsmlLog::new(
             None,
@@ -86,6 +88,7 @@ pub fn create_conversation(
 }
 
 pub fn close_conversation(id: &str, client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("close_conversation");
     csml_logger(
         CsmlLog::new(
             None,
@@ -136,6 +139,7 @@ pub fn close_conversation(id: &str, client: &Client, db: &mut Database) -> Resul
 }
 
 pub fn close_all_conversations(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("close_all_conversations");
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call close all conversations")),
         LogLvl::Info,
@@ -181,6 +185,7 @@ pub fn get_latest_open(
     client: &Client,
     db: &mut Database,
 ) -> Result<Option<DbConversation>, EngineError> {
+    let _db_call = db_call("get_latest_open");
     csml_logger(
         CsmlLog::new(
             None,
@@ -227,11 +232,153 @@ pub fn get_latest_open(
     Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
 }
 
+/**
+ * Move a conversation from one status to another, for instance from OPEN to HANDOFF
+ * when a human agent takes over the conversation.
+ * The conversation is only changed if it is still in from_status, returns false otherwise.
+ */
+pub fn set_conversation_status(
+    id: &str,
+    client: &Client,
+    from_status: &str,
+    status: &str,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let _db_call = db_call("set_conversation_status");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call set conversation status conversation_id: {}, status: {}",
+                id, status
+            ),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&client),
+            None,
+            None,
+            format!(
+                "db call set conversation status conversation_id: {}, from: {}, to: {}",
+                id, from_status, status
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::set_conversation_status(
+            id,
+            client,
+            from_status,
+            status,
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::set_conversation_status(
+            id,
+            client,
+            from_status,
+            status,
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::set_conversation_status(
+            id,
+            client,
+            from_status,
+            status,
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::set_conversation_status(
+            id,
+            client,
+            from_status,
+            status,
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn get_latest_by_status(
+    client: &Client,
+    status: &str,
+    db: &mut Database,
+) -> Result<Option<DbConversation>, EngineError> {
+    let _db_call = db_call("get_latest_by_status");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get latest conversation with status {}", status),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(&client),
+            None,
+            None,
+            format!("db call get latest conversation with status {}", status),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::conversations::get_latest_by_status(client, status, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::conversations::get_latest_by_status(client, status, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::conversations::get_latest_by_status(client, status, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::conversations::get_latest_by_status(client, status, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
 pub fn update_conversation(
     data: &mut ConversationInfo,
     flow_id: Option<String>,
     step_id: Option<String>,
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("update_conversation");
     csml_logger(
         CsmlLog::new(
             None,
@@ -312,6 +459,7 @@ pub fn get_client_conversations(
     limit: Option<i64>,
     pagination_key: Option<String>,
 ) -> Result<serde_json::Value, EngineError> {
+    let _db_call = db_call("get_client_conversations");
     csml_logger(
         CsmlLog::new(
             None,
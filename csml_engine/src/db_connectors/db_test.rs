@@ -62,6 +62,12 @@ mod tests {
             messages,
             ttl: None,
             low_data: false,
+            renderer: None,
+            stream: None,
+            unit_of_work: unit_of_work::UnitOfWork::new(),
+            handoff_events: vec![],
+            outbox_position: None,
+            outbox_blocked: false,
             db,
         }
     }
@@ -149,6 +155,166 @@ mod tests {
         assert_eq!(0, received_msgs.len());
     }
 
+    #[test]
+    fn ok_message_steps() {
+        make_migrations().unwrap_or({});
+
+        let client = Client {
+            user_id: "alexis".to_owned(),
+            bot_id: "analytics-botid".to_owned(),
+            channel_id: "some-channel-id".to_owned(),
+        };
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        data.client = client.clone();
+
+        messages::add_messages_bulk(
+            &mut data,
+            vec![gen_message("1"), gen_message("2")],
+            0,
+            "SEND",
+        )
+        .unwrap();
+
+        let from_date = chrono::Utc::now() - chrono::Duration::hours(1);
+        let to_date = chrono::Utc::now() + chrono::Duration::hours(1);
+
+        let steps = analytics::get_bot_message_steps(
+            &client.bot_id,
+            Some(&client.channel_id),
+            from_date,
+            to_date,
+            10,
+            &mut data.db,
+        )
+        .unwrap();
+
+        assert_eq!(2, steps.len());
+        assert_eq!(c_id, steps[0].conversation_id);
+        assert_eq!("Default", steps[0].flow_id);
+        assert_eq!("start", steps[0].step_id);
+
+        let steps = analytics::get_bot_message_steps(
+            &client.bot_id,
+            Some(&client.channel_id),
+            from_date,
+            to_date,
+            1,
+            &mut data.db,
+        )
+        .unwrap();
+        assert_eq!(1, steps.len());
+
+        let steps = analytics::get_bot_message_steps(
+            &client.bot_id,
+            Some(&client.channel_id),
+            from_date - chrono::Duration::hours(1),
+            from_date,
+            10,
+            &mut data.db,
+        )
+        .unwrap();
+        assert_eq!(0, steps.len());
+
+        let steps = analytics::get_bot_message_steps(
+            &client.bot_id,
+            Some("other"),
+            from_date,
+            to_date,
+            10,
+            &mut data.db,
+        )
+        .unwrap();
+        assert_eq!(0, steps.len());
+
+        user::delete_client(&client, &mut data.db).unwrap();
+    }
+
+    #[test]
+    fn ok_export_client_data() {
+        make_migrations().unwrap_or({});
+
+        let client = Client {
+            user_id: "alexis".to_owned(),
+            bot_id: "export-botid".to_owned(),
+            channel_id: "some-channel-id".to_owned(),
+        };
+        let mut db = init_db().unwrap();
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        memories::create_client_memory(
+            &client,
+            "key".to_owned(),
+            serde_json::json!("value"),
+            None,
+            &mut db,
+        )
+        .unwrap();
+        state::set_state_items(
+            &client,
+            "hold",
+            vec![("position", &serde_json::json!({"index": 1}))],
+            None,
+            &mut db,
+        )
+        .unwrap();
+
+        let mut data = get_conversation_info(vec![], c_id.clone(), db);
+        data.client = client.clone();
+
+        messages::add_messages_bulk(&mut data, vec![gen_message("1")], 0, "SEND").unwrap();
+
+        let next_run_at = chrono::Utc::now() + chrono::Duration::hours(1);
+        let schedule_id = schedules::create_schedule(
+            &client,
+            None,
+            &serde_json::json!({"flow_id": "Default"}),
+            next_run_at,
+            &mut data.db,
+        )
+        .unwrap();
+        for (position, status) in [(1, outbox::OUTBOX_DEAD), (2, outbox::OUTBOX_PENDING)] {
+            outbox::create_outbox_message(
+                &client,
+                &c_id,
+                "http://localhost/callback",
+                &gen_message(&position.to_string()),
+                status,
+                None,
+                position,
+                &mut data.db,
+            )
+            .unwrap();
+        }
+
+        let export = user::export_client_data(&client, &mut data.db).unwrap();
+
+        assert_eq!(export["client"]["bot_id"], "export-botid");
+        assert_eq!(export["conversations"][0]["id"], c_id);
+        assert_eq!(export["messages"][0]["payload"]["content"]["text"], "1");
+        assert_eq!(export["memories"][0]["value"], "value");
+        assert_eq!(export["state"][0]["value"]["index"], 1);
+        assert_eq!(export["schedules"][0]["id"], schedule_id);
+        assert_eq!(export["schedules"][0]["job"]["flow_id"], "Default");
+        assert_eq!(export["outbox"][0]["payload"]["content"]["text"], "2");
+        assert_eq!(export["dead_letters"][0]["payload"]["content"]["text"], "1");
+
+        user::delete_client(&client, &mut data.db).unwrap();
+
+        let export = user::export_client_data(&client, &mut data.db).unwrap();
+        assert_eq!(export["messages"].as_array().unwrap().len(), 0);
+        assert_eq!(export["schedules"].as_array().unwrap().len(), 0);
+        assert_eq!(export["outbox"].as_array().unwrap().len(), 0);
+        assert_eq!(export["dead_letters"].as_array().unwrap().len(), 0);
+    }
+
     #[test]
     fn ok_conversation() {
         make_migrations().unwrap_or({});
@@ -323,4 +489,318 @@ mod tests {
             value => panic!("bad format => {:?}", value),
         }
     }
+
+    #[test]
+    fn ok_unit_of_work() {
+        make_migrations().unwrap_or({});
+
+        let client = get_client();
+        let mut db = init_db().unwrap();
+
+        user::delete_client(&client, &mut db).unwrap();
+
+        let c_id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+
+        let mut mems = HashMap::new();
+        let values = vec![
+            ("key", serde_json::json!("value")),
+            ("tmp", serde_json::json!(42)),
+        ];
+        for (key, value) in values {
+            mems.insert(
+                key.to_owned(),
+                csml_interpreter::data::Memory {
+                    key: key.to_owned(),
+                    value,
+                },
+            );
+        }
+        let position = serde_json::json!({"index": 1});
+
+        let mut unit_of_work = unit_of_work::UnitOfWork::new();
+        unit_of_work.add_messages(
+            &client,
+            &c_id,
+            &get_context(),
+            vec![gen_message("1"), gen_message("2")],
+            0,
+            "SEND",
+            None,
+        );
+        unit_of_work.add_memories(&client, &mems, None);
+        unit_of_work.delete_memory(&client, "tmp");
+        unit_of_work.set_state_items(&client, "hold", vec![("position", &position)], None);
+        unit_of_work.update_conversation(&client, &c_id, None, Some("next".to_owned()));
+
+        // nothing is saved before the commit
+        let response = memories::internal_use_get_memories(&client, &mut db).unwrap();
+        assert_eq!(response.as_object().unwrap().len(), 0);
+
+        unit_of_work::commit(unit_of_work, &mut db).unwrap();
+
+        let response =
+            messages::get_client_messages(&client, &mut db, Some(10), None, None, None).unwrap();
+        assert_eq!(response["messages"].as_array().unwrap().len(), 2);
+
+        let response = memories::internal_use_get_memories(&client, &mut db).unwrap();
+        let memories = response.as_object().unwrap();
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories.get("key").unwrap(), &serde_json::json!("value"));
+
+        let hold = state::get_state_key(&client, "hold", "position", &mut db).unwrap();
+        assert_eq!(hold, Some(position));
+
+        let conversation = conversations::get_latest_open(&client, &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(conversation.step_id, "next");
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_rate_limits() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let scope = format!("test#{}", uuid::Uuid::new_v4());
+        let expires_at = chrono::Utc::now() + chrono::Duration::minutes(1);
+
+        assert_eq!(rate_limits::get_counter(&scope, "key", &mut db).unwrap(), None);
+
+        // only the first concurrent creation succeeds
+        assert!(rate_limits::set_counter(&scope, "key", None, 1, expires_at, &mut db).unwrap());
+        assert!(!rate_limits::set_counter(&scope, "key", None, 1, expires_at, &mut db).unwrap());
+
+        // the counter is only set if it was not changed in the meantime
+        assert!(rate_limits::set_counter(&scope, "key", Some(1), 2, expires_at, &mut db).unwrap());
+        assert!(!rate_limits::set_counter(&scope, "key", Some(1), 3, expires_at, &mut db).unwrap());
+        assert_eq!(rate_limits::get_counter(&scope, "key", &mut db).unwrap(), Some(2));
+
+        for count in 1..4 {
+            let value =
+                rate_limits::increment_counter(&scope, "other", 1, expires_at, &mut db).unwrap();
+            assert_eq!(value, count);
+        }
+
+        let value =
+            rate_limits::increment_counter(&scope, "other", -1, expires_at, &mut db).unwrap();
+        assert_eq!(value, 2);
+
+        let mut counters = rate_limits::get_counters(&scope, &mut db).unwrap();
+        counters.sort();
+        assert_eq!(counters, vec![("key".to_owned(), 2), ("other".to_owned(), 2)]);
+    }
+
+    #[test]
+    fn ok_locks() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let client = Client {
+            user_id: uuid::Uuid::new_v4().to_string(),
+            bot_id: "botid".to_owned(),
+            channel_id: "lock-channel".to_owned(),
+        };
+        let now = chrono::Utc::now();
+        let later = now + chrono::Duration::minutes(1);
+
+        // free lease
+        assert!(locks::acquire_lease(&client, "owner_1", later, &mut db).unwrap());
+
+        // held by another owner
+        assert!(!locks::acquire_lease(&client, "owner_2", later, &mut db).unwrap());
+
+        // re-entrant, the lease of the same owner is renewed
+        assert!(locks::acquire_lease(&client, "owner_1", now, &mut db).unwrap());
+
+        // expired, another owner takes it
+        std::thread::sleep(std::time::Duration::from_millis(1100));
+        assert!(locks::acquire_lease(&client, "owner_2", later, &mut db).unwrap());
+        assert!(!locks::acquire_lease(&client, "owner_1", later, &mut db).unwrap());
+
+        // only the owner releases the lease
+        locks::release_lease(&client, "owner_1", &mut db).unwrap();
+        assert!(!locks::acquire_lease(&client, "owner_3", later, &mut db).unwrap());
+
+        locks::release_lease(&client, "owner_2", &mut db).unwrap();
+        assert!(locks::acquire_lease(&client, "owner_3", later, &mut db).unwrap());
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
+
+    #[test]
+    fn ok_schedules() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let client = Client {
+            user_id: uuid::Uuid::new_v4().to_string(),
+            bot_id: "botid".to_owned(),
+            channel_id: "schedules-channel".to_owned(),
+        };
+        let job = serde_json::json!({"flow_id": "Default", "step_id": "start"});
+
+        let now = chrono::Utc::now();
+        let due = now - chrono::Duration::minutes(1);
+        let later = now + chrono::Duration::hours(1);
+
+        let recurring_id =
+            schedules::create_schedule(&client, Some("0 9 * * *"), &job, due, &mut db).unwrap();
+        let single_id = schedules::create_schedule(&client, None, &job, due, &mut db).unwrap();
+        let future_id = schedules::create_schedule(&client, None, &job, later, &mut db).unwrap();
+
+        let client_schedules = schedules::get_client_schedules(&client, &mut db).unwrap();
+        assert_eq!(client_schedules.len(), 3);
+        assert_eq!(client_schedules[0].job, job);
+
+        let due_ids: Vec<String> = schedules::get_due_schedules(now, &mut db)
+            .unwrap()
+            .into_iter()
+            .map(|schedule| schedule.id)
+            .collect();
+        assert!(due_ids.contains(&recurring_id));
+        assert!(due_ids.contains(&single_id));
+        assert!(!due_ids.contains(&future_id));
+
+        // a due schedule is claimed only once
+        assert!(
+            schedules::claim_schedule(&client, &recurring_id, now, Some(later), &mut db).unwrap()
+        );
+        assert!(
+            !schedules::claim_schedule(&client, &recurring_id, now, Some(later), &mut db).unwrap()
+        );
+        assert!(schedules::claim_schedule(&client, &single_id, now, None, &mut db).unwrap());
+        assert!(!schedules::claim_schedule(&client, &single_id, now, None, &mut db).unwrap());
+        // not due yet
+        assert!(!schedules::claim_schedule(&client, &future_id, now, None, &mut db).unwrap());
+
+        let client_schedules = schedules::get_client_schedules(&client, &mut db).unwrap();
+        assert_eq!(client_schedules.len(), 2);
+
+        assert!(schedules::delete_schedule(&client, &future_id, &mut db).unwrap());
+        assert!(!schedules::delete_schedule(&client, &future_id, &mut db).unwrap());
+        assert!(!schedules::delete_schedule(&client, "unknown-id", &mut db).unwrap());
+
+        user::delete_client(&client, &mut db).unwrap();
+        let client_schedules = schedules::get_client_schedules(&client, &mut db).unwrap();
+        assert_eq!(client_schedules.len(), 0);
+    }
+
+    #[test]
+    fn ok_outbox() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let client = Client {
+            user_id: uuid::Uuid::new_v4().to_string(),
+            bot_id: "botid".to_owned(),
+            channel_id: "outbox-channel".to_owned(),
+        };
+        let conversation_id = uuid::Uuid::new_v4().to_string();
+        let callback_url = "http://localhost/callback";
+
+        let position = outbox::get_last_outbox_position(&client, &conversation_id, &mut db);
+        assert_eq!(position.unwrap(), 0);
+
+        let dead_id = outbox::create_outbox_message(
+            &client,
+            &conversation_id,
+            callback_url,
+            &gen_message("1"),
+            outbox::OUTBOX_DEAD,
+            Some("callback_url responded with status 500"),
+            1,
+            &mut db,
+        )
+        .unwrap();
+        let pending_id = outbox::create_outbox_message(
+            &client,
+            &conversation_id,
+            callback_url,
+            &gen_message("2"),
+            outbox::OUTBOX_PENDING,
+            None,
+            2,
+            &mut db,
+        )
+        .unwrap();
+
+        // positions are numbered per conversation
+        let position = outbox::get_last_outbox_position(&client, &conversation_id, &mut db);
+        assert_eq!(position.unwrap(), 2);
+        let position = outbox::get_last_outbox_position(&client, "other-conversation", &mut db);
+        assert_eq!(position.unwrap(), 0);
+
+        let messages: Vec<DbOutboxMessage> = outbox::get_outbox_messages(&mut db)
+            .unwrap()
+            .into_iter()
+            .filter(|message| message.conversation_id == conversation_id)
+            .collect();
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id, dead_id);
+        assert_eq!(messages[0].status, outbox::OUTBOX_DEAD);
+        assert_eq!(messages[1].id, pending_id);
+        assert_eq!(messages[1].payload, gen_message("2"));
+
+        let now = chrono::Utc::now();
+        let lease_until = now + chrono::Duration::minutes(1);
+
+        // only pending messages are claimed, and only once until the lease expires
+        let claim = outbox::claim_outbox_message(&client, &dead_id, now, lease_until, &mut db);
+        assert!(!claim.unwrap());
+        let claim = outbox::claim_outbox_message(&client, &pending_id, now, lease_until, &mut db);
+        assert!(claim.unwrap());
+        let claim = outbox::claim_outbox_message(&client, &pending_id, now, lease_until, &mut db);
+        assert!(!claim.unwrap());
+        let later = lease_until + chrono::Duration::minutes(1);
+        let claim = outbox::claim_outbox_message(&client, &pending_id, lease_until, later, &mut db);
+        assert!(claim.unwrap());
+
+        outbox::update_outbox_message(
+            &client,
+            &pending_id,
+            outbox::OUTBOX_DEAD,
+            3,
+            Some("callback_url responded with status 500"),
+            now,
+            &mut db,
+        )
+        .unwrap();
+
+        let dead_letters =
+            outbox::get_client_outbox_messages(&client, outbox::OUTBOX_DEAD, &mut db).unwrap();
+        assert_eq!(dead_letters.len(), 2);
+        assert_eq!(dead_letters[1].attempts, 3);
+
+        outbox::delete_outbox_message(&client, &dead_id, &mut db).unwrap();
+        let position = outbox::get_last_outbox_position(&client, &conversation_id, &mut db);
+        assert_eq!(position.unwrap(), 2);
+
+        user::delete_client(&client, &mut db).unwrap();
+        let position = outbox::get_last_outbox_position(&client, &conversation_id, &mut db);
+        assert_eq!(position.unwrap(), 0);
+    }
+
+    #[test]
+    fn ok_api_keys() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let key_hash = uuid::Uuid::new_v4().to_string();
+        let bot_ids = Some(vec!["bot_id".to_owned()]);
+        let scopes = vec!["run".to_owned(), "read-data".to_owned()];
+
+        assert_eq!(api_keys::get_api_key(&key_hash, &mut db).unwrap(), None);
+
+        api_keys::create_api_key(&key_hash, &bot_ids, &scopes, &mut db).unwrap();
+
+        let api_key = api_keys::get_api_key(&key_hash, &mut db).unwrap().unwrap();
+        assert_eq!(api_key.bot_ids, bot_ids);
+        assert_eq!(api_key.scopes, scopes);
+
+        api_keys::delete_api_key(&key_hash, &mut db).unwrap();
+        assert_eq!(api_keys::get_api_key(&key_hash, &mut db).unwrap(), None);
+    }
 }
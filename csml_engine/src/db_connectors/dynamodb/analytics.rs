@@ -0,0 +1,173 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::DbMessageStep;
+use crate::EngineError;
+use chrono::{DateTime, SecondsFormat, Utc};
+use rusoto_dynamodb::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+// attributes of the messages needed by the analytics, without their payloads
+#[derive(Deserialize, Debug)]
+struct MessageStep {
+    conversation_id: String,
+    flow_id: String,
+    step_id: String,
+    interaction_order: i32,
+    message_order: i32,
+    created_at: String,
+}
+
+fn format_message_steps(
+    items: Option<Vec<HashMap<String, AttributeValue>>>,
+    messages: &mut Vec<DbMessageStep>,
+) -> Result<(), EngineError> {
+    for item in items.unwrap_or_default() {
+        let message: MessageStep = serde_dynamodb::from_hashmap(item)?;
+
+        let created_at = match DateTime::parse_from_rfc3339(&message.created_at) {
+            Ok(created_at) => created_at.timestamp_millis(),
+            Err(_) => {
+                return Err(EngineError::Format(format!(
+                    "invalid message date: {}",
+                    message.created_at
+                )))
+            }
+        };
+
+        messages.push(DbMessageStep {
+            conversation_id: message.conversation_id,
+            flow_id: message.flow_id,
+            step_id: message.step_id,
+            interaction_order: message.interaction_order,
+            message_order: message.message_order,
+            created_at,
+        });
+    }
+
+    Ok(())
+}
+
+/**
+ * Query the messages of a client created between from_date and to_date with the TimeIndex,
+ * whose range key starts with the creation date of the messages
+ */
+fn query_message_steps(
+    hash: &str,
+    from_date: &str,
+    to_date: &str,
+    limit: i64,
+    messages: &mut Vec<DbMessageStep>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeTimeKey"), String::from("range_time")),
+        (String::from("#createdAt"), String::from("created_at")),
+        (
+            String::from("#conversationId"),
+            String::from("conversation_id"),
+        ),
+        (String::from("#flowId"), String::from("flow_id")),
+        (String::from("#stepId"), String::from("step_id")),
+        (
+            String::from("#interactionOrder"),
+            String::from("interaction_order"),
+        ),
+        (String::from("#messageOrder"), String::from("message_order")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    // range_time = message#created_at#interaction_order#message_order#id,
+    // '~' sorts after the '#' following the date so the messages of to_date are included
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(hash.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":fromDate"),
+            AttributeValue {
+                s: Some(make_range(&["message", from_date])),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":toDate"),
+            AttributeValue {
+                s: Some(format!("{}~", make_range(&["message", to_date]))),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            index_name: Some("TimeIndex".to_owned()),
+            key_condition_expression: Some(
+                "#hashKey = :hashVal AND #rangeTimeKey BETWEEN :fromDate AND :toDate".to_owned(),
+            ),
+            projection_expression: Some(
+                "#conversationId, #flowId, #stepId, #interactionOrder, #messageOrder, #createdAt"
+                    .to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            limit: Some(limit - messages.len() as i64),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = db.runtime.block_on(future)?;
+
+        format_message_steps(data.items, messages)?;
+
+        pagination_key = data.last_evaluated_key;
+        if pagination_key.is_none() || messages.len() as i64 >= limit {
+            return Ok(());
+        }
+    }
+}
+
+pub fn get_bot_message_steps(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: DateTime<Utc>,
+    to_date: DateTime<Utc>,
+    limit: i64,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbMessageStep>, EngineError> {
+    let from_date = from_date.to_rfc3339_opts(SecondsFormat::Millis, true);
+    let to_date = to_date.to_rfc3339_opts(SecondsFormat::Millis, true);
+
+    // the clients of the bot are found from the keys of their conversations
+    let hash_prefix = match channel_id {
+        Some(channel_id) => format!("bot_id:{}#channel_id:{}#", bot_id, channel_id),
+        None => format!("bot_id:{}#", bot_id),
+    };
+
+    let mut messages = vec![];
+
+    for hash in get_class_hashes("conversation", Some(&hash_prefix), db)? {
+        query_message_steps(&hash, &from_date, &to_date, limit, &mut messages, db)?;
+
+        if messages.len() as i64 >= limit {
+            break;
+        }
+    }
+
+    Ok(messages)
+}
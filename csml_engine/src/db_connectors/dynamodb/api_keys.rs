@@ -0,0 +1,65 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::{dynamodb::ApiKey, DbApiKey};
+use crate::EngineError;
+use rusoto_dynamodb::*;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+pub fn create_api_key(
+    key_hash: &str,
+    bot_ids: &Option<Vec<String>>,
+    scopes: &[String],
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&ApiKey::new(key_hash, bot_ids, scopes))?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+pub fn get_api_key(
+    key_hash: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Option<DbApiKey>, EngineError> {
+    let input = GetItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&ApiKey::get_key(key_hash))?,
+        ..Default::default()
+    };
+
+    let future = db.client.get_item(input);
+    let res = db.runtime.block_on(future)?;
+
+    match res.item {
+        Some(item) => {
+            let api_key: ApiKey = serde_dynamodb::from_hashmap(item)?;
+
+            Ok(Some(DbApiKey {
+                key_hash: api_key.key_hash,
+                bot_ids: api_key.bot_ids,
+                scopes: api_key.scopes,
+                created_at: api_key.created_at,
+            }))
+        }
+        None => Ok(None),
+    }
+}
+
+pub fn delete_api_key(key_hash: &str, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&ApiKey::get_key(key_hash))?,
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
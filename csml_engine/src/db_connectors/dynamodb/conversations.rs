@@ -29,21 +29,55 @@ pub fn create_conversation(
     Ok(data.id.to_owned())
 }
 
+pub fn close_conversation(
+    id: &str,
+    client: &Client,
+    status: &str,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    // the conversation to close must still be open at this stage
+    set_conversation_status(id, client, "OPEN", status, db)?;
+
+    Ok(())
+}
+
 /**
  * Retrieve a conversation then set its status to the requested value.
  * For simplicity's sake, we first retrieve the item then must rewrite it
  * entirely. This is not great but necessary because STATUS is embedded
  * in range key (ideally, we would use a secondary index instead).
  */
-pub fn close_conversation(
+pub fn set_conversation_status(
     id: &str,
     client: &Client,
+    from_status: &str,
     status: &str,
     db: &mut DynamoDbClient,
-) -> Result<(), EngineError> {
-    // retrieve the old conversation, which at this stage must still be open
+) -> Result<bool, EngineError> {
+    // If no conversation matches the request, we assume its status already changed and move on
+    let (old_key, new_conv) = match format_status_change(id, client, from_status, status, db)? {
+        Some(change) => change,
+        None => return Ok(false),
+    };
+
+    let new_item = serde_dynamodb::to_hashmap(&new_conv)?;
+
+    replace_conversation(&old_key, new_item, db)
+}
+
+/**
+ * Key of the conversation in its current status, and the conversation with its new status
+ */
+pub fn format_status_change(
+    id: &str,
+    client: &Client,
+    from_status: &str,
+    status: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Option<(DynamoDbKey, Conversation)>, EngineError> {
+    // retrieve the old conversation with its current status
     let hash = Conversation::get_hash(client);
-    let range = Conversation::get_range("OPEN", id);
+    let range = Conversation::get_range(from_status, id);
     let old_key = DynamoDbKey::new(&hash, &range);
 
     // get conv with ID
@@ -57,9 +91,8 @@ pub fn close_conversation(
 
     let res = db.runtime.block_on(future)?;
 
-    // If no conversation matches the request, we assume it's already closed and move on
     let item = match res.item {
-        None => return Ok(()),
+        None => return Ok(None),
         Some(data) => data,
     };
 
@@ -70,34 +103,39 @@ pub fn close_conversation(
     new_conv.status = status.to_owned();
     new_conv.last_interaction_at = now.to_owned();
     new_conv.updated_at = now.to_owned();
-    new_conv.range_time = make_range(&["interaction", "CLOSED", &now, &id]);
-    new_conv.range = Conversation::get_range("CLOSED", &id);
-
-    let new_item = serde_dynamodb::to_hashmap(&new_conv)?;
+    new_conv.range_time = make_range(&["conversation", status, &now, &id]);
+    new_conv.range = Conversation::get_range(status, &id);
 
-    replace_conversation(&old_key, new_item, db)?;
-
-    Ok(())
+    Ok(Some((old_key, new_conv)))
 }
 
 /**
  * To close a conversation, we must replace the given conversation,
  * ideally in a transaction to make sure that we don't lose a conversation in the process.
+ * The old conversation must still exist, returns false if it was replaced in the meantime.
  */
 fn replace_conversation(
     old_key: &DynamoDbKey,
     new_item: HashMap<String, AttributeValue>,
     db: &mut DynamoDbClient,
-) -> Result<(), EngineError> {
+) -> Result<bool, EngineError> {
     let put = Put {
         table_name: get_table_name()?,
         item: new_item,
         ..Default::default()
     };
 
+    let expr_attr_names: HashMap<String, String> =
+        [(String::from("#hashKey"), String::from("hash"))]
+            .iter()
+            .cloned()
+            .collect();
+
     let del = Delete {
         table_name: get_table_name()?,
         key: serde_dynamodb::to_hashmap(old_key.to_owned())?,
+        condition_expression: Some("attribute_exists(#hashKey)".to_owned()),
+        expression_attribute_names: Some(expr_attr_names),
         ..Default::default()
     };
 
@@ -116,9 +154,13 @@ fn replace_conversation(
     };
 
     let future = db.client.transact_write_items(input);
-    db.runtime.block_on(future)?;
-
-    Ok(())
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(rusoto_core::RusotoError::Service(TransactWriteItemsError::TransactionCanceled(
+            _,
+        ))) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
 }
 
 fn get_all_open_conversations(
@@ -256,6 +298,14 @@ pub fn close_all_conversations(
 pub fn get_latest_open(
     client: &Client,
     db: &mut DynamoDbClient,
+) -> Result<Option<DbConversation>, EngineError> {
+    get_latest_by_status(client, "OPEN", db)
+}
+
+pub fn get_latest_by_status(
+    client: &Client,
+    status: &str,
+    db: &mut DynamoDbClient,
 ) -> Result<Option<DbConversation>, EngineError> {
     let hash = Conversation::get_hash(client);
 
@@ -279,7 +329,7 @@ pub fn get_latest_open(
         (
             String::from(":rangePrefix"),
             AttributeValue {
-                s: Some(String::from("conversation#OPEN")),
+                s: Some(make_range(&["conversation", status])),
                 ..Default::default()
             },
         ),
@@ -302,7 +352,7 @@ pub fn get_latest_open(
     let query = db.client.query(input);
     let data = match db.runtime.block_on(query) {
         Ok(data) => data,
-        Err(e) => return Err(EngineError::Manager(format!("get_latest_open {:?}", e))),
+        Err(e) => return Err(EngineError::Manager(format!("get_latest_by_status {:?}", e))),
     };
 
     // The query returns an array of items (max 1, based on the limit param above).
@@ -347,6 +397,31 @@ pub fn update_conversation(
     step_id: Option<String>,
     db: &mut DynamoDbClient,
 ) -> Result<(), EngineError> {
+    let update = format_conversation_update(conversation_id, client, flow_id, step_id)?;
+
+    let input = UpdateItemInput {
+        table_name: update.table_name,
+        key: update.key,
+        condition_expression: update.condition_expression,
+        update_expression: Some(update.update_expression),
+        expression_attribute_names: update.expression_attribute_names,
+        expression_attribute_values: update.expression_attribute_values,
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(()),
+        Err(e) => return Err(EngineError::Manager(format!("update_conversation {:?}", e))),
+    }
+}
+
+pub fn format_conversation_update(
+    conversation_id: &str,
+    client: &Client,
+    flow_id: Option<String>,
+    step_id: Option<String>,
+) -> Result<Update, EngineError> {
     let hash = Conversation::get_hash(client);
     let range = Conversation::get_range("OPEN", conversation_id);
 
@@ -415,21 +490,15 @@ pub fn update_conversation(
         );
     }
 
-    let input = UpdateItemInput {
+    Ok(Update {
         table_name: get_table_name()?,
         key: serde_dynamodb::to_hashmap(&DynamoDbKey::new(&hash, &range))?,
         condition_expression: Some(condition_expr),
-        update_expression: Some(update_expr.to_string()),
+        update_expression: update_expr.to_string(),
         expression_attribute_names: Some(expr_attr_names),
         expression_attribute_values: Some(expr_attr_values),
         ..Default::default()
-    };
-
-    let future = db.client.update_item(input);
-    match db.runtime.block_on(future) {
-        Ok(_) => Ok(()),
-        Err(e) => return Err(EngineError::Manager(format!("update_conversation {:?}", e))),
-    }
+    })
 }
 
 fn query_conversation(
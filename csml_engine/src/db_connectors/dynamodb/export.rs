@@ -0,0 +1,154 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::{Conversation, Message, State};
+use crate::{encrypt::decrypt_data, Client, EngineError};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+/**
+ * All the items of a client whose range starts with range_prefix (ex: message#)
+ */
+fn query_client_items(
+    client: &Client,
+    range_prefix: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<HashMap<String, AttributeValue>>, EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(make_hash(client)),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":rangePrefix"),
+            AttributeValue {
+                s: Some(range_prefix.to_owned()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut items = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(
+                "#hashKey = :hashVal and begins_with(#rangeKey, :rangePrefix)".to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = match db.runtime.block_on(future) {
+            Ok(data) => data,
+            Err(e) => return Err(EngineError::Manager(format!("query_client_items {:?}", e))),
+        };
+
+        items.append(&mut data.items.unwrap_or_default());
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(items);
+        }
+    }
+}
+
+pub fn export_conversations(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut conversations = vec![];
+    for item in query_client_items(client, "conversation#", db)? {
+        let conversation: Conversation = serde_dynamodb::from_hashmap(item)?;
+        conversations.push(conversation);
+    }
+    conversations.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+    Ok(conversations
+        .into_iter()
+        .map(|conversation| {
+            serde_json::json!({
+                "id": conversation.id,
+                "flow_id": conversation.flow_id,
+                "step_id": conversation.step_id,
+                "status": conversation.status,
+                "last_interaction_at": conversation.last_interaction_at,
+                "updated_at": conversation.updated_at,
+                "created_at": conversation.created_at,
+            })
+        })
+        .collect())
+}
+
+pub fn export_messages(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut messages = vec![];
+    for item in query_client_items(client, "message#", db)? {
+        let message: Message = serde_dynamodb::from_hashmap(item)?;
+        messages.push(message);
+    }
+    messages.sort_by(|a, b| {
+        (&a.created_at, a.interaction_order, a.message_order).cmp(&(
+            &b.created_at,
+            b.interaction_order,
+            b.message_order,
+        ))
+    });
+
+    let mut msgs = vec![];
+    for message in messages {
+        msgs.push(serde_json::json!({
+            "id": message.id,
+            "conversation_id": message.conversation_id,
+            "flow_id": message.flow_id,
+            "step_id": message.step_id,
+            "direction": message.direction,
+            "interaction_order": message.interaction_order,
+            "message_order": message.message_order,
+            "payload": decrypt_data(message.payload)?,
+            "created_at": message.created_at,
+        }));
+    }
+
+    Ok(msgs)
+}
+
+pub fn export_state(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut items = vec![];
+    for item in query_client_items(client, "state#", db)? {
+        let state: State = serde_dynamodb::from_hashmap(item)?;
+
+        items.push(serde_json::json!({
+            "type": state._type,
+            "key": state.key,
+            "value": decrypt_data(state.value)?,
+            "created_at": state.created_at,
+        }));
+    }
+
+    Ok(items)
+}
@@ -0,0 +1,122 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::Lock;
+use crate::{Client, EngineError};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+/**
+ * Take the lease of the client if it is free, expired or already ours.
+ * Returns false if another owner holds it.
+ */
+pub fn acquire_lease(
+    client: &Client,
+    owner: &str,
+    expires_at: i64,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#owner"), String::from("owner")),
+        (String::from("#expiresAt"), String::from("expires_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":owner"),
+            AttributeValue {
+                s: Some(owner.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":now"),
+            AttributeValue {
+                n: Some(chrono::Utc::now().timestamp().to_string()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&Lock::new(client, owner, expires_at))?,
+        table_name: get_table_name()?,
+        condition_expression: Some(
+            "attribute_not_exists(#hashKey) OR #expiresAt < :now OR #owner = :owner".to_owned(),
+        ),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(rusoto_core::RusotoError::Service(PutItemError::ConditionalCheckFailed(_))) => {
+            Ok(false)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn release_lease(
+    client: &Client,
+    owner: &str,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_names: HashMap<String, String> =
+        [(String::from("#owner"), String::from("owner"))]
+            .iter()
+            .cloned()
+            .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":owner"),
+        AttributeValue {
+            s: Some(owner.to_owned()),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&Lock::get_key(client))?,
+        // the lease may have expired and been taken by another owner
+        condition_expression: Some("#owner = :owner".to_owned()),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(()),
+        Err(rusoto_core::RusotoError::Service(DeleteItemError::ConditionalCheckFailed(_))) => {
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn delete_client_lease(client: &Client, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&Lock::get_key(client))?,
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
@@ -1,14 +1,14 @@
 use crate::data::DynamoDbClient;
-use crate::db_connectors::dynamodb::{get_db, DynamoDbKey, Memory, MemoryDeleteInfo, MemoryKeys};
-use crate::{encrypt::encrypt_data, Client, ConversationInfo, EngineError};
+use crate::db_connectors::dynamodb::{DynamoDbKey, Memory, MemoryDeleteInfo, MemoryKeys};
+use crate::{encrypt::encrypt_data, Client, EngineError};
 use csml_interpreter::data::Memory as InterpreterMemory;
 use rusoto_dynamodb::*;
 use std::collections::HashMap;
 
 use crate::db_connectors::dynamodb::utils::*;
 
-fn format_memories(
-    data: &ConversationInfo,
+pub fn format_memories(
+    client: &Client,
     memories: &HashMap<String, InterpreterMemory>,
     expires_at: Option<i64>,
 ) -> Result<Vec<Memory>, EngineError> {
@@ -16,7 +16,7 @@ fn format_memories(
 
     for (_, mem) in memories.iter() {
         res.push(Memory::new(
-            &data.client,
+            client,
             &mem.key,
             Some(encrypt_data(&mem.value)?),
             expires_at,
@@ -26,50 +26,6 @@ fn format_memories(
     Ok(res)
 }
 
-pub fn add_memories(
-    data: &mut ConversationInfo,
-    memories: &HashMap<String, InterpreterMemory>,
-    expires_at: Option<i64>,
-) -> Result<(), EngineError> {
-    if memories.len() == 0 {
-        return Ok(());
-    }
-
-    let memories = format_memories(data, memories, expires_at)?;
-
-    // We can only use BatchWriteItem on up to 25 items at once,
-    // so we need to split the memories to write into chunks of max
-    // 25 items.
-    for chunk in memories.chunks(25) {
-        let mut request_items = HashMap::new();
-
-        let mut items_to_write = vec![];
-        for data in chunk {
-            items_to_write.push(WriteRequest {
-                put_request: Some(PutRequest {
-                    item: serde_dynamodb::to_hashmap(&data)?,
-                    ..Default::default()
-                }),
-                ..Default::default()
-            });
-        }
-
-        request_items.insert(get_table_name()?, items_to_write);
-
-        let input = BatchWriteItemInput {
-            request_items,
-            ..Default::default()
-        };
-
-        let db = get_db(&mut data.db)?;
-        let future = db.client.batch_write_item(input);
-
-        db.runtime.block_on(future)?;
-    }
-
-    Ok(())
-}
-
 pub fn create_client_memory(
     client: &Client,
     key: String,
@@ -128,7 +84,7 @@ fn query_memories(
     Ok(data)
 }
 
-fn get_all_memories(
+pub fn get_all_memories(
     client: &Client,
     db: &mut DynamoDbClient,
 ) -> Result<Vec<serde_json::Value>, EngineError> {
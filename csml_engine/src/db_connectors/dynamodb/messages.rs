@@ -1,15 +1,18 @@
 use crate::db_connectors::dynamodb::{
-    get_db, DynamoDbClient, DynamoDbKey, Message, MessageFromDateInfo, MessageKeys,
+    DynamoDbClient, DynamoDbKey, Message, MessageFromDateInfo, MessageKeys,
 };
-use crate::{data::EngineError, encrypt::encrypt_data, Client, ConversationInfo};
+use crate::{data::EngineError, encrypt::encrypt_data, Client};
 use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
 use rusoto_dynamodb::*;
 use std::collections::HashMap;
 
 use crate::db_connectors::dynamodb::utils::*;
 
-fn format_messages(
-    data: &ConversationInfo,
+pub fn format_messages(
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     messages: &[serde_json::Value],
     interaction_order: i32,
     direction: &str,
@@ -19,10 +22,10 @@ fn format_messages(
 
     for (i, message) in messages.iter().enumerate() {
         res.push(Message::new(
-            &data.client,
-            &data.conversation_id,
-            &data.context.flow,
-            &data.context.step.get_step(),
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
             direction,
             interaction_order,
             i as i32,
@@ -71,18 +74,30 @@ pub fn write_messages_batch(
 }
 
 pub fn add_messages_bulk(
-    data: &mut ConversationInfo,
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     messages: &[serde_json::Value],
     interaction_order: i32,
     direction: &str,
     expires_at: Option<i64>,
+    db: &mut DynamoDbClient,
 ) -> Result<(), EngineError> {
     if messages.len() == 0 {
         return Ok(());
     }
 
-    let messages = format_messages(data, messages, interaction_order, direction, expires_at)?;
-    let db = get_db(&mut data.db)?;
+    let messages = format_messages(
+        client,
+        conversation_id,
+        flow_id,
+        step_id,
+        messages,
+        interaction_order,
+        direction,
+        expires_at,
+    )?;
 
     write_messages_batch(&messages, db)
 }
@@ -6,11 +6,19 @@ use std::collections::HashMap;
 use uuid::Uuid;
 
 pub mod aws_s3;
+pub mod analytics;
+pub mod api_keys;
 pub mod bot;
 pub mod conversations;
+pub mod export;
+pub mod locks;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod rate_limits;
+pub mod schedules;
 pub mod state;
+pub mod unit_of_work;
 pub mod utils;
 
 use crate::db_connectors::dynamodb::utils::*;
@@ -424,6 +432,294 @@ impl Message {
     }
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Schedule {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub id: String,
+    pub client: Option<Client>,
+    pub bot_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub user_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cron: Option<String>,
+    pub job: String,
+    pub next_run_at: i64,
+    pub updated_at: String,
+    pub created_at: String,
+}
+
+impl Schedule {
+    pub fn get_hash(client: &Client) -> String {
+        make_hash(client)
+    }
+
+    pub fn get_range(id: &str) -> String {
+        make_range(&["schedule", id])
+    }
+
+    pub fn get_key(client: &Client, id: &str) -> DynamoDbKey {
+        let hash = Self::get_hash(client);
+        let range = Self::get_range(id);
+        DynamoDbKey::new(&hash, &range)
+    }
+
+    /**
+     * hash = bot_id:xxxx#channel_id:xxxx#user_id:xxxx
+     * range = schedule#id
+     */
+    pub fn new(
+        client: &Client,
+        cron: Option<&str>,
+        encrypted_job: &str,
+        next_run_at: i64,
+    ) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let now = get_date_time();
+        let class_name = "schedule";
+        Self {
+            hash: Self::get_hash(client),
+            range: Self::get_range(&id),
+            class: class_name.to_owned(),
+            id,
+            client: Some(client.to_owned()),
+            bot_id: Some(client.bot_id.to_owned()),
+            channel_id: Some(client.channel_id.to_owned()),
+            user_id: Some(client.user_id.to_owned()),
+            cron: cron.map(|cron| cron.to_owned()),
+            job: encrypted_job.to_owned(),
+            next_run_at,
+            updated_at: now.to_owned(),
+            created_at: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Lock {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub client: Option<Client>,
+    pub bot_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub user_id: Option<String>,
+    pub owner: String,
+    pub expires_at: i64,
+    pub updated_at: String,
+    pub created_at: String,
+}
+
+impl Lock {
+    pub fn get_hash(client: &Client) -> String {
+        make_hash(client)
+    }
+
+    pub fn get_range() -> String {
+        make_range(&["lock"])
+    }
+
+    pub fn get_key(client: &Client) -> DynamoDbKey {
+        let hash = Self::get_hash(client);
+        let range = Self::get_range();
+        DynamoDbKey::new(&hash, &range)
+    }
+
+    /**
+     * hash = bot_id:xxxx#channel_id:xxxx#user_id:xxxx
+     * range = lock
+     */
+    pub fn new(client: &Client, owner: &str, expires_at: i64) -> Self {
+        let now = get_date_time();
+        let class_name = "lock";
+        Self {
+            hash: Self::get_hash(client),
+            range: Self::get_range(),
+            class: class_name.to_owned(),
+            client: Some(client.to_owned()),
+            bot_id: Some(client.bot_id.to_owned()),
+            channel_id: Some(client.channel_id.to_owned()),
+            user_id: Some(client.user_id.to_owned()),
+            owner: owner.to_owned(),
+            expires_at,
+            updated_at: now.to_owned(),
+            created_at: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RateLimit {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub scope: String,
+    pub name: String,
+    pub value: i64,
+    pub expires_at: i64,
+    pub updated_at: String,
+    pub created_at: String,
+}
+
+impl RateLimit {
+    pub fn get_hash(scope: &str) -> String {
+        make_range(&["rate_limit", scope])
+    }
+
+    pub fn get_range(name: &str) -> String {
+        name.to_owned()
+    }
+
+    pub fn get_key(scope: &str, name: &str) -> DynamoDbKey {
+        let hash = Self::get_hash(scope);
+        let range = Self::get_range(name);
+        DynamoDbKey::new(&hash, &range)
+    }
+
+    /**
+     * hash = rate_limit#scope
+     * range = name
+     */
+    pub fn new(scope: &str, name: &str, value: i64, expires_at: i64) -> Self {
+        let now = get_date_time();
+        let class_name = "rate_limit";
+        Self {
+            hash: Self::get_hash(scope),
+            range: Self::get_range(name),
+            class: class_name.to_owned(),
+            scope: scope.to_owned(),
+            name: name.to_owned(),
+            value,
+            expires_at,
+            updated_at: now.to_owned(),
+            created_at: now,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ApiKey {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub key_hash: String,
+    pub bot_ids: Option<Vec<String>>,
+    pub scopes: Vec<String>,
+    pub created_at: String,
+}
+
+impl ApiKey {
+    pub fn get_hash(key_hash: &str) -> String {
+        make_range(&["api_key", key_hash])
+    }
+
+    pub fn get_range() -> String {
+        make_range(&["api_key"])
+    }
+
+    pub fn get_key(key_hash: &str) -> DynamoDbKey {
+        let hash = Self::get_hash(key_hash);
+        let range = Self::get_range();
+        DynamoDbKey::new(&hash, &range)
+    }
+
+    /**
+     * hash = api_key#key_hash
+     * range = api_key
+     */
+    pub fn new(key_hash: &str, bot_ids: &Option<Vec<String>>, scopes: &[String]) -> Self {
+        let class_name = "api_key";
+        Self {
+            hash: Self::get_hash(key_hash),
+            range: Self::get_range(),
+            class: class_name.to_owned(),
+            key_hash: key_hash.to_owned(),
+            bot_ids: bot_ids.to_owned(),
+            scopes: scopes.to_owned(),
+            created_at: get_date_time(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct OutboxMessage {
+    pub hash: String,
+    pub range: String,
+    pub class: String,
+    pub id: String,
+    pub client: Option<Client>,
+    pub bot_id: Option<String>,
+    pub channel_id: Option<String>,
+    pub user_id: Option<String>,
+    pub conversation_id: String,
+    pub callback_url: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub position: i64,
+    pub next_attempt_at: i64,
+    pub updated_at: String,
+    pub created_at: String,
+}
+
+impl OutboxMessage {
+    pub fn get_hash(client: &Client) -> String {
+        make_hash(client)
+    }
+
+    pub fn get_range(id: &str) -> String {
+        make_range(&["outbox", id])
+    }
+
+    pub fn get_key(client: &Client, id: &str) -> DynamoDbKey {
+        let hash = Self::get_hash(client);
+        let range = Self::get_range(id);
+        DynamoDbKey::new(&hash, &range)
+    }
+
+    /**
+     * hash = bot_id:xxxx#channel_id:xxxx#user_id:xxxx
+     * range = outbox#id
+     */
+    pub fn new(
+        client: &Client,
+        conversation_id: &str,
+        callback_url: &str,
+        encrypted_payload: &str,
+        status: &str,
+        error: Option<&str>,
+        position: i64,
+    ) -> Self {
+        let id = Uuid::new_v4().to_string();
+        let time = chrono::Utc::now();
+        let now = get_date_time();
+        let class_name = "outbox";
+        Self {
+            hash: Self::get_hash(client),
+            range: Self::get_range(&id),
+            class: class_name.to_owned(),
+            id,
+            client: Some(client.to_owned()),
+            bot_id: Some(client.bot_id.to_owned()),
+            channel_id: Some(client.channel_id.to_owned()),
+            user_id: Some(client.user_id.to_owned()),
+            conversation_id: conversation_id.to_owned(),
+            callback_url: callback_url.to_owned(),
+            payload: encrypted_payload.to_owned(),
+            status: status.to_owned(),
+            attempts: 0,
+            error: error.map(|error| error.to_owned()),
+            position,
+            next_attempt_at: time.timestamp(),
+            updated_at: now.to_owned(),
+            created_at: now,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct StatDeleteInfo {
     #[serde(rename = "type")]
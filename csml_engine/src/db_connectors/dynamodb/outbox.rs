@@ -0,0 +1,401 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::OutboxMessage;
+use crate::db_connectors::{
+    outbox::{OUTBOX_DEAD, OUTBOX_PENDING},
+    DbOutboxMessage,
+};
+use crate::{
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError,
+};
+use chrono::{SecondsFormat, TimeZone};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_outbox_message_struct(message: OutboxMessage) -> Result<DbOutboxMessage, EngineError> {
+    let client = match message.client {
+        Some(client) => client,
+        None => Client {
+            bot_id: message.bot_id.unwrap_or_default(),
+            channel_id: message.channel_id.unwrap_or_default(),
+            user_id: message.user_id.unwrap_or_default(),
+        },
+    };
+
+    Ok(DbOutboxMessage {
+        id: message.id,
+        client,
+        conversation_id: message.conversation_id,
+        callback_url: message.callback_url,
+        payload: decrypt_data(message.payload)?,
+        status: message.status,
+        attempts: message.attempts,
+        error: message.error,
+        position: message.position,
+        next_attempt_at: chrono::Utc
+            .timestamp(message.next_attempt_at, 0)
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: message.created_at,
+    })
+}
+
+fn format_outbox_messages(
+    items: Option<Vec<HashMap<String, AttributeValue>>>,
+    messages: &mut Vec<DbOutboxMessage>,
+) -> Result<(), EngineError> {
+    for item in items.unwrap_or_default() {
+        let message: OutboxMessage = serde_dynamodb::from_hashmap(item)?;
+        messages.push(format_outbox_message_struct(message)?);
+    }
+
+    Ok(())
+}
+
+pub fn create_outbox_message(
+    client: &Client,
+    conversation_id: &str,
+    callback_url: &str,
+    payload: &serde_json::Value,
+    status: &str,
+    error: Option<&str>,
+    position: i64,
+    db: &mut DynamoDbClient,
+) -> Result<String, EngineError> {
+    let data = OutboxMessage::new(
+        client,
+        conversation_id,
+        callback_url,
+        &encrypt_data(payload)?,
+        status,
+        error,
+        position,
+    );
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&data)?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(data.id)
+}
+
+/**
+ * Outbox messages of a client, with any status if none is given
+ */
+fn query_outbox_messages(
+    hash: &str,
+    status: Option<&str>,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(hash.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":rangePrefix"),
+            AttributeValue {
+                s: Some(String::from("outbox#")),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let filter_expression = match status {
+        Some(status) => {
+            expr_attr_names.insert(String::from("#status"), String::from("status"));
+            expr_attr_values.insert(
+                String::from(":status"),
+                AttributeValue {
+                    s: Some(status.to_owned()),
+                    ..Default::default()
+                },
+            );
+            Some("#status = :status".to_owned())
+        }
+        None => None,
+    };
+
+    let mut messages = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(
+                "#hashKey = :hashVal AND begins_with(#rangeKey, :rangePrefix)".to_owned(),
+            ),
+            filter_expression: filter_expression.clone(),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = db.runtime.block_on(future)?;
+
+        format_outbox_messages(data.items, &mut messages)?;
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            break;
+        }
+    }
+
+    messages
+        .sort_by(|a, b| (&a.conversation_id, a.position).cmp(&(&b.conversation_id, b.position)));
+
+    Ok(messages)
+}
+
+pub fn get_last_outbox_position(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut DynamoDbClient,
+) -> Result<i64, EngineError> {
+    let messages = query_outbox_messages(&OutboxMessage::get_hash(client), None, db)?;
+
+    Ok(messages
+        .iter()
+        .filter(|message| message.conversation_id == conversation_id)
+        .map(|message| message.position)
+        .max()
+        .unwrap_or(0))
+}
+
+pub fn get_outbox_messages(db: &mut DynamoDbClient) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let mut messages = vec![];
+
+    for hash in get_class_hashes("outbox", None, db)? {
+        messages.append(&mut query_outbox_messages(&hash, None, db)?);
+    }
+
+    Ok(messages)
+}
+
+pub fn get_client_outbox_messages(
+    client: &Client,
+    status: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    query_outbox_messages(&OutboxMessage::get_hash(client), Some(status), db)
+}
+
+pub fn claim_outbox_message(
+    client: &Client,
+    id: &str,
+    now: i64,
+    lease_until: i64,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#status"), String::from("status")),
+        (
+            String::from("#nextAttemptAt"),
+            String::from("next_attempt_at"),
+        ),
+        (String::from("#updatedAt"), String::from("updated_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":pending"),
+            AttributeValue {
+                s: Some(OUTBOX_PENDING.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":now"),
+            AttributeValue {
+                n: Some(now.to_string()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":leaseUntil"),
+            AttributeValue {
+                n: Some(lease_until.to_string()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":updatedAt"),
+            AttributeValue {
+                s: Some(get_date_time()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&OutboxMessage::get_key(client, id))?,
+        condition_expression: Some("#status = :pending AND #nextAttemptAt <= :now".to_owned()),
+        update_expression: Some(
+            "SET #nextAttemptAt = :leaseUntil, #updatedAt = :updatedAt".to_owned(),
+        ),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(rusoto_core::RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => {
+            Ok(false)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn update_outbox_message(
+    client: &Client,
+    id: &str,
+    status: &str,
+    attempts: i32,
+    error: Option<&str>,
+    next_attempt_at: i64,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#status"), String::from("status")),
+        (String::from("#attempts"), String::from("attempts")),
+        (String::from("#error"), String::from("error")),
+        (
+            String::from("#nextAttemptAt"),
+            String::from("next_attempt_at"),
+        ),
+        (String::from("#updatedAt"), String::from("updated_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":status"),
+            AttributeValue {
+                s: Some(status.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":attempts"),
+            AttributeValue {
+                n: Some(attempts.to_string()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":nextAttemptAt"),
+            AttributeValue {
+                n: Some(next_attempt_at.to_string()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":updatedAt"),
+            AttributeValue {
+                s: Some(get_date_time()),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut update_expression = "SET #status = :status, #attempts = :attempts, \
+        #nextAttemptAt = :nextAttemptAt, #updatedAt = :updatedAt"
+        .to_owned();
+
+    match error {
+        Some(error) => {
+            expr_attr_values.insert(
+                String::from(":error"),
+                AttributeValue {
+                    s: Some(error.to_owned()),
+                    ..Default::default()
+                },
+            );
+            update_expression.push_str(", #error = :error");
+        }
+        None => update_expression.push_str(" REMOVE #error"),
+    }
+
+    let input = UpdateItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&OutboxMessage::get_key(client, id))?,
+        // do not recreate a message delivered or deleted in the meantime
+        condition_expression: Some("attribute_exists(#hashKey)".to_owned()),
+        update_expression: Some(update_expression),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.update_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(()),
+        Err(rusoto_core::RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => {
+            Ok(())
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn delete_outbox_message(
+    client: &Client,
+    id: &str,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&OutboxMessage::get_key(client, id))?,
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
+
+pub fn delete_client_outbox(client: &Client, db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    for status in [OUTBOX_PENDING, OUTBOX_DEAD].iter() {
+        for message in get_client_outbox_messages(client, status, db)? {
+            delete_outbox_message(client, &message.id, db)?;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,241 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::RateLimit;
+use crate::EngineError;
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+// attempts to increment a counter that is created or expired concurrently
+const MAX_INCREMENT_ATTEMPTS: usize = 3;
+
+fn number_value(value: i64) -> AttributeValue {
+    AttributeValue {
+        n: Some(value.to_string()),
+        ..Default::default()
+    }
+}
+
+pub fn get_counter(
+    scope: &str,
+    name: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Option<i64>, EngineError> {
+    let input = GetItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&RateLimit::get_key(scope, name))?,
+        ..Default::default()
+    };
+
+    let future = db.client.get_item(input);
+    let res = db.runtime.block_on(future)?;
+
+    match res.item {
+        Some(item) => {
+            let counter: RateLimit = serde_dynamodb::from_hashmap(item)?;
+
+            // expired items are removed by the TTL of the table with a delay
+            if counter.expires_at < chrono::Utc::now().timestamp() {
+                return Ok(None);
+            }
+
+            Ok(Some(counter.value))
+        }
+        None => Ok(None),
+    }
+}
+
+/**
+ * Set the counter only if its current value is `expected` (None if it does not exist
+ * or is expired). Returns false if the counter was changed in the meantime.
+ */
+pub fn set_counter(
+    scope: &str,
+    name: &str,
+    expected: Option<i64>,
+    value: i64,
+    expires_at: i64,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> =
+        [(String::from("#expiresAt"), String::from("expires_at"))]
+            .iter()
+            .cloned()
+            .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":now"),
+        number_value(chrono::Utc::now().timestamp()),
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let condition_expression = match expected {
+        Some(expected) => {
+            expr_attr_names.insert(String::from("#value"), String::from("value"));
+            expr_attr_values.insert(String::from(":expected"), number_value(expected));
+
+            "#value = :expected AND #expiresAt >= :now"
+        }
+        None => {
+            expr_attr_names.insert(String::from("#hashKey"), String::from("hash"));
+
+            "attribute_not_exists(#hashKey) OR #expiresAt < :now"
+        }
+    };
+
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&RateLimit::new(scope, name, value, expires_at))?,
+        table_name: get_table_name()?,
+        condition_expression: Some(condition_expression.to_owned()),
+        expression_attribute_names: Some(expr_attr_names),
+        expression_attribute_values: Some(expr_attr_values),
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    match db.runtime.block_on(future) {
+        Ok(_) => Ok(true),
+        Err(rusoto_core::RusotoError::Service(PutItemError::ConditionalCheckFailed(_))) => {
+            Ok(false)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/**
+ * Increment a live counter, or create it when it does not exist or is expired
+ */
+pub fn increment_counter(
+    scope: &str,
+    name: &str,
+    delta: i64,
+    expires_at: i64,
+    db: &mut DynamoDbClient,
+) -> Result<i64, EngineError> {
+    let expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#value"), String::from("value")),
+        (String::from("#expiresAt"), String::from("expires_at")),
+        (String::from("#updatedAt"), String::from("updated_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    for _ in 0..MAX_INCREMENT_ATTEMPTS {
+        let expr_attr_values: HashMap<String, AttributeValue> = [
+            (String::from(":delta"), number_value(delta)),
+            (
+                String::from(":now"),
+                number_value(chrono::Utc::now().timestamp()),
+            ),
+            (
+                String::from(":updatedAt"),
+                AttributeValue {
+                    s: Some(get_date_time()),
+                    ..Default::default()
+                },
+            ),
+        ]
+        .iter()
+        .cloned()
+        .collect();
+
+        let input = UpdateItemInput {
+            table_name: get_table_name()?,
+            key: serde_dynamodb::to_hashmap(&RateLimit::get_key(scope, name))?,
+            update_expression: Some(
+                "SET #value = #value + :delta, #updatedAt = :updatedAt".to_owned(),
+            ),
+            condition_expression: Some(
+                "attribute_exists(#hashKey) AND #expiresAt >= :now".to_owned(),
+            ),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values),
+            return_values: Some("UPDATED_NEW".to_owned()),
+            ..Default::default()
+        };
+
+        let future = db.client.update_item(input);
+        match db.runtime.block_on(future) {
+            Ok(output) => {
+                let value = output
+                    .attributes
+                    .and_then(|attributes| attributes.get("value").cloned())
+                    .and_then(|value| value.n)
+                    .and_then(|value| value.parse::<i64>().ok());
+
+                return Ok(value.unwrap_or(delta));
+            }
+            Err(rusoto_core::RusotoError::Service(UpdateItemError::ConditionalCheckFailed(_))) => {}
+            Err(err) => return Err(err.into()),
+        }
+
+        // the counter does not exist or is expired
+        if set_counter(scope, name, None, delta, expires_at, db)? {
+            return Ok(delta);
+        }
+    }
+
+    Err(EngineError::Manager(format!(
+        "failed to increment counter {} of {}",
+        name, scope
+    )))
+}
+
+pub fn get_counters(
+    scope: &str,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<(String, i64)>, EngineError> {
+    let expr_attr_names: HashMap<String, String> =
+        [(String::from("#hashKey"), String::from("hash"))]
+            .iter()
+            .cloned()
+            .collect();
+
+    let expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":hashVal"),
+        AttributeValue {
+            s: Some(RateLimit::get_hash(scope)),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let now = chrono::Utc::now().timestamp();
+    let mut counters = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some("#hashKey = :hashVal".to_owned()),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = db.runtime.block_on(future)?;
+
+        for item in data.items.unwrap_or_default() {
+            let counter: RateLimit = serde_dynamodb::from_hashmap(item)?;
+
+            if counter.expires_at >= now {
+                counters.push((counter.name, counter.value));
+            }
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            break;
+        }
+    }
+
+    Ok(counters)
+}
@@ -0,0 +1,293 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::Schedule;
+use crate::db_connectors::DbSchedule;
+use crate::{
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError,
+};
+use chrono::{SecondsFormat, TimeZone};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+fn format_schedule_struct(schedule: Schedule) -> Result<DbSchedule, EngineError> {
+    let client = match schedule.client {
+        Some(client) => client,
+        None => Client {
+            bot_id: schedule.bot_id.unwrap_or_default(),
+            channel_id: schedule.channel_id.unwrap_or_default(),
+            user_id: schedule.user_id.unwrap_or_default(),
+        },
+    };
+
+    Ok(DbSchedule {
+        id: schedule.id,
+        client,
+        cron: schedule.cron,
+        job: decrypt_data(schedule.job)?,
+        next_run_at: chrono::Utc
+            .timestamp(schedule.next_run_at, 0)
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: schedule.created_at,
+    })
+}
+
+fn format_schedules(
+    items: Option<Vec<HashMap<String, AttributeValue>>>,
+    schedules: &mut Vec<DbSchedule>,
+) -> Result<(), EngineError> {
+    for item in items.unwrap_or_default() {
+        let schedule: Schedule = serde_dynamodb::from_hashmap(item)?;
+        schedules.push(format_schedule_struct(schedule)?);
+    }
+
+    Ok(())
+}
+
+pub fn create_schedule(
+    client: &Client,
+    cron: Option<&str>,
+    job: &serde_json::Value,
+    next_run_at: i64,
+    db: &mut DynamoDbClient,
+) -> Result<String, EngineError> {
+    let data = Schedule::new(client, cron, &encrypt_data(job)?, next_run_at);
+    let input = PutItemInput {
+        item: serde_dynamodb::to_hashmap(&data)?,
+        table_name: get_table_name()?,
+        ..Default::default()
+    };
+
+    let future = db.client.put_item(input);
+    db.runtime.block_on(future)?;
+
+    Ok(data.id)
+}
+
+/**
+ * Query the schedules stored under a client hash,
+ * keeping only the ones due at `now` when it is given.
+ */
+fn query_schedules(
+    hash: &str,
+    now: Option<i64>,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#rangeKey"), String::from("range")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [
+        (
+            String::from(":hashVal"),
+            AttributeValue {
+                s: Some(hash.to_owned()),
+                ..Default::default()
+            },
+        ),
+        (
+            String::from(":rangePrefix"),
+            AttributeValue {
+                s: Some(String::from("schedule#")),
+                ..Default::default()
+            },
+        ),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let filter_expression = match now {
+        Some(now) => {
+            expr_attr_names.insert(String::from("#nextRunAt"), String::from("next_run_at"));
+            expr_attr_values.insert(
+                String::from(":now"),
+                AttributeValue {
+                    n: Some(now.to_string()),
+                    ..Default::default()
+                },
+            );
+
+            Some("#nextRunAt <= :now".to_owned())
+        }
+        None => None,
+    };
+
+    let mut schedules = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(
+                "#hashKey = :hashVal AND begins_with(#rangeKey, :rangePrefix)".to_owned(),
+            ),
+            filter_expression: filter_expression.clone(),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = db.runtime.block_on(future)?;
+
+        format_schedules(data.items, &mut schedules)?;
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(schedules);
+        }
+    }
+}
+
+pub fn get_client_schedules(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let mut schedules = query_schedules(&Schedule::get_hash(client), None, db)?;
+
+    schedules.sort_by(|a, b| a.next_run_at.cmp(&b.next_run_at));
+
+    Ok(schedules)
+}
+
+/**
+ * Schedules are stored under the hash of each client, the clients owning schedules
+ * are found through the ClassByClientIndex before querying their due schedules.
+ */
+pub fn get_due_schedules(
+    now: i64,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let mut schedules = vec![];
+
+    for hash in get_class_hashes("schedule", None, db)? {
+        schedules.append(&mut query_schedules(&hash, Some(now), db)?);
+    }
+
+    Ok(schedules)
+}
+
+pub fn claim_schedule(
+    client: &Client,
+    id: &str,
+    now: i64,
+    next_run_at: Option<i64>,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> = [
+        (String::from("#hashKey"), String::from("hash")),
+        (String::from("#nextRunAt"), String::from("next_run_at")),
+    ]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":now"),
+        AttributeValue {
+            n: Some(now.to_string()),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    // the schedule still exists and is still due
+    let condition_expression = Some("attribute_exists(#hashKey) AND #nextRunAt <= :now".to_owned());
+    let key = serde_dynamodb::to_hashmap(&Schedule::get_key(client, id))?;
+
+    match next_run_at {
+        Some(next_run_at) => {
+            expr_attr_names.insert(String::from("#updatedAt"), String::from("updated_at"));
+            expr_attr_values.insert(
+                String::from(":nextRunAt"),
+                AttributeValue {
+                    n: Some(next_run_at.to_string()),
+                    ..Default::default()
+                },
+            );
+            expr_attr_values.insert(
+                String::from(":updatedAt"),
+                AttributeValue {
+                    s: Some(get_date_time()),
+                    ..Default::default()
+                },
+            );
+
+            let input = UpdateItemInput {
+                table_name: get_table_name()?,
+                key,
+                condition_expression,
+                update_expression: Some(
+                    "SET #nextRunAt = :nextRunAt, #updatedAt = :updatedAt".to_owned(),
+                ),
+                expression_attribute_names: Some(expr_attr_names),
+                expression_attribute_values: Some(expr_attr_values),
+                ..Default::default()
+            };
+
+            match db.runtime.block_on(db.client.update_item(input)) {
+                Ok(_) => Ok(true),
+                Err(rusoto_core::RusotoError::Service(
+                    UpdateItemError::ConditionalCheckFailed(_),
+                )) => Ok(false),
+                Err(err) => Err(err.into()),
+            }
+        }
+        None => {
+            let input = DeleteItemInput {
+                table_name: get_table_name()?,
+                key,
+                condition_expression,
+                expression_attribute_names: Some(expr_attr_names),
+                expression_attribute_values: Some(expr_attr_values),
+                ..Default::default()
+            };
+
+            match db.runtime.block_on(db.client.delete_item(input)) {
+                Ok(_) => Ok(true),
+                Err(rusoto_core::RusotoError::Service(
+                    DeleteItemError::ConditionalCheckFailed(_),
+                )) => Ok(false),
+                Err(err) => Err(err.into()),
+            }
+        }
+    }
+}
+
+pub fn delete_schedule(
+    client: &Client,
+    id: &str,
+    db: &mut DynamoDbClient,
+) -> Result<bool, EngineError> {
+    let input = DeleteItemInput {
+        table_name: get_table_name()?,
+        key: serde_dynamodb::to_hashmap(&Schedule::get_key(client, id))?,
+        return_values: Some("ALL_OLD".to_owned()),
+        ..Default::default()
+    };
+
+    let future = db.client.delete_item(input);
+    let data = db.runtime.block_on(future)?;
+
+    Ok(data.attributes.is_some())
+}
+
+pub fn delete_client_schedules(
+    client: &Client,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    for schedule in get_client_schedules(client, db)? {
+        delete_schedule(client, &schedule.id, db)?;
+    }
+
+    Ok(())
+}
@@ -102,7 +102,7 @@ pub fn get_current_state(
     }
 }
 
-fn format_state_data(
+pub fn format_state_data(
     client: &Client,
     _type: &str,
     keys_values: Vec<(&str, &serde_json::Value)>,
@@ -0,0 +1,310 @@
+use crate::data::DynamoDbClient;
+use crate::db_connectors::dynamodb::{Conversation, DynamoDbKey, Memory, State};
+use crate::db_connectors::{unit_of_work::Write, utils::get_expires_at_for_dynamodb};
+use crate::{Client, EngineError};
+use rusoto_dynamodb::*;
+use std::collections::HashMap;
+
+use crate::db_connectors::dynamodb::utils::*;
+
+use super::{conversations, memories, messages, state};
+
+// maximum number of items of a TransactWriteItems request
+const MAX_TRANSACT_ITEMS: usize = 100;
+
+/**
+ * Items of a transaction. A transaction can not contain several operations
+ * on the same item, so only the last operation on each item is kept.
+ * Bulk items (messages, memories deleted all at once) can be committed
+ * in separate transactions when the interaction has too many items.
+ */
+#[derive(Default)]
+struct TransactItems {
+    items: Vec<(TransactWriteItem, bool)>,
+    positions: HashMap<(String, String), usize>,
+}
+
+impl TransactItems {
+    fn push(&mut self, hash: String, range: String, item: TransactWriteItem, bulk: bool) {
+        match self.positions.get(&(hash.to_owned(), range.to_owned())) {
+            Some(position) => self.items[*position] = (item, bulk),
+            None => {
+                self.positions.insert((hash, range), self.items.len());
+                self.items.push((item, bulk));
+            }
+        }
+    }
+
+    fn put(
+        &mut self,
+        hash: String,
+        range: String,
+        item: HashMap<String, AttributeValue>,
+        bulk: bool,
+    ) -> Result<(), EngineError> {
+        let put = Put {
+            table_name: get_table_name()?,
+            item,
+            ..Default::default()
+        };
+
+        self.push(
+            hash,
+            range,
+            TransactWriteItem {
+                put: Some(put),
+                ..Default::default()
+            },
+            bulk,
+        );
+
+        Ok(())
+    }
+
+    fn delete(&mut self, hash: String, range: String, bulk: bool) -> Result<(), EngineError> {
+        let delete = Delete {
+            table_name: get_table_name()?,
+            key: serde_dynamodb::to_hashmap(&DynamoDbKey::new(&hash, &range))?,
+            ..Default::default()
+        };
+
+        self.push(
+            hash,
+            range,
+            TransactWriteItem {
+                delete: Some(delete),
+                ..Default::default()
+            },
+            bulk,
+        );
+
+        Ok(())
+    }
+}
+
+/**
+ * Updates of the same conversation are merged in a single update
+ */
+struct ConversationUpdate {
+    client: Client,
+    flow_id: Option<String>,
+    step_id: Option<String>,
+}
+
+/**
+ * The writes are made with a single TransactWriteItems request.
+ * When the interaction has more than MAX_TRANSACT_ITEMS items, the bulk items are first
+ * committed by transactions of MAX_TRANSACT_ITEMS items, then the other writes together:
+ * a failure may leave some messages saved, but never a half-updated conversation.
+ */
+pub fn commit(writes: &[Write], db: &mut DynamoDbClient) -> Result<(), EngineError> {
+    let mut transact_items = TransactItems::default();
+    let mut conversation_updates: HashMap<(String, String), ConversationUpdate> = HashMap::new();
+
+    for write in writes {
+        match write {
+            Write::Messages {
+                client,
+                conversation_id,
+                flow_id,
+                step_id,
+                messages,
+                interaction_order,
+                direction,
+                ttl,
+            } => {
+                let messages = messages::format_messages(
+                    client,
+                    conversation_id,
+                    flow_id,
+                    step_id,
+                    messages,
+                    *interaction_order,
+                    direction,
+                    get_expires_at_for_dynamodb(*ttl),
+                )?;
+
+                for message in messages {
+                    let item = serde_dynamodb::to_hashmap(&message)?;
+                    transact_items.put(message.hash, message.range, item, true)?;
+                }
+            }
+            Write::Memories {
+                client,
+                memories,
+                ttl,
+            } => {
+                let memories =
+                    memories::format_memories(client, memories, get_expires_at_for_dynamodb(*ttl))?;
+
+                for memory in memories {
+                    let item = serde_dynamodb::to_hashmap(&memory)?;
+                    transact_items.put(memory.hash, memory.range, item, false)?;
+                }
+            }
+            Write::DeleteMemory { client, key } => {
+                transact_items.delete(Memory::get_hash(client), Memory::get_range(key), false)?;
+            }
+            Write::DeleteMemories { client } => {
+                for memory in memories::get_all_memories(client, db)? {
+                    if let Some(key) = memory["key"].as_str() {
+                        let (hash, range) = (Memory::get_hash(client), Memory::get_range(key));
+                        transact_items.delete(hash, range, true)?;
+                    }
+                }
+            }
+            Write::Conversation {
+                client,
+                conversation_id,
+                flow_id,
+                step_id,
+            } => {
+                let update = conversation_updates
+                    .entry((Conversation::get_hash(client), conversation_id.to_owned()))
+                    .or_insert(ConversationUpdate {
+                        client: client.to_owned(),
+                        flow_id: None,
+                        step_id: None,
+                    });
+
+                if flow_id.is_some() {
+                    update.flow_id = flow_id.to_owned();
+                }
+                if step_id.is_some() {
+                    update.step_id = step_id.to_owned();
+                }
+            }
+            Write::ConversationStatus {
+                client,
+                conversation_id,
+                from_status,
+                status,
+            } => {
+                let (old_key, mut new_conv) = match conversations::format_status_change(
+                    conversation_id,
+                    client,
+                    from_status,
+                    status,
+                    db,
+                )? {
+                    Some(change) => change,
+                    // the status of the conversation already changed
+                    None => continue,
+                };
+
+                // the pending updates are applied to the conversation with its new status
+                let update = conversation_updates
+                    .remove(&(Conversation::get_hash(client), conversation_id.to_owned()));
+
+                if let Some(update) = update {
+                    if let Some(flow_id) = update.flow_id {
+                        new_conv.flow_id = flow_id;
+                    }
+                    if let Some(step_id) = update.step_id {
+                        new_conv.step_id = step_id;
+                    }
+                }
+
+                transact_items.delete(old_key.hash, old_key.range, false)?;
+
+                let item = serde_dynamodb::to_hashmap(&new_conv)?;
+                transact_items.put(new_conv.hash, new_conv.range, item, false)?;
+            }
+            Write::State {
+                client,
+                _type,
+                key,
+                value,
+                ttl,
+            } => {
+                let states = state::format_state_data(
+                    client,
+                    _type,
+                    vec![(key.as_str(), value)],
+                    get_expires_at_for_dynamodb(*ttl),
+                )?;
+
+                for state in states {
+                    let item = serde_dynamodb::to_hashmap(&state)?;
+                    transact_items.put(state.hash, state.range, item, false)?;
+                }
+            }
+            Write::DeleteState { client, _type, key } => {
+                transact_items.delete(
+                    State::get_hash(client),
+                    State::get_range(_type, key),
+                    false,
+                )?;
+            }
+        }
+    }
+
+    for ((hash, conversation_id), update) in conversation_updates {
+        let update = conversations::format_conversation_update(
+            &conversation_id,
+            &update.client,
+            update.flow_id,
+            update.step_id,
+        )?;
+
+        transact_items.push(
+            hash,
+            Conversation::get_range("OPEN", &conversation_id),
+            TransactWriteItem {
+                update: Some(update),
+                ..Default::default()
+            },
+            false,
+        );
+    }
+
+    if transact_items.items.len() <= MAX_TRANSACT_ITEMS {
+        let items = transact_items
+            .items
+            .into_iter()
+            .map(|(item, _)| item)
+            .collect();
+        return transact_write(items, db);
+    }
+
+    let (bulk, items): (Vec<_>, Vec<_>) = transact_items
+        .items
+        .into_iter()
+        .partition(|(_, bulk)| *bulk);
+    let bulk: Vec<TransactWriteItem> = bulk.into_iter().map(|(item, _)| item).collect();
+    let items: Vec<TransactWriteItem> = items.into_iter().map(|(item, _)| item).collect();
+
+    if items.len() > MAX_TRANSACT_ITEMS {
+        return Err(EngineError::Manager(format!(
+            "the interaction has {} writes besides its messages and deleted memories, a DynamoDB transaction is limited to {} items",
+            items.len(),
+            MAX_TRANSACT_ITEMS
+        )));
+    }
+
+    // the conversation, memories and state are only updated once the bulk items are saved
+    for chunk in bulk.chunks(MAX_TRANSACT_ITEMS) {
+        transact_write(chunk.to_vec(), db)?;
+    }
+
+    transact_write(items, db)
+}
+
+fn transact_write(
+    items: Vec<TransactWriteItem>,
+    db: &mut DynamoDbClient,
+) -> Result<(), EngineError> {
+    if items.is_empty() {
+        return Ok(());
+    }
+
+    let input = TransactWriteItemsInput {
+        transact_items: items,
+        ..Default::default()
+    };
+
+    let future = db.client.transact_write_items(input);
+    db.runtime.block_on(future)?;
+
+    Ok(())
+}
@@ -1,4 +1,4 @@
-use crate::db_connectors::dynamodb::{Bot, Conversation, Memory, Message};
+use crate::db_connectors::dynamodb::{Bot, Class, Conversation, Memory, Message};
 use crate::{
     data::{DynamoBot, DynamoBotBincode, DynamoDbClient},
     encrypt::decrypt_data,
@@ -7,10 +7,10 @@ use crate::{
 
 use rusoto_core::RusotoError;
 use rusoto_dynamodb::{
-    BatchGetItemError, BatchGetItemInput, BatchWriteItemError, BatchWriteItemInput, DynamoDb,
-    GetItemError, GetItemInput,
+    AttributeValue, BatchGetItemError, BatchGetItemInput, BatchWriteItemError, BatchWriteItemInput,
+    DynamoDb, GetItemError, GetItemInput, QueryInput,
 };
-use std::{thread, time};
+use std::{collections::HashMap, thread, time};
 
 use rand::Rng;
 
@@ -399,3 +399,74 @@ pub fn execute_conversation_get_query(
         retry_times += 1;
     }
 }
+
+/**
+ * Return the hash of every client owning items of the given class, optionally only
+ * the hashes starting with `hash_prefix`, using the ClassByClientIndex instead of a scan of the table.
+ */
+pub fn get_class_hashes(
+    class: &str,
+    hash_prefix: Option<&str>,
+    db: &mut DynamoDbClient,
+) -> Result<Vec<String>, EngineError> {
+    let mut expr_attr_names: HashMap<String, String> =
+        [(String::from("#classKey"), String::from("class"))]
+            .iter()
+            .cloned()
+            .collect();
+
+    let mut expr_attr_values: HashMap<String, AttributeValue> = [(
+        String::from(":class"),
+        AttributeValue {
+            s: Some(class.to_owned()),
+            ..Default::default()
+        },
+    )]
+    .iter()
+    .cloned()
+    .collect();
+
+    let mut key_condition_expression = "#classKey = :class".to_owned();
+    if let Some(hash_prefix) = hash_prefix {
+        expr_attr_names.insert(String::from("#hashKey"), String::from("hash"));
+        expr_attr_values.insert(
+            String::from(":hashPrefix"),
+            AttributeValue {
+                s: Some(hash_prefix.to_owned()),
+                ..Default::default()
+            },
+        );
+        key_condition_expression.push_str(" AND begins_with(#hashKey, :hashPrefix)");
+    }
+
+    let mut hashes: Vec<String> = vec![];
+    let mut pagination_key = None;
+
+    loop {
+        let input = QueryInput {
+            table_name: get_table_name()?,
+            key_condition_expression: Some(key_condition_expression.clone()),
+            index_name: Some("ClassByClientIndex".to_owned()),
+            expression_attribute_names: Some(expr_attr_names.clone()),
+            expression_attribute_values: Some(expr_attr_values.clone()),
+            exclusive_start_key: pagination_key,
+            ..Default::default()
+        };
+
+        let future = db.client.query(input);
+        let data = db.runtime.block_on(future)?;
+
+        // the index is sorted by hash, the items of a client are next to each other
+        for item in data.items.unwrap_or_default() {
+            let class: Class = serde_dynamodb::from_hashmap(item)?;
+            if hashes.last() != Some(&class.hash) {
+                hashes.push(class.hash);
+            }
+        }
+
+        pagination_key = data.last_evaluated_key;
+        if let None = &pagination_key {
+            return Ok(hashes);
+        }
+    }
+}
@@ -0,0 +1,128 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+
+/**
+ * Conditional write of the lease of a client: succeeds if there is no lease,
+ * if it is expired or if it is already held by the same owner.
+ */
+pub fn acquire_lease(
+    client: &Client,
+    owner: &str,
+    expires_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let _db_call = db_call("acquire_lease");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call acquire lease {} expires_at: {}", owner, expires_at),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call acquire lease {} expires_at: {}", owner, expires_at),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::locks::acquire_lease(
+            client,
+            owner,
+            bson::DateTime::from_chrono(expires_at),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::locks::acquire_lease(client, owner, expires_at.timestamp(), db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::locks::acquire_lease(
+            client,
+            owner,
+            expires_at.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::locks::acquire_lease(client, owner, expires_at.naive_utc(), db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Remove the lease of a client, only if it is still held by the given owner
+ */
+pub fn release_lease(client: &Client, owner: &str, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("release_lease");
+    csml_logger(
+        CsmlLog::new(None, None, None, format!("db call release lease {}", owner)),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call release lease {}", owner),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::locks::release_lease(client, owner, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::locks::release_lease(client, owner, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::locks::release_lease(client, owner, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::locks::release_lease(client, owner, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
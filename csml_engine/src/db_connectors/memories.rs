@@ -10,60 +10,10 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 use csml_interpreter::data::csml_logs::{LogLvl, CsmlLog, csml_logger};
 
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
-use crate::{Client, ConversationInfo, Database, EngineError, Memory};
+use crate::{Client, Database, EngineError};
 use crate::db_connectors::utils::*;
-use std::collections::HashMap;
-
-pub fn add_memories(
-    data: &mut ConversationInfo,
-    memories: &HashMap<String, Memory>,
-) -> Result<(), EngineError> {
-    csml_logger(
-        CsmlLog::new(
-            None,
-            None,
-            None,
-            format!("db call save memories {:?}", memories.keys())
-        ),
-        LogLvl::Info
-    );
-    csml_logger(
-        CsmlLog::new(
-            None,
-            None,
-            None,
-            format!("db call save memories {:?}", memories.keys())
-        ),
-        LogLvl::Debug
-    );
-
-    #[cfg(feature = "mongo")]
-    if is_mongodb() {
-        let expires_at = get_expires_at_for_mongodb(data.ttl);
-        return mongodb_connector::memories::add_memories(data, &memories, expires_at);
-    }
-
-    #[cfg(feature = "dynamo")]
-    if is_dynamodb() {
-        let expires_at = get_expires_at_for_dynamodb(data.ttl);
-        return dynamodb_connector::memories::add_memories(data, &memories, expires_at);
-    }
-
-    #[cfg(feature = "postgresql")]
-    if is_postgresql() {
-        let expires_at = get_expires_at_for_postgresql(data.ttl);
-        return postgresql_connector::memories::add_memories(data, &memories, expires_at);
-    }
-
-    #[cfg(feature = "sqlite")]
-    if is_sqlite() {
-        let expires_at = get_expires_at_for_sqlite(data.ttl);
-        return sqlite_connector::memories::add_memories(data, &memories, expires_at);
-    }
-
-    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
-}
 
 pub fn create_client_memory(
     client: &Client,
@@ -72,6 +22,7 @@ pub fn create_client_memory(
     ttl: Option<chrono::Duration>,
     db: &mut Database
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("create_client_memory");
 
     csml_logger(
         CsmlLog::new(
@@ -125,6 +76,7 @@ pub fn create_client_memory(
 }
 
 pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<serde_json::Value, EngineError> {
+    let _db_call = db_call("internal_use_get_memories");
     csml_logger(
         CsmlLog::new(
             None,
@@ -273,6 +225,7 @@ pub fn internal_use_get_memories(client: &Client, db: &mut Database) -> Result<s
 
 
 pub fn delete_client_memory(client: &Client, key: &str, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_client_memory");
     csml_logger(
         CsmlLog::new(
             None,
@@ -320,6 +273,7 @@ pub fn delete_client_memory(client: &Client, key: &str, db: &mut Database) -> Re
 }
 
 pub fn delete_client_memories(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_client_memories");
     csml_logger(
         CsmlLog::new(
             None,
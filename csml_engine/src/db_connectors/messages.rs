@@ -8,6 +8,7 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 use crate::db_connectors::utils::*;
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Client, ConversationInfo, Database, EngineError};
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
@@ -18,6 +19,7 @@ pub fn add_messages_bulk(
     interaction_order: i32,
     direction: &str,
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("add_messages_bulk");
     csml_logger(
         CsmlLog::new(
             None,
@@ -40,52 +42,70 @@ pub fn add_messages_bulk(
     #[cfg(feature = "mongo")]
     if is_mongodb() {
         let expires_at = get_expires_at_for_mongodb(data.ttl);
+        let db = mongodb_connector::get_db(&data.db)?;
 
         return mongodb_connector::messages::add_messages_bulk(
-            data,
+            &data.client,
+            &data.conversation_id,
+            &data.context.flow,
+            data.context.step.get_step_ref(),
             &msgs,
             interaction_order,
             direction,
             expires_at,
+            db,
         );
     }
 
     #[cfg(feature = "dynamo")]
     if is_dynamodb() {
         let expires_at = get_expires_at_for_dynamodb(data.ttl);
+        let db = dynamodb_connector::get_db(&mut data.db)?;
 
         return dynamodb_connector::messages::add_messages_bulk(
-            data,
+            &data.client,
+            &data.conversation_id,
+            &data.context.flow,
+            data.context.step.get_step_ref(),
             &msgs,
             interaction_order,
             direction,
             expires_at,
+            db,
         );
     }
 
     #[cfg(feature = "postgresql")]
     if is_postgresql() {
         let expires_at = get_expires_at_for_postgresql(data.ttl);
+        let db = postgresql_connector::get_db(&data.db)?;
 
         return postgresql_connector::messages::add_messages_bulk(
-            data,
+            &data.conversation_id,
+            &data.context.flow,
+            data.context.step.get_step_ref(),
             &msgs,
             interaction_order,
             direction,
             expires_at,
+            db,
         );
     }
 
     #[cfg(feature = "sqlite")]
     if is_sqlite() {
         let expires_at = get_expires_at_for_sqlite(data.ttl);
+        let db = sqlite_connector::get_db(&data.db)?;
 
         return sqlite_connector::messages::add_messages_bulk(
-            data,
+            &data.conversation_id,
+            &data.context.flow,
+            data.context.step.get_step_ref(),
             &msgs,
             interaction_order,
             direction,
             expires_at,
+            db,
         );
     }
 
@@ -100,6 +120,7 @@ pub fn get_client_messages(
     from_date: Option<i64>,
     to_date: Option<i64>,
 ) -> Result<serde_json::Value, EngineError> {
+    let _db_call = db_call("get_client_messages");
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call get messages")),
         LogLvl::Info,
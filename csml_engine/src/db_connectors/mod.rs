@@ -45,11 +45,18 @@ use self::postgresql as postgresql_connector;
 #[cfg(feature = "sqlite")]
 use self::sqlite as sqlite_connector;
 
+pub mod analytics;
+pub mod api_keys;
 pub mod bot;
 pub mod conversations;
+pub mod locks;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod rate_limits;
+pub mod schedules;
 pub mod state;
+pub mod unit_of_work;
 
 pub mod user;
 pub mod clean_db;
@@ -84,18 +91,35 @@ pub struct DbConversation {
 }
 
 #[derive(Serialize, Deserialize, Debug)]
-pub struct DbMemory {
+pub struct DbSchedule {
+    pub id: String,
+    pub client: Client,
+    pub cron: Option<String>,
+    pub job: serde_json::Value,
+    pub next_run_at: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DbOutboxMessage {
     pub id: String,
     pub client: Client,
-    pub interaction_id: String,
     pub conversation_id: String,
-    pub flow_id: String,
-    pub step_id: String,
-    pub memory_order: i32,
-    pub interaction_order: i32,
-    pub key: String,
-    pub value: serde_json::Value,
-    pub expires_at: Option<String>,
+    pub callback_url: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub error: Option<String>,
+    pub position: i64,
+    pub next_attempt_at: String,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DbApiKey {
+    pub key_hash: String,
+    pub bot_ids: Option<Vec<String>>,
+    pub scopes: Vec<String>,
     pub created_at: String,
 }
 
@@ -113,15 +137,18 @@ pub struct DbMessage {
     pub created_at: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-pub struct DbState {
-    pub id: String,
-    pub client: Client,
-    #[serde(rename = "type")]
-    pub _type: String,
-    pub value: serde_json::Value,
-    pub expires_at: Option<String>,
-    pub created_at: String,
+/**
+ * Position of a message in a conversation, without its payload. Used to compute the analytics
+ */
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DbMessageStep {
+    pub conversation_id: String,
+    pub flow_id: String,
+    pub step_id: String,
+    pub interaction_order: i32,
+    pub message_order: i32,
+    // unix timestamp in milliseconds
+    pub created_at: i64,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -157,6 +184,27 @@ impl BotVersion {
     }
 }
 
+/**
+ * Span of a db call, its duration is also observed in csml_db_call_duration_seconds
+ */
+pub struct DbCall {
+    _span: csml_interpreter::csml_tracing::Span,
+    _timer: csml_interpreter::csml_metrics::Timer,
+}
+
+pub fn db_call(operation: &str) -> DbCall {
+    DbCall {
+        _span: csml_interpreter::csml_tracing::Span::start(
+            &format!("db {}", operation),
+            vec![("db.operation", operation.to_owned())],
+        ),
+        _timer: csml_interpreter::csml_metrics::Timer::new(
+            "csml_db_call_duration_seconds",
+            vec![("operation", operation.to_owned())],
+        ),
+    }
+}
+
 #[cfg(feature = "mongo")]
 pub fn is_mongodb() -> bool {
     // If the env var is not set at all, use mongodb by default
@@ -0,0 +1,56 @@
+use crate::{db_connectors::DbMessageStep, EngineError, MongoDbClient};
+use bson::{doc, Document};
+
+pub fn get_bot_message_steps(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: bson::DateTime,
+    to_date: bson::DateTime,
+    limit: i64,
+    db: &MongoDbClient,
+) -> Result<Vec<DbMessageStep>, EngineError> {
+    let collection = db.client.collection::<Document>("message");
+
+    let mut filter = doc! {
+        "client.bot_id": bot_id,
+        "created_at": {"$gte": from_date, "$lte": to_date}
+    };
+    if let Some(channel_id) = channel_id {
+        filter.insert("client.channel_id", channel_id);
+    }
+
+    // the payloads are not needed to compute the analytics
+    let find_options = mongodb::options::FindOptions::builder()
+        .projection(doc! {
+            "conversation_id": 1,
+            "flow_id": 1,
+            "step_id": 1,
+            "interaction_order": 1,
+            "message_order": 1,
+            "created_at": 1,
+        })
+        .batch_size(1000)
+        .limit(limit)
+        .build();
+
+    let cursor = collection.find(filter, find_options)?;
+
+    let mut messages = vec![];
+    for doc in cursor {
+        let message = doc?;
+
+        messages.push(DbMessageStep {
+            conversation_id: message.get_str("conversation_id").unwrap().to_owned(),
+            flow_id: message.get_str("flow_id").unwrap().to_owned(),
+            step_id: message.get_str("step_id").unwrap().to_owned(),
+            interaction_order: message.get_i32("interaction_order").unwrap(),
+            message_order: message.get_i32("message_order").unwrap(),
+            created_at: message
+                .get_datetime("created_at")
+                .unwrap()
+                .timestamp_millis(),
+        });
+    }
+
+    Ok(messages)
+}
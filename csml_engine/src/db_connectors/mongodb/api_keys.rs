@@ -0,0 +1,58 @@
+use crate::{db_connectors::DbApiKey, EngineError, MongoDbClient};
+use bson::{doc, Bson, Document};
+use chrono::SecondsFormat;
+
+fn format_api_key_struct(api_key: Document) -> Result<DbApiKey, EngineError> {
+    let bot_ids = match api_key.get("bot_ids") {
+        Some(Bson::Null) | None => None,
+        Some(bot_ids) => Some(bson::from_bson(bot_ids.to_owned())?),
+    };
+
+    Ok(DbApiKey {
+        key_hash: api_key.get_str("_id").unwrap().to_owned(),
+        bot_ids,
+        scopes: bson::from_bson(api_key.get("scopes").unwrap().to_owned())?,
+        created_at: api_key
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+pub fn create_api_key(
+    key_hash: &str,
+    bot_ids: &Option<Vec<String>>,
+    scopes: &[String],
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("api_key");
+
+    let api_key = doc! {
+        "_id": key_hash,
+        "bot_ids": bson::to_bson(bot_ids)?,
+        "scopes": bson::to_bson(scopes)?,
+        "created_at": bson::DateTime::from_chrono(chrono::Utc::now()),
+    };
+
+    collection.insert_one(api_key, None)?;
+
+    Ok(())
+}
+
+pub fn get_api_key(key_hash: &str, db: &MongoDbClient) -> Result<Option<DbApiKey>, EngineError> {
+    let collection = db.client.collection::<Document>("api_key");
+
+    match collection.find_one(doc! { "_id": key_hash }, None)? {
+        Some(api_key) => Ok(Some(format_api_key_struct(api_key)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn delete_api_key(key_hash: &str, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("api_key");
+
+    collection.delete_one(doc! { "_id": key_hash }, None)?;
+
+    Ok(())
+}
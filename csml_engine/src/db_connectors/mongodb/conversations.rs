@@ -2,7 +2,7 @@ use crate::{db_connectors::DbConversation, Client, EngineError, MongoDbClient};
 use bson::{doc, Document};
 use chrono::SecondsFormat;
 
-fn format_conversation_struct(
+pub fn format_conversation_struct(
     conversation: bson::document::Document,
 ) -> Result<DbConversation, EngineError> {
     Ok(DbConversation {
@@ -65,24 +65,31 @@ pub fn close_conversation(
 ) -> Result<(), EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
-    let filter = doc! {
-        "_id": bson::oid::ObjectId::parse_str(id).unwrap(),
-        "client.bot_id": client.bot_id.to_owned(),
-        "client.user_id": client.user_id.to_owned(),
-        "client.channel_id": client.channel_id.to_owned(),
-    };
-
     collection.update_one(
-        filter,
-        doc! {
-            "$set": { "status": status },
-            "$currentDate": { "last_interaction_at": true }
-        },
+        format_conversation_filter(id, client),
+        format_status_update(status),
         None,
     )?;
     Ok(())
 }
 
+pub fn set_conversation_status(
+    id: &str,
+    client: &Client,
+    from_status: &str,
+    status: &str,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let mut filter = format_conversation_filter(id, client);
+    filter.insert("status", from_status);
+
+    let result = collection.update_one(filter, format_status_update(status), None)?;
+
+    Ok(result.modified_count > 0)
+}
+
 pub fn close_all_conversations(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
@@ -107,11 +114,19 @@ pub fn close_all_conversations(client: &Client, db: &MongoDbClient) -> Result<()
 pub fn get_latest_open(
     client: &Client,
     db: &MongoDbClient,
+) -> Result<Option<DbConversation>, EngineError> {
+    get_latest_by_status(client, "OPEN", db)
+}
+
+pub fn get_latest_by_status(
+    client: &Client,
+    status: &str,
+    db: &MongoDbClient,
 ) -> Result<Option<DbConversation>, EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
     let filter = doc! {
-        "status": "OPEN",
+        "status": status,
         "client.bot_id": client.bot_id.to_owned(),
         "client.user_id": client.user_id.to_owned(),
         "client.channel_id": client.channel_id.to_owned(),
@@ -139,13 +154,31 @@ pub fn update_conversation(
 ) -> Result<(), EngineError> {
     let collection = db.client.collection::<Document>("conversation");
 
-    let filter = doc! {
-        "_id": bson::oid::ObjectId::parse_str(conversation_id).unwrap(),
+    collection.update_one(
+        format_conversation_filter(conversation_id, client),
+        format_conversation_update(flow_id, step_id),
+        None,
+    )?;
+    Ok(())
+}
+
+pub fn format_conversation_filter(id: &str, client: &Client) -> Document {
+    doc! {
+        "_id": bson::oid::ObjectId::parse_str(id).unwrap(),
         "client.bot_id": client.bot_id.to_owned(),
         "client.user_id": client.user_id.to_owned(),
         "client.channel_id": client.channel_id.to_owned(),
-    };
+    }
+}
 
+pub fn format_status_update(status: &str) -> Document {
+    doc! {
+        "$set": { "status": status },
+        "$currentDate": { "last_interaction_at": true }
+    }
+}
+
+pub fn format_conversation_update(flow_id: Option<String>, step_id: Option<String>) -> Document {
     let doc = match (flow_id, step_id) {
         (Some(flow_id), Some(step_id)) => doc! {
             "flow_id": flow_id,
@@ -160,13 +193,10 @@ pub fn update_conversation(
         (None, None) => doc! {},
     };
 
-    let update = doc! {
+    doc! {
         "$set": doc,
         "$currentDate": { "last_interaction_at": true }
-    };
-
-    collection.update_one(filter, update, None)?;
-    Ok(())
+    }
 }
 
 pub fn delete_user_conversations(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
@@ -0,0 +1,102 @@
+use crate::db_connectors::mongodb::{
+    conversations::format_conversation_struct, messages::format_message_struct,
+};
+use crate::{encrypt::decrypt_data, Client, EngineError, MongoDbClient};
+use bson::{doc, Document};
+use chrono::SecondsFormat;
+
+fn client_filter(client: &Client) -> Document {
+    doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    }
+}
+
+pub fn export_conversations(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let collection = db.client.collection::<Document>("conversation");
+
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": 1 })
+        .build();
+    let cursor = collection.find(client_filter(client), find_options)?;
+
+    let mut conversations = vec![];
+    for doc in cursor {
+        let conversation = format_conversation_struct(doc?)?;
+
+        conversations.push(serde_json::json!({
+            "id": conversation.id,
+            "flow_id": conversation.flow_id,
+            "step_id": conversation.step_id,
+            "status": conversation.status,
+            "last_interaction_at": conversation.last_interaction_at,
+            "updated_at": conversation.updated_at,
+            "created_at": conversation.created_at,
+        }));
+    }
+
+    Ok(conversations)
+}
+
+pub fn export_messages(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let collection = db.client.collection::<Document>("message");
+
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "created_at": 1, "interaction_order": 1, "message_order": 1 })
+        .build();
+    let cursor = collection.find(client_filter(client), find_options)?;
+
+    let mut messages = vec![];
+    for doc in cursor {
+        let message = format_message_struct(doc?)?;
+
+        messages.push(serde_json::json!({
+            "id": message.id,
+            "conversation_id": message.conversation_id,
+            "flow_id": message.flow_id,
+            "step_id": message.step_id,
+            "direction": message.direction,
+            "interaction_order": message.interaction_order,
+            "message_order": message.message_order,
+            "payload": message.payload,
+            "created_at": message.created_at,
+        }));
+    }
+
+    Ok(messages)
+}
+
+pub fn export_state(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let collection = db.client.collection::<Document>("state");
+
+    let cursor = collection.find(client_filter(client), None)?;
+
+    let mut items = vec![];
+    for doc in cursor {
+        let state = doc?;
+        let value = decrypt_data(state.get_str("value").unwrap().to_owned())?;
+
+        items.push(serde_json::json!({
+            "type": state.get_str("type").unwrap(),
+            "key": state.get_str("key").unwrap(),
+            "value": value,
+            "created_at": state
+                .get_datetime("created_at")
+                .unwrap()
+                .to_chrono()
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+        }));
+    }
+
+    Ok(items)
+}
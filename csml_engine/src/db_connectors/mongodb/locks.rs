@@ -0,0 +1,83 @@
+use crate::{Client, EngineError, MongoDbClient};
+use bson::{doc, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+
+// error code of MongoDB when a unique index (here _id) is violated
+const DUPLICATE_KEY: i32 = 11000;
+
+fn get_lock_id(client: &Client) -> String {
+    format!(
+        "bot_id:{}#channel_id:{}#user_id:{}",
+        client.bot_id, client.channel_id, client.user_id
+    )
+}
+
+/**
+ * Take the lease of the client if it is free, expired or already ours.
+ * When it is held by another owner, the filter does not match and
+ * the upsert fails on the _id of the existing lease.
+ */
+pub fn acquire_lease(
+    client: &Client,
+    owner: &str,
+    expires_at: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("lock");
+    let now = bson::DateTime::from_chrono(chrono::Utc::now());
+
+    let filter = doc! {
+        "_id": get_lock_id(client),
+        "$or": [
+            { "expires_at": { "$lt": now } },
+            { "owner": owner },
+        ],
+    };
+
+    let update = doc! {
+        "$set": {
+            "client": bson::to_bson(&client)?,
+            "owner": owner,
+            "expires_at": expires_at,
+            "updated_at": now,
+        },
+        "$setOnInsert": { "created_at": now },
+    };
+
+    let options = mongodb::options::UpdateOptions::builder()
+        .upsert(true)
+        .build();
+
+    match collection.update_one(filter, update, options) {
+        Ok(_) => Ok(true),
+        Err(err) => match err.kind.as_ref() {
+            ErrorKind::Write(WriteFailure::WriteError(write_error))
+                if write_error.code == DUPLICATE_KEY =>
+            {
+                Ok(false)
+            }
+            _ => Err(err.into()),
+        },
+    }
+}
+
+pub fn release_lease(client: &Client, owner: &str, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("lock");
+
+    let filter = doc! {
+        "_id": get_lock_id(client),
+        "owner": owner,
+    };
+
+    collection.delete_one(filter, None)?;
+
+    Ok(())
+}
+
+pub fn delete_client_lease(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("lock");
+
+    collection.delete_one(doc! { "_id": get_lock_id(client) }, None)?;
+
+    Ok(())
+}
@@ -1,13 +1,16 @@
 use crate::{
-    db_connectors::{mongodb::get_db, DbMessage},
+    db_connectors::DbMessage,
     encrypt::{decrypt_data, encrypt_data},
-    Client, ConversationInfo, EngineError, MongoDbClient,
+    Client, EngineError, MongoDbClient,
 };
 use bson::{doc, Document};
 use chrono::SecondsFormat;
 
-fn format_messages(
-    data: &ConversationInfo,
+pub fn format_messages(
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     messages: &[serde_json::Value],
     interaction_order: i32,
     direction: &str,
@@ -18,7 +21,10 @@ fn format_messages(
         .enumerate()
         .map(|(i, var)| {
             format_message(
-                data,
+                client,
+                conversation_id,
+                flow_id,
+                step_id,
                 var.clone(),
                 i as i32,
                 interaction_order,
@@ -30,7 +36,10 @@ fn format_messages(
 }
 
 fn format_message(
-    data: &ConversationInfo,
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     message: serde_json::Value,
     msg_order: i32,
     interaction_order: i32,
@@ -39,10 +48,10 @@ fn format_message(
 ) -> Result<Document, EngineError> {
     let time = bson::DateTime::from_chrono(chrono::Utc::now());
     let doc = doc! {
-        "client": bson::to_bson(&data.client)?,
-        "conversation_id": &data.conversation_id,
-        "flow_id": &data.context.flow,
-        "step_id": &data.context.step.get_step(),
+        "client": bson::to_bson(client)?,
+        "conversation_id": conversation_id,
+        "flow_id": flow_id,
+        "step_id": step_id,
         "message_order": msg_order,
         "interaction_order": interaction_order,
         "direction": direction,
@@ -54,7 +63,7 @@ fn format_message(
     Ok(doc)
 }
 
-fn format_message_struct(message: bson::document::Document) -> Result<DbMessage, EngineError> {
+pub fn format_message_struct(message: bson::document::Document) -> Result<DbMessage, EngineError> {
     let encrypted_payload: String = message.get_str("payload").unwrap().to_owned();
     let payload = decrypt_data(encrypted_payload)?;
 
@@ -77,17 +86,29 @@ fn format_message_struct(message: bson::document::Document) -> Result<DbMessage,
 }
 
 pub fn add_messages_bulk(
-    data: &ConversationInfo,
+    client: &Client,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     msgs: &[serde_json::Value],
     interaction_order: i32,
     direction: &str,
     expires_at: Option<bson::DateTime>,
+    db: &MongoDbClient,
 ) -> Result<(), EngineError> {
     if msgs.len() == 0 {
         return Ok(());
     }
-    let docs = format_messages(data, msgs, interaction_order, direction, expires_at)?;
-    let db = get_db(&data.db)?;
+    let docs = format_messages(
+        client,
+        conversation_id,
+        flow_id,
+        step_id,
+        msgs,
+        interaction_order,
+        direction,
+        expires_at,
+    )?;
 
     let message = db.client.collection::<Document>("message");
 
@@ -1,8 +1,16 @@
+pub mod analytics;
+pub mod api_keys;
 pub mod bot;
 pub mod conversations;
+pub mod export;
+pub mod locks;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod rate_limits;
+pub mod schedules;
 pub mod state;
+pub mod unit_of_work;
 
 use crate::{Database, EngineError, MongoDbClient};
 use bson::{doc, Document};
@@ -50,7 +58,7 @@ pub fn init() -> Result<Database, EngineError> {
     };
 
     let client = mongodb::sync::Client::with_uri_str(&uri)?;
-    let mongodb_client = MongoDbClient::new(client.database(&dbname));
+    let mongodb_client = MongoDbClient::new(client.database(&dbname), client);
     create_ttl_indexes(&mongodb_client);
     create_client_indexes(&mongodb_client);
 
@@ -139,6 +147,18 @@ fn create_ttl_indexes(
     .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
     .build();
     state.create_index(index,None).ok();
+
+    // create index expires_at for rate_limit
+    let rate_limit = db.client.collection::<Document>("rate_limit");
+    let index: IndexModel = IndexModel::builder()
+    .keys(
+        doc! {
+            "expires_at": 1
+        }
+    )
+    .options(Some(IndexOptions::builder().expire_after(CoreDuration::new(0, 0)).build()))
+    .build();
+    rate_limit.create_index(index,None).ok();
 }
 
 fn create_client_indexes(
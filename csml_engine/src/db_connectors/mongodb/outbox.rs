@@ -0,0 +1,237 @@
+use crate::{
+    db_connectors::{outbox::OUTBOX_PENDING, DbOutboxMessage},
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::SecondsFormat;
+
+fn format_outbox_message_struct(message: Document) -> Result<DbOutboxMessage, EngineError> {
+    let payload = message.get_str("payload").unwrap().to_owned();
+
+    Ok(DbOutboxMessage {
+        id: message.get_str("id").unwrap().to_owned(),
+        client: bson::from_bson(message.get("client").unwrap().to_owned())?,
+        conversation_id: message.get_str("conversation_id").unwrap().to_owned(),
+        callback_url: message.get_str("callback_url").unwrap().to_owned(),
+        payload: decrypt_data(payload)?,
+        status: message.get_str("status").unwrap().to_owned(),
+        attempts: message.get_i32("attempts").unwrap_or_default(),
+        error: message.get_str("error").ok().map(|error| error.to_owned()),
+        position: message.get_i64("position").unwrap_or_default(),
+        next_attempt_at: message
+            .get_datetime("next_attempt_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: message
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+fn format_outbox_messages(
+    cursor: mongodb::sync::Cursor<Document>,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let mut messages = vec![];
+
+    for doc in cursor {
+        if let Ok(doc) = doc {
+            messages.push(format_outbox_message_struct(doc)?);
+        }
+    }
+
+    Ok(messages)
+}
+
+pub fn create_outbox_message(
+    client: &Client,
+    conversation_id: &str,
+    callback_url: &str,
+    payload: &serde_json::Value,
+    status: &str,
+    error: Option<&str>,
+    position: i64,
+    db: &MongoDbClient,
+) -> Result<String, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+    let now = chrono::Utc::now();
+    let time = bson::DateTime::from_chrono(now);
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let message = doc! {
+        "id": &id,
+        "client": bson::to_bson(&client)?,
+        "conversation_id": conversation_id,
+        "callback_url": callback_url,
+        "payload": encrypt_data(payload)?,
+        "status": status,
+        "attempts": 0,
+        "error": error,
+        "position": position,
+        "next_attempt_at": &time,
+        "updated_at": &time,
+        "created_at": &time
+    };
+
+    collection.insert_one(message, None)?;
+
+    Ok(id)
+}
+
+pub fn get_last_outbox_position(
+    client: &Client,
+    conversation_id: &str,
+    db: &MongoDbClient,
+) -> Result<i64, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "conversation_id": conversation_id,
+    };
+    let find_options = mongodb::options::FindOneOptions::builder()
+        .sort(doc! { "position": -1 })
+        .build();
+
+    match collection.find_one(filter, find_options)? {
+        Some(message) => Ok(message.get_i64("position").unwrap_or_default()),
+        None => Ok(0),
+    }
+}
+
+pub fn get_outbox_messages(db: &MongoDbClient) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "conversation_id": 1, "position": 1 })
+        .build();
+
+    let cursor = collection.find(doc! {}, find_options)?;
+
+    format_outbox_messages(cursor)
+}
+
+pub fn get_client_outbox_messages(
+    client: &Client,
+    status: &str,
+    db: &MongoDbClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "status": status,
+    };
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "position": 1 })
+        .build();
+
+    let cursor = collection.find(filter, find_options)?;
+
+    format_outbox_messages(cursor)
+}
+
+pub fn claim_outbox_message(
+    client: &Client,
+    id: &str,
+    now: bson::DateTime,
+    lease_until: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "id": id,
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "status": OUTBOX_PENDING,
+        "next_attempt_at": { "$lte": now },
+    };
+
+    let result = collection.update_one(
+        filter,
+        doc! {
+            "$set": { "next_attempt_at": lease_until },
+            "$currentDate": { "updated_at": true }
+        },
+        None,
+    )?;
+
+    Ok(result.modified_count > 0)
+}
+
+pub fn update_outbox_message(
+    client: &Client,
+    id: &str,
+    status: &str,
+    attempts: i32,
+    error: Option<&str>,
+    next_attempt_at: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "id": id,
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+
+    collection.update_one(
+        filter,
+        doc! {
+            "$set": {
+                "status": status,
+                "attempts": attempts,
+                "error": error,
+                "next_attempt_at": next_attempt_at,
+            },
+            "$currentDate": { "updated_at": true }
+        },
+        None,
+    )?;
+
+    Ok(())
+}
+
+pub fn delete_outbox_message(
+    client: &Client,
+    id: &str,
+    db: &MongoDbClient,
+) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "id": id,
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+
+    collection.delete_one(filter, None)?;
+
+    Ok(())
+}
+
+pub fn delete_client_outbox(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("outbox");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
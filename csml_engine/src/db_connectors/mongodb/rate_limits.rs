@@ -0,0 +1,161 @@
+use crate::{EngineError, MongoDbClient};
+use bson::{doc, Document};
+use mongodb::error::{ErrorKind, WriteFailure};
+use mongodb::options::{FindOneAndUpdateOptions, ReturnDocument, UpdateOptions};
+
+// error code of MongoDB when a unique index (here _id) is violated
+const DUPLICATE_KEY: i32 = 11000;
+
+fn get_counter_id(scope: &str, name: &str) -> String {
+    format!("{}#{}", scope, name)
+}
+
+fn is_duplicate_key(err: &mongodb::error::Error) -> bool {
+    match err.kind.as_ref() {
+        ErrorKind::Write(WriteFailure::WriteError(write_error)) => {
+            write_error.code == DUPLICATE_KEY
+        }
+        _ => false,
+    }
+}
+
+pub fn get_counter(
+    scope: &str,
+    name: &str,
+    db: &MongoDbClient,
+) -> Result<Option<i64>, EngineError> {
+    let collection = db.client.collection::<Document>("rate_limit");
+    let now = bson::DateTime::from_chrono(chrono::Utc::now());
+
+    let filter = doc! {
+        "_id": get_counter_id(scope, name),
+        "expires_at": { "$gte": now },
+    };
+
+    match collection.find_one(filter, None)? {
+        Some(counter) => Ok(counter.get_i64("value").ok()),
+        None => Ok(None),
+    }
+}
+
+/**
+ * Set the counter only if its current value is `expected` (None if it does not exist
+ * or is expired). When the counter was changed in the meantime, the filter does not
+ * match and the upsert fails on the _id of the existing counter.
+ */
+pub fn set_counter(
+    scope: &str,
+    name: &str,
+    expected: Option<i64>,
+    value: i64,
+    expires_at: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("rate_limit");
+    let now = bson::DateTime::from_chrono(chrono::Utc::now());
+
+    let filter = match expected {
+        Some(expected) => doc! {
+            "_id": get_counter_id(scope, name),
+            "value": expected,
+            "expires_at": { "$gte": now },
+        },
+        None => doc! {
+            "_id": get_counter_id(scope, name),
+            "expires_at": { "$lt": now },
+        },
+    };
+
+    let update = doc! {
+        "$set": {
+            "scope": scope,
+            "name": name,
+            "value": value,
+            "expires_at": expires_at,
+            "updated_at": now,
+        },
+        "$setOnInsert": { "created_at": now },
+    };
+
+    let options = UpdateOptions::builder().upsert(true).build();
+
+    match collection.update_one(filter, update, options) {
+        Ok(_) => Ok(true),
+        Err(err) if is_duplicate_key(&err) => Ok(false),
+        Err(err) => Err(err.into()),
+    }
+}
+
+pub fn increment_counter(
+    scope: &str,
+    name: &str,
+    delta: i64,
+    expires_at: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<i64, EngineError> {
+    let collection = db.client.collection::<Document>("rate_limit");
+    let now = bson::DateTime::from_chrono(chrono::Utc::now());
+
+    // the TTL index removes expired counters with a delay
+    collection.delete_one(
+        doc! {
+            "_id": get_counter_id(scope, name),
+            "expires_at": { "$lt": now },
+        },
+        None,
+    )?;
+
+    let update = doc! {
+        "$inc": { "value": delta },
+        "$set": { "updated_at": now },
+        "$setOnInsert": {
+            "scope": scope,
+            "name": name,
+            "expires_at": expires_at,
+            "created_at": now,
+        },
+    };
+
+    let options = FindOneAndUpdateOptions::builder()
+        .upsert(true)
+        .return_document(ReturnDocument::After)
+        .build();
+
+    let filter = doc! { "_id": get_counter_id(scope, name) };
+
+    let counter =
+        match collection.find_one_and_update(filter.clone(), update.clone(), options.clone()) {
+            Ok(counter) => counter,
+            // concurrent upserts: the counter now exists and can be incremented
+            Err(err) if is_duplicate_key(&err) => {
+                collection.find_one_and_update(filter, update, options)?
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+    match counter {
+        Some(counter) => Ok(counter.get_i64("value").unwrap_or(1)),
+        None => Ok(1),
+    }
+}
+
+pub fn get_counters(scope: &str, db: &MongoDbClient) -> Result<Vec<(String, i64)>, EngineError> {
+    let collection = db.client.collection::<Document>("rate_limit");
+    let now = bson::DateTime::from_chrono(chrono::Utc::now());
+
+    let filter = doc! {
+        "scope": scope,
+        "expires_at": { "$gte": now },
+    };
+
+    let mut counters = vec![];
+    for counter in collection.find(filter, None)? {
+        let counter = counter?;
+
+        if let (Ok(name), Ok(value)) = (counter.get_str("name"), counter.get_i64("value")) {
+            counters.push((name.to_owned(), value));
+        }
+    }
+
+    Ok(counters)
+}
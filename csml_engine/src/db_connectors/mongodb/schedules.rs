@@ -0,0 +1,168 @@
+use crate::{
+    db_connectors::DbSchedule,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, MongoDbClient,
+};
+use bson::{doc, Document};
+use chrono::SecondsFormat;
+
+fn format_schedule_struct(schedule: Document) -> Result<DbSchedule, EngineError> {
+    let job = schedule.get_str("job").unwrap().to_owned();
+
+    Ok(DbSchedule {
+        id: schedule.get_str("id").unwrap().to_owned(),
+        client: bson::from_bson(schedule.get("client").unwrap().to_owned())?,
+        cron: schedule.get_str("cron").ok().map(|cron| cron.to_owned()),
+        job: decrypt_data(job)?,
+        next_run_at: schedule
+            .get_datetime("next_run_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: schedule
+            .get_datetime("created_at")
+            .unwrap()
+            .to_chrono()
+            .to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+fn format_schedules(
+    cursor: mongodb::sync::Cursor<Document>,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let mut schedules = vec![];
+
+    for doc in cursor {
+        if let Ok(doc) = doc {
+            schedules.push(format_schedule_struct(doc)?);
+        }
+    }
+
+    Ok(schedules)
+}
+
+pub fn create_schedule(
+    client: &Client,
+    cron: Option<&str>,
+    job: &serde_json::Value,
+    next_run_at: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<String, EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+    let time = bson::DateTime::from_chrono(chrono::Utc::now());
+    let id = uuid::Uuid::new_v4().to_string();
+
+    let schedule = doc! {
+        "id": &id,
+        "client": bson::to_bson(&client)?,
+        "cron": cron,
+        "job": encrypt_data(job)?,
+        "next_run_at": next_run_at,
+        "updated_at": &time,
+        "created_at": &time
+    };
+
+    collection.insert_one(schedule, None)?;
+
+    Ok(id)
+}
+
+pub fn get_client_schedules(
+    client: &Client,
+    db: &MongoDbClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+    let find_options = mongodb::options::FindOptions::builder()
+        .sort(doc! { "next_run_at": 1 })
+        .build();
+
+    let cursor = collection.find(filter, find_options)?;
+
+    format_schedules(cursor)
+}
+
+pub fn get_due_schedules(
+    now: bson::DateTime,
+    db: &MongoDbClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+
+    let filter = doc! {
+        "next_run_at": { "$lte": now },
+    };
+
+    let cursor = collection.find(filter, None)?;
+
+    format_schedules(cursor)
+}
+
+pub fn claim_schedule(
+    client: &Client,
+    id: &str,
+    now: bson::DateTime,
+    next_run_at: Option<bson::DateTime>,
+    db: &MongoDbClient,
+) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+
+    let filter = doc! {
+        "id": id,
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+        "next_run_at": { "$lte": now },
+    };
+
+    let count = match next_run_at {
+        Some(next_run_at) => {
+            collection
+                .update_one(
+                    filter,
+                    doc! {
+                        "$set": { "next_run_at": next_run_at },
+                        "$currentDate": { "updated_at": true }
+                    },
+                    None,
+                )?
+                .modified_count
+        }
+        None => collection.delete_one(filter, None)?.deleted_count,
+    };
+
+    Ok(count > 0)
+}
+
+pub fn delete_schedule(client: &Client, id: &str, db: &MongoDbClient) -> Result<bool, EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+
+    let filter = doc! {
+        "id": id,
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+
+    let result = collection.delete_one(filter, None)?;
+
+    Ok(result.deleted_count > 0)
+}
+
+pub fn delete_client_schedules(client: &Client, db: &MongoDbClient) -> Result<(), EngineError> {
+    let collection = db.client.collection::<Document>("schedule");
+
+    let filter = doc! {
+        "client.bot_id": client.bot_id.to_owned(),
+        "client.user_id": client.user_id.to_owned(),
+        "client.channel_id": client.channel_id.to_owned(),
+    };
+
+    collection.delete_many(filter, None)?;
+
+    Ok(())
+}
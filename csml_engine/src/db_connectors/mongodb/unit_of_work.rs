@@ -0,0 +1,287 @@
+use crate::db_connectors::{unit_of_work::Write, utils::get_expires_at_for_mongodb};
+use crate::{EngineError, MongoDbClient};
+use bson::{doc, Document};
+use mongodb::sync::ClientSession;
+use std::sync::OnceLock;
+
+use super::{conversations, memories, messages, state};
+
+static TRANSACTIONS_SUPPORTED: OnceLock<bool> = OnceLock::new();
+
+/**
+ * Transactions are only available on replica sets and sharded clusters
+ */
+fn supports_transactions(db: &MongoDbClient) -> bool {
+    if let Some(supported) = TRANSACTIONS_SUPPORTED.get() {
+        return *supported;
+    }
+
+    match db.client.run_command(doc! { "isMaster": 1 }, None) {
+        Ok(reply) => {
+            let supported =
+                reply.get_str("setName").is_ok() || matches!(reply.get_str("msg"), Ok("isdbgrid"));
+
+            *TRANSACTIONS_SUPPORTED.get_or_init(|| supported)
+        }
+        Err(_) => false,
+    }
+}
+
+/**
+ * Writes without transaction are only allowed explicitly, for development on a standalone server
+ */
+fn allows_non_atomic_writes() -> bool {
+    match std::env::var("MONGODB_ALLOW_NON_ATOMIC_WRITES") {
+        Ok(val) => val.to_lowercase() == "true",
+        Err(_) => false,
+    }
+}
+
+/**
+ * All the writes are made in a session transaction, aborted on the first error.
+ * A standalone server does not support transactions: the interaction fails, unless
+ * MONGODB_ALLOW_NON_ATOMIC_WRITES is set to apply the writes one by one.
+ */
+pub fn commit(writes: &[Write], db: &MongoDbClient) -> Result<(), EngineError> {
+    if !supports_transactions(db) {
+        if !allows_non_atomic_writes() {
+            return Err(EngineError::Manager(
+                "MongoDB transactions are only available on replica sets and sharded clusters, \
+                set MONGODB_ALLOW_NON_ATOMIC_WRITES=true to write without transaction"
+                    .to_owned(),
+            ));
+        }
+
+        for write in writes {
+            apply_write(write, db)?;
+        }
+
+        return Ok(());
+    }
+
+    let mut session = db.sessions.start_session(None)?;
+    session.start_transaction(None)?;
+
+    for write in writes {
+        if let Err(err) = apply_write_with_session(write, db, &mut session) {
+            session.abort_transaction().ok();
+            return Err(err);
+        }
+    }
+
+    session.commit_transaction()?;
+
+    Ok(())
+}
+
+fn apply_write_with_session(
+    write: &Write,
+    db: &MongoDbClient,
+    session: &mut ClientSession,
+) -> Result<(), EngineError> {
+    match write {
+        Write::Messages {
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
+            messages,
+            interaction_order,
+            direction,
+            ttl,
+        } => {
+            let docs = messages::format_messages(
+                client,
+                conversation_id,
+                flow_id,
+                step_id,
+                messages,
+                *interaction_order,
+                direction,
+                get_expires_at_for_mongodb(*ttl),
+            )?;
+
+            db.client
+                .collection::<Document>("message")
+                .insert_many_with_session(docs, None, session)?;
+        }
+        Write::Memories {
+            client,
+            memories,
+            ttl,
+        } => {
+            let docs =
+                memories::format_memories(client, memories, get_expires_at_for_mongodb(*ttl))?;
+
+            db.client
+                .collection::<Document>("memory")
+                .insert_many_with_session(docs, None, session)?;
+        }
+        Write::DeleteMemory { client, key } => {
+            let filter = doc! {
+                "client.bot_id": client.bot_id.to_owned(),
+                "client.user_id": client.user_id.to_owned(),
+                "client.channel_id": client.channel_id.to_owned(),
+                "key": key,
+            };
+
+            db.client
+                .collection::<Document>("memory")
+                .delete_many_with_session(filter, None, session)?;
+        }
+        Write::DeleteMemories { client } => {
+            let filter = doc! {
+                "client.bot_id": client.bot_id.to_owned(),
+                "client.user_id": client.user_id.to_owned(),
+                "client.channel_id": client.channel_id.to_owned(),
+            };
+
+            db.client
+                .collection::<Document>("memory")
+                .delete_many_with_session(filter, None, session)?;
+        }
+        Write::Conversation {
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
+        } => {
+            db.client
+                .collection::<Document>("conversation")
+                .update_one_with_session(
+                    conversations::format_conversation_filter(conversation_id, client),
+                    conversations::format_conversation_update(
+                        flow_id.to_owned(),
+                        step_id.to_owned(),
+                    ),
+                    None,
+                    session,
+                )?;
+        }
+        Write::ConversationStatus {
+            client,
+            conversation_id,
+            from_status,
+            status,
+        } => {
+            let mut filter = conversations::format_conversation_filter(conversation_id, client);
+            filter.insert("status", from_status);
+
+            // nothing is changed if the status of the conversation already changed
+            db.client
+                .collection::<Document>("conversation")
+                .update_one_with_session(
+                    filter,
+                    conversations::format_status_update(status),
+                    None,
+                    session,
+                )?;
+        }
+        Write::State {
+            client,
+            _type,
+            key,
+            value,
+            ttl,
+        } => {
+            let docs = state::format_state_data(
+                client,
+                _type,
+                vec![(key.as_str(), value)],
+                get_expires_at_for_mongodb(*ttl),
+            )?;
+
+            db.client
+                .collection::<Document>("state")
+                .insert_many_with_session(docs, None, session)?;
+        }
+        Write::DeleteState { client, _type, key } => {
+            let filter = doc! {
+                "client": bson::to_bson(client)?,
+                "type": _type,
+                "key": key,
+            };
+
+            db.client
+                .collection::<Document>("state")
+                .delete_one_with_session(filter, None, session)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn apply_write(write: &Write, db: &MongoDbClient) -> Result<(), EngineError> {
+    match write {
+        Write::Messages {
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
+            messages,
+            interaction_order,
+            direction,
+            ttl,
+        } => messages::add_messages_bulk(
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
+            messages,
+            *interaction_order,
+            direction,
+            get_expires_at_for_mongodb(*ttl),
+            db,
+        ),
+        Write::Memories {
+            client,
+            memories,
+            ttl,
+        } => memories::add_memories(client, memories, get_expires_at_for_mongodb(*ttl), db),
+        Write::DeleteMemory { client, key } => memories::delete_client_memory(client, key, db),
+        Write::DeleteMemories { client } => memories::delete_client_memories(client, db),
+        Write::Conversation {
+            client,
+            conversation_id,
+            flow_id,
+            step_id,
+        } => conversations::update_conversation(
+            conversation_id,
+            client,
+            flow_id.to_owned(),
+            step_id.to_owned(),
+            db,
+        ),
+        Write::ConversationStatus {
+            client,
+            conversation_id,
+            from_status,
+            status,
+        } => {
+            conversations::set_conversation_status(
+                conversation_id,
+                client,
+                from_status,
+                status,
+                db,
+            )?;
+            Ok(())
+        }
+        Write::State {
+            client,
+            _type,
+            key,
+            value,
+            ttl,
+        } => state::set_state_items(
+            client,
+            _type,
+            vec![(key.as_str(), value)],
+            get_expires_at_for_mongodb(*ttl),
+            db,
+        ),
+        Write::DeleteState { client, _type, key } => {
+            state::delete_state_key(client, _type, key, db)
+        }
+    }
+}
@@ -0,0 +1,491 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::db_connectors::DbOutboxMessage;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+
+// waiting to be delivered to the callback_url
+pub const OUTBOX_PENDING: &str = "pending";
+// undeliverable, kept until replayed or deleted
+pub const OUTBOX_DEAD: &str = "dead";
+
+pub fn create_outbox_message(
+    client: &Client,
+    conversation_id: &str,
+    callback_url: &str,
+    payload: &serde_json::Value,
+    status: &str,
+    error: Option<&str>,
+    position: i64,
+    db: &mut Database,
+) -> Result<String, EngineError> {
+    let _db_call = db_call("create_outbox_message");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call create {} outbox message", status),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call create {} outbox message", status),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::create_outbox_message(
+            client,
+            conversation_id,
+            callback_url,
+            payload,
+            status,
+            error,
+            position,
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::create_outbox_message(
+            client,
+            conversation_id,
+            callback_url,
+            payload,
+            status,
+            error,
+            position,
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::create_outbox_message(
+            client,
+            conversation_id,
+            callback_url,
+            payload,
+            status,
+            error,
+            position,
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::create_outbox_message(
+            client,
+            conversation_id,
+            callback_url,
+            payload,
+            status,
+            error,
+            position,
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Messages of a conversation are numbered in the order they are sent,
+ * returns the number of the last one still in the outbox or 0 if there is none
+ */
+pub fn get_last_outbox_position(
+    client: &Client,
+    conversation_id: &str,
+    db: &mut Database,
+) -> Result<i64, EngineError> {
+    let _db_call = db_call("get_last_outbox_position");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get last outbox position"),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!(
+                "db call get last outbox position conversation_id: {}",
+                conversation_id
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::get_last_outbox_position(client, conversation_id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::get_last_outbox_position(client, conversation_id, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::get_last_outbox_position(client, conversation_id, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::get_last_outbox_position(client, conversation_id, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Pending and dead messages of every client, sorted by conversation and position
+ */
+pub fn get_outbox_messages(db: &mut Database) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let _db_call = db_call("get_outbox_messages");
+    csml_logger(
+        CsmlLog::new(None, None, None, format!("db call get outbox messages")),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::get_outbox_messages(db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::get_outbox_messages(db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::get_outbox_messages(db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::get_outbox_messages(db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn get_client_outbox_messages(
+    client: &Client,
+    status: &str,
+    db: &mut Database,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let _db_call = db_call("get_client_outbox_messages");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get client {} outbox messages", status),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call get client {} outbox messages", status),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::get_client_outbox_messages(client, status, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::get_client_outbox_messages(client, status, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::get_client_outbox_messages(client, status, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::get_client_outbox_messages(client, status, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Take a pending message before sending it: its next attempt is moved to `lease_until`,
+ * only if it is still pending and due at `now`. Returns false if it was taken
+ * by another instance, delivered or moved to the dead letters in the meantime.
+ */
+pub fn claim_outbox_message(
+    client: &Client,
+    id: &str,
+    now: DateTime<Utc>,
+    lease_until: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let _db_call = db_call("claim_outbox_message");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call claim outbox message {}", id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call claim outbox message {} until {}", id, lease_until),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::claim_outbox_message(
+            client,
+            id,
+            bson::DateTime::from_chrono(now),
+            bson::DateTime::from_chrono(lease_until),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::claim_outbox_message(
+            client,
+            id,
+            now.timestamp(),
+            lease_until.timestamp(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::claim_outbox_message(
+            client,
+            id,
+            now.naive_utc(),
+            lease_until.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::claim_outbox_message(
+            client,
+            id,
+            now.naive_utc(),
+            lease_until.naive_utc(),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn update_outbox_message(
+    client: &Client,
+    id: &str,
+    status: &str,
+    attempts: i32,
+    error: Option<&str>,
+    next_attempt_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let _db_call = db_call("update_outbox_message");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call update outbox message {} status: {} attempts: {}",
+                id, status, attempts
+            ),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!(
+                "db call update outbox message {} status: {} attempts: {}",
+                id, status, attempts
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::update_outbox_message(
+            client,
+            id,
+            status,
+            attempts,
+            error,
+            bson::DateTime::from_chrono(next_attempt_at),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::update_outbox_message(
+            client,
+            id,
+            status,
+            attempts,
+            error,
+            next_attempt_at.timestamp(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::update_outbox_message(
+            client,
+            id,
+            status,
+            attempts,
+            error,
+            next_attempt_at.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::update_outbox_message(
+            client,
+            id,
+            status,
+            attempts,
+            error,
+            next_attempt_at.naive_utc(),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn delete_outbox_message(
+    client: &Client,
+    id: &str,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_outbox_message");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call delete outbox message {}", id),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call delete outbox message {}", id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::outbox::delete_outbox_message(client, id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::outbox::delete_outbox_message(client, id, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::outbox::delete_outbox_message(client, id, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::outbox::delete_outbox_message(client, id, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
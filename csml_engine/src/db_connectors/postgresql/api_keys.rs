@@ -0,0 +1,65 @@
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::{db_connectors::DbApiKey, EngineError, PostgresqlClient};
+
+use super::{models, schema::csml_api_keys};
+
+fn format_api_key_struct(api_key: models::ApiKey) -> Result<DbApiKey, EngineError> {
+    let bot_ids = match api_key.bot_ids {
+        Some(bot_ids) => Some(serde_json::from_str(&bot_ids)?),
+        None => None,
+    };
+
+    Ok(DbApiKey {
+        key_hash: api_key.key_hash,
+        bot_ids,
+        scopes: serde_json::from_str(&api_key.scopes)?,
+        created_at: api_key
+            .created_at
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string(),
+    })
+}
+
+pub fn create_api_key(
+    key_hash: &str,
+    bot_ids: &Option<Vec<String>>,
+    scopes: &[String],
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let bot_ids = match bot_ids {
+        Some(bot_ids) => Some(serde_json::to_string(bot_ids)?),
+        None => None,
+    };
+
+    let new_api_key = models::NewApiKey {
+        key_hash,
+        bot_ids,
+        scopes: serde_json::to_string(scopes)?,
+    };
+
+    diesel::insert_into(csml_api_keys::table)
+        .values(&new_api_key)
+        .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn get_api_key(key_hash: &str, db: &PostgresqlClient) -> Result<Option<DbApiKey>, EngineError> {
+    let api_key = csml_api_keys::table
+        .filter(csml_api_keys::key_hash.eq(key_hash))
+        .first::<models::ApiKey>(&db.client)
+        .optional()?;
+
+    match api_key {
+        Some(api_key) => Ok(Some(format_api_key_struct(api_key)?)),
+        None => Ok(None),
+    }
+}
+
+pub fn delete_api_key(key_hash: &str, db: &PostgresqlClient) -> Result<(), EngineError> {
+    diesel::delete(csml_api_keys::table.filter(csml_api_keys::key_hash.eq(key_hash)))
+        .execute(&db.client)?;
+
+    Ok(())
+}
@@ -7,7 +7,7 @@ use crate::{
 use super::{
     schema::{
         csml_conversations,
-        csml_memories, csml_states
+        csml_memories, csml_rate_limits, csml_states
     }
 };
 
@@ -31,5 +31,10 @@ pub fn delete_expired_data(
         .filter(csml_states::expires_at.lt(date_now))
     ).execute(&db.client).ok();
 
+    diesel::delete(
+        csml_rate_limits::table
+        .filter(csml_rate_limits::expires_at.lt(date_now))
+    ).execute(&db.client).ok();
+
     Ok(())
 }
\ No newline at end of file
@@ -1,10 +1,9 @@
 use diesel::{RunQueryDsl, ExpressionMethods, QueryDsl};
 
 use crate::{
-    db_connectors::postgresql::get_db,
     encrypt::{decrypt_data, encrypt_data},
     EngineError, PostgresqlClient,
-    ConversationInfo, Memory, Client
+    Memory, Client
 };
 
 use super::{
@@ -16,18 +15,17 @@ use chrono::{NaiveDateTime};
 use std::collections::HashMap;
 
 pub fn add_memories(
-    data: &mut ConversationInfo,
+    client: &Client,
     memories: &HashMap<String, Memory>,
     expires_at: Option<NaiveDateTime>,
+    db: &PostgresqlClient,
 ) -> Result<(), EngineError> {
     if memories.is_empty() {
         return Ok(());
     }
 
-    let db = get_db(&data.db)?;
-
     for (key, mem) in memories.iter() {
-        create_client_memory(&data.client, key, &mem.value, expires_at, db)?;
+        create_client_memory(client, key, &mem.value, expires_at, db)?;
     }
 
     Ok(())
@@ -1,9 +1,8 @@
 use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
 
 use crate::{
-    db_connectors::postgresql::get_db,
     encrypt::{decrypt_data, encrypt_data},
-    Client, ConversationInfo, EngineError, PostgresqlClient,
+    Client, EngineError, PostgresqlClient,
 };
 
 use super::{
@@ -14,28 +13,29 @@ use super::{
 use chrono::NaiveDateTime;
 
 pub fn add_messages_bulk(
-    data: &ConversationInfo,
+    conversation_id: &str,
+    flow_id: &str,
+    step_id: &str,
     msgs: &[serde_json::Value],
     interaction_order: i32,
     direction: &str,
     expires_at: Option<NaiveDateTime>,
+    db: &PostgresqlClient,
 ) -> Result<(), EngineError> {
     if msgs.len() == 0 {
         return Ok(());
     }
 
-    let db = get_db(&data.db)?;
-
     let mut new_messages = vec![];
     for (message_order, message) in msgs.iter().enumerate() {
-        let conversation_id = uuid::Uuid::parse_str(&data.conversation_id).unwrap();
+        let conversation_id = uuid::Uuid::parse_str(conversation_id).unwrap();
 
         let msg = models::NewMessages {
             id: uuid::Uuid::new_v4(),
             conversation_id,
 
-            flow_id: &data.context.flow,
-            step_id: data.context.step.get_step_ref(),
+            flow_id,
+            step_id,
             direction,
             payload: encrypt_data(&message)?,
             content_type: &message["content_type"].as_str().unwrap_or("text"),
@@ -132,6 +132,118 @@ pub struct NewMessages<'a> {
     pub expires_at: Option<NaiveDateTime>,
 }
 
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_locks"]
+pub struct NewLock<'a> {
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub owner: &'a str,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_rate_limits"]
+pub struct NewRateLimit<'a> {
+    pub scope: &'a str,
+    pub name: &'a str,
+
+    pub value: i64,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[primary_key(key_hash)]
+#[table_name = "csml_api_keys"]
+pub struct ApiKey {
+    pub key_hash: String,
+
+    pub bot_ids: Option<String>,
+    pub scopes: String,
+
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, PartialEq, Debug)]
+#[table_name = "csml_api_keys"]
+pub struct NewApiKey<'a> {
+    pub key_hash: &'a str,
+
+    pub bot_ids: Option<String>,
+    pub scopes: String,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_outbox"]
+pub struct OutboxMessage {
+    pub id: Uuid,
+
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+
+    pub conversation_id: String,
+    pub callback_url: String,
+    pub payload: String,
+    pub status: String,
+    pub attempts: i32,
+    pub error: Option<String>,
+    pub position: i64,
+    pub next_attempt_at: NaiveDateTime,
+
+    pub updated_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_outbox"]
+pub struct NewOutboxMessage<'a> {
+    pub id: Uuid,
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub conversation_id: &'a str,
+    pub callback_url: &'a str,
+    pub payload: String,
+    pub status: &'a str,
+    pub attempts: i32,
+    pub error: Option<&'a str>,
+    pub position: i64,
+    pub next_attempt_at: NaiveDateTime,
+}
+
+#[derive(Identifiable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_schedules"]
+pub struct Schedule {
+    pub id: Uuid,
+
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+
+    pub cron: Option<String>,
+    pub job: String,
+    pub next_run_at: NaiveDateTime,
+
+    pub updated_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Queryable, PartialEq, Debug)]
+#[table_name = "csml_schedules"]
+pub struct NewSchedule<'a> {
+    pub id: Uuid,
+    pub bot_id: &'a str,
+    pub channel_id: &'a str,
+    pub user_id: &'a str,
+
+    pub cron: Option<&'a str>,
+    pub job: String,
+    pub next_run_at: NaiveDateTime,
+}
+
 #[derive(Identifiable, Insertable, Queryable, Associations, PartialEq, Debug)]
 #[table_name = "csml_states"]
 pub struct State {
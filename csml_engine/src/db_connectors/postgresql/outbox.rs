@@ -0,0 +1,226 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    db_connectors::{outbox::OUTBOX_PENDING, DbOutboxMessage},
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, PostgresqlClient,
+};
+
+use super::{models, schema::csml_outbox};
+use chrono::NaiveDateTime;
+
+fn format_outbox_message_struct(
+    message: models::OutboxMessage,
+) -> Result<DbOutboxMessage, EngineError> {
+    Ok(DbOutboxMessage {
+        id: message.id.to_string(),
+        client: Client {
+            bot_id: message.bot_id,
+            channel_id: message.channel_id,
+            user_id: message.user_id,
+        },
+        conversation_id: message.conversation_id,
+        callback_url: message.callback_url,
+        payload: decrypt_data(message.payload)?,
+        status: message.status,
+        attempts: message.attempts,
+        error: message.error,
+        position: message.position,
+        next_attempt_at: message
+            .next_attempt_at
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string(),
+        created_at: message
+            .created_at
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string(),
+    })
+}
+
+pub fn create_outbox_message(
+    client: &Client,
+    conversation_id: &str,
+    callback_url: &str,
+    payload: &serde_json::Value,
+    status: &str,
+    error: Option<&str>,
+    position: i64,
+    db: &PostgresqlClient,
+) -> Result<String, EngineError> {
+    let id = uuid::Uuid::new_v4();
+    let now = chrono::Utc::now();
+
+    let new_message = models::NewOutboxMessage {
+        id,
+        bot_id: &client.bot_id,
+        channel_id: &client.channel_id,
+        user_id: &client.user_id,
+        conversation_id,
+        callback_url,
+        payload: encrypt_data(payload)?,
+        status,
+        attempts: 0,
+        error,
+        position,
+        next_attempt_at: now.naive_utc(),
+    };
+
+    diesel::insert_into(csml_outbox::table)
+        .values(&new_message)
+        .execute(&db.client)?;
+
+    Ok(id.to_string())
+}
+
+pub fn get_last_outbox_position(
+    client: &Client,
+    conversation_id: &str,
+    db: &PostgresqlClient,
+) -> Result<i64, EngineError> {
+    let position: Option<i64> = csml_outbox::table
+        .filter(csml_outbox::bot_id.eq(&client.bot_id))
+        .filter(csml_outbox::channel_id.eq(&client.channel_id))
+        .filter(csml_outbox::user_id.eq(&client.user_id))
+        .filter(csml_outbox::conversation_id.eq(conversation_id))
+        .select(diesel::dsl::max(csml_outbox::position))
+        .first(&db.client)?;
+
+    Ok(position.unwrap_or(0))
+}
+
+pub fn get_outbox_messages(db: &PostgresqlClient) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let messages: Vec<models::OutboxMessage> = csml_outbox::table
+        .order_by((
+            csml_outbox::conversation_id.asc(),
+            csml_outbox::position.asc(),
+        ))
+        .load(&db.client)?;
+
+    messages
+        .into_iter()
+        .map(format_outbox_message_struct)
+        .collect()
+}
+
+pub fn get_client_outbox_messages(
+    client: &Client,
+    status: &str,
+    db: &PostgresqlClient,
+) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let messages: Vec<models::OutboxMessage> = csml_outbox::table
+        .filter(csml_outbox::bot_id.eq(&client.bot_id))
+        .filter(csml_outbox::channel_id.eq(&client.channel_id))
+        .filter(csml_outbox::user_id.eq(&client.user_id))
+        .filter(csml_outbox::status.eq(status))
+        .order_by(csml_outbox::position.asc())
+        .load(&db.client)?;
+
+    messages
+        .into_iter()
+        .map(format_outbox_message_struct)
+        .collect()
+}
+
+pub fn claim_outbox_message(
+    client: &Client,
+    id: &str,
+    now: NaiveDateTime,
+    lease_until: NaiveDateTime,
+    db: &PostgresqlClient,
+) -> Result<bool, EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(false),
+    };
+
+    let count = diesel::update(
+        csml_outbox::table
+            .filter(csml_outbox::id.eq(id))
+            .filter(csml_outbox::bot_id.eq(&client.bot_id))
+            .filter(csml_outbox::channel_id.eq(&client.channel_id))
+            .filter(csml_outbox::user_id.eq(&client.user_id))
+            .filter(csml_outbox::status.eq(OUTBOX_PENDING))
+            .filter(csml_outbox::next_attempt_at.le(now)),
+    )
+    .set((
+        csml_outbox::next_attempt_at.eq(lease_until),
+        csml_outbox::updated_at.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(&db.client)?;
+
+    Ok(count > 0)
+}
+
+pub fn update_outbox_message(
+    client: &Client,
+    id: &str,
+    status: &str,
+    attempts: i32,
+    error: Option<&str>,
+    next_attempt_at: NaiveDateTime,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    diesel::update(
+        csml_outbox::table
+            .filter(csml_outbox::id.eq(id))
+            .filter(csml_outbox::bot_id.eq(&client.bot_id))
+            .filter(csml_outbox::channel_id.eq(&client.channel_id))
+            .filter(csml_outbox::user_id.eq(&client.user_id)),
+    )
+    .set((
+        csml_outbox::status.eq(status),
+        csml_outbox::attempts.eq(attempts),
+        csml_outbox::error.eq(error),
+        csml_outbox::next_attempt_at.eq(next_attempt_at),
+        csml_outbox::updated_at.eq(chrono::Utc::now().naive_utc()),
+    ))
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_outbox_message(
+    client: &Client,
+    id: &str,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    let id = match uuid::Uuid::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(()),
+    };
+
+    diesel::delete(
+        csml_outbox::table
+            .filter(csml_outbox::id.eq(id))
+            .filter(csml_outbox::bot_id.eq(&client.bot_id))
+            .filter(csml_outbox::channel_id.eq(&client.channel_id))
+            .filter(csml_outbox::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_client_outbox(client: &Client, db: &PostgresqlClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_outbox::table
+            .filter(csml_outbox::bot_id.eq(&client.bot_id))
+            .filter(csml_outbox::channel_id.eq(&client.channel_id))
+            .filter(csml_outbox::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &PostgresqlClient) -> Result<(), EngineError> {
+    diesel::delete(csml_outbox::table.filter(csml_outbox::bot_id.eq(bot_id)))
+        .execute(&db.client)?;
+
+    Ok(())
+}
@@ -0,0 +1,132 @@
+use diesel::{ExpressionMethods, OptionalExtension, QueryDsl, RunQueryDsl};
+
+use crate::{EngineError, PostgresqlClient};
+
+use super::{models, schema::csml_rate_limits};
+use chrono::NaiveDateTime;
+
+pub fn get_counter(
+    scope: &str,
+    name: &str,
+    db: &PostgresqlClient,
+) -> Result<Option<i64>, EngineError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let value = csml_rate_limits::table
+        .filter(csml_rate_limits::scope.eq(scope))
+        .filter(csml_rate_limits::name.eq(name))
+        .filter(csml_rate_limits::expires_at.ge(now))
+        .select(csml_rate_limits::value)
+        .first::<i64>(&db.client)
+        .optional()?;
+
+    Ok(value)
+}
+
+/**
+ * Set the counter only if its current value is `expected` (None if it does not exist
+ * or is expired). Returns false if the counter was changed in the meantime.
+ */
+pub fn set_counter(
+    scope: &str,
+    name: &str,
+    expected: Option<i64>,
+    value: i64,
+    expires_at: NaiveDateTime,
+    db: &PostgresqlClient,
+) -> Result<bool, EngineError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    if let Some(expected) = expected {
+        let updated = diesel::update(
+            csml_rate_limits::table
+                .filter(csml_rate_limits::scope.eq(scope))
+                .filter(csml_rate_limits::name.eq(name))
+                .filter(csml_rate_limits::value.eq(expected))
+                .filter(csml_rate_limits::expires_at.ge(now)),
+        )
+        .set((
+            csml_rate_limits::value.eq(value),
+            csml_rate_limits::expires_at.eq(expires_at),
+            csml_rate_limits::updated_at.eq(now),
+        ))
+        .execute(&db.client)?;
+
+        return Ok(updated > 0);
+    }
+
+    delete_expired_counter(scope, name, db)?;
+
+    let new_counter = models::NewRateLimit {
+        scope,
+        name,
+        value,
+        expires_at,
+    };
+
+    // the primary key makes concurrent inserts fail for all but one request
+    let inserted = diesel::insert_into(csml_rate_limits::table)
+        .values(&new_counter)
+        .on_conflict_do_nothing()
+        .execute(&db.client)?;
+
+    Ok(inserted > 0)
+}
+
+pub fn increment_counter(
+    scope: &str,
+    name: &str,
+    delta: i64,
+    expires_at: NaiveDateTime,
+    db: &PostgresqlClient,
+) -> Result<i64, EngineError> {
+    delete_expired_counter(scope, name, db)?;
+
+    let new_counter = models::NewRateLimit {
+        scope,
+        name,
+        value: delta,
+        expires_at,
+    };
+
+    let value = diesel::insert_into(csml_rate_limits::table)
+        .values(&new_counter)
+        .on_conflict((csml_rate_limits::scope, csml_rate_limits::name))
+        .do_update()
+        .set((
+            csml_rate_limits::value.eq(csml_rate_limits::value + delta),
+            csml_rate_limits::updated_at.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .returning(csml_rate_limits::value)
+        .get_result::<i64>(&db.client)?;
+
+    Ok(value)
+}
+
+pub fn get_counters(scope: &str, db: &PostgresqlClient) -> Result<Vec<(String, i64)>, EngineError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let counters = csml_rate_limits::table
+        .filter(csml_rate_limits::scope.eq(scope))
+        .filter(csml_rate_limits::expires_at.ge(now))
+        .select((csml_rate_limits::name, csml_rate_limits::value))
+        .load::<(String, i64)>(&db.client)?;
+
+    Ok(counters)
+}
+
+fn delete_expired_counter(
+    scope: &str,
+    name: &str,
+    db: &PostgresqlClient,
+) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_rate_limits::table
+            .filter(csml_rate_limits::scope.eq(scope))
+            .filter(csml_rate_limits::name.eq(name))
+            .filter(csml_rate_limits::expires_at.lt(chrono::Utc::now().naive_utc())),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
@@ -9,6 +9,15 @@ table! {
     }
 }
 
+table! {
+    csml_api_keys (key_hash) {
+        key_hash -> Varchar,
+        bot_ids -> Nullable<Text>,
+        scopes -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_conversations (id) {
         id -> Uuid,
@@ -25,6 +34,18 @@ table! {
     }
 }
 
+table! {
+    csml_locks (bot_id, channel_id, user_id) {
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        owner -> Varchar,
+        expires_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_memories (id) {
         id -> Uuid,
@@ -56,6 +77,50 @@ table! {
     }
 }
 
+table! {
+    csml_outbox (id) {
+        id -> Uuid,
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        conversation_id -> Varchar,
+        callback_url -> Varchar,
+        payload -> Varchar,
+        status -> Varchar,
+        attempts -> Integer,
+        error -> Nullable<Varchar>,
+        position -> BigInt,
+        next_attempt_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_rate_limits (scope, name) {
+        scope -> Varchar,
+        name -> Varchar,
+        value -> Int8,
+        expires_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_schedules (id) {
+        id -> Uuid,
+        bot_id -> Varchar,
+        channel_id -> Varchar,
+        user_id -> Varchar,
+        cron -> Nullable<Varchar>,
+        job -> Varchar,
+        next_run_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_states (id) {
         id -> Uuid,
@@ -76,8 +141,13 @@ joinable!(csml_messages -> csml_conversations (conversation_id));
 
 allow_tables_to_appear_in_same_query!(
     cmsl_bot_versions,
+    csml_api_keys,
     csml_conversations,
+    csml_locks,
     csml_memories,
     csml_messages,
+    csml_outbox,
+    csml_rate_limits,
+    csml_schedules,
     csml_states,
 );
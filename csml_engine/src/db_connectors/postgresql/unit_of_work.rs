@@ -0,0 +1,87 @@
+use diesel::Connection;
+
+use crate::db_connectors::{unit_of_work::Write, utils::get_expires_at_for_postgresql};
+use crate::{EngineError, PostgresqlClient};
+
+use super::{conversations, memories, messages, state};
+
+/**
+ * All the writes are made in a single SQL transaction, rolled back on the first error
+ */
+pub fn commit(writes: &[Write], db: &PostgresqlClient) -> Result<(), EngineError> {
+    db.client.transaction::<_, EngineError, _>(|| {
+        for write in writes {
+            apply_write(write, db)?;
+        }
+
+        Ok(())
+    })
+}
+
+fn apply_write(write: &Write, db: &PostgresqlClient) -> Result<(), EngineError> {
+    match write {
+        Write::Messages {
+            client: _,
+            conversation_id,
+            flow_id,
+            step_id,
+            messages,
+            interaction_order,
+            direction,
+            ttl,
+        } => messages::add_messages_bulk(
+            conversation_id,
+            flow_id,
+            step_id,
+            messages,
+            *interaction_order,
+            direction,
+            get_expires_at_for_postgresql(*ttl),
+            db,
+        ),
+        Write::Memories {
+            client,
+            memories,
+            ttl,
+        } => memories::add_memories(client, memories, get_expires_at_for_postgresql(*ttl), db),
+        Write::DeleteMemory { client, key } => memories::delete_client_memory(client, key, db),
+        Write::DeleteMemories { client } => memories::delete_client_memories(client, db),
+        Write::Conversation {
+            client: _,
+            conversation_id,
+            flow_id,
+            step_id,
+        } => conversations::update_conversation(
+            conversation_id,
+            flow_id.to_owned(),
+            step_id.to_owned(),
+            db,
+        ),
+        Write::ConversationStatus {
+            client,
+            conversation_id,
+            from_status,
+            status,
+        } => {
+            // nothing is changed if the status of the conversation already changed
+            conversations::set_conversation_status(conversation_id, client, from_status, status, db)?;
+            Ok(())
+        }
+        Write::State {
+            client,
+            _type,
+            key,
+            value,
+            ttl,
+        } => state::set_state_items(
+            client,
+            _type,
+            vec![(key.as_str(), value)],
+            get_expires_at_for_postgresql(*ttl),
+            db,
+        ),
+        Write::DeleteState { client, _type, key } => {
+            state::delete_state_key(client, _type, key, db)
+        }
+    }
+}
@@ -0,0 +1,252 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Database, EngineError};
+use chrono::{DateTime, Utc};
+
+/**
+ * Value of a counter, None if it does not exist or is expired
+ */
+pub fn get_counter(scope: &str, name: &str, db: &mut Database) -> Result<Option<i64>, EngineError> {
+    let _db_call = db_call("get_counter");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get counter {} of {}", name, scope),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::rate_limits::get_counter(scope, name, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::rate_limits::get_counter(scope, name, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::rate_limits::get_counter(scope, name, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::rate_limits::get_counter(scope, name, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Conditional write of a counter: succeeds only if its current value is still `expected`
+ */
+pub fn set_counter(
+    scope: &str,
+    name: &str,
+    expected: Option<i64>,
+    value: i64,
+    expires_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let _db_call = db_call("set_counter");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call set counter {} of {} from {:?} to {}",
+                name, scope, expected, value
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::rate_limits::set_counter(
+            scope,
+            name,
+            expected,
+            value,
+            bson::DateTime::from_chrono(expires_at),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::rate_limits::set_counter(
+            scope,
+            name,
+            expected,
+            value,
+            expires_at.timestamp(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::rate_limits::set_counter(
+            scope,
+            name,
+            expected,
+            value,
+            expires_at.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::rate_limits::set_counter(
+            scope,
+            name,
+            expected,
+            value,
+            expires_at.naive_utc(),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Atomic increment of a counter by `delta`, created with the value `delta` if it does not
+ * exist or is expired. A negative delta decrements the counter.
+ */
+pub fn increment_counter(
+    scope: &str,
+    name: &str,
+    delta: i64,
+    expires_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<i64, EngineError> {
+    let _db_call = db_call("increment_counter");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call increment counter {} of {}", name, scope),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::rate_limits::increment_counter(
+            scope,
+            name,
+            delta,
+            bson::DateTime::from_chrono(expires_at),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::rate_limits::increment_counter(
+            scope,
+            name,
+            delta,
+            expires_at.timestamp(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::rate_limits::increment_counter(
+            scope,
+            name,
+            delta,
+            expires_at.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::rate_limits::increment_counter(
+            scope,
+            name,
+            delta,
+            expires_at.naive_utc(),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Names and values of the live counters of a scope
+ */
+pub fn get_counters(scope: &str, db: &mut Database) -> Result<Vec<(String, i64)>, EngineError> {
+    let _db_call = db_call("get_counters");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get counters of {}", scope),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::rate_limits::get_counters(scope, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::rate_limits::get_counters(scope, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::rate_limits::get_counters(scope, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::rate_limits::get_counters(scope, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
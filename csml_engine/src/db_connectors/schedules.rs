@@ -0,0 +1,321 @@
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::db_connectors::DbSchedule;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Database, EngineError};
+use chrono::{DateTime, Utc};
+
+pub fn create_schedule(
+    client: &Client,
+    cron: Option<&str>,
+    job: &serde_json::Value,
+    next_run_at: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<String, EngineError> {
+    let _db_call = db_call("create_schedule");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call create schedule next_run_at: {}", next_run_at),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call create schedule next_run_at: {}", next_run_at),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::schedules::create_schedule(
+            client,
+            cron,
+            job,
+            bson::DateTime::from_chrono(next_run_at),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::schedules::create_schedule(
+            client,
+            cron,
+            job,
+            next_run_at.timestamp(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::schedules::create_schedule(
+            client,
+            cron,
+            job,
+            next_run_at.naive_utc(),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::schedules::create_schedule(
+            client,
+            cron,
+            job,
+            next_run_at.naive_utc(),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn get_client_schedules(
+    client: &Client,
+    db: &mut Database,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let _db_call = db_call("get_client_schedules");
+    csml_logger(
+        CsmlLog::new(None, None, None, format!("db call get client schedules")),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call get client schedules"),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::schedules::get_client_schedules(client, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::schedules::get_client_schedules(client, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::schedules::get_client_schedules(client, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::schedules::get_client_schedules(client, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+pub fn get_due_schedules(
+    now: DateTime<Utc>,
+    db: &mut Database,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let _db_call = db_call("get_due_schedules");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call get due schedules: {}", now),
+        ),
+        LogLvl::Info,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::schedules::get_due_schedules(
+            bson::DateTime::from_chrono(now),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::schedules::get_due_schedules(now.timestamp(), db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::schedules::get_due_schedules(now.naive_utc(), db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::schedules::get_due_schedules(now.naive_utc(), db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Move a due schedule to its next run, or remove it when next_run_at is None.
+ * The schedule is only changed if it is still due at `now`, so that a single
+ * instance dispatches it when several instances process the schedules.
+ * Returns false if the schedule was already claimed or cancelled.
+ */
+pub fn claim_schedule(
+    client: &Client,
+    id: &str,
+    now: DateTime<Utc>,
+    next_run_at: Option<DateTime<Utc>>,
+    db: &mut Database,
+) -> Result<bool, EngineError> {
+    let _db_call = db_call("claim_schedule");
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!(
+                "db call claim schedule {} next_run_at: {:?}",
+                id, next_run_at
+            ),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!(
+                "db call claim schedule {} next_run_at: {:?}",
+                id, next_run_at
+            ),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::schedules::claim_schedule(
+            client,
+            id,
+            bson::DateTime::from_chrono(now),
+            next_run_at.map(bson::DateTime::from_chrono),
+            db,
+        );
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::schedules::claim_schedule(
+            client,
+            id,
+            now.timestamp(),
+            next_run_at.map(|next_run_at| next_run_at.timestamp()),
+            db,
+        );
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::schedules::claim_schedule(
+            client,
+            id,
+            now.naive_utc(),
+            next_run_at.map(|next_run_at| next_run_at.naive_utc()),
+            db,
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::schedules::claim_schedule(
+            client,
+            id,
+            now.naive_utc(),
+            next_run_at.map(|next_run_at| next_run_at.naive_utc()),
+            db,
+        );
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
+
+/**
+ * Returns false if the client has no schedule with this id
+ */
+pub fn delete_schedule(client: &Client, id: &str, db: &mut Database) -> Result<bool, EngineError> {
+    let _db_call = db_call("delete_schedule");
+    csml_logger(
+        CsmlLog::new(None, None, None, format!("db call delete schedule {}", id)),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call delete schedule {}", id),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::schedules::delete_schedule(client, id, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::schedules::delete_schedule(client, id, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::schedules::delete_schedule(client, id, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::schedules::delete_schedule(client, id, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
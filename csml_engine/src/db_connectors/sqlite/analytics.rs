@@ -0,0 +1,56 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{db_connectors::DbMessageStep, EngineError, SqliteClient};
+
+use super::{
+    models,
+    schema::{csml_conversations, csml_messages},
+};
+use chrono::NaiveDateTime;
+
+pub fn get_bot_message_steps(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: NaiveDateTime,
+    to_date: NaiveDateTime,
+    limit: i64,
+    db: &SqliteClient,
+) -> Result<Vec<DbMessageStep>, EngineError> {
+    let mut query = csml_conversations::table
+        .inner_join(csml_messages::table)
+        .filter(csml_conversations::bot_id.eq(bot_id))
+        .filter(csml_messages::created_at.ge(from_date))
+        .filter(csml_messages::created_at.le(to_date))
+        .select((
+            csml_messages::conversation_id,
+            csml_messages::flow_id,
+            csml_messages::step_id,
+            csml_messages::interaction_order,
+            csml_messages::message_order,
+            csml_messages::created_at,
+        ))
+        .into_boxed();
+
+    if let Some(channel_id) = channel_id {
+        query = query.filter(csml_conversations::channel_id.eq(channel_id));
+    }
+
+    let messages: Vec<(models::UUID, String, String, i32, i32, NaiveDateTime)> =
+        query.limit(limit).load(&db.client)?;
+
+    Ok(messages
+        .into_iter()
+        .map(
+            |(conversation_id, flow_id, step_id, interaction_order, message_order, created_at)| {
+                DbMessageStep {
+                    conversation_id: conversation_id.to_string(),
+                    flow_id,
+                    step_id,
+                    interaction_order,
+                    message_order,
+                    created_at: created_at.timestamp_millis(),
+                }
+            },
+        )
+        .collect())
+}
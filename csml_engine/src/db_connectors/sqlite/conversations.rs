@@ -57,6 +57,32 @@ pub fn close_conversation(
     Ok(())
 }
 
+pub fn set_conversation_status(
+    id: &str,
+    client: &Client,
+    from_status: &str,
+    status: &str,
+    db: &SqliteClient,
+) -> Result<bool, EngineError> {
+    let id = match models::UUID::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(false),
+    };
+
+    let count = diesel::update(
+        csml_conversations::table
+        .filter(csml_conversations::id.eq(id))
+        .filter(csml_conversations::bot_id.eq(&client.bot_id))
+        .filter(csml_conversations::channel_id.eq(&client.channel_id))
+        .filter(csml_conversations::user_id.eq(&client.user_id))
+        .filter(csml_conversations::status.eq(from_status))
+    )
+    .set(csml_conversations::status.eq(status))
+    .execute(&db.client)?;
+
+    Ok(count > 0)
+}
+
 pub fn close_all_conversations(client: &Client, db: &SqliteClient) -> Result<(), EngineError> {
     diesel::update(
         csml_conversations::table
@@ -73,12 +99,20 @@ pub fn close_all_conversations(client: &Client, db: &SqliteClient) -> Result<(),
 pub fn get_latest_open(
     client: &Client,
     db: &SqliteClient,
+) -> Result<Option<DbConversation>, EngineError> {
+    get_latest_by_status(client, "OPEN", db)
+}
+
+pub fn get_latest_by_status(
+    client: &Client,
+    status: &str,
+    db: &SqliteClient,
 ) -> Result<Option<DbConversation>, EngineError> {
     let result: Result<models::Conversation, diesel::result::Error> = csml_conversations::table
         .filter(csml_conversations::bot_id.eq(&client.bot_id))
         .filter(csml_conversations::channel_id.eq(&client.channel_id))
         .filter(csml_conversations::user_id.eq(&client.user_id))
-        .filter(csml_conversations::status.eq("OPEN"))
+        .filter(csml_conversations::status.eq(status))
         .order_by(csml_conversations::updated_at.desc())
         .limit(1)
         .get_result(&db.client);
@@ -0,0 +1,96 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{encrypt::decrypt_data, Client, EngineError, SqliteClient};
+
+use super::{
+    models,
+    schema::{csml_conversations, csml_messages, csml_states},
+};
+use chrono::NaiveDateTime;
+
+fn format_date(date: NaiveDateTime) -> String {
+    date.format("%Y-%m-%dT%H:%M:%S%.fZ").to_string()
+}
+
+pub fn export_conversations(
+    client: &Client,
+    db: &SqliteClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let conversations: Vec<models::Conversation> = csml_conversations::table
+        .filter(csml_conversations::bot_id.eq(&client.bot_id))
+        .filter(csml_conversations::channel_id.eq(&client.channel_id))
+        .filter(csml_conversations::user_id.eq(&client.user_id))
+        .order_by(csml_conversations::created_at.asc())
+        .load(&db.client)?;
+
+    Ok(conversations
+        .into_iter()
+        .map(|conversation| {
+            serde_json::json!({
+                "id": conversation.id.to_string(),
+                "flow_id": conversation.flow_id,
+                "step_id": conversation.step_id,
+                "status": conversation.status,
+                "last_interaction_at": format_date(conversation.last_interaction_at),
+                "updated_at": format_date(conversation.updated_at),
+                "created_at": format_date(conversation.created_at),
+            })
+        })
+        .collect())
+}
+
+pub fn export_messages(
+    client: &Client,
+    db: &SqliteClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let messages: Vec<models::Message> = csml_conversations::table
+        .filter(csml_conversations::bot_id.eq(&client.bot_id))
+        .filter(csml_conversations::channel_id.eq(&client.channel_id))
+        .filter(csml_conversations::user_id.eq(&client.user_id))
+        .inner_join(csml_messages::table)
+        .select(csml_messages::all_columns)
+        .order_by(csml_messages::created_at.asc())
+        .then_order_by(csml_messages::interaction_order.asc())
+        .then_order_by(csml_messages::message_order.asc())
+        .load(&db.client)?;
+
+    let mut msgs = vec![];
+    for message in messages {
+        msgs.push(serde_json::json!({
+            "id": message.id.to_string(),
+            "conversation_id": message.conversation_id.to_string(),
+            "flow_id": message.flow_id,
+            "step_id": message.step_id,
+            "direction": message.direction,
+            "interaction_order": message.interaction_order,
+            "message_order": message.message_order,
+            "payload": decrypt_data(message.payload)?,
+            "created_at": format_date(message.created_at),
+        }));
+    }
+
+    Ok(msgs)
+}
+
+pub fn export_state(
+    client: &Client,
+    db: &SqliteClient,
+) -> Result<Vec<serde_json::Value>, EngineError> {
+    let states: Vec<models::State> = csml_states::table
+        .filter(csml_states::bot_id.eq(&client.bot_id))
+        .filter(csml_states::channel_id.eq(&client.channel_id))
+        .filter(csml_states::user_id.eq(&client.user_id))
+        .load(&db.client)?;
+
+    let mut items = vec![];
+    for state in states {
+        items.push(serde_json::json!({
+            "type": state.type_,
+            "key": state.key,
+            "value": decrypt_data(state.value)?,
+            "created_at": format_date(state.created_at),
+        }));
+    }
+
+    Ok(items)
+}
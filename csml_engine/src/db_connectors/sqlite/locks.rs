@@ -0,0 +1,87 @@
+use diesel::{BoolExpressionMethods, ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{Client, EngineError, SqliteClient};
+
+use super::{models, schema::csml_locks};
+use chrono::NaiveDateTime;
+
+/**
+ * Take the lease of the client if it is free, expired or already ours.
+ * Returns false if another owner holds it.
+ */
+pub fn acquire_lease(
+    client: &Client,
+    owner: &str,
+    expires_at: NaiveDateTime,
+    db: &SqliteClient,
+) -> Result<bool, EngineError> {
+    let now = chrono::Utc::now().naive_utc();
+
+    let updated = diesel::update(
+        csml_locks::table
+            .filter(csml_locks::bot_id.eq(&client.bot_id))
+            .filter(csml_locks::channel_id.eq(&client.channel_id))
+            .filter(csml_locks::user_id.eq(&client.user_id))
+            .filter(
+                csml_locks::expires_at
+                    .lt(now)
+                    .or(csml_locks::owner.eq(owner)),
+            ),
+    )
+    .set((
+        csml_locks::owner.eq(owner),
+        csml_locks::expires_at.eq(expires_at),
+        csml_locks::updated_at.eq(now),
+    ))
+    .execute(&db.client)?;
+
+    if updated > 0 {
+        return Ok(true);
+    }
+
+    let new_lock = models::NewLock {
+        bot_id: &client.bot_id,
+        channel_id: &client.channel_id,
+        user_id: &client.user_id,
+        owner,
+        expires_at,
+    };
+
+    // the primary key on the client makes concurrent inserts fail for all but one owner
+    let inserted = diesel::insert_or_ignore_into(csml_locks::table)
+        .values(&new_lock)
+        .execute(&db.client)?;
+
+    Ok(inserted > 0)
+}
+
+pub fn release_lease(client: &Client, owner: &str, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_locks::table
+            .filter(csml_locks::bot_id.eq(&client.bot_id))
+            .filter(csml_locks::channel_id.eq(&client.channel_id))
+            .filter(csml_locks::user_id.eq(&client.user_id))
+            .filter(csml_locks::owner.eq(owner)),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_client_lease(client: &Client, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_locks::table
+            .filter(csml_locks::bot_id.eq(&client.bot_id))
+            .filter(csml_locks::channel_id.eq(&client.channel_id))
+            .filter(csml_locks::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(csml_locks::table.filter(csml_locks::bot_id.eq(bot_id))).execute(&db.client)?;
+
+    Ok(())
+}
@@ -3,10 +3,9 @@ use diesel::sql_query;
 use diesel::sql_types;
 
 use crate::{
-    db_connectors::sqlite::get_db,
     encrypt::{decrypt_data, encrypt_data},
     EngineError, SqliteClient,
-    ConversationInfo, Memory, Client,
+    Memory, Client,
 };
 
 use super::{
@@ -18,18 +17,17 @@ use chrono::{NaiveDateTime};
 use std::collections::HashMap;
 
 pub fn add_memories(
-    data: &mut ConversationInfo,
+    client: &Client,
     memories: &HashMap<String, Memory>,
     expires_at: Option<NaiveDateTime>,
+    db: &SqliteClient,
 ) -> Result<(), EngineError> {
     if memories.is_empty() {
         return Ok(());
     }
 
-    let db = get_db(&data.db)?;
-
     for (key, mem) in memories.iter() {
-        create_client_memory(&data.client, key, &mem.value, expires_at, db)?;
+        create_client_memory(client, key, &mem.value, expires_at, db)?;
     }
 
     Ok(())
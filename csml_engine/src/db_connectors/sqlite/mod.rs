@@ -1,8 +1,16 @@
+pub mod analytics;
+pub mod api_keys;
 pub mod bot;
 pub mod conversations;
+pub mod export;
+pub mod locks;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod rate_limits;
+pub mod schedules;
 pub mod state;
+pub mod unit_of_work;
 
 pub mod pagination;
 
@@ -0,0 +1,152 @@
+use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+
+use crate::{
+    db_connectors::DbSchedule,
+    encrypt::{decrypt_data, encrypt_data},
+    Client, EngineError, SqliteClient,
+};
+
+use super::{models, schema::csml_schedules};
+use chrono::NaiveDateTime;
+
+fn format_schedule_struct(schedule: models::Schedule) -> Result<DbSchedule, EngineError> {
+    Ok(DbSchedule {
+        id: schedule.id.to_string(),
+        client: Client {
+            bot_id: schedule.bot_id,
+            channel_id: schedule.channel_id,
+            user_id: schedule.user_id,
+        },
+        cron: schedule.cron,
+        job: decrypt_data(schedule.job)?,
+        next_run_at: schedule
+            .next_run_at
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string(),
+        created_at: schedule
+            .created_at
+            .format("%Y-%m-%dT%H:%M:%S%.fZ")
+            .to_string(),
+    })
+}
+
+pub fn create_schedule(
+    client: &Client,
+    cron: Option<&str>,
+    job: &serde_json::Value,
+    next_run_at: NaiveDateTime,
+    db: &SqliteClient,
+) -> Result<String, EngineError> {
+    let id = models::UUID::new_v4();
+
+    let new_schedule = models::NewSchedule {
+        id,
+        bot_id: &client.bot_id,
+        channel_id: &client.channel_id,
+        user_id: &client.user_id,
+        cron,
+        job: encrypt_data(job)?,
+        next_run_at,
+    };
+
+    diesel::insert_into(csml_schedules::table)
+        .values(&new_schedule)
+        .execute(&db.client)?;
+
+    Ok(id.to_string())
+}
+
+pub fn get_client_schedules(
+    client: &Client,
+    db: &SqliteClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let schedules: Vec<models::Schedule> = csml_schedules::table
+        .filter(csml_schedules::bot_id.eq(&client.bot_id))
+        .filter(csml_schedules::channel_id.eq(&client.channel_id))
+        .filter(csml_schedules::user_id.eq(&client.user_id))
+        .order_by(csml_schedules::next_run_at.asc())
+        .load(&db.client)?;
+
+    schedules.into_iter().map(format_schedule_struct).collect()
+}
+
+pub fn get_due_schedules(
+    now: NaiveDateTime,
+    db: &SqliteClient,
+) -> Result<Vec<DbSchedule>, EngineError> {
+    let schedules: Vec<models::Schedule> = csml_schedules::table
+        .filter(csml_schedules::next_run_at.le(now))
+        .order_by(csml_schedules::next_run_at.asc())
+        .load(&db.client)?;
+
+    schedules.into_iter().map(format_schedule_struct).collect()
+}
+
+pub fn claim_schedule(
+    client: &Client,
+    id: &str,
+    now: NaiveDateTime,
+    next_run_at: Option<NaiveDateTime>,
+    db: &SqliteClient,
+) -> Result<bool, EngineError> {
+    let id = match models::UUID::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(false),
+    };
+
+    let schedule = csml_schedules::table
+        .filter(csml_schedules::id.eq(id))
+        .filter(csml_schedules::bot_id.eq(&client.bot_id))
+        .filter(csml_schedules::channel_id.eq(&client.channel_id))
+        .filter(csml_schedules::user_id.eq(&client.user_id))
+        .filter(csml_schedules::next_run_at.le(now));
+
+    let count = match next_run_at {
+        Some(next_run_at) => diesel::update(schedule)
+            .set((
+                csml_schedules::next_run_at.eq(next_run_at),
+                csml_schedules::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ))
+            .execute(&db.client)?,
+        None => diesel::delete(schedule).execute(&db.client)?,
+    };
+
+    Ok(count > 0)
+}
+
+pub fn delete_schedule(client: &Client, id: &str, db: &SqliteClient) -> Result<bool, EngineError> {
+    let id = match models::UUID::parse_str(id) {
+        Ok(id) => id,
+        Err(_) => return Ok(false),
+    };
+
+    let count = diesel::delete(
+        csml_schedules::table
+            .filter(csml_schedules::id.eq(id))
+            .filter(csml_schedules::bot_id.eq(&client.bot_id))
+            .filter(csml_schedules::channel_id.eq(&client.channel_id))
+            .filter(csml_schedules::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(count > 0)
+}
+
+pub fn delete_client_schedules(client: &Client, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(
+        csml_schedules::table
+            .filter(csml_schedules::bot_id.eq(&client.bot_id))
+            .filter(csml_schedules::channel_id.eq(&client.channel_id))
+            .filter(csml_schedules::user_id.eq(&client.user_id)),
+    )
+    .execute(&db.client)?;
+
+    Ok(())
+}
+
+pub fn delete_all_bot_data(bot_id: &str, db: &SqliteClient) -> Result<(), EngineError> {
+    diesel::delete(csml_schedules::table.filter(csml_schedules::bot_id.eq(bot_id)))
+        .execute(&db.client)?;
+
+    Ok(())
+}
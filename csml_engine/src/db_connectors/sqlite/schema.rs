@@ -9,6 +9,15 @@ table! {
     }
 }
 
+table! {
+    csml_api_keys (key_hash) {
+        key_hash -> Text,
+        bot_ids -> Nullable<Text>,
+        scopes -> Text,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_conversations (id) {
         id -> Binary,
@@ -25,6 +34,18 @@ table! {
     }
 }
 
+table! {
+    csml_locks (bot_id, channel_id, user_id) {
+        bot_id -> Text,
+        channel_id -> Text,
+        user_id -> Text,
+        owner -> Text,
+        expires_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_memories (id) {
         id -> Binary,
@@ -56,6 +77,50 @@ table! {
     }
 }
 
+table! {
+    csml_outbox (id) {
+        id -> Binary,
+        bot_id -> Text,
+        channel_id -> Text,
+        user_id -> Text,
+        conversation_id -> Text,
+        callback_url -> Text,
+        payload -> Text,
+        status -> Text,
+        attempts -> Integer,
+        error -> Nullable<Text>,
+        position -> BigInt,
+        next_attempt_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_rate_limits (scope, name) {
+        scope -> Text,
+        name -> Text,
+        value -> BigInt,
+        expires_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    csml_schedules (id) {
+        id -> Binary,
+        bot_id -> Text,
+        channel_id -> Text,
+        user_id -> Text,
+        cron -> Nullable<Text>,
+        job -> Text,
+        next_run_at -> Timestamp,
+        updated_at -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     csml_states (id) {
         id -> Binary,
@@ -76,8 +141,13 @@ joinable!(csml_messages -> csml_conversations (conversation_id));
 
 allow_tables_to_appear_in_same_query!(
     cmsl_bot_versions,
+    csml_api_keys,
     csml_conversations,
+    csml_locks,
     csml_memories,
     csml_messages,
+    csml_outbox,
+    csml_rate_limits,
+    csml_schedules,
     csml_states,
 );
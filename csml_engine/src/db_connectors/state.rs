@@ -9,6 +9,7 @@ use crate::db_connectors::{is_sqlite, sqlite_connector};
 
 
 use csml_interpreter::data::csml_logs::{LogLvl, CsmlLog, csml_logger};
+use crate::db_connectors::db_call;
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Database, EngineError};
 use crate::db_connectors::utils::*;
@@ -20,6 +21,7 @@ pub fn delete_state_key(
     key: &str,
     db: &mut Database,
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_state_key");
     csml_logger(
         CsmlLog::new(
             None,
@@ -72,6 +74,7 @@ pub fn get_state_key(
     _key: &str,
     db: &mut Database,
 ) -> Result<Option<serde_json::Value>, EngineError> {
+    let _db_call = db_call("get_state_key");
     csml_logger(
         CsmlLog::new(
             None,
@@ -122,6 +125,7 @@ pub fn get_current_state(
     client: &Client,
     db: &mut Database,
 ) -> Result<Option<serde_json::Value>, EngineError> {
+    let _db_call = db_call("get_current_state");
     csml_logger(
         CsmlLog::new(
             None,
@@ -175,6 +179,7 @@ pub fn set_state_items(
     ttl: Option<chrono::Duration>,
     _db: &mut Database,
 ) -> Result<(), EngineError> {
+    let _db_call = db_call("set_state_items");
     csml_logger(
         CsmlLog::new(
             None,
@@ -255,7 +260,8 @@ mod tests {
             step_name: "step_name".to_owned(),
             flow_name: "flow_name".to_owned(),
             previous: None,
-            secure: false
+            secure: false,
+            timeout: None,
         };
 
         let state_hold: serde_json::Value = serde_json::json!({
@@ -0,0 +1,306 @@
+/**
+ * Writes produced while interpreting a step are not sent to the database right away:
+ * they are buffered in a UnitOfWork and committed all at once at the end of the step,
+ * so that a failure halfway through does not leave, for instance, the messages of a step
+ * saved without its memories.
+ *
+ * Each connector commits the writes atomically with the tools of its database:
+ * - SQL transactions on PostgreSQL and SQLite
+ * - a session transaction on MongoDB (transactions require a replica set, a standalone
+ *   server is refused unless MONGODB_ALLOW_NON_ATOMIC_WRITES is set)
+ * - TransactWriteItems on DynamoDB (a transaction holds at most 100 items: above that,
+ *   the messages and the memories deleted all at once are committed in separate transactions)
+ *
+ * Reads made during the step do not see the buffered writes.
+ */
+#[cfg(feature = "dynamo")]
+use crate::db_connectors::{dynamodb as dynamodb_connector, is_dynamodb};
+#[cfg(feature = "mongo")]
+use crate::db_connectors::{is_mongodb, mongodb as mongodb_connector};
+#[cfg(feature = "postgresql")]
+use crate::db_connectors::{is_postgresql, postgresql_connector};
+#[cfg(feature = "sqlite")]
+use crate::db_connectors::{is_sqlite, sqlite_connector};
+
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use crate::db_connectors::db_call;
+use crate::error_messages::ERROR_DB_SETUP;
+use crate::{Client, Context, Database, EngineError, Memory};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone)]
+pub enum Write {
+    Messages {
+        client: Client,
+        conversation_id: String,
+        flow_id: String,
+        step_id: String,
+        messages: Vec<Value>,
+        interaction_order: i32,
+        direction: String,
+        ttl: Option<chrono::Duration>,
+    },
+    Memories {
+        client: Client,
+        memories: HashMap<String, Memory>,
+        ttl: Option<chrono::Duration>,
+    },
+    DeleteMemory {
+        client: Client,
+        key: String,
+    },
+    DeleteMemories {
+        client: Client,
+    },
+    Conversation {
+        client: Client,
+        conversation_id: String,
+        flow_id: Option<String>,
+        step_id: Option<String>,
+    },
+    ConversationStatus {
+        client: Client,
+        conversation_id: String,
+        from_status: String,
+        status: String,
+    },
+    State {
+        client: Client,
+        _type: String,
+        key: String,
+        value: Value,
+        ttl: Option<chrono::Duration>,
+    },
+    DeleteState {
+        client: Client,
+        _type: String,
+        key: String,
+    },
+}
+
+impl Write {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Write::Messages { .. } => "messages",
+            Write::Memories { .. } => "memories",
+            Write::DeleteMemory { .. } => "delete_memory",
+            Write::DeleteMemories { .. } => "delete_memories",
+            Write::Conversation { .. } => "conversation",
+            Write::ConversationStatus { .. } => "conversation_status",
+            Write::State { .. } => "state",
+            Write::DeleteState { .. } => "delete_state",
+        }
+    }
+}
+
+/**
+ * Buffer of the writes of one interaction, in the order they were made
+ */
+#[derive(Debug, Clone, Default)]
+pub struct UnitOfWork {
+    pub writes: Vec<Write>,
+}
+
+impl UnitOfWork {
+    pub fn new() -> Self {
+        Self { writes: vec![] }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.writes.is_empty()
+    }
+
+    /**
+     * Kinds and counts of the buffered writes, their content is never logged
+     */
+    pub fn summary(&self) -> String {
+        let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+        for write in self.writes.iter() {
+            *counts.entry(write.kind()).or_default() += 1;
+        }
+
+        counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", kind, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    pub fn add_messages(
+        &mut self,
+        client: &Client,
+        conversation_id: &str,
+        context: &Context,
+        messages: Vec<Value>,
+        interaction_order: i32,
+        direction: &str,
+        ttl: Option<chrono::Duration>,
+    ) {
+        if messages.is_empty() {
+            return;
+        }
+
+        self.writes.push(Write::Messages {
+            client: client.to_owned(),
+            conversation_id: conversation_id.to_owned(),
+            flow_id: context.flow.to_owned(),
+            step_id: context.step.get_step(),
+            messages,
+            interaction_order,
+            direction: direction.to_owned(),
+            ttl,
+        });
+    }
+
+    pub fn add_memories(
+        &mut self,
+        client: &Client,
+        memories: &HashMap<String, Memory>,
+        ttl: Option<chrono::Duration>,
+    ) {
+        if memories.is_empty() {
+            return;
+        }
+
+        self.writes.push(Write::Memories {
+            client: client.to_owned(),
+            memories: memories.to_owned(),
+            ttl,
+        });
+    }
+
+    pub fn delete_memory(&mut self, client: &Client, key: &str) {
+        self.writes.push(Write::DeleteMemory {
+            client: client.to_owned(),
+            key: key.to_owned(),
+        });
+    }
+
+    pub fn delete_memories(&mut self, client: &Client) {
+        self.writes.push(Write::DeleteMemories {
+            client: client.to_owned(),
+        });
+    }
+
+    pub fn update_conversation(
+        &mut self,
+        client: &Client,
+        conversation_id: &str,
+        flow_id: Option<String>,
+        step_id: Option<String>,
+    ) {
+        self.writes.push(Write::Conversation {
+            client: client.to_owned(),
+            conversation_id: conversation_id.to_owned(),
+            flow_id,
+            step_id,
+        });
+    }
+
+    pub fn set_conversation_status(
+        &mut self,
+        client: &Client,
+        conversation_id: &str,
+        from_status: &str,
+        status: &str,
+    ) {
+        self.writes.push(Write::ConversationStatus {
+            client: client.to_owned(),
+            conversation_id: conversation_id.to_owned(),
+            from_status: from_status.to_owned(),
+            status: status.to_owned(),
+        });
+    }
+
+    /**
+     * Same as conversations::close_conversation: the previous bot info is removed
+     * at the end of the conversation
+     */
+    pub fn close_conversation(&mut self, client: &Client, conversation_id: &str) {
+        self.delete_state_key(client, "bot", "previous");
+        self.set_conversation_status(client, conversation_id, "OPEN", "CLOSED");
+    }
+
+    pub fn set_state_items(
+        &mut self,
+        client: &Client,
+        _type: &str,
+        keys_values: Vec<(&str, &Value)>,
+        ttl: Option<chrono::Duration>,
+    ) {
+        for (key, value) in keys_values {
+            self.writes.push(Write::State {
+                client: client.to_owned(),
+                _type: _type.to_owned(),
+                key: key.to_owned(),
+                value: value.to_owned(),
+                ttl,
+            });
+        }
+    }
+
+    pub fn delete_state_key(&mut self, client: &Client, _type: &str, key: &str) {
+        self.writes.push(Write::DeleteState {
+            client: client.to_owned(),
+            _type: _type.to_owned(),
+            key: key.to_owned(),
+        });
+    }
+}
+
+/**
+ * Apply all the writes of the unit of work atomically: either all of them are saved or none
+ */
+pub fn commit(unit_of_work: UnitOfWork, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("commit");
+    if unit_of_work.is_empty() {
+        return Ok(());
+    }
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call commit {} writes", unit_of_work.writes.len()),
+        ),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            None,
+            None,
+            None,
+            format!("db call commit writes {}", unit_of_work.summary()),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+        return mongodb_connector::unit_of_work::commit(&unit_of_work.writes, db);
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+        return dynamodb_connector::unit_of_work::commit(&unit_of_work.writes, db);
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+        return postgresql_connector::unit_of_work::commit(&unit_of_work.writes, db);
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+        return sqlite_connector::unit_of_work::commit(&unit_of_work.writes, db);
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
@@ -7,11 +7,15 @@ use crate::db_connectors::{is_postgresql, postgresql_connector};
 #[cfg(feature = "sqlite")]
 use crate::db_connectors::{is_sqlite, sqlite_connector};
 
+use crate::db_connectors::outbox::{OUTBOX_DEAD, OUTBOX_PENDING};
+use crate::db_connectors::{db_call, DbOutboxMessage, DbSchedule};
 use crate::error_messages::ERROR_DB_SETUP;
 use crate::{Client, Database, EngineError};
+use chrono::SecondsFormat;
 use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
 
 pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineError> {
+    let _db_call = db_call("delete_client");
     csml_logger(
         CsmlLog::new(None, None, None, format!("db call delete client")),
         LogLvl::Info,
@@ -29,6 +33,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         mongodb_connector::memories::delete_client_memories(client, db)?;
         mongodb_connector::messages::delete_user_messages(client, db)?;
         mongodb_connector::state::delete_user_state(client, db)?;
+        mongodb_connector::schedules::delete_client_schedules(client, db)?;
+        mongodb_connector::outbox::delete_client_outbox(client, db)?;
+        mongodb_connector::locks::delete_client_lease(client, db)?;
 
         return Ok(());
     }
@@ -41,6 +48,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         dynamodb_connector::messages::delete_user_messages(client, db)?;
         dynamodb_connector::conversations::delete_user_conversations(client, db)?;
         dynamodb_connector::state::delete_user_state(client, db)?;
+        dynamodb_connector::schedules::delete_client_schedules(client, db)?;
+        dynamodb_connector::outbox::delete_client_outbox(client, db)?;
+        dynamodb_connector::locks::delete_client_lease(client, db)?;
 
         return Ok(());
     }
@@ -53,6 +63,9 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         postgresql_connector::memories::delete_client_memories(client, db)?;
         postgresql_connector::messages::delete_user_messages(client, db)?;
         postgresql_connector::state::delete_user_state(client, db)?;
+        postgresql_connector::schedules::delete_client_schedules(client, db)?;
+        postgresql_connector::outbox::delete_client_outbox(client, db)?;
+        postgresql_connector::locks::delete_client_lease(client, db)?;
 
         return Ok(());
     }
@@ -65,9 +78,125 @@ pub fn delete_client(client: &Client, db: &mut Database) -> Result<(), EngineErr
         sqlite_connector::memories::delete_client_memories(client, db)?;
         sqlite_connector::messages::delete_user_messages(client, db)?;
         sqlite_connector::state::delete_user_state(client, db)?;
+        sqlite_connector::schedules::delete_client_schedules(client, db)?;
+        sqlite_connector::outbox::delete_client_outbox(client, db)?;
+        sqlite_connector::locks::delete_client_lease(client, db)?;
 
         return Ok(());
     }
 
     Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
 }
+
+fn format_client_data(
+    client: &Client,
+    conversations: Vec<serde_json::Value>,
+    messages: Vec<serde_json::Value>,
+    memories: serde_json::Value,
+    state: Vec<serde_json::Value>,
+    schedules: Vec<DbSchedule>,
+    outbox: Vec<DbOutboxMessage>,
+    dead_letters: Vec<DbOutboxMessage>,
+) -> serde_json::Value {
+    serde_json::json!({
+        "client": client,
+        "exported_at": chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "conversations": conversations,
+        "messages": messages,
+        "memories": memories,
+        "state": state,
+        "schedules": schedules,
+        "outbox": outbox,
+        "dead_letters": dead_letters,
+    })
+}
+
+/**
+ * Every conversation, message, memory, state entry, schedule
+ * and callback waiting in the outbox stored for a client, decrypted
+ */
+pub fn export_client_data(
+    client: &Client,
+    db: &mut Database,
+) -> Result<serde_json::Value, EngineError> {
+    let _db_call = db_call("export_client_data");
+    csml_logger(
+        CsmlLog::new(None, None, None, format!("db call export client data")),
+        LogLvl::Info,
+    );
+    csml_logger(
+        CsmlLog::new(
+            Some(client),
+            None,
+            None,
+            format!("db call export client data"),
+        ),
+        LogLvl::Debug,
+    );
+
+    #[cfg(feature = "mongo")]
+    if is_mongodb() {
+        let db = mongodb_connector::get_db(db)?;
+
+        return Ok(format_client_data(
+            client,
+            mongodb_connector::export::export_conversations(client, db)?,
+            mongodb_connector::export::export_messages(client, db)?,
+            mongodb_connector::memories::get_memories(client, db)?,
+            mongodb_connector::export::export_state(client, db)?,
+            mongodb_connector::schedules::get_client_schedules(client, db)?,
+            mongodb_connector::outbox::get_client_outbox_messages(client, OUTBOX_PENDING, db)?,
+            mongodb_connector::outbox::get_client_outbox_messages(client, OUTBOX_DEAD, db)?,
+        ));
+    }
+
+    #[cfg(feature = "dynamo")]
+    if is_dynamodb() {
+        let db = dynamodb_connector::get_db(db)?;
+
+        return Ok(format_client_data(
+            client,
+            dynamodb_connector::export::export_conversations(client, db)?,
+            dynamodb_connector::export::export_messages(client, db)?,
+            dynamodb_connector::memories::get_memories(client, db)?,
+            dynamodb_connector::export::export_state(client, db)?,
+            dynamodb_connector::schedules::get_client_schedules(client, db)?,
+            dynamodb_connector::outbox::get_client_outbox_messages(client, OUTBOX_PENDING, db)?,
+            dynamodb_connector::outbox::get_client_outbox_messages(client, OUTBOX_DEAD, db)?,
+        ));
+    }
+
+    #[cfg(feature = "postgresql")]
+    if is_postgresql() {
+        let db = postgresql_connector::get_db(db)?;
+
+        return Ok(format_client_data(
+            client,
+            postgresql_connector::export::export_conversations(client, db)?,
+            postgresql_connector::export::export_messages(client, db)?,
+            postgresql_connector::memories::get_memories(client, db)?,
+            postgresql_connector::export::export_state(client, db)?,
+            postgresql_connector::schedules::get_client_schedules(client, db)?,
+            postgresql_connector::outbox::get_client_outbox_messages(client, OUTBOX_PENDING, db)?,
+            postgresql_connector::outbox::get_client_outbox_messages(client, OUTBOX_DEAD, db)?,
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    if is_sqlite() {
+        let db = sqlite_connector::get_db(db)?;
+
+        return Ok(format_client_data(
+            client,
+            sqlite_connector::export::export_conversations(client, db)?,
+            sqlite_connector::export::export_messages(client, db)?,
+            sqlite_connector::memories::get_memories(client, db)?,
+            sqlite_connector::export::export_state(client, db)?,
+            sqlite_connector::schedules::get_client_schedules(client, db)?,
+            sqlite_connector::outbox::get_client_outbox_messages(client, OUTBOX_PENDING, db)?,
+            sqlite_connector::outbox::get_client_outbox_messages(client, OUTBOX_DEAD, db)?,
+        ));
+    }
+
+    Err(EngineError::Manager(ERROR_DB_SETUP.to_owned()))
+}
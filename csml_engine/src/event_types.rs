@@ -0,0 +1,347 @@
+use crate::data::{EngineError, FlowTrigger};
+
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+
+/**
+ * Derive the main value of an event (the `event` variable in CSML) from its content.
+ * An error message is returned if the content is not valid for this type.
+ */
+pub type ContentValueFn = fn(&Value) -> Result<String, String>;
+
+/**
+ * Description of an event content_type accepted by the engine.
+ * `schema` is an optional subset of JSON schema (type, properties, required, enum)
+ * that the event content must match before its content_value is derived.
+ */
+#[derive(Debug, Clone)]
+pub struct EventType {
+    pub content_type: String,
+    pub content_value: ContentValueFn,
+    pub schema: Option<Value>,
+}
+
+impl EventType {
+    pub fn new(content_type: &str, content_value: ContentValueFn, schema: Option<Value>) -> Self {
+        Self {
+            content_type: content_type.to_owned(),
+            content_value,
+            schema,
+        }
+    }
+}
+
+static EVENT_TYPES: OnceLock<RwLock<HashMap<String, EventType>>> = OnceLock::new();
+
+////////////////////////////////////////////////////////////////////////////////
+// BUILT-IN EVENT TYPES
+////////////////////////////////////////////////////////////////////////////////
+
+fn url_value(content: &Value) -> Result<String, String> {
+    match content["url"].as_str() {
+        Some(url) => Ok(url.to_owned()),
+        None => Err("no url content in event".to_owned()),
+    }
+}
+
+fn payload_value(content: &Value) -> Result<String, String> {
+    match content["payload"].as_str() {
+        Some(payload) => Ok(payload.to_owned()),
+        None => Err("no payload content in event".to_owned()),
+    }
+}
+
+fn text_value(content: &Value) -> Result<String, String> {
+    match content["text"].as_str() {
+        Some(text) => Ok(text.to_owned()),
+        None => Err("no text content in event".to_owned()),
+    }
+}
+
+fn regex_value(content: &Value) -> Result<String, String> {
+    match content["payload"].as_str() {
+        Some(payload) => Ok(payload.to_owned()),
+        None => Err("invalid payload for event type regex".to_owned()),
+    }
+}
+
+fn flow_trigger_value(content: &Value) -> Result<String, String> {
+    match serde_json::from_value::<FlowTrigger>(content.clone()) {
+        Ok(_) => Ok(content.to_string()),
+        Err(_) => Err(
+            "invalid content for event type flow_trigger: expect flow_id and optional step_id"
+                .to_owned(),
+        ),
+    }
+}
+
+fn timeout_value(content: &Value) -> Result<String, String> {
+    match serde_json::from_value::<FlowTrigger>(content.clone()) {
+        Ok(FlowTrigger {
+            step_id: Some(_), ..
+        }) => Ok(content.to_string()),
+        _ => Err("invalid content for event type timeout: expect flow_id and step_id".to_owned()),
+    }
+}
+
+fn location_value(content: &Value) -> Result<String, String> {
+    Ok(format!("{},{}", content["latitude"], content["longitude"]))
+}
+
+fn contact_value(content: &Value) -> Result<String, String> {
+    Ok(content["phone_number"]
+        .as_str()
+        .unwrap_or_default()
+        .to_owned())
+}
+
+fn reaction_value(content: &Value) -> Result<String, String> {
+    Ok(content["emoji"].as_str().unwrap_or_default().to_owned())
+}
+
+fn built_in_event_types() -> HashMap<String, EventType> {
+    let mut event_types = vec![];
+
+    for content_type in ["file", "audio", "video", "image", "url"].iter() {
+        event_types.push(EventType::new(content_type, url_value, None));
+    }
+
+    event_types.push(EventType::new("payload", payload_value, None));
+    event_types.push(EventType::new("text", text_value, None));
+    event_types.push(EventType::new("regex", regex_value, None));
+    event_types.push(EventType::new("flow_trigger", flow_trigger_value, None));
+    event_types.push(EventType::new("timeout", timeout_value, None));
+
+    event_types.push(EventType::new(
+        "location",
+        location_value,
+        Some(json!({
+            "type": "object",
+            "required": ["latitude", "longitude"],
+            "properties": {
+                "latitude": {"type": "number"},
+                "longitude": {"type": "number"},
+                "name": {"type": "string"},
+                "address": {"type": "string"},
+            }
+        })),
+    ));
+    event_types.push(EventType::new(
+        "contact",
+        contact_value,
+        Some(json!({
+            "type": "object",
+            "required": ["phone_number"],
+            "properties": {
+                "phone_number": {"type": "string"},
+                "first_name": {"type": "string"},
+                "last_name": {"type": "string"},
+                "email": {"type": "string"},
+                "user_id": {"type": "string"},
+            }
+        })),
+    ));
+    event_types.push(EventType::new(
+        "reaction",
+        reaction_value,
+        Some(json!({
+            "type": "object",
+            "required": ["emoji"],
+            "properties": {
+                "emoji": {"type": "string"},
+                "message_id": {"type": "string"},
+                "action": {"type": "string", "enum": ["add", "remove"]},
+            }
+        })),
+    ));
+
+    event_types
+        .into_iter()
+        .map(|event_type| (event_type.content_type.to_owned(), event_type))
+        .collect()
+}
+
+fn event_types() -> &'static RwLock<HashMap<String, EventType>> {
+    EVENT_TYPES.get_or_init(|| RwLock::new(built_in_event_types()))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// SCHEMA VALIDATION
+////////////////////////////////////////////////////////////////////////////////
+
+fn match_type(expected: &str, value: &Value) -> bool {
+    match expected {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+/**
+ * Check a value against a subset of JSON schema:
+ * `type`, `enum`, `required`, `properties` and `items`.
+ */
+pub fn validate_schema(schema: &Value, value: &Value, path: &str) -> Result<(), String> {
+    if let Some(expected) = schema["type"].as_str() {
+        if !match_type(expected, value) {
+            return Err(format!("{} must be of type {}", path, expected));
+        }
+    }
+
+    if let Some(values) = schema["enum"].as_array() {
+        if !values.contains(value) {
+            return Err(format!("{} must be one of {}", path, schema["enum"]));
+        }
+    }
+
+    if let Some(required) = schema["required"].as_array() {
+        for key in required.iter().filter_map(|key| key.as_str()) {
+            if value.get(key).map_or(true, |value| value.is_null()) {
+                return Err(format!("{}.{} is required", path, key));
+            }
+        }
+    }
+
+    if let (Some(properties), Some(object)) = (schema["properties"].as_object(), value.as_object())
+    {
+        for (key, property) in properties.iter() {
+            if let Some(value) = object.get(key) {
+                validate_schema(property, value, &format!("{}.{}", path, key))?;
+            }
+        }
+    }
+
+    if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+        for (index, value) in array.iter().enumerate() {
+            validate_schema(items, value, &format!("{}[{}]", path, index))?;
+        }
+    }
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Declare a new event content_type, or replace an existing one.
+ * Events of this type are then accepted by `start_conversation`
+ * and their content is available in CSML as `event.<field>`.
+ */
+pub fn register_event_type(event_type: EventType) {
+    let mut event_types = match event_types().write() {
+        Ok(event_types) => event_types,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    event_types.insert(event_type.content_type.to_owned(), event_type);
+}
+
+/**
+ * List the content_types currently accepted in events.
+ */
+pub fn get_event_types() -> Vec<String> {
+    let event_types = match event_types().read() {
+        Ok(event_types) => event_types,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    let mut content_types: Vec<String> = event_types.keys().cloned().collect();
+    content_types.sort();
+
+    content_types
+}
+
+/**
+ * Prepare a formatted "content" for the event object, based on the user's input.
+ * This will trim extra data and only keep the main value.
+ */
+pub fn get_event_content(content_type: &str, content: &Value) -> Result<String, EngineError> {
+    let event_type = {
+        let event_types = match event_types().read() {
+            Ok(event_types) => event_types,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        match event_types.get(content_type) {
+            Some(event_type) => event_type.clone(),
+            None => {
+                return Err(EngineError::Interpreter(format!(
+                    "{} is not a valid content_type",
+                    content_type
+                )))
+            }
+        }
+    };
+
+    if let Some(schema) = &event_type.schema {
+        validate_schema(schema, content, "content").map_err(|err| {
+            EngineError::Interpreter(format!(
+                "invalid content for event type {}: {}",
+                content_type, err
+            ))
+        })?;
+    }
+
+    (event_type.content_value)(content).map_err(EngineError::Interpreter)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_location() {
+        let content = json!({"latitude": 48.85, "longitude": 2.35});
+
+        assert_eq!(
+            get_event_content("location", &content).unwrap(),
+            "48.85,2.35"
+        );
+    }
+
+    #[test]
+    fn ko_location_missing_field() {
+        let content = json!({"latitude": 48.85});
+
+        assert!(get_event_content("location", &content).is_err());
+    }
+
+    #[test]
+    fn ko_reaction_invalid_action() {
+        let content = json!({"emoji": "👍", "action": "edit"});
+
+        assert!(get_event_content("reaction", &content).is_err());
+    }
+
+    #[test]
+    fn ok_register_event_type() {
+        fn sticker_value(content: &Value) -> Result<String, String> {
+            Ok(content["sticker_id"]
+                .as_str()
+                .unwrap_or_default()
+                .to_owned())
+        }
+
+        assert!(get_event_content("sticker", &json!({"sticker_id": "42"})).is_err());
+
+        register_event_type(EventType::new(
+            "sticker",
+            sticker_value,
+            Some(json!({"type": "object", "required": ["sticker_id"]})),
+        ));
+
+        assert_eq!(
+            get_event_content("sticker", &json!({"sticker_id": "42"})).unwrap(),
+            "42"
+        );
+        assert!(get_event_content("sticker", &json!({})).is_err());
+    }
+}
@@ -0,0 +1,486 @@
+use crate::data::{AgentMessageRequest, ConversationInfo, CsmlRequest, Database, EngineError};
+use crate::db_connectors::{
+    conversations, messages, state, unit_of_work::UnitOfWork, DbConversation,
+};
+use crate::init::init_context;
+use crate::send::send_to_handoff_webhook;
+use crate::utils::{
+    get_low_data_mode_value, get_ttl_duration_value, messages_formatter, send_msg_to_callback_url,
+};
+
+use chrono::{SecondsFormat, Utc};
+use csml_interpreter::data::{context::ContextStepInfo, csml_bot::CsmlBot, Client, Event, Message};
+use serde_json::{json, map::Map, Value};
+
+/**
+ * Conversation data of a conversation handed over to a human agent.
+ * The bot is not run in this state, so only what is needed to save
+ * and send messages is loaded.
+ */
+fn init_handoff_info(
+    request_id: String,
+    client: &Client,
+    callback_url: Option<String>,
+    metadata: Value,
+    conversation: &DbConversation,
+    ttl: Option<chrono::Duration>,
+    low_data: bool,
+    renderer: Option<String>,
+    mut db: Database,
+) -> ConversationInfo {
+    let mut context = init_context(
+        conversation.flow_id.to_owned(),
+        client.to_owned(),
+        &None,
+        &mut db,
+    );
+    context.step = ContextStepInfo::Normal(conversation.step_id.to_owned());
+
+    ConversationInfo {
+        request_id,
+        conversation_id: conversation.id.to_owned(),
+        callback_url,
+        client: client.to_owned(),
+        context,
+        metadata,
+        messages: vec![],
+        ttl,
+        low_data,
+        renderer,
+        stream: None,
+        unit_of_work: UnitOfWork::new(),
+        handoff_events: vec![],
+        outbox_position: None,
+        outbox_blocked: false,
+        db,
+    }
+}
+
+fn save_handoff(
+    client: &Client,
+    handoff: &Value,
+    ttl: Option<chrono::Duration>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    // state items are inserted, remove the previous handoff first
+    state::delete_state_key(client, "handoff", "conversation", db)?;
+    state::set_state_items(client, "handoff", vec![("conversation", handoff)], ttl, db)
+}
+
+pub fn get_handoff_conversation(
+    client: &Client,
+    db: &mut Database,
+) -> Result<DbConversation, EngineError> {
+    match conversations::get_latest_by_status(client, "HANDOFF", db)? {
+        Some(conversation) => Ok(conversation),
+        None => Err(EngineError::Format(
+            "no conversation of this client is handed over to an agent".to_owned(),
+        )),
+    }
+}
+
+/**
+ * Hand the current conversation over to a human agent: the conversation goes
+ * to the HANDOFF status and the bot stops answering until it is resumed.
+ * The bot information is kept in the state of the client to resume it later on.
+ */
+pub fn start_handoff(
+    data: &mut ConversationInfo,
+    bot: &CsmlBot,
+    reason: Option<String>,
+    metadata: Value,
+) -> Result<(), EngineError> {
+    let handoff = json!({
+        "conversation_id": data.conversation_id,
+        "flow_id": data.context.flow,
+        "step_id": data.context.step.get_step(),
+        "reason": reason,
+        "metadata": metadata,
+        "callback_url": data.callback_url,
+        "started_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "bot": {
+            "apps_endpoint": bot.apps_endpoint,
+            "multibot": bot.multibot,
+        },
+    });
+
+    // saved with the other writes of the step, see `interpret_step`
+    data.unit_of_work.set_conversation_status(
+        &data.client,
+        &data.conversation_id,
+        "OPEN",
+        "HANDOFF",
+    );
+    data.unit_of_work
+        .delete_state_key(&data.client, "handoff", "conversation");
+    data.unit_of_work.set_state_items(
+        &data.client,
+        "handoff",
+        vec![("conversation", &handoff)],
+        data.ttl,
+    );
+
+    // sent once the step is committed, see `interpret_step`
+    data.handoff_events.push(json!({
+        "type": "handoff",
+        "client": data.client,
+        "conversation_id": data.conversation_id,
+        "flow_id": handoff["flow_id"],
+        "step_id": handoff["step_id"],
+        "reason": handoff["reason"],
+        "metadata": handoff["metadata"],
+    }));
+
+    Ok(())
+}
+
+/**
+ * Save an event of a user whose conversation is handed over to a human agent
+ * and forward it to the handoff webhook instead of running the bot.
+ */
+pub fn forward_to_agent(
+    request: &CsmlRequest,
+    event: &Event,
+    conversation: DbConversation,
+    mut db: Database,
+) -> Result<Map<String, Value>, EngineError> {
+    let ttl = get_ttl_duration_value(Some(event));
+    let low_data = get_low_data_mode_value(event);
+
+    // agent messages are sent to the latest callback_url of the user
+    if let Some(callback_url) = &request.callback_url {
+        let handoff = state::get_state_key(&request.client, "handoff", "conversation", &mut db)?;
+
+        if let Some(mut handoff) = handoff {
+            if handoff["callback_url"].as_str() != Some(callback_url) {
+                handoff["callback_url"] = json!(callback_url);
+                save_handoff(&request.client, &handoff, ttl, &mut db)?;
+            }
+        }
+    }
+
+    let mut data = init_handoff_info(
+        request.request_id.to_owned(),
+        &request.client,
+        request.callback_url.to_owned(),
+        request.metadata.to_owned(),
+        &conversation,
+        ttl,
+        low_data,
+        request.renderer.to_owned(),
+        db,
+    );
+
+    let payload = match event.secure {
+        true => json!({"content_type": "secure"}),
+        false => request.payload.to_owned(),
+    };
+
+    if !data.low_data {
+        messages::add_messages_bulk(&mut data, vec![payload.clone()], 0, "RECEIVE")?;
+    }
+
+    send_to_handoff_webhook(json!({
+        "type": "message",
+        "client": data.client,
+        "conversation_id": data.conversation_id,
+        "request_id": data.request_id,
+        "payload": payload,
+        "metadata": data.metadata,
+    }));
+
+    let mut result = messages_formatter(&mut data, vec![], 0, false);
+    result.insert("handoff".to_owned(), Value::Bool(true));
+
+    Ok(result)
+}
+
+/**
+ * Save a message of the human agent in the conversation
+ * and send it to the callback_url of the user.
+ */
+pub fn send_agent_message(
+    request: AgentMessageRequest,
+    mut db: Database,
+) -> Result<Map<String, Value>, EngineError> {
+    let message = match (
+        request.payload["content_type"].as_str(),
+        request.payload.get("content"),
+    ) {
+        (Some(content_type), Some(content)) => Message {
+            content_type: content_type.to_owned(),
+            content: content.to_owned(),
+        },
+        _ => {
+            return Err(EngineError::Format(
+                "invalid agent message: expect a content_type and a content".to_owned(),
+            ))
+        }
+    };
+
+    let conversation = get_handoff_conversation(&request.client, &mut db)?;
+    let handoff = state::get_state_key(&request.client, "handoff", "conversation", &mut db)?
+        .unwrap_or(Value::Null);
+
+    let mut data = init_handoff_info(
+        uuid::Uuid::new_v4().to_string(),
+        &request.client,
+        handoff["callback_url"].as_str().map(|url| url.to_owned()),
+        request.metadata,
+        &conversation,
+        get_ttl_duration_value(None),
+        false,
+        None,
+        db,
+    );
+
+    messages::add_messages_bulk(
+        &mut data,
+        vec![message.clone().message_to_json()],
+        0,
+        "SEND",
+    )?;
+    send_msg_to_callback_url(&mut data, vec![message.clone()], 0, false);
+
+    Ok(messages_formatter(&mut data, vec![message], 0, false))
+}
+
+/**
+ * Give the conversation back: the conversation leaves the HANDOFF status
+ * and the handoff information is returned so that the bot can be resumed.
+ */
+pub fn end_handoff(client: &Client, status: &str, db: &mut Database) -> Result<Value, EngineError> {
+    let conversation = get_handoff_conversation(client, db)?;
+    let handoff =
+        state::get_state_key(client, "handoff", "conversation", db)?.unwrap_or(Value::Null);
+
+    // the conversation may have been resumed by another request in the meantime
+    if !conversations::set_conversation_status(&conversation.id, client, "HANDOFF", status, db)? {
+        return Err(EngineError::Format(
+            "no conversation of this client is handed over to an agent".to_owned(),
+        ));
+    }
+    state::delete_state_key(client, "handoff", "conversation", db)?;
+
+    send_to_handoff_webhook(json!({
+        "type": "handoff_end",
+        "client": client,
+        "conversation_id": conversation.id,
+        "status": status,
+    }));
+
+    Ok(handoff)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::ResumeRequest;
+    use crate::db_connectors::{bot, init_db};
+    use crate::utils::format_event;
+    use csml_interpreter::data::CsmlFlow;
+
+    fn get_client() -> Client {
+        Client {
+            bot_id: uuid::Uuid::new_v4().to_string(),
+            channel_id: "handoff-channel".to_owned(),
+            user_id: "test".to_owned(),
+        }
+    }
+
+    fn get_bot(bot_id: &str) -> CsmlBot {
+        CsmlBot {
+            id: bot_id.to_owned(),
+            name: "bot".to_owned(),
+            apps_endpoint: None,
+            flows: vec![CsmlFlow {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: "Default".to_owned(),
+                content: "start: say \"hello\" goto end".to_owned(),
+                commands: vec![],
+            }],
+            native_components: None,
+            custom_components: None,
+            default_flow: "Default".to_owned(),
+            bot_ast: None,
+            no_interruption_delay: None,
+            env: None,
+            modules: None,
+            multibot: None,
+        }
+    }
+
+    /**
+     * Open a conversation and hand it over to an agent
+     */
+    fn start_test_handoff(client: &Client, db: &mut Database) -> DbConversation {
+        let id = conversations::create_conversation("Default", "start", client, None, db).unwrap();
+
+        assert!(
+            conversations::set_conversation_status(&id, client, "OPEN", "HANDOFF", db).unwrap()
+        );
+        // the conversation is not open anymore
+        assert!(
+            !conversations::set_conversation_status(&id, client, "OPEN", "CLOSED", db).unwrap()
+        );
+
+        let handoff = json!({
+            "conversation_id": id,
+            "flow_id": "Default",
+            "step_id": "start",
+            "callback_url": null,
+            "bot": {"apps_endpoint": null, "multibot": null},
+        });
+        save_handoff(client, &handoff, None, db).unwrap();
+
+        get_handoff_conversation(client, db).unwrap()
+    }
+
+    fn get_messages(client: &Client, db: &mut Database) -> Vec<Value> {
+        let messages = messages::get_client_messages(client, db, None, None, None, None).unwrap();
+
+        messages["messages"].as_array().unwrap().to_owned()
+    }
+
+    #[test]
+    fn ok_start_handoff() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        let id =
+            conversations::create_conversation("Default", "start", &client, None, &mut db).unwrap();
+        let conversation = conversations::get_latest_by_status(&client, "OPEN", &mut db)
+            .unwrap()
+            .unwrap();
+        assert_eq!(conversation.id, id);
+
+        let mut data = init_handoff_info(
+            uuid::Uuid::new_v4().to_string(),
+            &client,
+            None,
+            json!({}),
+            &conversation,
+            None,
+            false,
+            None,
+            init_db().unwrap(),
+        );
+        start_handoff(&mut data, &get_bot(&client.bot_id), None, json!({})).unwrap();
+
+        // nothing is saved nor sent to the webhook before the step is committed
+        assert_eq!(data.handoff_events.len(), 1);
+        assert_eq!(data.handoff_events[0]["type"], "handoff");
+        assert!(
+            conversations::get_latest_by_status(&client, "HANDOFF", &mut db)
+                .unwrap()
+                .is_none()
+        );
+
+        let unit_of_work = std::mem::take(&mut data.unit_of_work);
+        crate::db_connectors::unit_of_work::commit(unit_of_work, &mut db).unwrap();
+        assert_eq!(get_handoff_conversation(&client, &mut db).unwrap().id, id);
+    }
+
+    #[test]
+    fn ok_forward_to_agent() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        let conversation = start_test_handoff(&client, &mut db);
+
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: client.clone(),
+            callback_url: None,
+            payload: json!({"content_type": "text", "content": {"text": "hello"}}),
+            metadata: json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+        let event = format_event(&request).unwrap();
+
+        let response =
+            forward_to_agent(&request, &event, conversation, init_db().unwrap()).unwrap();
+        assert_eq!(response["handoff"], json!(true));
+        assert_eq!(response["messages"], json!([]));
+
+        // the message of the user is saved in the conversation handed over
+        let messages = get_messages(&client, &mut db);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["direction"], "RECEIVE");
+        assert_eq!(messages[0]["payload"]["content"]["text"], "hello");
+    }
+
+    #[test]
+    fn ok_send_agent_message() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        start_test_handoff(&client, &mut db);
+
+        let request = AgentMessageRequest {
+            client: client.clone(),
+            payload: json!({"content_type": "text", "content": {"text": "Hi!"}}),
+            metadata: json!({}),
+        };
+
+        let response = send_agent_message(request, init_db().unwrap()).unwrap();
+        assert_eq!(response["messages"][0]["payload"]["content"]["text"], "Hi!");
+
+        let messages = get_messages(&client, &mut db);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["direction"], "SEND");
+
+        let request = AgentMessageRequest {
+            client: client.clone(),
+            payload: json!({"text": "Hi!"}),
+            metadata: json!({}),
+        };
+        assert!(send_agent_message(request, init_db().unwrap()).is_err());
+    }
+
+    #[test]
+    fn ok_resume_conversation() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        bot::create_bot_version(client.bot_id.clone(), get_bot(&client.bot_id), &mut db).unwrap();
+        let conversation = start_test_handoff(&client, &mut db);
+
+        let request = ResumeRequest {
+            client: client.clone(),
+            flow_id: "Default".to_owned(),
+            step_id: Some("start".to_owned()),
+            callback_url: None,
+            metadata: json!({}),
+        };
+
+        let response = crate::resume_conversation(request.clone()).unwrap();
+        assert_eq!(
+            response["messages"][0]["payload"]["content"]["text"],
+            "hello"
+        );
+
+        // the conversation handed over is not in the HANDOFF status anymore
+        assert!(
+            conversations::get_latest_by_status(&client, "HANDOFF", &mut db)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            state::get_state_key(&client, "handoff", "conversation", &mut db)
+                .unwrap()
+                .is_none()
+        );
+        assert!(!conversations::set_conversation_status(
+            &conversation.id,
+            &client,
+            "HANDOFF",
+            "OPEN",
+            &mut db
+        )
+        .unwrap());
+
+        // the conversation can only be resumed once
+        assert!(crate::resume_conversation(request).is_err());
+    }
+}
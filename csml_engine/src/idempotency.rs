@@ -0,0 +1,147 @@
+/**
+ * Channel providers retry their webhooks when they do not get an answer in time,
+ * which would run the same event twice. The response of each request is stored
+ * in the state of the client under its request_id, and returned as is when
+ * a request with the same request_id is received again.
+ *
+ * The responses are kept ENGINE_IDEMPOTENCY_TTL seconds, one day by default.
+ * Clients that reuse a fixed request_id for different requests must disable the check
+ * with ENGINE_IDEMPOTENCY_TTL=0.
+ */
+use crate::data::{CsmlRequest, Database, EngineError};
+use crate::db_connectors::state::{delete_state_key, get_state_key, set_state_items};
+
+use chrono::Utc;
+use serde_json::{json, Map, Value};
+
+const DEFAULT_IDEMPOTENCY_TTL: i64 = 86400; // seconds
+
+fn get_idempotency_ttl() -> Option<chrono::Duration> {
+    let ttl = match std::env::var("ENGINE_IDEMPOTENCY_TTL") {
+        Ok(val) => val.parse::<i64>().unwrap_or(DEFAULT_IDEMPOTENCY_TTL),
+        Err(_) => DEFAULT_IDEMPOTENCY_TTL,
+    };
+
+    match ttl {
+        ttl if ttl > 0 => Some(chrono::Duration::seconds(ttl)),
+        _ => None,
+    }
+}
+
+fn get_response(
+    request: &CsmlRequest,
+    db: &mut Database,
+) -> Result<Option<Map<String, Value>>, EngineError> {
+    let processed = get_state_key(&request.client, "request", &request.request_id, db)?;
+
+    match processed {
+        // expired items are only removed by delete_expired_data
+        Some(processed) if processed["expires_at"].as_i64() >= Some(Utc::now().timestamp()) => {
+            match processed["response"].to_owned() {
+                Value::Object(response) => Ok(Some(response)),
+                _ => Ok(None),
+            }
+        }
+        _ => Ok(None),
+    }
+}
+
+fn save_response(
+    request: &CsmlRequest,
+    response: &Map<String, Value>,
+    ttl: chrono::Duration,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    let processed = json!({
+        "expires_at": (Utc::now() + ttl).timestamp(),
+        "response": response,
+    });
+
+    // an expired response of the same request_id may still be stored
+    delete_state_key(&request.client, "request", &request.request_id, db)?;
+    set_state_items(
+        &request.client,
+        "request",
+        vec![(request.request_id.as_str(), &processed)],
+        Some(ttl),
+        db,
+    )
+}
+
+/**
+ * Response of the first processing of the request, if it was already processed
+ */
+pub fn get_processed_request(
+    request: &CsmlRequest,
+    db: &mut Database,
+) -> Result<Option<Map<String, Value>>, EngineError> {
+    if request.request_id.is_empty() || get_idempotency_ttl().is_none() {
+        return Ok(None);
+    }
+
+    get_response(request, db)
+}
+
+pub fn save_processed_request(
+    request: &CsmlRequest,
+    response: &Map<String, Value>,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    match get_idempotency_ttl() {
+        Some(ttl) if !request.request_id.is_empty() => save_response(request, response, ttl, db),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::init_db;
+    use crate::Client;
+
+    #[test]
+    fn ok_processed_request() {
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: Client {
+                bot_id: "bot_id".to_owned(),
+                channel_id: "channel_id".to_owned(),
+                user_id: "test".to_owned(),
+            },
+            callback_url: None,
+            payload: json!({"content_type": "text", "content": {"text": "hello"}}),
+            metadata: json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+        let mut db = init_db().unwrap();
+
+        assert!(get_response(&request, &mut db).unwrap().is_none());
+
+        let mut response = Map::new();
+        response.insert("request_id".to_owned(), json!(request.request_id));
+        response.insert("messages".to_owned(), json!([]));
+        save_response(&request, &response, chrono::Duration::minutes(1), &mut db).unwrap();
+
+        assert_eq!(
+            get_response(&request, &mut db).unwrap(),
+            Some(response.clone())
+        );
+
+        // enabled by default
+        assert_eq!(
+            get_processed_request(&request, &mut db).unwrap(),
+            Some(response)
+        );
+
+        std::env::set_var("ENGINE_IDEMPOTENCY_TTL", "0");
+        let processed = get_processed_request(&request, &mut db);
+        std::env::remove_var("ENGINE_IDEMPOTENCY_TTL");
+        assert!(processed.unwrap().is_none());
+
+        delete_state_key(&request.client, "request", &request.request_id, &mut db).unwrap();
+    }
+}
@@ -1,5 +1,6 @@
-use crate::db_connectors::{conversations::*, memories::*, state};
+use crate::db_connectors::{conversations::*, memories::*, state, unit_of_work::UnitOfWork};
 use crate::interpreter_actions::SwitchBot;
+use crate::send::start_outbox_delivery;
 use crate::{
     data::{ConversationInfo, CsmlRequest, Database, EngineError},
     utils::{
@@ -11,6 +12,7 @@ use crate::{
 
 use csml_interpreter::data::context::ContextStepInfo;
 use csml_interpreter::{
+    csml_tracing,
     data::{
         ast::Flow,
         context::{get_hashmap_from_json, get_hashmap_from_mem},
@@ -83,6 +85,12 @@ pub fn init_conversation_info<'a>(
         messages: vec![],
         ttl,
         low_data,
+        renderer: request.renderer.clone(),
+        stream: None,
+        unit_of_work: UnitOfWork::new(),
+        handoff_events: vec![],
+        outbox_position: None,
+        outbox_blocked: false,
         db,
     };
 
@@ -100,6 +108,20 @@ pub fn init_conversation_info<'a>(
  * Initialize the bot
  */
 pub fn init_bot(bot: &mut CsmlBot) -> Result<(), EngineError> {
+    let mut span = csml_tracing::Span::start("init_bot", vec![("bot_id", bot.id.to_owned())]);
+
+    let result = load_bot(bot);
+    if let Err(err) = &result {
+        span.set_error(format!("{:?}", err));
+    }
+
+    result
+}
+
+/**
+ * Load the native components and the modules of the bot, then build its ast
+ */
+fn load_bot(bot: &mut CsmlBot) -> Result<(), EngineError> {
     // load native components into the bot
     bot.native_components = match load_components() {
         Ok(components) => Some(components),
@@ -297,6 +319,11 @@ pub fn switch_bot(
         None => bot.get_default_flow_name(),
     };
 
+    // the messages queued for the previous bot are delivered with its client
+    start_outbox_delivery(data);
+    data.outbox_position = None;
+    data.outbox_blocked = false;
+
     // update client with the new bot id
     data.client.bot_id = bot.id.to_owned();
 
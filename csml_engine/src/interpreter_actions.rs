@@ -1,12 +1,15 @@
-use crate::db_connectors::{conversations::*, memories::*, messages::*, state::*};
+use crate::data::*;
+use crate::db_connectors::{schedules, state::*, unit_of_work};
+use crate::handoff::start_handoff;
+use crate::send::send_to_handoff_webhook;
 use crate::utils::*;
-use crate::{data::*, delete_client_memories};
 
 use csml_interpreter::data::context::ContextStepInfo;
 use csml_interpreter::{
+    csml_tracing,
     data::{
         ast::ForgetMemory, csml_bot::CsmlBot, csml_flow::CsmlFlow, csml_logs::*, Client, Event,
-        Hold, Memory, Message, MultiBot, MSG,
+        Hold, HoldTimeout, Memory, Message, MultiBot, TargetBot, MSG,
     },
     interpret,
 };
@@ -68,7 +71,12 @@ pub fn interpret_step(
         LogLvl::Debug,
     );
     let new_bot = bot.clone();
+    // the spans and logs of the interpreter are part of the request of this thread
+    let trace_context = csml_tracing::current_context();
+    let log_context = get_log_context();
     thread::spawn(move || {
+        let _parent = csml_tracing::enter(trace_context);
+        let _log_context = enter_log_context(log_context);
         interpret(new_bot, context, event, Some(sender));
     });
 
@@ -82,16 +90,16 @@ pub fn interpret_step(
             MSG::Forget(mem) => match mem {
                 ForgetMemory::ALL => {
                     memories.clear();
-                    delete_client_memories(&data.client)?;
+                    data.unit_of_work.delete_memories(&data.client);
                 }
                 ForgetMemory::SINGLE(memory) => {
                     memories.remove(&memory.ident);
-                    crate::delete_client_memory(&data.client, &memory.ident)?;
+                    data.unit_of_work.delete_memory(&data.client, &memory.ident);
                 }
                 ForgetMemory::LIST(mem_list) => {
                     for mem in mem_list.iter() {
                         memories.remove(&mem.ident);
-                        crate::delete_client_memory(&data.client, &mem.ident)?;
+                        data.unit_of_work.delete_memory(&data.client, &mem.ident);
                     }
                 }
             },
@@ -125,7 +133,8 @@ pub fn interpret_step(
                 log_lvl,
             } => {
                 csml_logger(
-                    CsmlLog::new(Some(&data.client), Some(flow), Some(line), message),
+                    CsmlLog::new(Some(&data.client), Some(flow), Some(line), message)
+                        .with_event("csml_log"),
                     log_lvl,
                 );
             }
@@ -136,9 +145,10 @@ pub fn interpret_step(
                 flow_name,
                 previous,
                 secure,
+                timeout,
             }) => {
                 let hash = get_current_step_hash(&data.context, bot)?;
-                let state_hold: Value = serde_json::json!({
+                let mut state_hold: Value = serde_json::json!({
                     "index": index,
                     "step_vars": step_vars,
                     "hash": hash,
@@ -146,6 +156,10 @@ pub fn interpret_step(
                     "secure": secure
                 });
 
+                if let Some(timeout) = &timeout {
+                    state_hold["timeout"] = set_hold_timeout(data, bot, timeout)?;
+                }
+
                 csml_logger(
                     CsmlLog::new(
                         None,
@@ -165,13 +179,12 @@ pub fn interpret_step(
                     LogLvl::Debug,
                 );
 
-                set_state_items(
+                data.unit_of_work.set_state_items(
                     &data.client,
                     "hold",
                     vec![("position", &state_hold)],
                     data.ttl,
-                    &mut data.db,
-                )?;
+                );
                 data.context.hold = Some(Hold {
                     index,
                     step_vars,
@@ -179,6 +192,7 @@ pub fn interpret_step(
                     flow_name,
                     previous,
                     secure,
+                    timeout,
                 });
             }
             MSG::Next {
@@ -227,7 +241,70 @@ pub fn interpret_step(
 
                 send_msg_to_callback_url(data, vec![err_msg.clone()], interaction_order, true);
                 data.messages.push(err_msg);
-                close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+                data.unit_of_work.close_conversation(&data.client, &data.conversation_id);
+            }
+
+            MSG::GetCachedToken { key, reply } => {
+                let token_client = get_token_cache_client(&data.client);
+                let token = match get_state_key(&token_client, "oauth2", &key, &mut data.db) {
+                    Ok(token) => token,
+                    Err(err) => {
+                        csml_logger(
+                            CsmlLog::new(
+                                Some(&data.client),
+                                Some(data.context.flow.to_string()),
+                                None,
+                                format!("failed to read cached token: {:?}", err),
+                            ),
+                            LogLvl::Error,
+                        );
+                        None
+                    }
+                };
+
+                // the interpreter may have stopped waiting, a failed reply is not an error
+                reply.send(token).ok();
+            }
+
+            MSG::CacheToken {
+                key,
+                token,
+                expires_in,
+            } => {
+                let token_client = get_token_cache_client(&data.client);
+
+                // saved with the other writes of the interaction, see `interpret_step`
+                // state items are inserted, remove the previous token first
+                data.unit_of_work.delete_state_key(&token_client, "oauth2", &key);
+                data.unit_of_work.set_state_items(
+                    &token_client,
+                    "oauth2",
+                    vec![(&key, &token)],
+                    chrono::Duration::try_seconds(expires_in),
+                );
+            }
+
+            MSG::Handoff { reason, metadata } => {
+                csml_logger(
+                    CsmlLog::new(
+                        None,
+                        Some(data.context.flow.to_string()),
+                        None,
+                        "handoff to agent".to_owned(),
+                    ),
+                    LogLvl::Info,
+                );
+                csml_logger(
+                    CsmlLog::new(
+                        Some(&data.client),
+                        Some(data.context.flow.to_string()),
+                        None,
+                        format!("handoff to agent, reason {:?}", reason),
+                    ),
+                    LogLvl::Debug,
+                );
+
+                start_handoff(data, bot, reason, metadata)?;
             }
         }
     }
@@ -240,10 +317,28 @@ pub fn interpret_step(
         .collect();
 
     if !data.low_data {
-        add_messages_bulk(data, msgs, interaction_order, "SEND")?;
+        data.unit_of_work.add_messages(
+            &data.client,
+            &data.conversation_id,
+            &data.context,
+            msgs,
+            interaction_order,
+            "SEND",
+            data.ttl,
+        );
     }
 
-    add_memories(data, &memories)?;
+    data.unit_of_work.add_memories(&data.client, &memories, data.ttl);
+
+    // all the writes of the step are saved at once
+    let unit_of_work = std::mem::take(&mut data.unit_of_work);
+    let handoff_events = std::mem::take(&mut data.handoff_events);
+    unit_of_work::commit(unit_of_work, &mut data.db)?;
+
+    // the webhook is only told about the handoff once the conversation is handed over
+    for event in handoff_events {
+        send_to_handoff_webhook(event);
+    }
 
     Ok((
         messages_formatter(
@@ -256,31 +351,103 @@ pub fn interpret_step(
     ))
 }
 
+/**
+ * Register the timeout of a hold as a single run schedule of the client,
+ * dispatched by `process_due_timeouts` once its deadline is reached.
+ * The schedule is created right away: if the hold is not saved with the rest of
+ * the interaction, its id does not match any hold and the timeout is dropped.
+ */
+fn set_hold_timeout(
+    data: &mut ConversationInfo,
+    bot: &CsmlBot,
+    timeout: &HoldTimeout,
+) -> Result<Value, EngineError> {
+    let deadline = match chrono::Duration::try_seconds(timeout.duration)
+        .and_then(|duration| chrono::Utc::now().checked_add_signed(duration))
+    {
+        Some(deadline) => deadline,
+        None => {
+            return Err(EngineError::Interpreter(format!(
+                "hold timeout of {} seconds is out of range",
+                timeout.duration
+            )))
+        }
+    };
+
+    let job = serde_json::json!({
+        "type": HOLD_TIMEOUT_JOB,
+        "flow_id": data.context.flow,
+        "step_id": timeout.step,
+        "callback_url": data.callback_url,
+        "metadata": data.metadata,
+        "apps_endpoint": bot.apps_endpoint,
+        "multibot": bot.multibot,
+    });
+
+    let id = schedules::create_schedule(&data.client, None, &job, deadline, &mut data.db)?;
+
+    Ok(serde_json::json!({
+        "id": id,
+        "deadline": deadline.timestamp(),
+        "flow": data.context.flow,
+        "step": timeout.step,
+    }))
+}
+
+/**
+ * Tokens cached by the OAuth2 builtin are shared by every user of a bot,
+ * so they are stored in the state of a bot-level client.
+ */
+fn get_token_cache_client(client: &Client) -> Client {
+    Client::new(
+        client.bot_id.to_owned(),
+        "_oauth2".to_owned(),
+        "_bot".to_owned(),
+    )
+}
+
 fn manage_switch_bot<'a>(
     data: &mut ConversationInfo,
     interaction_order: &mut i32,
     bot: &'a CsmlBot,
     flow: Option<String>,
     step: Option<ContextStepInfo>,
-    target_bot: String,
+    target_bot: TargetBot,
 ) -> Result<InterpreterReturn, EngineError> {
-    // check if we are allow to switch to 'target_bot'
+    let TargetBot {
+        bot: target_bot,
+        payload,
+    } = target_bot;
 
+    // check if we are allow to switch to 'target_bot'
     let next_bot = if let Some(multibot) = &bot.multibot {
-        multibot.iter().find(
-            |&MultiBot {
-                 id,
-                 name,
-                 version_id: _,
-             }| match name {
-                Some(name) => target_bot == *id || target_bot == *name,
-                None => target_bot == *id,
-            },
-        )
+        multibot
+            .iter()
+            .find(
+                |&MultiBot {
+                     id,
+                     name,
+                     version_id: _,
+                 }| match name {
+                    Some(name) => target_bot == *id || target_bot == *name,
+                    None => target_bot == *id,
+                },
+            )
+            .cloned()
     } else {
         None
     };
 
+    // going back to the bot that switched to this one is always allowed
+    let next_bot = next_bot.or_else(|| match &data.context.previous_bot {
+        Some(previous_bot) if previous_bot.bot == target_bot => Some(MultiBot {
+            id: previous_bot.bot.to_owned(),
+            name: None,
+            version_id: None,
+        }),
+        _ => None,
+    });
+
     let next_bot = match next_bot {
         Some(next_bot) => next_bot,
         None => {
@@ -398,15 +565,16 @@ fn manage_switch_bot<'a>(
         LogLvl::Info,
     );
 
-    close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+    data.unit_of_work.close_conversation(&data.client, &data.conversation_id);
 
     let previous_bot: Value = serde_json::json!({
         "bot": data.client.bot_id,
         "flow": data.context.flow,
         "step": data.context.step,
+        "payload": payload,
     });
 
-    set_state_items(
+    data.unit_of_work.set_state_items(
         &Client::new(
             next_bot.id.to_owned(),
             data.client.channel_id.clone(),
@@ -415,8 +583,7 @@ fn manage_switch_bot<'a>(
         "bot",
         vec![("previous", &previous_bot)],
         data.ttl,
-        &mut data.db,
-    )?;
+    );
 
     Ok(InterpreterReturn::SwitchBot(SwitchBot {
         bot_id: next_bot.id.to_owned(),
@@ -536,11 +703,12 @@ fn goto_flow<'a>(
     data.context.flow = nextflow;
     data.context.step = nextstep;
 
-    update_conversation(
-        data,
+    data.unit_of_work.update_conversation(
+        &data.client,
+        &data.conversation_id,
         Some(current_flow.id.clone()),
         Some(data.context.step.get_step()),
-    )?;
+    );
 
     *interaction_order += 1;
 
@@ -561,15 +729,151 @@ fn goto_step<'a>(
 
         // send end of conversation
         send_msg_to_callback_url(data, vec![], *interaction_order, *conversation_end);
-        close_conversation(&data.conversation_id, &data.client, &mut data.db)?;
+        data.unit_of_work.close_conversation(&data.client, &data.conversation_id);
 
         // break interpret_step loop
         return Ok(*conversation_end);
     } else {
         data.context.step = nextstep;
-        update_conversation(data, None, Some(data.context.step.get_step()))?;
+        data.unit_of_work.update_conversation(
+            &data.client,
+            &data.conversation_id,
+            None,
+            Some(data.context.step.get_step()),
+        );
     }
 
     *interaction_order += 1;
     Ok(false)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::{bot, init_db};
+    use crate::{process_due_timeouts, start_conversation, BotOpt, CsmlRequest};
+
+    fn get_client() -> Client {
+        Client {
+            bot_id: uuid::Uuid::new_v4().to_string(),
+            channel_id: "timeout-channel".to_owned(),
+            user_id: "test".to_owned(),
+        }
+    }
+
+    fn get_bot(bot_id: &str) -> CsmlBot {
+        let content = r#"
+        start:
+            hold(timeout = 1, on_timeout = "late")
+            say "answered"
+            goto end
+
+        late:
+            say "too late"
+            goto end
+        "#;
+
+        CsmlBot {
+            id: bot_id.to_owned(),
+            name: "bot".to_owned(),
+            apps_endpoint: None,
+            flows: vec![CsmlFlow {
+                id: uuid::Uuid::new_v4().to_string(),
+                name: "Default".to_owned(),
+                content: content.to_owned(),
+                commands: vec![],
+            }],
+            native_components: None,
+            custom_components: None,
+            default_flow: "Default".to_owned(),
+            bot_ast: None,
+            no_interruption_delay: None,
+            env: None,
+            modules: None,
+            multibot: None,
+        }
+    }
+
+    fn send_text(client: &Client, text: &str) -> serde_json::Map<String, Value> {
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: client.clone(),
+            callback_url: None,
+            payload: serde_json::json!({"content_type": "text", "content": {"text": text}}),
+            metadata: serde_json::json!({}),
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+        let bot_opt = BotOpt::BotId {
+            bot_id: client.bot_id.clone(),
+            apps_endpoint: None,
+            multibot: None,
+        };
+
+        start_conversation(request, bot_opt).unwrap()
+    }
+
+    fn get_hold_timeouts(client: &Client, db: &mut Database) -> Vec<Value> {
+        schedules::get_client_schedules(client, db)
+            .unwrap()
+            .into_iter()
+            .filter(|schedule| schedule.job["type"] == HOLD_TIMEOUT_JOB)
+            .map(|schedule| schedule.job)
+            .collect()
+    }
+
+    #[test]
+    fn ok_hold_timeout() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        bot::create_bot_version(client.bot_id.clone(), get_bot(&client.bot_id), &mut db).unwrap();
+
+        send_text(&client, "hello");
+
+        let timeouts = get_hold_timeouts(&client, &mut db);
+        assert_eq!(timeouts.len(), 1);
+        assert_eq!(timeouts[0]["flow_id"], "Default");
+        assert_eq!(timeouts[0]["step_id"], "late");
+
+        std::thread::sleep(std::time::Duration::from_millis(1500));
+
+        let results = process_due_timeouts().unwrap();
+        let result = results
+            .iter()
+            .find(|result| result["client"] == serde_json::json!(client))
+            .expect("the timeout of the client should be dispatched");
+        assert_eq!(
+            result["messages"][0]["payload"]["content"]["text"],
+            "too late"
+        );
+
+        // the timeout is claimed and only dispatched once
+        assert!(get_hold_timeouts(&client, &mut db).is_empty());
+        let results = process_due_timeouts().unwrap();
+        assert!(results
+            .iter()
+            .all(|result| result["client"] != serde_json::json!(client)));
+    }
+
+    #[test]
+    fn ok_hold_answered_before_timeout() {
+        let client = get_client();
+        let mut db = init_db().unwrap();
+        bot::create_bot_version(client.bot_id.clone(), get_bot(&client.bot_id), &mut db).unwrap();
+
+        send_text(&client, "hello");
+        assert_eq!(get_hold_timeouts(&client, &mut db).len(), 1);
+
+        let response = send_text(&client, "answer");
+        assert_eq!(
+            response["messages"][0]["payload"]["content"]["text"],
+            "answered"
+        );
+
+        // the pending timeout is removed when the user answers
+        assert!(get_hold_timeouts(&client, &mut db).is_empty());
+    }
+}
@@ -1,13 +1,25 @@
+pub mod analytics;
+pub mod auth;
+pub mod channels;
+mod client_lock;
 pub mod data;
 
 mod db_connectors;
 mod encrypt;
 mod error_messages;
+pub mod event_types;
+mod handoff;
+mod idempotency;
 mod init;
 mod interpreter_actions;
+#[cfg(feature = "otlp")]
+mod otlp;
+pub mod rate_limit;
 mod send;
 mod utils;
 
+pub use csml_interpreter::csml_metrics as metrics;
+pub use csml_interpreter::csml_tracing;
 pub use csml_interpreter::{
     data::{
         ast::{Expr, Flow, InstructionScope},
@@ -30,9 +42,11 @@ extern crate diesel_migrations;
 
 use data::*;
 use db_connectors::{
-    bot, clean_db, conversations, init_db, memories, messages, state,
+    bot, clean_db, conversations, init_db, memories, messages,
+    outbox::{self, OUTBOX_DEAD, OUTBOX_PENDING},
+    schedules, state,
     state::{delete_state_key, set_state_items},
-    user, BotVersion, BotVersionCreated, DbConversation,
+    user, BotVersion, BotVersionCreated, DbConversation, DbOutboxMessage, DbSchedule,
 };
 use init::*;
 use interpreter_actions::{interpret_step, SwitchBot};
@@ -59,13 +73,95 @@ use std::{collections::HashMap, env};
  */
 pub fn start_conversation(
     request: CsmlRequest,
-    mut bot_opt: BotOpt,
+    bot_opt: BotOpt,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    run_conversation(request, bot_opt, None)
+}
+
+/**
+ * Same as `start_conversation`, but each message is also sent to `stream` as soon as
+ * the interpreter emits it, in the same format as the callback_url requests.
+ * The sender is dropped once the conversation returns.
+ */
+pub fn start_conversation_stream(
+    request: CsmlRequest,
+    bot_opt: BotOpt,
+    stream: std::sync::mpsc::Sender<serde_json::Value>,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    run_conversation(request, bot_opt, Some(stream))
+}
+
+fn run_conversation(
+    request: CsmlRequest,
+    bot_opt: BotOpt,
+    stream: Option<std::sync::mpsc::Sender<serde_json::Value>>,
 ) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
     init_logger();
+    init_tracing();
+
+    let remote_parent = request
+        .traceparent
+        .as_deref()
+        .and_then(csml_tracing::SpanContext::from_traceparent);
+    let _parent = csml_tracing::enter(remote_parent);
+    let _log_context = enter_log_context(LogContext {
+        request_id: Some(request.request_id.to_owned()),
+        client: Some(request.client.to_owned()),
+        ..Default::default()
+    });
+    let mut span = csml_tracing::Span::start(
+        "start_conversation",
+        vec![
+            ("bot_id", request.client.bot_id.to_owned()),
+            ("channel_id", request.client.channel_id.to_owned()),
+            ("request_id", request.request_id.to_owned()),
+        ],
+    );
+
+    let result = process_conversation(request, bot_opt, stream);
+    if let Err(err) = &result {
+        span.set_error(format!("{:?}", err));
+    }
 
+    result
+}
+
+fn process_conversation(
+    request: CsmlRequest,
+    mut bot_opt: BotOpt,
+    stream: Option<std::sync::mpsc::Sender<serde_json::Value>>,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
     let mut formatted_event = format_event(&request)?;
+    if formatted_event.secure {
+        add_secret(&formatted_event.content_value);
+    }
+
     let mut db = init_db()?;
 
+    // requests of the same client are processed one at a time, the lock is released on return
+    let _lock = match client_lock::lock_client(&request.client, &mut db)? {
+        Some(lock) => lock,
+        None => return Ok(client_lock::dropped_response(&request)),
+    };
+
+    // a retried request gets the response of its first processing
+    if let Some(response) = idempotency::get_processed_request(&request, &mut db)? {
+        if let Some(stream) = &stream {
+            let _ = stream.send(serde_json::json!(response));
+        }
+        return Ok(response);
+    }
+
+    // a human agent is handling the conversation, the bot must not answer
+    if let Some(conversation) =
+        conversations::get_latest_by_status(&request.client, "HANDOFF", &mut db)?
+    {
+        let response = handoff::forward_to_agent(&request, &formatted_event, conversation, db)?;
+
+        idempotency::save_processed_request(&request, &response, &mut init_db()?)?;
+        return Ok(response);
+    }
+
     let mut bot = bot_opt.search_bot(&mut db)?;
     init_bot(&mut bot)?;
 
@@ -76,6 +172,8 @@ pub fn start_conversation(
         &bot,
         db,
     )?;
+    data.stream = stream;
+    set_conversation_id(&data.conversation_id);
 
     check_for_hold(&mut data, &bot, &mut formatted_event)?;
 
@@ -122,13 +220,18 @@ pub fn start_conversation(
 
     let result = interpret_step(&mut data, formatted_event.to_owned(), &bot);
 
-    check_switch_bot(
+    let response = check_switch_bot(
         result,
         &mut data,
         &mut bot,
         &mut bot_opt,
         &mut formatted_event,
-    )
+    )?;
+
+    send::start_outbox_delivery(&data);
+    idempotency::save_processed_request(&request, &response, &mut data.db)?;
+
+    Ok(response)
 }
 
 fn check_switch_bot(
@@ -219,6 +322,39 @@ pub fn get_client_messages(
     messages::get_client_messages(client, &mut db, limit, pagination_key, from_date, to_date)
 }
 
+/**
+ * Visits, ends, median time spent and transitions of each step of a bot, from the
+ * messages created between from_date and to_date (unix timestamps in seconds)
+ */
+pub fn get_step_analytics(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+) -> Result<analytics::StepAnalytics, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    analytics::get_step_analytics(bot_id, channel_id, from_date, to_date, &mut db)
+}
+
+/**
+ * Number of conversations that visited the steps of the funnel in order, written
+ * as (flow_id, step_id)
+ */
+pub fn get_funnel(
+    bot_id: &str,
+    channel_id: Option<&str>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+    funnel: &[(String, String)],
+) -> Result<Vec<analytics::FunnelStep>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    analytics::get_funnel(bot_id, channel_id, from_date, to_date, funnel, &mut db)
+}
+
 pub fn get_client_conversations(
     client: &Client,
     limit: Option<i64>,
@@ -392,6 +528,17 @@ pub fn delete_client(client: &Client) -> Result<(), EngineError> {
     user::delete_client(client, &mut db)
 }
 
+/**
+ * Export all data related to a given Client in a single json document
+ * (conversations, messages, memories and state), decrypted
+ */
+pub fn export_client_data(client: &Client) -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    user::export_client_data(client, &mut db)
+}
+
 /**
  * List all the steps in every flow of a given CSML bot
  */
@@ -458,9 +605,81 @@ pub fn user_close_all_conversations(client: Client) -> Result<(), EngineError> {
     init_logger();
 
     state::delete_state_key(&client, "hold", "position", &mut db)?;
+
+    // a conversation handed over to a human agent is closed as well
+    if conversations::get_latest_by_status(&client, "HANDOFF", &mut db)?.is_some() {
+        handoff::end_handoff(&client, "CLOSED", &mut db)?;
+    }
+
     conversations::close_all_conversations(&client, &mut db)
 }
 
+/**
+ * Send a message of a human agent to a user whose conversation is handed over
+ * to an agent. The message is saved in the conversation and sent to the callback_url
+ * of the user's latest request.
+ */
+pub fn send_agent_message(
+    request: AgentMessageRequest,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let db = init_db()?;
+    init_logger();
+
+    handoff::send_agent_message(request, db)
+}
+
+/**
+ * Give a conversation handed over to a human agent back to the bot.
+ * The bot is resumed at the requested flow and step as if a flow_trigger was received.
+ */
+pub fn resume_conversation(
+    request: ResumeRequest,
+) -> Result<serde_json::Map<String, serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let handoff = handoff::end_handoff(&request.client, "OPEN", &mut db)?;
+
+    let metadata = match request.metadata {
+        serde_json::Value::Null => serde_json::json!({}),
+        metadata => metadata,
+    };
+
+    let callback_url = match request.callback_url {
+        Some(callback_url) => Some(callback_url),
+        None => handoff["callback_url"].as_str().map(|url| url.to_owned()),
+    };
+
+    let csml_request = CsmlRequest {
+        request_id: uuid::Uuid::new_v4().to_string(),
+        client: request.client.clone(),
+        callback_url,
+        payload: serde_json::json!({
+            "content_type": "flow_trigger",
+            "content": {
+                "flow_id": request.flow_id,
+                "step_id": request.step_id,
+            },
+        }),
+        metadata,
+        step_limit: None,
+        ttl_duration: None,
+        low_data_mode: None,
+        renderer: None,
+        traceparent: None,
+    };
+
+    let bot_opt = BotOpt::BotId {
+        bot_id: request.client.bot_id.to_owned(),
+        apps_endpoint: handoff["bot"]["apps_endpoint"]
+            .as_str()
+            .map(|endpoint| endpoint.to_owned()),
+        multibot: serde_json::from_value(handoff["bot"]["multibot"].clone()).unwrap_or(None),
+    };
+
+    start_conversation(csml_request, bot_opt)
+}
+
 /**
  * Verify if the user is currently on hold in a given conversation.
  *
@@ -513,9 +732,14 @@ fn check_for_hold(
                 flow_name: data.context.flow.to_owned(),
                 previous: serde_json::from_value(hold["previous"].clone()).unwrap_or(None),
                 secure: secure_hold,
+                timeout: None,
             });
 
             state::delete_state_key(&data.client, "hold", "position", &mut data.db)?;
+            // the user answered in time, the pending timeout of this hold is not needed anymore
+            if let Some(id) = hold["timeout"]["id"].as_str() {
+                schedules::delete_schedule(&data.client, id, &mut data.db)?;
+            }
         }
         // user is not on hold
         Ok(None) => (),
@@ -524,6 +748,16 @@ fn check_for_hold(
     Ok(())
 }
 
+/**
+ * Export the tracing spans to an OpenTelemetry collector, when csml_engine is built with
+ * the `otlp` feature and OTEL_EXPORTER_OTLP_ENDPOINT is set. Does nothing otherwise.
+ * Called on each conversation, embedders can call it earlier to also export their own spans.
+ */
+pub fn init_tracing() {
+    #[cfg(feature = "otlp")]
+    otlp::init();
+}
+
 /**
  * get server status
  */
@@ -562,10 +796,10 @@ pub fn get_status() -> Result<serde_json::Value, EngineError> {
         Err(_) => status.insert("server_port".to_owned(), serde_json::json!(5000)), // DEFAULT
     };
 
-    match std::env::var("ENGINE_SERVER_API_KEYS") {
-        Ok(_) => status.insert("server_auth_enabled".to_owned(), serde_json::json!(true)),
-        Err(_) => status.insert("server_auth_enabled".to_owned(), serde_json::json!(false)),
-    };
+    status.insert(
+        "server_auth_enabled".to_owned(),
+        serde_json::json!(auth::is_enabled()),
+    );
 
     match std::env::var("ENCRYPTION_SECRET") {
         Ok(_) => status.insert("encryption_enabled".to_owned(), serde_json::json!(true)),
@@ -611,3 +845,327 @@ pub fn delete_expired_data() -> Result<(), EngineError> {
 
     clean_db::delete_expired_data(&mut db)
 }
+
+/**
+ * Schedule a flow_trigger event for a given client, either once at `run_at`
+ * or on every occurrence of a `cron` expression.
+ * The messages of each run are sent to the callback_url of the schedule.
+ */
+pub fn create_schedule(request: ScheduleRequest) -> Result<DbSchedule, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let now = Utc::now();
+    let next_run_at = match (&request.run_at, &request.cron) {
+        (Some(run_at), None) => match DateTime::parse_from_rfc3339(run_at) {
+            Ok(run_at) => run_at.with_timezone(&Utc),
+            Err(_) => {
+                return Err(EngineError::Format(format!(
+                    "invalid run_at date [{}], expected an RFC 3339 date",
+                    run_at
+                )))
+            }
+        },
+        (None, Some(cron)) => get_next_schedule_run(cron, &now)?,
+        _ => {
+            return Err(EngineError::Format(
+                "a schedule expects either a run_at date or a cron expression".to_owned(),
+            ))
+        }
+    };
+
+    let job = serde_json::json!({
+        "flow_id": request.flow_id,
+        "step_id": request.step_id,
+        "callback_url": request.callback_url,
+        "metadata": request.metadata,
+        "apps_endpoint": request.apps_endpoint,
+        "multibot": request.multibot,
+    });
+
+    let id = schedules::create_schedule(
+        &request.client,
+        request.cron.as_deref(),
+        &job,
+        next_run_at,
+        &mut db,
+    )?;
+
+    Ok(DbSchedule {
+        id,
+        client: request.client,
+        cron: request.cron,
+        job,
+        next_run_at: next_run_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        created_at: now.to_rfc3339_opts(SecondsFormat::Millis, true),
+    })
+}
+
+/**
+ * List the schedules of a given client, sorted by next run
+ */
+pub fn get_client_schedules(client: &Client) -> Result<Vec<DbSchedule>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let schedules = schedules::get_client_schedules(client, &mut db)?;
+
+    Ok(schedules
+        .into_iter()
+        .filter(|schedule| schedule.job["type"] != HOLD_TIMEOUT_JOB)
+        .collect())
+}
+
+/**
+ * Cancel a schedule, the runs already dispatched are not affected.
+ * Returns false if the client has no schedule with this id
+ */
+pub fn cancel_schedule(client: &Client, id: &str) -> Result<bool, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    schedules::delete_schedule(client, id, &mut db)
+}
+
+/**
+ * Dispatch every schedule whose next run is reached through start_conversation.
+ * Recurring schedules are moved to their next run and single runs are removed
+ * before being dispatched, so that a failing run is never dispatched twice.
+ * Each schedule is claimed with a conditional write, so when several instances
+ * process the schedules at the same time a run is dispatched by only one of them.
+ * This function is meant to be called periodically by the server or a scheduled job.
+ */
+pub fn process_due_schedules() -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let now = Utc::now();
+    let mut results = vec![];
+
+    for schedule in schedules::get_due_schedules(now, &mut db)? {
+        // hold timeouts are dispatched by process_due_timeouts
+        if schedule.job["type"] == HOLD_TIMEOUT_JOB {
+            continue;
+        }
+
+        let client = schedule.client;
+        let job = schedule.job;
+
+        let next_run_at = match schedule
+            .cron
+            .as_deref()
+            .map(|cron| get_next_schedule_run(cron, &now))
+        {
+            Some(Ok(next_run_at)) => Some(next_run_at),
+            _ => None,
+        };
+
+        // another instance already dispatched this run
+        if !schedules::claim_schedule(&client, &schedule.id, now, next_run_at, &mut db)? {
+            continue;
+        }
+
+        let metadata = match &job["metadata"] {
+            serde_json::Value::Null => serde_json::json!({}),
+            metadata => metadata.clone(),
+        };
+
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: client.clone(),
+            callback_url: job["callback_url"].as_str().map(|url| url.to_owned()),
+            payload: serde_json::json!({
+                "content_type": "flow_trigger",
+                "content": {
+                    "flow_id": job["flow_id"],
+                    "step_id": job["step_id"],
+                },
+            }),
+            metadata,
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+
+        let bot_opt = BotOpt::BotId {
+            bot_id: client.bot_id.to_owned(),
+            apps_endpoint: job["apps_endpoint"]
+                .as_str()
+                .map(|endpoint| endpoint.to_owned()),
+            multibot: serde_json::from_value(job["multibot"].clone()).unwrap_or(None),
+        };
+
+        match start_conversation(request, bot_opt) {
+            Ok(result) => results.push(serde_json::json!(result)),
+            Err(err) => csml_logger(
+                CsmlLog::new(
+                    Some(&client),
+                    job["flow_id"].as_str().map(|flow| flow.to_owned()),
+                    None,
+                    format!("failed to process schedule {}: {:?}", schedule.id, err),
+                ),
+                LogLvl::Error,
+            ),
+        }
+    }
+
+    Ok(results)
+}
+
+/**
+ * Send the timeout event of every hold whose deadline is reached.
+ * The conversation goes to the on_timeout step of the hold as if a flow_trigger
+ * was received, and the messages are sent to the callback_url of the holding request.
+ * Timeouts are stored as single run schedules and claimed the same way.
+ * This function is meant to be called periodically by the server or a scheduled job.
+ */
+pub fn process_due_timeouts() -> Result<Vec<serde_json::Value>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let now = Utc::now();
+    let mut results = vec![];
+
+    for schedule in schedules::get_due_schedules(now, &mut db)? {
+        if schedule.job["type"] != HOLD_TIMEOUT_JOB {
+            continue;
+        }
+
+        let client = schedule.client;
+        let job = schedule.job;
+
+        // another instance already dispatched this timeout
+        if !schedules::claim_schedule(&client, &schedule.id, now, None, &mut db)? {
+            continue;
+        }
+
+        // the user may have answered or the conversation moved on to another hold
+        let hold = state::get_state_key(&client, "hold", "position", &mut db)?;
+        let deadline = match hold {
+            Some(hold) if hold["timeout"]["id"] == schedule.id.as_str() => {
+                hold["timeout"]["deadline"].clone()
+            }
+            _ => continue,
+        };
+
+        let metadata = match &job["metadata"] {
+            serde_json::Value::Null => serde_json::json!({}),
+            metadata => metadata.clone(),
+        };
+
+        let request = CsmlRequest {
+            request_id: uuid::Uuid::new_v4().to_string(),
+            client: client.clone(),
+            callback_url: job["callback_url"].as_str().map(|url| url.to_owned()),
+            payload: serde_json::json!({
+                "content_type": "timeout",
+                "content": {
+                    "flow_id": job["flow_id"],
+                    "step_id": job["step_id"],
+                    "deadline": deadline,
+                },
+            }),
+            metadata,
+            step_limit: None,
+            ttl_duration: None,
+            low_data_mode: None,
+            renderer: None,
+            traceparent: None,
+        };
+
+        let bot_opt = BotOpt::BotId {
+            bot_id: client.bot_id.to_owned(),
+            apps_endpoint: job["apps_endpoint"]
+                .as_str()
+                .map(|endpoint| endpoint.to_owned()),
+            multibot: serde_json::from_value(job["multibot"].clone()).unwrap_or(None),
+        };
+
+        match start_conversation(request, bot_opt) {
+            Ok(result) => results.push(serde_json::json!(result)),
+            Err(err) => csml_logger(
+                CsmlLog::new(
+                    Some(&client),
+                    job["flow_id"].as_str().map(|flow| flow.to_owned()),
+                    None,
+                    format!("failed to process hold timeout: {:?}", err),
+                ),
+                LogLvl::Error,
+            ),
+        }
+    }
+
+    Ok(results)
+}
+
+/**
+ * Deliver the pending messages of the outbox to their callback_url.
+ * The messages of a conversation are sent in order: when one fails, the next ones
+ * wait until it is delivered, and a message moved to the dead letters after
+ * ENGINE_OUTBOX_MAX_ATTEMPTS blocks its conversation until it is replayed or deleted.
+ * Retries are delayed with the same exponential backoff as the synchronous delivery.
+ * This function is meant to be called periodically by the server or a scheduled job.
+ */
+pub fn process_outbox() -> Result<serde_json::Value, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let messages = outbox::get_outbox_messages(&mut db)?;
+    let (delivered, dead) = send::deliver_outbox_messages(messages, &mut db)?;
+
+    Ok(serde_json::json!({
+        "delivered": delivered,
+        "dead": dead,
+    }))
+}
+
+/**
+ * List the messages of a client that could not be delivered to their callback_url
+ */
+pub fn get_dead_letters(client: &Client) -> Result<Vec<DbOutboxMessage>, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    outbox::get_client_outbox_messages(client, OUTBOX_DEAD, &mut db)
+}
+
+/**
+ * Put the dead letters of a client back in the outbox, all of them or only the given ids.
+ * They are delivered by the next `process_outbox` call, before the later messages
+ * of their conversation. Returns the number of replayed messages.
+ */
+pub fn replay_dead_letters(
+    client: &Client,
+    ids: Option<Vec<String>>,
+) -> Result<usize, EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    let now = Utc::now();
+    let mut count = 0;
+
+    for message in outbox::get_client_outbox_messages(client, OUTBOX_DEAD, &mut db)? {
+        if let Some(ids) = &ids {
+            if !ids.contains(&message.id) {
+                continue;
+            }
+        }
+
+        outbox::update_outbox_message(client, &message.id, OUTBOX_PENDING, 0, None, now, &mut db)?;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+/**
+ * Remove a dead letter without delivering it
+ */
+pub fn delete_dead_letter(client: &Client, id: &str) -> Result<(), EngineError> {
+    let mut db = init_db()?;
+    init_logger();
+
+    outbox::delete_outbox_message(client, id, &mut db)
+}
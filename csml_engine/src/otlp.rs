@@ -0,0 +1,187 @@
+/**
+ * Export of the spans to an OpenTelemetry collector, with the OTLP/HTTP protocol in json.
+ *
+ * The spans are sent in batches by a background thread, so that a slow or unreachable
+ * collector does not slow down the conversations. Spans are dropped when the queue is full.
+ */
+use csml_interpreter::csml_tracing::{self, SpanData, SpanExporter};
+use serde_json::{json, Value};
+use std::sync::{mpsc, Mutex, Once};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+const MAX_QUEUE_SIZE: usize = 2048;
+const MAX_BATCH_SIZE: usize = 512;
+const BATCH_DELAY: Duration = Duration::from_secs(2);
+
+static INIT: Once = Once::new();
+
+struct OtlpExporter {
+    sender: Mutex<mpsc::SyncSender<SpanData>>,
+}
+
+impl SpanExporter for OtlpExporter {
+    fn export(&self, span: SpanData) {
+        if let Ok(sender) = self.sender.lock() {
+            let _ = sender.try_send(span);
+        }
+    }
+}
+
+fn unix_nanos(time: SystemTime) -> String {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(duration) => duration.as_nanos().to_string(),
+        Err(_) => "0".to_owned(),
+    }
+}
+
+fn format_span(span: &SpanData) -> Value {
+    let attributes: Vec<Value> = span
+        .attributes
+        .iter()
+        .map(|(key, value)| json!({"key": key, "value": {"stringValue": value}}))
+        .collect();
+
+    let status = match &span.error {
+        // STATUS_CODE_ERROR
+        Some(error) => json!({"code": 2, "message": error}),
+        // STATUS_CODE_UNSET
+        None => json!({"code": 0}),
+    };
+
+    let mut value = json!({
+        "traceId": span.context.trace_id,
+        "spanId": span.context.span_id,
+        "name": span.name,
+        // SPAN_KIND_INTERNAL
+        "kind": 1,
+        "startTimeUnixNano": unix_nanos(span.start_time),
+        "endTimeUnixNano": unix_nanos(span.end_time),
+        "attributes": attributes,
+        "status": status,
+    });
+
+    if let Some(parent_span_id) = &span.parent_span_id {
+        value["parentSpanId"] = json!(parent_span_id);
+    }
+
+    value
+}
+
+fn format_request(service_name: &str, spans: &[SpanData]) -> Value {
+    json!({
+        "resourceSpans": [{
+            "resource": {
+                "attributes": [
+                    {"key": "service.name", "value": {"stringValue": service_name}}
+                ]
+            },
+            "scopeSpans": [{
+                "scope": {
+                    "name": "csml_engine",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+                "spans": spans.iter().map(format_span).collect::<Vec<Value>>(),
+            }]
+        }]
+    })
+}
+
+fn send_batch(url: &str, service_name: &str, spans: &[SpanData]) {
+    let body = format_request(service_name, spans);
+
+    if let Err(err) = ureq::post(url).send_json(body) {
+        eprintln!("OTLP export error: {}", err);
+    }
+}
+
+fn start_worker(url: String, service_name: String, receiver: mpsc::Receiver<SpanData>) {
+    std::thread::spawn(move || {
+        let mut batch = vec![];
+        let mut deadline = Instant::now() + BATCH_DELAY;
+
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(span) => batch.push(span),
+                Err(mpsc::RecvTimeoutError::Timeout) => {}
+                Err(mpsc::RecvTimeoutError::Disconnected) => return,
+            }
+
+            // send the spans every BATCH_DELAY, or as soon as a batch is full
+            if batch.len() >= MAX_BATCH_SIZE || Instant::now() >= deadline {
+                if !batch.is_empty() {
+                    send_batch(&url, &service_name, &batch);
+                    batch.clear();
+                }
+
+                deadline = Instant::now() + BATCH_DELAY;
+            }
+        }
+    });
+}
+
+/**
+ * Set the OTLP exporter when OTEL_EXPORTER_OTLP_ENDPOINT is set (ex: http://localhost:4318).
+ * The spans are sent to {endpoint}/v1/traces, unless OTEL_EXPORTER_OTLP_TRACES_ENDPOINT is set.
+ */
+pub fn init() {
+    INIT.call_once(|| {
+        let url = match (
+            std::env::var("OTEL_EXPORTER_OTLP_TRACES_ENDPOINT"),
+            std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT"),
+        ) {
+            (Ok(url), _) => url,
+            (Err(_), Ok(endpoint)) => format!("{}/v1/traces", endpoint.trim_end_matches('/')),
+            _ => return,
+        };
+        let service_name =
+            std::env::var("OTEL_SERVICE_NAME").unwrap_or_else(|_| "csml_engine".to_owned());
+
+        let (sender, receiver) = mpsc::sync_channel(MAX_QUEUE_SIZE);
+        let exporter = OtlpExporter {
+            sender: Mutex::new(sender),
+        };
+
+        if csml_tracing::set_exporter(Box::new(exporter)).is_ok() {
+            start_worker(url, service_name, receiver);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use csml_interpreter::csml_tracing::SpanContext;
+
+    #[test]
+    fn ok_format_request() {
+        let span = SpanData {
+            name: "execute_step".to_owned(),
+            context: SpanContext {
+                trace_id: "4bf92f3577b34da6a3ce929d0e0e4736".to_owned(),
+                span_id: "00f067aa0ba902b7".to_owned(),
+                sampled: true,
+            },
+            parent_span_id: Some("a3ce929d0e0e4736".to_owned()),
+            start_time: UNIX_EPOCH + Duration::from_millis(1500),
+            end_time: UNIX_EPOCH + Duration::from_millis(2000),
+            attributes: vec![("flow".to_owned(), "Default".to_owned())],
+            error: Some("step not found".to_owned()),
+        };
+
+        let request = format_request("csml", &[span]);
+        let resource = &request["resourceSpans"][0];
+        let span = &resource["scopeSpans"][0]["spans"][0];
+
+        assert_eq!(
+            resource["resource"]["attributes"][0]["value"]["stringValue"],
+            "csml"
+        );
+        assert_eq!(span["traceId"], "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(span["parentSpanId"], "a3ce929d0e0e4736");
+        assert_eq!(span["startTimeUnixNano"], "1500000000");
+        assert_eq!(span["endTimeUnixNano"], "2000000000");
+        assert_eq!(span["attributes"][0]["key"], "flow");
+        assert_eq!(span["status"]["code"], 2);
+        assert_eq!(span["status"]["message"], "step not found");
+    }
+}
@@ -0,0 +1,411 @@
+/**
+ * Token buckets and daily quotas used to limit the requests sent to the engine.
+ *
+ * A bucket of `capacity` tokens is refilled over `period`: up to `capacity` requests
+ * can be made at once, then one request every `period / capacity`. Buckets are
+ * implemented with the generic cell rate algorithm, so that a single value is stored
+ * per bucket: the time at which the bucket is full again.
+ *
+ * Buckets and quotas are stored either in the memory of the process, or in db
+ * (RateLimitStore::Db) to be shared by several instances.
+ */
+use crate::data::{Database, EngineError};
+use crate::db_connectors::{init_db, rate_limits};
+
+use chrono::{DateTime, Duration as ChronoDuration, TimeZone, Utc};
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+// attempts to update a bucket modified concurrently by another instance
+const MAX_DB_ATTEMPTS: usize = 5;
+// number of buckets kept in memory before the full ones are removed
+const MAX_MEMORY_BUCKETS: usize = 10_000;
+
+const DAILY_INTERACTIONS_SCOPE: &str = "daily_interactions";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateLimitStore {
+    Memory,
+    Db,
+}
+
+impl RateLimitStore {
+    pub fn from_name(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "db" => RateLimitStore::Db,
+            _ => RateLimitStore::Memory,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    pub capacity: u32,
+    pub period: Duration,
+}
+
+impl RateLimit {
+    /**
+     * Parse a limit written `capacity/seconds`, e.g. `100/60` for 100 requests per minute
+     */
+    pub fn parse(value: &str) -> Option<Self> {
+        let (capacity, seconds) = value.trim().split_once('/')?;
+
+        let capacity = capacity.trim().parse::<u32>().ok()?;
+        let seconds = seconds.trim().parse::<u64>().ok()?;
+
+        if capacity == 0 || seconds == 0 {
+            return None;
+        }
+
+        Some(Self {
+            capacity,
+            period: Duration::from_secs(seconds),
+        })
+    }
+
+    fn period_millis(&self) -> i64 {
+        self.period.as_millis() as i64
+    }
+
+    fn interval_millis(&self) -> i64 {
+        std::cmp::max(self.period_millis() / self.capacity as i64, 1)
+    }
+}
+
+/**
+ * Take a token from the bucket whose theoretical arrival time is `tat` (in milliseconds).
+ * Returns the new arrival time, or the time to wait before a token is available.
+ */
+fn take_token_at(tat: Option<i64>, now: i64, limit: &RateLimit) -> Result<i64, Duration> {
+    let tat = std::cmp::max(tat.unwrap_or(now), now);
+    let new_tat = tat + limit.interval_millis();
+    let allowed_at = new_tat - limit.period_millis();
+
+    if now < allowed_at {
+        return Err(Duration::from_millis((allowed_at - now) as u64));
+    }
+
+    Ok(new_tat)
+}
+
+static MEMORY_BUCKETS: OnceLock<Mutex<HashMap<String, i64>>> = OnceLock::new();
+static MEMORY_COUNTERS: OnceLock<Mutex<HashMap<(String, String), i64>>> = OnceLock::new();
+
+fn take_memory_token(scope: &str, key: &str, limit: &RateLimit) -> Result<(), Duration> {
+    let mutex = MEMORY_BUCKETS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut buckets = match mutex.lock() {
+        Ok(buckets) => buckets,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    let now = Utc::now().timestamp_millis();
+    let id = format!("{}#{}", scope, key);
+
+    let new_tat = take_token_at(buckets.get(&id).cloned(), now, limit)?;
+
+    if buckets.len() >= MAX_MEMORY_BUCKETS {
+        // a bucket whose arrival time is passed is full, the same as no bucket
+        buckets.retain(|_, tat| *tat > now);
+    }
+    buckets.insert(id, new_tat);
+
+    Ok(())
+}
+
+/**
+ * A counter expires at its theoretical arrival time, once the bucket is full again
+ */
+fn get_expires_at(tat: i64) -> Result<DateTime<Utc>, EngineError> {
+    match Utc.timestamp_millis_opt(tat).single() {
+        Some(expires_at) => Ok(expires_at),
+        None => Err(EngineError::Manager(format!(
+            "invalid rate limit timestamp {}",
+            tat
+        ))),
+    }
+}
+
+fn take_db_token(
+    scope: &str,
+    key: &str,
+    limit: &RateLimit,
+    db: &mut Database,
+) -> Result<Result<(), Duration>, EngineError> {
+    for _ in 0..MAX_DB_ATTEMPTS {
+        let now = Utc::now().timestamp_millis();
+        let tat = rate_limits::get_counter(scope, key, db)?;
+
+        let new_tat = match take_token_at(tat, now, limit) {
+            Ok(new_tat) => new_tat,
+            Err(retry_after) => return Ok(Err(retry_after)),
+        };
+
+        if rate_limits::set_counter(scope, key, tat, new_tat, get_expires_at(new_tat)?, db)? {
+            return Ok(Ok(()));
+        }
+    }
+
+    // too many concurrent requests on the same bucket
+    Ok(Err(Duration::from_millis(limit.interval_millis() as u64)))
+}
+
+/**
+ * Take a token from the bucket of `key` in `scope` (e.g. the bucket of a bot_id).
+ * Returns Err with the time to wait before retrying if the bucket is empty.
+ */
+pub fn take_token(
+    store: RateLimitStore,
+    scope: &str,
+    key: &str,
+    limit: &RateLimit,
+) -> Result<Result<(), Duration>, EngineError> {
+    match store {
+        RateLimitStore::Memory => Ok(take_memory_token(scope, key, limit)),
+        RateLimitStore::Db => {
+            let mut db = init_db()?;
+            take_db_token(scope, key, limit, &mut db)
+        }
+    }
+}
+
+fn return_memory_token(scope: &str, key: &str, limit: &RateLimit) {
+    let mutex = MEMORY_BUCKETS.get_or_init(|| Mutex::new(HashMap::new()));
+    let mut buckets = match mutex.lock() {
+        Ok(buckets) => buckets,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+
+    if let Some(tat) = buckets.get_mut(&format!("{}#{}", scope, key)) {
+        *tat -= limit.interval_millis();
+    }
+}
+
+fn return_db_token(
+    scope: &str,
+    key: &str,
+    limit: &RateLimit,
+    db: &mut Database,
+) -> Result<(), EngineError> {
+    for _ in 0..MAX_DB_ATTEMPTS {
+        let tat = match rate_limits::get_counter(scope, key, db)? {
+            Some(tat) => tat,
+            // the bucket expired, it is already full
+            None => return Ok(()),
+        };
+        let new_tat = tat - limit.interval_millis();
+
+        if rate_limits::set_counter(scope, key, Some(tat), new_tat, get_expires_at(new_tat)?, db)? {
+            return Ok(());
+        }
+    }
+
+    Err(EngineError::Manager(format!(
+        "failed to return the token of {} in {}",
+        key, scope
+    )))
+}
+
+/**
+ * Put back a token taken with `take_token`, when the request is rejected by another limit
+ */
+pub fn return_token(
+    store: RateLimitStore,
+    scope: &str,
+    key: &str,
+    limit: &RateLimit,
+) -> Result<(), EngineError> {
+    match store {
+        RateLimitStore::Memory => {
+            return_memory_token(scope, key, limit);
+            Ok(())
+        }
+        RateLimitStore::Db => {
+            let mut db = init_db()?;
+            return_db_token(scope, key, limit, &mut db)
+        }
+    }
+}
+
+fn get_day(now: DateTime<Utc>) -> String {
+    now.format("%Y-%m-%d").to_string()
+}
+
+/**
+ * Time left before the quotas of the day are reset, at midnight UTC
+ */
+pub fn get_quota_reset_delay() -> Duration {
+    let now = Utc::now();
+    let tomorrow = match (now + ChronoDuration::days(1))
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+    {
+        Some(tomorrow) => tomorrow.and_utc(),
+        None => return Duration::from_secs(0),
+    };
+
+    (tomorrow - now).to_std().unwrap_or(Duration::from_secs(0))
+}
+
+/**
+ * Count an interaction of the bot in its daily quota. Returns false, without counting
+ * the interaction, if the bot already made `quota` interactions today.
+ */
+pub fn take_daily_interaction(
+    store: RateLimitStore,
+    bot_id: &str,
+    quota: i64,
+) -> Result<bool, EngineError> {
+    let now = Utc::now();
+    let scope = format!("{}#{}", DAILY_INTERACTIONS_SCOPE, get_day(now));
+
+    match store {
+        RateLimitStore::Memory => {
+            let mutex = MEMORY_COUNTERS.get_or_init(|| Mutex::new(HashMap::new()));
+            let mut counters = match mutex.lock() {
+                Ok(counters) => counters,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+
+            // only the counters of the current day are kept
+            counters.retain(|(counter_scope, _), _| *counter_scope == scope);
+
+            let count = counters.entry((scope, bot_id.to_owned())).or_insert(0);
+            if *count >= quota {
+                return Ok(false);
+            }
+            *count += 1;
+
+            Ok(true)
+        }
+        RateLimitStore::Db => {
+            let mut db = init_db()?;
+            // kept one more day, so that the usage of the previous day can be checked
+            let expires_at = now + ChronoDuration::days(2);
+
+            let count = rate_limits::increment_counter(&scope, bot_id, 1, expires_at, &mut db)?;
+            if count > quota {
+                // the rejected interaction is not part of the usage of the bot
+                rate_limits::increment_counter(&scope, bot_id, -1, expires_at, &mut db)?;
+                return Ok(false);
+            }
+
+            Ok(true)
+        }
+    }
+}
+
+/**
+ * Number of interactions of each bot today
+ */
+pub fn get_daily_interactions(store: RateLimitStore) -> Result<HashMap<String, i64>, EngineError> {
+    let scope = format!("{}#{}", DAILY_INTERACTIONS_SCOPE, get_day(Utc::now()));
+
+    match store {
+        RateLimitStore::Memory => {
+            let mutex = MEMORY_COUNTERS.get_or_init(|| Mutex::new(HashMap::new()));
+            let counters = match mutex.lock() {
+                Ok(counters) => counters,
+                Err(poisoned) => poisoned.into_inner(),
+            };
+
+            Ok(counters
+                .iter()
+                .filter(|((counter_scope, _), _)| *counter_scope == scope)
+                .map(|((_, bot_id), count)| (bot_id.to_owned(), *count))
+                .collect())
+        }
+        RateLimitStore::Db => {
+            let mut db = init_db()?;
+
+            Ok(rate_limits::get_counters(&scope, &mut db)?
+                .into_iter()
+                .collect())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ok_parse() {
+        assert_eq!(
+            RateLimit::parse("100/60"),
+            Some(RateLimit {
+                capacity: 100,
+                period: Duration::from_secs(60)
+            })
+        );
+        assert_eq!(RateLimit::parse("0/60"), None);
+        assert_eq!(RateLimit::parse("100"), None);
+        assert_eq!(RateLimit::parse("a/b"), None);
+    }
+
+    #[test]
+    fn ok_take_token_at() {
+        // 2 tokens, refilled every 500ms
+        let limit = RateLimit::parse("2/1").unwrap();
+        let now = 1_000_000;
+
+        let tat = take_token_at(None, now, &limit).unwrap();
+        let tat = take_token_at(Some(tat), now, &limit).unwrap();
+        assert_eq!(
+            take_token_at(Some(tat), now, &limit),
+            Err(Duration::from_millis(500))
+        );
+
+        // a token is available again after 500ms
+        let tat = take_token_at(Some(tat), now + 500, &limit).unwrap();
+        assert!(take_token_at(Some(tat), now + 500, &limit).is_err());
+
+        // the bucket is full again after the period
+        let tat = take_token_at(Some(tat), now + 2000, &limit).unwrap();
+        assert!(take_token_at(Some(tat), now + 2000, &limit).is_ok());
+    }
+
+    #[test]
+    fn ok_memory_token() {
+        let limit = RateLimit::parse("1/60").unwrap();
+
+        assert_eq!(take_memory_token("test", "ok_memory_token", &limit), Ok(()));
+        assert!(take_memory_token("test", "ok_memory_token", &limit).is_err());
+        // buckets are independent
+        assert_eq!(
+            take_memory_token("test", "ok_memory_token_2", &limit),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn ok_return_memory_token() {
+        let limit = RateLimit::parse("1/60").unwrap();
+
+        assert_eq!(
+            take_memory_token("test", "ok_return_memory_token", &limit),
+            Ok(())
+        );
+        return_memory_token("test", "ok_return_memory_token", &limit);
+
+        assert_eq!(
+            take_memory_token("test", "ok_return_memory_token", &limit),
+            Ok(())
+        );
+        assert!(take_memory_token("test", "ok_return_memory_token", &limit).is_err());
+    }
+
+    #[test]
+    fn ok_daily_interaction() {
+        let bot_id = "ok_daily_interaction";
+
+        assert!(take_daily_interaction(RateLimitStore::Memory, bot_id, 2).unwrap());
+        assert!(take_daily_interaction(RateLimitStore::Memory, bot_id, 2).unwrap());
+        assert!(!take_daily_interaction(RateLimitStore::Memory, bot_id, 2).unwrap());
+
+        // the rejected interactions are not counted
+        let interactions = get_daily_interactions(RateLimitStore::Memory).unwrap();
+        assert_eq!(interactions.get(bot_id), Some(&2));
+    }
+}
@@ -1,27 +1,502 @@
-use crate::data::{ConversationInfo};
+use crate::data::{ConversationInfo, Database, EngineError};
+use crate::db_connectors::outbox::{self, OUTBOX_DEAD, OUTBOX_PENDING};
+use crate::db_connectors::{init_db, DbOutboxMessage};
+use csml_interpreter::data::csml_logs::{csml_logger, CsmlLog, LogLvl};
+
+use openssl::{hash::MessageDigest, pkey::PKey, sign::Signer};
+use std::time::Duration;
+
+const DEFAULT_MAX_ATTEMPTS: u32 = 3;
+const DEFAULT_RETRY_DELAY: u64 = 500; // milliseconds
+const MAX_RETRY_DELAY: u64 = 3_600_000; // 1 hour
+const DEFAULT_OUTBOX_MAX_ATTEMPTS: i32 = 10;
+// a claimed message is sent again if it is not delivered within this delay
+const OUTBOX_CLAIM_TTL: i64 = 60; // seconds
+
+#[derive(Debug)]
+pub struct TransferError {
+    // transport errors, 429 and 5xx responses may succeed later
+    pub retryable: bool,
+    pub message: String,
+}
+
+fn get_env_number(name: &str, default: u64) -> u64 {
+    match std::env::var(name) {
+        Ok(val) => val.parse::<u64>().unwrap_or(default),
+        Err(_) => default,
+    }
+}
+
+/**
+ * HMAC-SHA256 of "{timestamp}.{body}", hex-encoded.
+ * The receiver recomputes it with the shared secret and rejects old timestamps to prevent replays.
+ */
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> Option<String> {
+    let key = PKey::hmac(secret.as_bytes()).ok()?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).ok()?;
+    signer.update(format!("{}.{}", timestamp, body).as_bytes()).ok()?;
+
+    Some(hex::encode(signer.sign_to_vec().ok()?))
+}
+
+/**
+ * Delay before the given retry (starting at 1), doubled on each attempt.
+ * The first delay is set with ENGINE_CALLBACK_RETRY_DELAY (in milliseconds).
+ */
+pub fn get_retry_delay(retry: u32) -> Duration {
+    let base = get_env_number("ENGINE_CALLBACK_RETRY_DELAY", DEFAULT_RETRY_DELAY);
+    let delay = base.saturating_mul(2u64.saturating_pow(retry.saturating_sub(1)));
+
+    Duration::from_millis(std::cmp::min(delay, MAX_RETRY_DELAY))
+}
+
+/**
+ * Send a single request to the callback_url.
+ * When ENGINE_CALLBACK_SECRET is set, the request is signed with the
+ * X-CSML-Timestamp (unix time in seconds) and X-CSML-Signature (`sha256=` + signature) headers.
+ */
+pub fn transfer(callback_url: &str, msg: &serde_json::Value) -> Result<(), TransferError> {
+    let body = msg.to_string();
 
-fn format_and_transfer(callback_url: &str, msg: serde_json::Value) {
     let mut request = ureq::post(callback_url);
 
     request = request.set("Accept", "application/json")
                     .set("Content-Type", "application/json");
 
-    let response = request.send_json(msg);
+    if let Ok(secret) = std::env::var("ENGINE_CALLBACK_SECRET") {
+        let timestamp = chrono::Utc::now().timestamp();
+
+        if let Some(signature) = sign_payload(&secret, timestamp, &body) {
+            request = request.set("X-CSML-Timestamp", &timestamp.to_string())
+                            .set("X-CSML-Signature", &format!("sha256={}", signature));
+        }
+    }
 
-    if let Err(err) = response{
-        eprintln!("callback_url call failed: {:?}", err.to_string());
+    match request.send_string(&body) {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, _)) => Err(TransferError {
+            retryable: code == 429 || code >= 500,
+            message: format!("callback_url responded with status {}", code),
+        }),
+        Err(err) => Err(TransferError {
+            retryable: true,
+            message: err.to_string(),
+        }),
     }
 }
 
+/**
+ * Send a request to the handoff webhook, retrying with an exponential backoff
+ * up to ENGINE_CALLBACK_MAX_ATTEMPTS times.
+ */
+fn format_and_transfer(callback_url: &str, msg: &serde_json::Value) -> Result<(), TransferError> {
+    let max_attempts = get_env_number("ENGINE_CALLBACK_MAX_ATTEMPTS", DEFAULT_MAX_ATTEMPTS as u64) as u32;
+    let mut attempt = 1;
+
+    loop {
+        match transfer(callback_url, msg) {
+            Ok(()) => return Ok(()),
+            Err(err) if err.retryable && attempt < max_attempts => {
+                std::thread::sleep(get_retry_delay(attempt));
+                attempt += 1;
+            }
+            Err(err) => {
+                eprintln!("callback_url call failed: {:?}", err.message);
+                return Err(err);
+            }
+        }
+    }
+}
+
+fn is_async_callback() -> bool {
+    match std::env::var("ENGINE_CALLBACK_ASYNC") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+/**
+ * Load the position of the last message of the conversation in the outbox, and whether
+ * the next messages must wait behind it: pending messages always block the conversation,
+ * dead letters only with ENGINE_CALLBACK_ASYNC=true.
+ */
+fn load_outbox_position(c_info: &mut ConversationInfo) -> Result<i64, EngineError> {
+    if let Some(position) = c_info.outbox_position {
+        return Ok(position);
+    }
+
+    let position =
+        outbox::get_last_outbox_position(&c_info.client, &c_info.conversation_id, &mut c_info.db)?;
+
+    let mut messages =
+        outbox::get_client_outbox_messages(&c_info.client, OUTBOX_PENDING, &mut c_info.db)?;
+    if is_async_callback() {
+        messages.append(&mut outbox::get_client_outbox_messages(
+            &c_info.client,
+            OUTBOX_DEAD,
+            &mut c_info.db,
+        )?);
+    }
+
+    c_info.outbox_blocked = messages
+        .iter()
+        .any(|message| message.conversation_id == c_info.conversation_id);
+    c_info.outbox_position = Some(position);
+
+    Ok(position)
+}
+
+/**
+ * Store a message in the outbox after the previous messages of the conversation
+ */
+fn queue_message(
+    c_info: &mut ConversationInfo,
+    callback_url: &str,
+    msg: &serde_json::Value,
+    status: &str,
+    error: Option<&str>,
+) -> Result<String, EngineError> {
+    let position = load_outbox_position(c_info)?;
+
+    let id = outbox::create_outbox_message(
+        &c_info.client,
+        &c_info.conversation_id,
+        callback_url,
+        msg,
+        status,
+        error,
+        position + 1,
+        &mut c_info.db,
+    )?;
+    c_info.outbox_position = Some(position + 1);
+    if status == OUTBOX_PENDING {
+        c_info.outbox_blocked = true;
+    }
+
+    Ok(id)
+}
+
 /**
  * If a callback_url is defined, we must send each message to its endpoint as it comes.
+ * Messages are also pushed to the stream of the request, if any.
  * Otherwise, just continue!
+ *
+ * Messages of a conversation are always delivered in order: while an earlier message
+ * is pending in the outbox, the next ones are queued behind it instead of being sent.
+ * A message that fails with a retryable error is queued and retried by the outbox worker,
+ * the others are kept as dead letters.
+ *
+ * With ENGINE_CALLBACK_ASYNC=true, every message goes through the outbox,
+ * see `start_outbox_delivery`, and dead letters block their conversation
+ * until they are replayed or deleted.
  */
 pub fn send_to_callback_url(c_info: &mut ConversationInfo, msg: serde_json::Value) {
+    if let Some(stream) = &c_info.stream {
+        // the receiver may be gone if the client disconnected
+        let _ = stream.send(msg.clone());
+    }
+
     let callback_url = match &c_info.callback_url {
-        Some(callback_url) => callback_url,
+        Some(callback_url) => callback_url.to_owned(),
         None => return,
     };
 
-    format_and_transfer(callback_url, msg)
+    let blocked = match load_outbox_position(c_info) {
+        Ok(_) => c_info.outbox_blocked,
+        Err(err) => {
+            eprintln!("outbox position failed: {:?}", err);
+            false
+        }
+    };
+
+    if is_async_callback() || blocked {
+        match queue_message(c_info, &callback_url, &msg, OUTBOX_PENDING, None) {
+            Ok(_) => return,
+            // the message is still sent if it can not be stored
+            Err(err) => eprintln!("outbox insert failed: {:?}", err),
+        }
+    }
+
+    let err = match transfer(&callback_url, &msg) {
+        Ok(()) => return,
+        Err(err) => err,
+    };
+    eprintln!("callback_url call failed: {:?}", err.message);
+
+    let res = match err.retryable {
+        // retried by the outbox worker, the interaction does not wait for it
+        true => queue_message(c_info, &callback_url, &msg, OUTBOX_PENDING, Some(&err.message))
+            .and_then(|id| {
+                let delay = chrono::Duration::from_std(get_retry_delay(1))
+                    .unwrap_or_else(|_| chrono::Duration::zero());
+
+                outbox::update_outbox_message(
+                    &c_info.client,
+                    &id,
+                    OUTBOX_PENDING,
+                    1,
+                    Some(&err.message),
+                    chrono::Utc::now() + delay,
+                    &mut c_info.db,
+                )
+            }),
+        false => queue_message(c_info, &callback_url, &msg, OUTBOX_DEAD, Some(&err.message))
+            .map(|_| ()),
+    };
+
+    if let Err(err) = res {
+        eprintln!("outbox insert failed: {:?}", err);
+    }
+}
+
+/**
+ * Deliver the messages queued in the outbox during the interaction right away,
+ * in a background thread, instead of waiting for the next `process_outbox`.
+ */
+pub fn start_outbox_delivery(c_info: &ConversationInfo) {
+    if c_info.callback_url.is_none() || !c_info.outbox_blocked {
+        return;
+    }
+
+    let client = c_info.client.to_owned();
+    std::thread::spawn(move || {
+        let result = init_db().and_then(|mut db| {
+            let mut messages =
+                outbox::get_client_outbox_messages(&client, OUTBOX_PENDING, &mut db)?;
+            messages.append(&mut outbox::get_client_outbox_messages(
+                &client,
+                OUTBOX_DEAD,
+                &mut db,
+            )?);
+
+            deliver_outbox_messages(messages, &mut db)
+        });
+
+        if let Err(err) = result {
+            eprintln!("outbox delivery failed: {:?}", err);
+        }
+    });
+}
+
+fn get_conversation_key(message: &DbOutboxMessage) -> String {
+    format!(
+        "bot_id:{}#channel_id:{}#user_id:{}#conversation_id:{}",
+        message.client.bot_id,
+        message.client.channel_id,
+        message.client.user_id,
+        message.conversation_id
+    )
+}
+
+/**
+ * Send the pending messages of each conversation in order, stopping at the first message
+ * that is not due yet, taken by another instance or failing, or at a dead letter
+ * with ENGINE_CALLBACK_ASYNC=true.
+ * Each message is claimed before being sent, so that it is only sent once
+ * when several instances deliver the outbox at the same time.
+ * Returns the number of delivered messages and of messages moved to the dead letters.
+ */
+pub fn deliver_outbox_messages(
+    mut messages: Vec<DbOutboxMessage>,
+    db: &mut Database,
+) -> Result<(usize, usize), EngineError> {
+    let max_attempts = match std::env::var("ENGINE_OUTBOX_MAX_ATTEMPTS") {
+        Ok(val) => val.parse::<i32>().unwrap_or(DEFAULT_OUTBOX_MAX_ATTEMPTS),
+        Err(_) => DEFAULT_OUTBOX_MAX_ATTEMPTS,
+    };
+
+    messages.sort_by_cached_key(|message| (get_conversation_key(message), message.position));
+
+    let now = chrono::Utc::now();
+    let lease_until = now + chrono::Duration::seconds(OUTBOX_CLAIM_TTL);
+    let mut blocked_conversations = std::collections::HashSet::new();
+    let (mut delivered, mut dead) = (0, 0);
+    let dead_letters_block = is_async_callback();
+
+    for message in messages {
+        let conversation = get_conversation_key(&message);
+        if blocked_conversations.contains(&conversation) {
+            continue;
+        }
+
+        if message.status == OUTBOX_DEAD {
+            if dead_letters_block {
+                blocked_conversations.insert(conversation);
+            }
+            continue;
+        }
+
+        // the next messages wait behind this one
+        blocked_conversations.insert(conversation.to_owned());
+
+        if !outbox::claim_outbox_message(&message.client, &message.id, now, lease_until, db)? {
+            continue;
+        }
+
+        let err = match transfer(&message.callback_url, &message.payload) {
+            Ok(()) => {
+                outbox::delete_outbox_message(&message.client, &message.id, db)?;
+                blocked_conversations.remove(&conversation);
+                delivered += 1;
+                continue;
+            }
+            Err(err) => err,
+        };
+
+        let attempts = message.attempts + 1;
+
+        if err.retryable && attempts < max_attempts {
+            let delay = chrono::Duration::from_std(get_retry_delay(attempts as u32))
+                .unwrap_or_else(|_| chrono::Duration::zero());
+
+            outbox::update_outbox_message(
+                &message.client,
+                &message.id,
+                OUTBOX_PENDING,
+                attempts,
+                Some(&err.message),
+                now + delay,
+                db,
+            )?;
+        } else {
+            csml_logger(
+                CsmlLog::new(
+                    Some(&message.client),
+                    None,
+                    None,
+                    format!(
+                        "outbox message {} moved to dead letters: {}",
+                        message.id, err.message
+                    ),
+                ),
+                LogLvl::Error,
+            );
+
+            outbox::update_outbox_message(
+                &message.client,
+                &message.id,
+                OUTBOX_DEAD,
+                attempts,
+                Some(&err.message),
+                now,
+                db,
+            )?;
+            dead += 1;
+
+            if !dead_letters_block {
+                blocked_conversations.remove(&conversation);
+            }
+        }
+    }
+
+    Ok((delivered, dead))
+}
+
+/**
+ * While a conversation is handed over to a human agent, its events are sent
+ * to the webhook configured with ENGINE_HANDOFF_WEBHOOK_URL, if any.
+ * The request and its retries are made in a background thread.
+ */
+pub fn send_to_handoff_webhook(msg: serde_json::Value) {
+    if let Ok(webhook_url) = std::env::var("ENGINE_HANDOFF_WEBHOOK_URL") {
+        std::thread::spawn(move || {
+            let _ = format_and_transfer(&webhook_url, &msg);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db_connectors::user;
+    use crate::make_migrations;
+    use csml_interpreter::data::Client;
+
+    fn get_client_messages(client: &Client, db: &mut Database) -> Vec<DbOutboxMessage> {
+        let mut messages = outbox::get_client_outbox_messages(client, OUTBOX_PENDING, db).unwrap();
+        messages.append(&mut outbox::get_client_outbox_messages(client, OUTBOX_DEAD, db).unwrap());
+
+        messages
+    }
+
+    #[test]
+    fn ok_sign_payload() {
+        let signature = sign_payload("secret", 1700000000, r#"{"messages":[]}"#).unwrap();
+
+        assert_eq!(
+            signature,
+            "314f8633e672e05f3b205601dea5cc977107bfde9535845fa8a5bdd18d373af8"
+        );
+    }
+
+    #[test]
+    fn ok_retry_delay() {
+        assert_eq!(get_retry_delay(1), Duration::from_millis(500));
+        assert_eq!(get_retry_delay(3), Duration::from_millis(2000));
+        assert_eq!(get_retry_delay(64), Duration::from_millis(MAX_RETRY_DELAY));
+    }
+
+    #[test]
+    fn ok_outbox_delivery_order() {
+        make_migrations().unwrap_or({});
+
+        let mut db = init_db().unwrap();
+        let client = Client {
+            bot_id: "botid".to_owned(),
+            channel_id: "outbox-channel".to_owned(),
+            user_id: uuid::Uuid::new_v4().to_string(),
+        };
+        let conversation_id = uuid::Uuid::new_v4().to_string();
+        // nothing listens on this port, the delivery fails and can be retried
+        let callback_url = "http://127.0.0.1:1/callback";
+        let msg = serde_json::json!({"messages": []});
+
+        let dead_id = outbox::create_outbox_message(
+            &client,
+            &conversation_id,
+            callback_url,
+            &msg,
+            OUTBOX_DEAD,
+            Some("callback_url responded with status 500"),
+            1,
+            &mut db,
+        )
+        .unwrap();
+        let pending_id = outbox::create_outbox_message(
+            &client,
+            &conversation_id,
+            callback_url,
+            &msg,
+            OUTBOX_PENDING,
+            None,
+            2,
+            &mut db,
+        )
+        .unwrap();
+
+        // with ENGINE_CALLBACK_ASYNC=true, the dead letter blocks the next message of the conversation
+        std::env::set_var("ENGINE_CALLBACK_ASYNC", "true");
+        let messages = get_client_messages(&client, &mut db);
+        let result = deliver_outbox_messages(messages, &mut db);
+        std::env::remove_var("ENGINE_CALLBACK_ASYNC");
+        assert_eq!(result.unwrap(), (0, 0));
+
+        let messages = get_client_messages(&client, &mut db);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id, pending_id);
+        assert_eq!(messages[0].attempts, 0);
+
+        // otherwise, the next message is sent and retried later
+        let messages = get_client_messages(&client, &mut db);
+        assert_eq!(deliver_outbox_messages(messages, &mut db).unwrap(), (0, 0));
+
+        let messages = get_client_messages(&client, &mut db);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].id, pending_id);
+        assert_eq!(messages[0].attempts, 1);
+        assert!(messages[0].error.is_some());
+        assert_eq!(messages[1].id, dead_id);
+
+        user::delete_client(&client, &mut db).unwrap();
+    }
 }
@@ -1,6 +1,8 @@
 use crate::{
+    channels::{render_message, select_renderer, Fallback, Renderer},
     data::{ConversationInfo, CsmlRequest, Database, EngineError, FlowTrigger},
     db_connectors::state::delete_state_key,
+    event_types::get_event_content,
     send::send_to_callback_url,
     CsmlBot, CsmlFlow,
 };
@@ -58,67 +60,6 @@ pub fn validate_memory_key_format(key: &str) -> Result<(), EngineError> {
     Ok(())
 }
 
-/**
- * Prepare a formatted "content" for the event object, based on the user's input.
- * This will trim extra data and only keep the main value.
- */
-pub fn get_event_content(content_type: &str, metadata: &Value) -> Result<String, EngineError> {
-    match content_type {
-        file if ["file", "audio", "video", "image", "url"].contains(&file) => {
-            if let Some(val) = metadata["url"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no url content in event".to_owned(),
-                ))
-            }
-        }
-        payload if payload == "payload" => {
-            if let Some(val) = metadata["payload"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no payload content in event".to_owned(),
-                ))
-            }
-        }
-        text if text == "text" => {
-            if let Some(val) = metadata["text"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "no text content in event".to_owned(),
-                ))
-            }
-        }
-        regex if regex == "regex" => {
-            if let Some(val) = metadata["payload"].as_str() {
-                Ok(val.to_string())
-            } else {
-                Err(EngineError::Interpreter(
-                    "invalid payload for event type regex".to_owned(),
-                ))
-            }
-        }
-        flow_trigger if flow_trigger == "flow_trigger" => {
-            match serde_json::from_value::<FlowTrigger>(metadata.clone()) {
-                Ok(_flow_trigger) => {
-                    Ok(metadata.to_string())
-                }
-                Err(_) => {
-                    Err(EngineError::Interpreter(
-                        "invalid content for event type flow_trigger: expect flow_id and optional step_id".to_owned(),
-                    ))
-                }
-            }
-        }
-        content_type => Err(EngineError::Interpreter(format!(
-            "{} is not a valid content_type",
-            content_type
-        ))),
-    }
-}
-
 /**
  * Format the incoming (JSON-formatted) event into an Event struct.
  */
@@ -177,10 +118,23 @@ pub fn send_msg_to_callback_url(
 /**
  * Update ConversationInfo data with current information about the request.
  */
-fn add_info_to_message(data: &ConversationInfo, mut msg: Message, interaction_order: i32) -> Value {
-    let payload = msg.message_to_json();
-
+fn add_info_to_message(
+    data: &ConversationInfo,
+    mut msg: Message,
+    interaction_order: i32,
+    renderer: Option<&dyn Renderer>,
+) -> Value {
     let mut map_msg: Map<String, Value> = Map::new();
+
+    if let Some(renderer) = renderer {
+        map_msg.insert("channel".to_owned(), json!(renderer.name()));
+        map_msg.insert(
+            "rendered".to_owned(),
+            render_message(renderer, &msg, Fallback::from_env()),
+        );
+    }
+
+    let payload = msg.message_to_json();
     map_msg.insert("payload".to_owned(), payload);
     map_msg.insert("interaction_order".to_owned(), json!(interaction_order));
     map_msg.insert("conversation_id".to_owned(), json!(data.conversation_id));
@@ -200,9 +154,10 @@ pub fn messages_formatter(
     interaction_order: i32,
     end: bool,
 ) -> Map<String, Value> {
+    let renderer = select_renderer(&data.renderer, &data.client);
     let msgs = vec_msg
         .into_iter()
-        .map(|msg| add_info_to_message(data, msg, interaction_order))
+        .map(|msg| add_info_to_message(data, msg, interaction_order, renderer.as_deref()))
         .collect();
     let mut map: Map<String, Value> = Map::new();
 
@@ -266,6 +221,7 @@ pub fn get_default_flow<'a>(bot: &'a CsmlBot) -> Result<&'a CsmlFlow, EngineErro
 /**
  * Find a flow in a bot based on the user's input.
  * - flow_trigger events must will match a flow's id or name and reset the hold position
+ * - timeout events are sent when a hold expires and go to its on_timeout step the same way
  * - other events will try to match a flow trigger
  */
 pub fn search_flow<'a>(
@@ -275,7 +231,7 @@ pub fn search_flow<'a>(
     db: &mut Database,
 ) -> Result<(&'a CsmlFlow, String), EngineError> {
     match event {
-        event if event.content_type == "flow_trigger" => {
+        event if event.content_type == "flow_trigger" || event.content_type == "timeout" => {
             delete_state_key(&client, "hold", "position", db)?;
 
             let flow_trigger: FlowTrigger = serde_json::from_str(&event.content_value)?;
@@ -455,6 +411,34 @@ pub fn clean_hold_and_restart(data: &mut ConversationInfo) -> Result<(), EngineE
     return Ok(());
 }
 
+/**
+ * Compute the next run of a recurring schedule.
+ * Cron expressions use the standard 5 fields (minute hour day month weekday),
+ * with Sunday as 0 or 7. A 6 fields variant starting with the seconds is also accepted.
+ */
+pub fn get_next_schedule_run(
+    cron: &str,
+    after: &chrono::DateTime<Utc>,
+) -> Result<chrono::DateTime<Utc>, EngineError> {
+    let schedule = match croner::Cron::new(cron).with_seconds_optional().parse() {
+        Ok(schedule) => schedule,
+        Err(err) => {
+            return Err(EngineError::Format(format!(
+                "invalid cron expression [{}]: {}",
+                cron, err
+            )))
+        }
+    };
+
+    match schedule.find_next_occurrence(after, false) {
+        Ok(next_run) => Ok(next_run),
+        Err(_) => Err(EngineError::Format(format!(
+            "cron expression [{}] has no upcoming run",
+            cron
+        ))),
+    }
+}
+
 pub fn get_ttl_duration_value(event: Option<&Event>) -> Option<chrono::Duration> {
     if let Some(event) = event {
         if let Some(ttl) = event.ttl_duration {
@@ -484,3 +468,48 @@ pub fn get_low_data_mode_value(event: &Event) -> bool {
 
     return false;
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn date(day: u32, hour: u32, min: u32, sec: u32) -> chrono::DateTime<Utc> {
+        Utc.with_ymd_and_hms(2021, 6, day, hour, min, sec).unwrap()
+    }
+
+    #[test]
+    fn ok_next_schedule_run() {
+        // Saturday
+        let after = date(5, 10, 0, 0);
+
+        let next_run = get_next_schedule_run("0 9 * * *", &after).unwrap();
+        assert_eq!(next_run, date(6, 9, 0, 0));
+
+        // weekdays are 0 (or 7) for Sunday to 6 for Saturday
+        let next_run = get_next_schedule_run("0 9 * * 1-5", &after).unwrap();
+        assert_eq!(next_run, date(7, 9, 0, 0));
+
+        let next_run = get_next_schedule_run("0 9 * * 0", &after).unwrap();
+        assert_eq!(next_run, date(6, 9, 0, 0));
+
+        let next_run = get_next_schedule_run("0 9 * * 7", &after).unwrap();
+        assert_eq!(next_run, date(6, 9, 0, 0));
+
+        let next_run = get_next_schedule_run("0 9 * * Fri", &after).unwrap();
+        assert_eq!(next_run, date(11, 9, 0, 0));
+
+        // with seconds
+        let next_run = get_next_schedule_run("30 0 12 * * *", &after).unwrap();
+        assert_eq!(next_run, date(5, 12, 0, 30));
+    }
+
+    #[test]
+    fn ko_next_schedule_run() {
+        let after = date(5, 10, 0, 0);
+
+        assert!(get_next_schedule_run("", &after).is_err());
+        assert!(get_next_schedule_run("every day", &after).is_err());
+        assert!(get_next_schedule_run("0 25 * * *", &after).is_err());
+    }
+}
@@ -1,6 +1,6 @@
 use csml_engine::{
     data::{BotOpt, CsmlRequest},
-    delete_client, start_conversation,
+    delete_client, start_conversation, start_conversation_stream,
 };
 use csml_interpreter::data::{csml_bot::CsmlBot, csml_flow::CsmlFlow, Client};
 use serde::{Deserialize, Serialize};
@@ -8,6 +8,7 @@ use serde_json::json;
 use std::fs;
 use std::io::prelude::*;
 use std::path::Path;
+use std::sync::mpsc;
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -96,7 +97,7 @@ fn init_bot(bot_name: &str) -> Result<CsmlBot, std::io::Error> {
 
 fn init_request(string: &str, bot_id: String, channel_id: String) -> CsmlRequest {
     CsmlRequest {
-        request_id: "tmp".to_owned(),
+        request_id: Uuid::new_v4().to_string(),
         client: Client {
             user_id: "test".to_owned(),
             bot_id,
@@ -111,6 +112,8 @@ fn init_request(string: &str, bot_id: String, channel_id: String) -> CsmlRequest
         ttl_duration: None,
         step_limit: None,
         low_data_mode: None,
+        renderer: None,
+        traceparent: None,
     }
 }
 
@@ -428,3 +431,58 @@ fn ok_test_memory() {
     })
     .unwrap();
 }
+
+#[test]
+fn ok_test_stream() {
+    let bot = CsmlBot {
+        id: "stream".to_owned(),
+        name: "stream".to_owned(),
+        apps_endpoint: None,
+        flows: vec![CsmlFlow {
+            id: "Default".to_owned(),
+            name: "Default".to_owned(),
+            commands: vec![],
+            content: "start: say \"one\" say \"two\" say \"three\" goto end".to_owned(),
+        }],
+        native_components: None,
+        custom_components: None,
+        default_flow: "Default".to_owned(),
+        bot_ast: None,
+        no_interruption_delay: None,
+        env: None,
+        modules: None,
+        multibot: None,
+    };
+
+    let channel_id = Uuid::new_v4().to_string();
+    let bot_id = Uuid::new_v4().to_string();
+    let (sender, receiver) = mpsc::channel();
+
+    let obj = start_conversation_stream(
+        init_request("start", bot_id.clone(), channel_id.clone()),
+        BotOpt::CsmlBot(bot),
+        sender,
+    )
+    .unwrap();
+
+    // every message was streamed before the end of the conversation was returned
+    let streamed: Vec<serde_json::Value> = receiver
+        .try_iter()
+        .flat_map(|response| response["messages"].as_array().cloned().unwrap_or_default())
+        .collect();
+    let texts: Vec<&str> = streamed
+        .iter()
+        .map(|message| message["payload"]["content"]["text"].as_str().unwrap())
+        .collect();
+
+    assert_eq!(texts, vec!["one", "two", "three"]);
+    assert_eq!(obj["messages"].as_array().unwrap(), &streamed);
+    assert!(obj["conversation_end"].as_bool().unwrap());
+
+    delete_client(&Client {
+        user_id: "test".to_owned(),
+        bot_id,
+        channel_id,
+    })
+    .unwrap();
+}
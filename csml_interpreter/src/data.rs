@@ -4,6 +4,8 @@ pub mod context;
 pub mod csml_bot;
 pub mod csml_flow;
 pub mod csml_logs;
+pub mod csml_metrics;
+pub mod csml_tracing;
 pub mod csml_result;
 pub mod data;
 pub mod error_info;
@@ -29,14 +31,14 @@ pub use csml_result::CsmlResult;
 pub use data::Data;
 pub use event::Event;
 pub use fn_args_type::ArgsType;
-pub use hold::{Hold, IndexInfo};
+pub use hold::{Hold, HoldTimeout, IndexInfo};
 pub use literal::Literal;
 pub use memories::{Memory, MemoryType};
 pub use message::Message;
 pub use message_data::MessageData;
 pub use position::Position;
 
-pub use msg::MSG;
+pub use msg::{TargetBot, MSG};
 
 // limit of steps in a single execution
 pub static STEP_LIMIT: usize = 100;
@@ -199,6 +199,8 @@ pub enum GotoType {
         step: Option<GotoValueType>,
         flow: Option<GotoValueType>,
         bot: Option<GotoValueType>,
+        // goto step@flow in bot with {...}
+        payload: Option<Box<Expr>>,
     },
 }
 
@@ -226,6 +228,8 @@ pub enum ForgetMemory {
 pub enum PreviousType {
     Step(Interval),
     Flow(Interval),
+    // previous bot with {...}
+    Bot(Option<Box<Expr>>, Interval),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -242,8 +246,8 @@ pub enum AssignType {
 pub enum ObjectType {
     Goto(GotoType, Interval),
     Previous(PreviousType, Interval),
-    Hold(Interval),
-    HoldSecure(Interval),
+    Hold(Option<Box<Expr>>, Interval),
+    HoldSecure(Option<Box<Expr>>, Interval),
     Say(Box<Expr>),
     Debug(Box<Expr>, Interval),
     Log {
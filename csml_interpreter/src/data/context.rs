@@ -56,6 +56,9 @@ pub struct PreviousBot {
     pub bot: String,
     pub flow: String,
     pub step: String,
+    // payload sent by the previous bot with `goto ... in bot with {...}`
+    #[serde(default)]
+    pub payload: serde_json::Value,
 }
 
 #[derive(Debug, Clone)]
@@ -1,10 +1,18 @@
 use crate::data::Client;
 
+use chrono::SecondsFormat;
 use log::{debug, error, info, trace, warn};
+use regex::Regex;
+use std::cell::RefCell;
 use std::io::Write;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 
 use serde::{Deserialize, Serialize};
 
+// target of the json lines of csml_logger, printed as they are by the logger
+const JSON_LOG_TARGET: &str = "csml_json";
+const REDACTED: &str = "[REDACTED]";
+
 #[derive(PartialEq, Copy, Clone, Debug, Serialize, Deserialize)]
 pub enum LogLvl {
     Error,
@@ -14,6 +22,36 @@ pub enum LogLvl {
     Trace,
 }
 
+impl LogLvl {
+    fn to_level(self) -> log::Level {
+        match self {
+            LogLvl::Error => log::Level::Error,
+            LogLvl::Warn => log::Level::Warn,
+            LogLvl::Info => log::Level::Info,
+            LogLvl::Debug => log::Level::Debug,
+            LogLvl::Trace => log::Level::Trace,
+        }
+    }
+}
+
+#[derive(PartialEq, Copy, Clone, Debug)]
+pub enum LogFormat {
+    Text,
+    Json,
+}
+
+/**
+ * Set with CSML_LOG_FORMAT=json, text by default
+ */
+pub fn get_log_format() -> LogFormat {
+    static LOG_FORMAT: OnceLock<LogFormat> = OnceLock::new();
+
+    *LOG_FORMAT.get_or_init(|| match std::env::var("CSML_LOG_FORMAT") {
+        Ok(format) if format.eq_ignore_ascii_case("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    })
+}
+
 pub struct CsmlLog {
     bot_id: Option<String>,
     user_id: Option<String>,
@@ -21,6 +59,7 @@ pub struct CsmlLog {
     flow: Option<String>,
     line: Option<u32>,
     message: String,
+    event: Option<String>,
 }
 
 impl std::fmt::Debug for CsmlLog {
@@ -29,6 +68,10 @@ impl std::fmt::Debug for CsmlLog {
 
         let mut debug_struct = ds.field("message", &self.message);
 
+        if let Some(event) = &self.event {
+            debug_struct = debug_struct.field("event", event);
+        }
+
         if let Some(flow) = &self.flow {
             debug_struct = debug_struct.field("flow", flow);
         }
@@ -83,8 +126,170 @@ impl CsmlLog {
             flow,
             line,
             message,
+            event: None,
+        }
+    }
+
+    /**
+     * Name of the structured event, ex: csml_log for the log action of the bots
+     */
+    pub fn with_event(mut self, event: &str) -> Self {
+        self.event = Some(event.to_owned());
+        self
+    }
+
+    fn to_json(&self, log_lvl: LogLvl, context: &LogContext) -> serde_json::Value {
+        // the client of the log, or the client of the request being processed
+        let (bot_id, user_id, channel_id) = match (&self.bot_id, &context.client) {
+            (None, Some(client)) => (
+                Some(client.bot_id.to_owned()),
+                Some(client.user_id.to_owned()),
+                Some(client.channel_id.to_owned()),
+            ),
+            _ => (
+                self.bot_id.to_owned(),
+                self.user_id.to_owned(),
+                self.channel_id.to_owned(),
+            ),
+        };
+
+        serde_json::json!({
+            "level": format!("{:?}", log_lvl).to_lowercase(),
+            "timestamp": chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+            "event": self.event,
+            "request_id": context.request_id,
+            "conversation_id": context.conversation_id,
+            "bot_id": bot_id,
+            "user_id": user_id,
+            "channel_id": channel_id,
+            "flow": self.flow,
+            "line": self.line,
+            "message": self.message,
+        })
+    }
+}
+
+/**
+ * Request being processed by the current thread, added to the json logs
+ */
+#[derive(Debug, Clone, Default)]
+pub struct LogContext {
+    pub request_id: Option<String>,
+    pub conversation_id: Option<String>,
+    pub client: Option<Client>,
+    // values removed from the logs, ex: the content of secure events. They are shared
+    // with the contexts cloned for the other threads of the request.
+    pub secrets: Arc<Mutex<Vec<String>>>,
+}
+
+thread_local! {
+    static LOG_CONTEXT: RefCell<LogContext> = RefCell::new(LogContext::default());
+}
+
+pub fn get_log_context() -> LogContext {
+    LOG_CONTEXT.with(|context| context.borrow().clone())
+}
+
+/**
+ * Set the log context of this thread, the previous one is restored when the guard is dropped
+ */
+pub fn enter_log_context(context: LogContext) -> LogContextGuard {
+    let previous = LOG_CONTEXT.with(|current| current.replace(context));
+
+    LogContextGuard { previous }
+}
+
+pub struct LogContextGuard {
+    previous: LogContext,
+}
+
+impl Drop for LogContextGuard {
+    fn drop(&mut self) {
+        let previous = std::mem::take(&mut self.previous);
+
+        LOG_CONTEXT.with(|current| current.replace(previous));
+    }
+}
+
+pub fn set_conversation_id(conversation_id: &str) {
+    LOG_CONTEXT.with(|context| {
+        context.borrow_mut().conversation_id = Some(conversation_id.to_owned());
+    });
+}
+
+/**
+ * The secret is replaced by [REDACTED] in the logs of the request
+ */
+pub fn add_secret(secret: &str) {
+    if secret.is_empty() {
+        return;
+    }
+
+    LOG_CONTEXT.with(|context| {
+        let context = context.borrow();
+        let mut secrets = match context.secrets.lock() {
+            Ok(secrets) => secrets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        if !secrets.iter().any(|elem| elem == secret) {
+            secrets.push(secret.to_owned());
+        }
+    });
+}
+
+pub type Redactor = Box<dyn Fn(&str) -> String + Send + Sync>;
+
+static REDACTORS: RwLock<Vec<Redactor>> = RwLock::new(Vec::new());
+
+/**
+ * Add a redaction hook, called on each log message after the default redaction
+ */
+pub fn add_redactor(redactor: Redactor) {
+    if let Ok(mut redactors) = REDACTORS.write() {
+        redactors.push(redactor);
+    }
+}
+
+fn get_secret_pattern() -> &'static Regex {
+    static SECRET_PATTERN: OnceLock<Regex> = OnceLock::new();
+
+    // values of credentials, as headers (authorization: Bearer xxx) or keys ("password": "xxx")
+    SECRET_PATTERN.get_or_init(|| {
+        Regex::new(
+            r#"(?i)((?:authorization|x-api-key|api_key|apikey|password|secret|token)"?\s*[:=]\s*"?)(?:bearer\s+|basic\s+)?[^\s",}\]]+"#,
+        )
+        .unwrap()
+    })
+}
+
+/**
+ * Remove the secrets of the log context, the credentials and apply the redaction hooks
+ */
+pub fn redact(message: &str) -> String {
+    let mut message = LOG_CONTEXT.with(|context| {
+        let context = context.borrow();
+        let secrets = match context.secrets.lock() {
+            Ok(secrets) => secrets,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+
+        secrets.iter().fold(message.to_owned(), |message, secret| {
+            message.replace(secret, REDACTED)
+        })
+    });
+
+    message = get_secret_pattern()
+        .replace_all(&message, format!("${{1}}{}", REDACTED))
+        .into_owned();
+
+    if let Ok(redactors) = REDACTORS.read() {
+        for redactor in redactors.iter() {
+            message = redactor(&message);
         }
     }
+
+    message
 }
 
 pub fn init_logger() {
@@ -97,6 +302,22 @@ pub fn init_logger() {
         .filter_module("os_info", log::LevelFilter::Error)
         .filter_module("ureq", log::LevelFilter::Error)
         .format(|buf, record| {
+            if record.target() == JSON_LOG_TARGET {
+                return writeln!(buf, "{}", record.args());
+            }
+
+            // logs of the other crates
+            if get_log_format() == LogFormat::Json {
+                let line = serde_json::json!({
+                    "level": record.level().as_str().to_lowercase(),
+                    "timestamp": chrono::Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+                    "target": record.target(),
+                    "message": redact(&record.args().to_string()),
+                });
+
+                return writeln!(buf, "{}", line);
+            }
+
             let style = buf.default_level_style(record.level());
 
             let timestamp = buf.timestamp_millis();
@@ -114,7 +335,20 @@ pub fn init_logger() {
         .try_init();
 }
 
-pub fn csml_logger(log_message: CsmlLog, log_lvl: LogLvl) {
+pub fn csml_logger(mut log_message: CsmlLog, log_lvl: LogLvl) {
+    if !log::log_enabled!(log_lvl.to_level()) {
+        return;
+    }
+
+    log_message.message = redact(&log_message.message);
+
+    if get_log_format() == LogFormat::Json {
+        let line = log_message.to_json(log_lvl, &get_log_context());
+
+        log::log!(target: JSON_LOG_TARGET, log_lvl.to_level(), "{}", line);
+        return;
+    }
+
     match log_lvl {
         LogLvl::Error => error!("{:?}", log_message),
         LogLvl::Warn => warn!("{:?}", log_message),
@@ -0,0 +1,271 @@
+/**
+ * Metrics facade of the interpreter and the engine.
+ *
+ * Counters and histograms are always kept in a process-wide registry, that can be
+ * rendered in the Prometheus text format with `render()`. Embedders exporting their
+ * metrics elsewhere can also forward every observation to their own recorder with
+ * `set_recorder`.
+ */
+use std::collections::BTreeMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Instant;
+
+// default buckets of the Prometheus clients, in seconds
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+pub trait MetricsRecorder: Send + Sync {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64);
+    fn observe_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64);
+}
+
+type Labels = Vec<(String, String)>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    // number of observations lower or equal to each bucket of DEFAULT_BUCKETS
+    pub buckets: Vec<u64>,
+    pub sum: f64,
+    pub count: u64,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        Self {
+            buckets: vec![0; DEFAULT_BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn observe(&mut self, value: f64) {
+        for (index, bound) in DEFAULT_BUCKETS.iter().enumerate() {
+            if value <= *bound {
+                self.buckets[index] += 1;
+            }
+        }
+
+        self.sum += value;
+        self.count += 1;
+    }
+}
+
+#[derive(Default)]
+pub struct Registry {
+    counters: Mutex<BTreeMap<(String, Labels), u64>>,
+    histograms: Mutex<BTreeMap<(String, Labels), Histogram>>,
+}
+
+fn to_labels(labels: &[(&str, &str)]) -> Labels {
+    let mut labels: Labels = labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+    // the same labels given in another order are the same series
+    labels.sort();
+    labels
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    match mutex.lock() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+fn format_labels(labels: &[(String, String)], extra: Option<(&str, String)>) -> String {
+    let mut pairs: Vec<String> = labels
+        .iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, escape_label_value(value)))
+        .collect();
+
+    if let Some((key, value)) = extra {
+        pairs.push(format!("{}=\"{}\"", key, value));
+    }
+
+    match pairs.is_empty() {
+        true => String::new(),
+        false => format!("{{{}}}", pairs.join(",")),
+    }
+}
+
+impl Registry {
+    pub fn get_counter(&self, name: &str, labels: &[(&str, &str)]) -> u64 {
+        let counters = lock(&self.counters);
+
+        counters
+            .get(&(name.to_owned(), to_labels(labels)))
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    pub fn get_histogram(&self, name: &str, labels: &[(&str, &str)]) -> Option<Histogram> {
+        let histograms = lock(&self.histograms);
+
+        histograms
+            .get(&(name.to_owned(), to_labels(labels)))
+            .cloned()
+    }
+
+    /**
+     * Text exposition format of Prometheus
+     */
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+
+        let counters = lock(&self.counters);
+        let mut previous_name = None;
+        for ((name, labels), value) in counters.iter() {
+            if previous_name != Some(name) {
+                output.push_str(&format!("# TYPE {} counter\n", name));
+                previous_name = Some(name);
+            }
+
+            output.push_str(&format!(
+                "{}{} {}\n",
+                name,
+                format_labels(labels, None),
+                value
+            ));
+        }
+
+        let histograms = lock(&self.histograms);
+        let mut previous_name = None;
+        for ((name, labels), histogram) in histograms.iter() {
+            if previous_name != Some(name) {
+                output.push_str(&format!("# TYPE {} histogram\n", name));
+                previous_name = Some(name);
+            }
+
+            for (bound, count) in DEFAULT_BUCKETS.iter().zip(histogram.buckets.iter()) {
+                output.push_str(&format!(
+                    "{}_bucket{} {}\n",
+                    name,
+                    format_labels(labels, Some(("le", bound.to_string()))),
+                    count
+                ));
+            }
+            output.push_str(&format!(
+                "{}_bucket{} {}\n",
+                name,
+                format_labels(labels, Some(("le", "+Inf".to_owned()))),
+                histogram.count
+            ));
+            output.push_str(&format!(
+                "{}_sum{} {}\n",
+                name,
+                format_labels(labels, None),
+                histogram.sum
+            ));
+            output.push_str(&format!(
+                "{}_count{} {}\n",
+                name,
+                format_labels(labels, None),
+                histogram.count
+            ));
+        }
+
+        output
+    }
+}
+
+impl MetricsRecorder for Registry {
+    fn increment_counter(&self, name: &str, labels: &[(&str, &str)], value: u64) {
+        let mut counters = lock(&self.counters);
+
+        *counters
+            .entry((name.to_owned(), to_labels(labels)))
+            .or_insert(0) += value;
+    }
+
+    fn observe_histogram(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let mut histograms = lock(&self.histograms);
+
+        histograms
+            .entry((name.to_owned(), to_labels(labels)))
+            .or_insert_with(Histogram::new)
+            .observe(value);
+    }
+}
+
+static REGISTRY: OnceLock<Registry> = OnceLock::new();
+static RECORDER: OnceLock<Box<dyn MetricsRecorder>> = OnceLock::new();
+
+pub fn registry() -> &'static Registry {
+    REGISTRY.get_or_init(Registry::default)
+}
+
+/**
+ * Forward the metrics to another recorder, in addition to the registry.
+ * Only one recorder can be set, the recorder is given back if one is already set.
+ */
+pub fn set_recorder(recorder: Box<dyn MetricsRecorder>) -> Result<(), Box<dyn MetricsRecorder>> {
+    RECORDER.set(recorder)
+}
+
+pub fn increment_counter(name: &str, labels: &[(&str, &str)]) {
+    add_counter(name, labels, 1);
+}
+
+pub fn add_counter(name: &str, labels: &[(&str, &str)], value: u64) {
+    registry().increment_counter(name, labels, value);
+
+    if let Some(recorder) = RECORDER.get() {
+        recorder.increment_counter(name, labels, value);
+    }
+}
+
+pub fn observe_histogram(name: &str, labels: &[(&str, &str)], value: f64) {
+    registry().observe_histogram(name, labels, value);
+
+    if let Some(recorder) = RECORDER.get() {
+        recorder.observe_histogram(name, labels, value);
+    }
+}
+
+/**
+ * Metrics of the registry in the text exposition format of Prometheus
+ */
+pub fn render() -> String {
+    registry().render()
+}
+
+/**
+ * Observe the time elapsed since its creation in a histogram (in seconds) when dropped
+ */
+pub struct Timer {
+    name: &'static str,
+    labels: Vec<(&'static str, String)>,
+    start: Instant,
+}
+
+impl Timer {
+    pub fn new(name: &'static str, labels: Vec<(&'static str, String)>) -> Self {
+        Self {
+            name,
+            labels,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for Timer {
+    fn drop(&mut self) {
+        let labels: Vec<(&str, &str)> = self
+            .labels
+            .iter()
+            .map(|(key, value)| (*key, value.as_str()))
+            .collect();
+
+        observe_histogram(self.name, &labels, self.start.elapsed().as_secs_f64());
+    }
+}
@@ -0,0 +1,234 @@
+/**
+ * Tracing facade of the interpreter and the engine.
+ *
+ * Spans follow the W3C trace context: each span has the trace id of its parent, or a
+ * new one when it is the first span of the thread. The parent of the first span can
+ * come from a `traceparent` header with `enter`, which also continues a trace in
+ * another thread. Finished spans are given to the exporter set with `set_exporter`,
+ * and dropped when no exporter is set.
+ */
+use std::cell::RefCell;
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpanContext {
+    pub trace_id: String,
+    pub span_id: String,
+    pub sampled: bool,
+}
+
+impl SpanContext {
+    /**
+     * Parse a W3C traceparent header: {version}-{trace_id}-{parent_id}-{flags}
+     */
+    pub fn from_traceparent(traceparent: &str) -> Option<Self> {
+        let parts: Vec<&str> = traceparent.trim().split('-').collect();
+
+        match parts.as_slice() {
+            [version, trace_id, span_id, flags, ..]
+                if is_hex(version, 2)
+                    && *version != "ff"
+                    && is_hex(trace_id, 32)
+                    && is_hex(span_id, 16)
+                    && is_hex(flags, 2)
+                    && !is_zero(trace_id)
+                    && !is_zero(span_id) =>
+            {
+                let flags = u8::from_str_radix(flags, 16).ok()?;
+
+                Some(Self {
+                    trace_id: trace_id.to_lowercase(),
+                    span_id: span_id.to_lowercase(),
+                    sampled: flags & 1 == 1,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_traceparent(&self) -> String {
+        let flags = match self.sampled {
+            true => "01",
+            false => "00",
+        };
+
+        format!("00-{}-{}-{}", self.trace_id, self.span_id, flags)
+    }
+}
+
+fn is_hex(value: &str, len: usize) -> bool {
+    value.len() == len && value.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+fn is_zero(value: &str) -> bool {
+    value.chars().all(|c| c == '0')
+}
+
+fn new_trace_id() -> String {
+    format!("{:032x}", rand::random::<u128>().max(1))
+}
+
+fn new_span_id() -> String {
+    format!("{:016x}", rand::random::<u64>().max(1))
+}
+
+#[derive(Debug, Clone)]
+pub struct SpanData {
+    pub name: String,
+    pub context: SpanContext,
+    pub parent_span_id: Option<String>,
+    pub start_time: SystemTime,
+    pub end_time: SystemTime,
+    pub attributes: Vec<(String, String)>,
+    pub error: Option<String>,
+}
+
+pub trait SpanExporter: Send + Sync {
+    fn export(&self, span: SpanData);
+}
+
+static EXPORTER: OnceLock<Box<dyn SpanExporter>> = OnceLock::new();
+
+thread_local! {
+    // contexts of the spans in progress in this thread, the last one is the parent of new spans
+    static CURRENT: RefCell<Vec<SpanContext>> = RefCell::new(vec![]);
+}
+
+/**
+ * Only one exporter can be set, the exporter is given back if one is already set.
+ */
+pub fn set_exporter(exporter: Box<dyn SpanExporter>) -> Result<(), Box<dyn SpanExporter>> {
+    EXPORTER.set(exporter)
+}
+
+pub fn current_context() -> Option<SpanContext> {
+    CURRENT.with(|current| current.borrow().last().cloned())
+}
+
+/**
+ * traceparent header to send with the outbound requests of the current span
+ */
+pub fn current_traceparent() -> Option<String> {
+    current_context().map(|context| context.to_traceparent())
+}
+
+fn push_context(context: SpanContext) {
+    CURRENT.with(|current| current.borrow_mut().push(context));
+}
+
+fn remove_context(context: &SpanContext) {
+    CURRENT.with(|current| {
+        let mut current = current.borrow_mut();
+
+        if let Some(index) = current.iter().rposition(|elem| elem == context) {
+            current.remove(index);
+        }
+    });
+}
+
+/**
+ * The spans started in this thread are children of `context` until the guard is dropped.
+ * Used with the traceparent of incoming requests, or the context of another thread.
+ */
+pub fn enter(context: Option<SpanContext>) -> ContextGuard {
+    if let Some(context) = &context {
+        push_context(context.to_owned());
+    }
+
+    ContextGuard { context }
+}
+
+pub struct ContextGuard {
+    context: Option<SpanContext>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        if let Some(context) = &self.context {
+            remove_context(context);
+        }
+    }
+}
+
+/**
+ * A span is ended and exported when dropped
+ */
+pub struct Span {
+    name: String,
+    context: SpanContext,
+    parent_span_id: Option<String>,
+    start_time: SystemTime,
+    attributes: Vec<(String, String)>,
+    error: Option<String>,
+}
+
+impl Span {
+    pub fn start(name: &str, attributes: Vec<(&str, String)>) -> Self {
+        let (context, parent_span_id) = match current_context() {
+            Some(parent) => (
+                SpanContext {
+                    trace_id: parent.trace_id,
+                    span_id: new_span_id(),
+                    sampled: parent.sampled,
+                },
+                Some(parent.span_id),
+            ),
+            None => (
+                SpanContext {
+                    trace_id: new_trace_id(),
+                    span_id: new_span_id(),
+                    sampled: true,
+                },
+                None,
+            ),
+        };
+
+        push_context(context.clone());
+
+        Self {
+            name: name.to_owned(),
+            context,
+            parent_span_id,
+            start_time: SystemTime::now(),
+            attributes: attributes
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value))
+                .collect(),
+            error: None,
+        }
+    }
+
+    pub fn context(&self) -> &SpanContext {
+        &self.context
+    }
+
+    pub fn set_attribute(&mut self, key: &str, value: String) {
+        self.attributes.push((key.to_owned(), value));
+    }
+
+    pub fn set_error(&mut self, error: String) {
+        self.error = Some(error);
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        remove_context(&self.context);
+
+        let exporter = match EXPORTER.get() {
+            Some(exporter) if self.context.sampled => exporter,
+            _ => return,
+        };
+
+        exporter.export(SpanData {
+            name: std::mem::take(&mut self.name),
+            context: self.context.clone(),
+            parent_span_id: self.parent_span_id.take(),
+            start_time: self.start_time,
+            end_time: SystemTime::now(),
+            attributes: std::mem::take(&mut self.attributes),
+            error: self.error.take(),
+        });
+    }
+}
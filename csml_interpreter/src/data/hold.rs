@@ -13,6 +13,13 @@ pub struct IndexInfo {
     pub loop_index: Vec<usize>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HoldTimeout {
+    // duration in seconds
+    pub duration: i64,
+    pub step: String,
+}
+
 #[derive(Debug, Clone)]
 pub struct Hold {
     pub index: IndexInfo,
@@ -21,6 +28,7 @@ pub struct Hold {
     pub flow_name: String,
     pub previous: Option<PreviousInfo>,
     pub secure: bool,
+    pub timeout: Option<HoldTimeout>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -43,9 +51,15 @@ impl Hold {
             flow_name,
             previous,
             secure,
+            timeout: None,
         }
     }
 
+    pub fn with_timeout(mut self, timeout: Option<HoldTimeout>) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
     pub fn default() -> Self {
         Self {
             index: IndexInfo {
@@ -57,6 +71,7 @@ impl Hold {
             flow_name: "".to_owned(),
             previous: None,
             secure: false,
+            timeout: None,
         }
     }
 }
@@ -65,6 +80,45 @@ impl Hold {
 // PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
+/**
+ * Check that a hold timeout duration is positive and that its deadline is a valid date.
+ */
+pub fn check_timeout_duration(seconds: i64) -> Option<i64> {
+    if seconds <= 0 {
+        return None;
+    }
+
+    chrono::Duration::try_seconds(seconds)
+        .and_then(|duration| chrono::Utc::now().checked_add_signed(duration))
+        .map(|_| seconds)
+}
+
+/**
+ * Parse a hold timeout duration: a number of seconds or a string
+ * with a unit suffix like "30s", "10m", "2h" or "1d".
+ */
+pub fn parse_timeout_duration(duration: &str) -> Option<i64> {
+    let duration = duration.trim();
+
+    if let Ok(seconds) = duration.parse::<i64>() {
+        return check_timeout_duration(seconds);
+    }
+
+    let (unit_index, _) = duration.char_indices().last()?;
+    let (value, unit) = duration.split_at(unit_index);
+    let value = value.trim().parse::<i64>().ok()?;
+
+    let seconds = match unit {
+        "s" => Some(value),
+        "m" => value.checked_mul(60),
+        "h" => value.checked_mul(60 * 60),
+        "d" => value.checked_mul(60 * 60 * 24),
+        _ => None,
+    }?;
+
+    check_timeout_duration(seconds)
+}
+
 pub fn hold_index_start_loop<'a>(
     data: &mut Data,
     mut array: &'a [Literal],
@@ -33,6 +33,8 @@ pub enum ContentType {
     Jwt,
     Crypto,
     Time,
+    OAuth2,
+    GraphQL,
     Primitive,
 }
 
@@ -194,6 +196,8 @@ impl ContentType {
             "jwt" => ContentType::Jwt,
             "crypto" => ContentType::Crypto,
             "time" => ContentType::Time,
+            "oauth2" => ContentType::OAuth2,
+            "graphql" => ContentType::GraphQL,
             "event" => ContentType::Event(String::from("")),
             _ => ContentType::Primitive,
         }
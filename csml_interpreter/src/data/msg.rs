@@ -9,6 +9,13 @@ use std::sync::mpsc;
 // DATA STRUCTURE
 ////////////////////////////////////////////////////////////////////////////////
 
+#[derive(Debug, Clone)]
+pub struct TargetBot {
+    pub bot: String,
+    // sent with `goto ... in bot with {...}`, the target bot reads it in `_switch.payload`
+    pub payload: Option<serde_json::Value>,
+}
+
 #[derive(Debug)]
 pub enum MSG {
     Remember(Memory),
@@ -24,9 +31,24 @@ pub enum MSG {
     Next {
         flow: Option<String>,
         step: Option<ContextStepInfo>,
-        bot: Option<String>,
+        bot: Option<TargetBot>,
     },
     Error(Message),
+    // bot level token cache, used by the OAuth2 builtin
+    GetCachedToken {
+        key: String,
+        reply: mpsc::Sender<Option<serde_json::Value>>,
+    },
+    CacheToken {
+        key: String,
+        token: serde_json::Value,
+        expires_in: i64,
+    },
+    // the conversation is handed over to a human agent
+    Handoff {
+        reason: Option<String>,
+        metadata: serde_json::Value,
+    },
 }
 
 ////////////////////////////////////////////////////////////////////////////////
@@ -9,6 +9,7 @@ pub mod string;
 
 pub mod tools;
 pub mod tools_crypto;
+pub mod tools_graphql;
 pub mod tools_jwt;
 pub mod tools_smtp;
 pub mod tools_time;
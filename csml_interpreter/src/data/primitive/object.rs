@@ -3,12 +3,12 @@ use crate::data::position::Position;
 use crate::data::{
     ast::Interval,
     csml_logs::*,
-    literal,
+    csml_tracing, literal,
     literal::ContentType,
     message::Message,
     primitive::{
-        tools_crypto, tools_jwt, tools_smtp, tools_time, Data, MessageData, Primitive,
-        PrimitiveArray, PrimitiveBoolean, PrimitiveInt, PrimitiveNull, PrimitiveString,
+        tools_crypto, tools_graphql, tools_jwt, tools_smtp, tools_time, Data, MessageData,
+        Primitive, PrimitiveArray, PrimitiveBoolean, PrimitiveInt, PrimitiveNull, PrimitiveString,
         PrimitiveType, Right, MSG,
     },
     tokens::TYPES,
@@ -16,7 +16,8 @@ use crate::data::{
 };
 use crate::error_format::*;
 use crate::interpreter::{
-    builtins::http_builtin::http_request, json_to_rust::json_to_literal,
+    builtins::http_builtin::{get_http_object, http_request},
+    json_to_rust::json_to_literal,
     variable_handler::match_literals::match_obj,
 };
 use std::cmp::Ordering;
@@ -52,6 +53,7 @@ const FUNCTIONS_SMTP: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map
     "tls" => (PrimitiveObject::smtp_tls as PrimitiveMethod, Right::Read),
     "starttls" => (PrimitiveObject::starttls as PrimitiveMethod, Right::Read),
     "set_auth_mechanism" => (PrimitiveObject::set_auth_mechanism as PrimitiveMethod, Right::Read),
+    "dry_run" => (PrimitiveObject::dry_run as PrimitiveMethod, Right::Read),
     "send" => (PrimitiveObject::smtp_send as PrimitiveMethod, Right::Read),
 };
 
@@ -65,6 +67,18 @@ const FUNCTIONS_TIME: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map
     "parse" => (PrimitiveObject::parse_date as PrimitiveMethod, Right::Read),
 };
 
+const FUNCTIONS_GRAPHQL: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
+    "set" => (PrimitiveObject::graphql_set as PrimitiveMethod, Right::Read),
+    "disable_ssl_verify" => (PrimitiveObject::graphql_disable_ssl_verify as PrimitiveMethod, Right::Read),
+    "auth" => (PrimitiveObject::graphql_auth as PrimitiveMethod, Right::Read),
+    "query" => (PrimitiveObject::graphql_query as PrimitiveMethod, Right::Read),
+    "mutation" => (PrimitiveObject::graphql_mutation as PrimitiveMethod, Right::Read),
+};
+
+const FUNCTIONS_OAUTH2: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
+    "http" => (PrimitiveObject::oauth2_http as PrimitiveMethod, Right::Read),
+};
+
 const FUNCTIONS_JWT: phf::Map<&'static str, (PrimitiveMethod, Right)> = phf_map! {
     "sign" => (PrimitiveObject::jwt_sign as PrimitiveMethod, Right::Read),
     "decode" => (PrimitiveObject::jwt_decode as PrimitiveMethod, Right::Read),
@@ -795,6 +809,40 @@ impl PrimitiveObject {
         Ok(result)
     }
 
+    fn dry_run(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let usage = "dry_run(BOOLEAN) => smtp object";
+
+        let dry_run = match args.get("arg0") {
+            Some(lit) => Literal::get_value::<bool>(
+                &lit.primitive,
+                &data.context.flow,
+                lit.interval,
+                format!("usage: {}", usage),
+            )?,
+            _ => &true,
+        };
+
+        let mut object = object.to_owned();
+
+        object.value.insert(
+            "dry_run".to_owned(),
+            PrimitiveBoolean::get_literal(*dry_run, interval),
+        );
+
+        let mut result = PrimitiveObject::get_literal(&object.value, interval);
+
+        result.set_content_type("smtp");
+
+        Ok(result)
+    }
+
     fn smtp_send(
         object: &mut PrimitiveObject,
         args: &HashMap<String, Literal>,
@@ -845,11 +893,24 @@ impl PrimitiveObject {
             ),
             LogLvl::Debug,
         );
+
+        // in dry run mode, the raw RFC 5322 message is returned instead of being sent
+        if let Some(lit) = object.value.get("dry_run") {
+            if lit.primitive.as_bool() {
+                let raw_email = String::from_utf8_lossy(&email.formatted()).to_string();
+
+                return Ok(PrimitiveString::get_literal(&raw_email, interval));
+            }
+        }
+
         let mailer = tools_smtp::get_mailer(&mut object.value, data, interval)?;
+        let mut span =
+            csml_tracing::Span::start("smtp_call", vec![("flow", data.context.flow.to_owned())]);
 
         match mailer.send(&email) {
             Ok(_) => Ok(PrimitiveBoolean::get_literal(true, interval)),
             Err(e) => {
+                span.set_error(e.to_string());
                 csml_logger(
                     CsmlLog::new(
                         None,
@@ -1257,6 +1318,134 @@ impl PrimitiveObject {
 }
 
 impl PrimitiveObject {
+    fn graphql_set(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let mut result = Self::set(object, args, additional_info, data, interval, content_type)?;
+
+        result.set_content_type("graphql");
+
+        Ok(result)
+    }
+
+    fn graphql_disable_ssl_verify(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let mut result =
+            Self::disable_ssl_verify(object, args, additional_info, data, interval, content_type)?;
+
+        result.set_content_type("graphql");
+
+        Ok(result)
+    }
+
+    fn graphql_auth(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let mut result = Self::auth(object, args, additional_info, data, interval, content_type)?;
+
+        result.set_content_type("graphql");
+
+        Ok(result)
+    }
+
+    fn graphql_query(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        graphql_send(object, args, "query", data, interval)
+    }
+
+    fn graphql_mutation(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        graphql_send(object, args, "mutation", data, interval)
+    }
+
+    fn oauth2_http(
+        object: &mut PrimitiveObject,
+        args: &HashMap<String, Literal>,
+        _additional_info: &Option<HashMap<String, Literal>>,
+        data: &mut Data,
+        interval: Interval,
+        _content_type: &str,
+    ) -> Result<Literal, ErrorInfo> {
+        let url = match args.get("arg0") {
+            Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => lit,
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    ERROR_OAUTH2_HTTP.to_owned(),
+                ))
+            }
+        };
+
+        let (token_type, access_token) = match (
+            object.value.get("token_type"),
+            object.value.get("access_token"),
+        ) {
+            (Some(token_type), Some(access_token)) => (
+                token_type.primitive.to_string(),
+                access_token.primitive.to_string(),
+            ),
+            _ => {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    ERROR_OAUTH2_TOKEN.to_owned(),
+                ))
+            }
+        };
+
+        // token_type is case insensitive but most APIs only accept "Bearer"
+        let token_type = match token_type.to_lowercase().as_ref() {
+            "bearer" => "Bearer".to_owned(),
+            _ => token_type,
+        };
+
+        let mut http = PrimitiveObject::new(&get_http_object(url, interval));
+
+        // the header holds the access token, it can not be displayed or saved
+        let mut authorization =
+            PrimitiveString::get_literal(&format!("{} {}", token_type, access_token), interval);
+        authorization.secure_variable = true;
+
+        let mut header = HashMap::new();
+        header.insert("Authorization".to_owned(), authorization);
+        let literal = PrimitiveObject::get_literal(&header, interval);
+
+        insert_to_object(&header, &mut http, "header", &data.context.flow, &literal);
+
+        let mut result = PrimitiveObject::get_literal(&http.value, interval);
+
+        result.set_content_type("http");
+
+        Ok(result)
+    }
+
     fn jwt_sign(
         object: &mut PrimitiveObject,
         args: &HashMap<String, Literal>,
@@ -1419,6 +1608,32 @@ impl PrimitiveObject {
             }
         };
 
+        // JWT(jwt).verify(jwks, Optional<claims>)
+        let jwks = match args.get("arg0") {
+            Some(lit) => tools_jwt::get_jwks(lit, &data.context.flow, interval)?,
+            None => None,
+        };
+        if let Some(keys) = jwks {
+            validation.validate_nbf = true;
+            if let Some(lit) = args.get("arg1") {
+                tools_jwt::get_validation(lit, &data.context.flow, interval, &mut validation)?
+            }
+
+            let (key, algo) = tools_jwt::get_jwks_key(&keys, token, &data.context.flow, interval)?;
+            validation.algorithms = vec![algo];
+
+            return match jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation) {
+                Ok(token_message) => {
+                    tools_jwt::token_data_to_literal(token_message, &data.context.flow, interval)
+                }
+                Err(e) => Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!("Invalid JWT verify {:?}", e.kind()),
+                )),
+            };
+        }
+
+        // JWT(jwt).verify(claims, algo, secret | jwks)
         match args.get("arg0") {
             Some(lit) => {
                 tools_jwt::get_validation(lit, &data.context.flow, interval, &mut validation)?
@@ -1431,13 +1646,25 @@ impl PrimitiveObject {
             }
         }
 
+        let jwks = match args.get("arg2") {
+            Some(lit) => tools_jwt::get_jwks(lit, &data.context.flow, interval)?,
+            None => None,
+        };
+
         match args.get("arg1") {
             Some(algo) if algo.primitive.get_type() == PrimitiveType::PrimitiveString => {
-                validation.algorithms = vec![tools_jwt::get_algorithm(
-                    algo,
-                    &data.context.flow,
-                    interval,
-                )?];
+                validation.algorithms = match jwks {
+                    Some(_) => vec![tools_jwt::get_jwks_algorithm(
+                        algo,
+                        &data.context.flow,
+                        interval,
+                    )?],
+                    None => vec![tools_jwt::get_algorithm(
+                        algo,
+                        &data.context.flow,
+                        interval,
+                    )?],
+                };
             }
             _ => {
                 return Err(gen_error_info(
@@ -1447,8 +1674,13 @@ impl PrimitiveObject {
             }
         };
 
-        let key = match args.get("arg2") {
-            Some(key) if key.primitive.get_type() == PrimitiveType::PrimitiveString => {
+        let key = match (args.get("arg2"), jwks) {
+            (_, Some(keys)) => {
+                let (key, _) = tools_jwt::get_jwks_key(&keys, token, &data.context.flow, interval)?;
+
+                key
+            }
+            (Some(key), None) if key.primitive.get_type() == PrimitiveType::PrimitiveString => {
                 let key = Literal::get_value::<String>(
                     &key.primitive,
                     &data.context.flow,
@@ -2525,6 +2757,30 @@ fn insert_to_object(
         .or_insert_with(|| literal.to_owned());
 }
 
+fn graphql_send(
+    object: &PrimitiveObject,
+    args: &HashMap<String, Literal>,
+    operation: &str,
+    data: &mut Data,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let body = tools_graphql::format_body(args, operation, &data.context.flow, interval)?;
+
+    let mut object = object.to_owned();
+    object.value.insert("body".to_owned(), body);
+
+    match http_request(&object.value, "post", &data.context.flow, interval, false) {
+        Ok((value, response_info)) => {
+            tools_graphql::response_to_literal(value, response_info, &data.context.flow, interval)
+        }
+        Err(err) => Err(tools_graphql::format_request_error(
+            err,
+            &data.context.flow,
+            interval,
+        )),
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
@@ -2734,6 +2990,8 @@ impl Primitive for PrimitiveObject {
         let jwt = vec![FUNCTIONS_JWT];
         let crypto = vec![FUNCTIONS_CRYPTO];
         let time = vec![FUNCTIONS_TIME];
+        let graphql = vec![FUNCTIONS_GRAPHQL, FUNCTIONS_READ, FUNCTIONS_WRITE];
+        let oauth2 = vec![FUNCTIONS_OAUTH2, FUNCTIONS_READ];
         let generics = vec![FUNCTIONS_READ, FUNCTIONS_WRITE];
 
         let mut is_event = false;
@@ -2751,6 +3009,8 @@ impl Primitive for PrimitiveObject {
             ContentType::Jwt => ("", jwt),
             ContentType::Crypto => ("", crypto),
             ContentType::Time => ("", time),
+            ContentType::GraphQL => ("", graphql),
+            ContentType::OAuth2 => ("", oauth2),
             ContentType::Primitive => ("", generics),
         };
 
@@ -0,0 +1,296 @@
+use crate::data::{
+    ast::Interval,
+    error_info::ErrorInfo,
+    position::Position,
+    primitive::{PrimitiveObject, PrimitiveString, PrimitiveType},
+    Literal,
+};
+use crate::error_format::*;
+use crate::interpreter::json_to_rust::json_to_literal;
+use std::collections::HashMap;
+
+////////////////////////////////////////////////////////////////////////////////
+// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn format_document(document: &str, operation: &str) -> String {
+    let trimmed = document.trim_start();
+
+    match operation {
+        // allow the selection set alone: .mutation("createUser(name: \"csml\") { id }")
+        "mutation" if !trimmed.starts_with("mutation") => format!("mutation {{ {} }}", document),
+        _ => document.to_owned(),
+    }
+}
+
+fn gen_graphql_error(
+    errors: &serde_json::Value,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<ErrorInfo, ErrorInfo> {
+    let message = match errors.get(0).and_then(|error| error["message"].as_str()) {
+        Some(message) => format!("{}: {}", ERROR_GRAPHQL_ERRORS, message),
+        None => ERROR_GRAPHQL_ERRORS.to_owned(),
+    };
+
+    let mut error = gen_error_info(Position::new(interval, flow_name), message);
+    error.add_info("errors", json_to_literal(errors, interval, flow_name)?);
+
+    Ok(error)
+}
+
+fn get_errors(value: &serde_json::Value) -> Option<&serde_json::Value> {
+    match value.get("errors") {
+        Some(serde_json::Value::Array(errors)) if !errors.is_empty() => value.get("errors"),
+        _ => None,
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn format_body(
+    args: &HashMap<String, Literal>,
+    operation: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let usage = format!("[{}] {}", operation, ERROR_GRAPHQL_QUERY);
+
+    let document = match args.get("arg0") {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            lit.primitive.to_string()
+        }
+        _ => return Err(gen_error_info(Position::new(interval, flow_name), usage)),
+    };
+
+    let mut body = HashMap::new();
+    body.insert(
+        "query".to_owned(),
+        PrimitiveString::get_literal(&format_document(&document, operation), interval),
+    );
+
+    match args.get("arg1") {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveObject => {
+            body.insert("variables".to_owned(), lit.to_owned());
+        }
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveNull => {}
+        Some(_) => return Err(gen_error_info(Position::new(interval, flow_name), usage)),
+        None => {}
+    }
+
+    match args.get("arg2") {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            body.insert("operationName".to_owned(), lit.to_owned());
+        }
+        Some(_) => return Err(gen_error_info(Position::new(interval, flow_name), usage)),
+        None => {}
+    }
+
+    Ok(PrimitiveObject::get_literal(&body, interval))
+}
+
+pub fn response_to_literal(
+    value: serde_json::Value,
+    response_info: HashMap<String, Literal>,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    if let Some(errors) = get_errors(&value) {
+        let mut error = gen_graphql_error(errors, flow_name, interval)?;
+        error.add_info_block(response_info);
+
+        return Err(error);
+    }
+
+    match value.get("data") {
+        Some(data) if !data.is_null() => {
+            let mut literal = json_to_literal(data, interval, flow_name)?;
+            // add additional information about the http request response: status and headers
+            literal.add_info_block(response_info);
+
+            Ok(literal)
+        }
+        _ => {
+            let mut error = gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_GRAPHQL_NO_DATA.to_owned(),
+            );
+            error.add_info_block(response_info);
+
+            Err(error)
+        }
+    }
+}
+
+/**
+ * GraphQL servers may answer with a non 2xx status and the list of errors in the body,
+ * in that case replace the generic HTTP error by the GraphQL errors.
+ */
+pub fn format_request_error(error: ErrorInfo, flow_name: &str, interval: Interval) -> ErrorInfo {
+    let info = match &error.additional_info {
+        Some(info) => info,
+        None => return error,
+    };
+
+    let body = match info.get("body") {
+        Some(body) => body.primitive.to_string(),
+        None => return error,
+    };
+
+    let errors = match serde_json::from_str::<serde_json::Value>(&body) {
+        Ok(value) => match get_errors(&value) {
+            Some(errors) => errors.to_owned(),
+            None => return error,
+        },
+        Err(_) => return error,
+    };
+
+    match gen_graphql_error(&errors, flow_name, interval) {
+        Ok(mut graphql_error) => {
+            for key in ["status", "headers"].iter() {
+                if let Some(value) = info.get(*key) {
+                    graphql_error.add_info(key, value.to_owned());
+                }
+            }
+
+            graphql_error
+        }
+        Err(_) => error,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::primitive::PrimitiveInt;
+    use serde_json::json;
+
+    fn response_info() -> HashMap<String, Literal> {
+        let mut info = HashMap::new();
+        info.insert(
+            "status".to_owned(),
+            PrimitiveInt::get_literal(200, Interval::default()),
+        );
+
+        info
+    }
+
+    fn http_error(status: i64, body: &str) -> ErrorInfo {
+        let mut error = gen_error_info(
+            Position::new(Interval::default(), "flow"),
+            "HTTP request failed".to_owned(),
+        );
+        error.add_info(
+            "status",
+            PrimitiveInt::get_literal(status, Interval::default()),
+        );
+        error.add_info(
+            "body",
+            PrimitiveString::get_literal(body, Interval::default()),
+        );
+
+        error
+    }
+
+    #[test]
+    fn ok_response_data() {
+        let value = json!({"data": {"user": {"id": 42}}});
+
+        let literal =
+            response_to_literal(value, response_info(), "flow", Interval::default()).unwrap();
+
+        assert_eq!(literal.primitive.to_json(), json!({"user": {"id": 42}}));
+        let info = literal.additional_info.unwrap();
+        assert_eq!(info["status"].primitive.to_json(), json!(200));
+    }
+
+    #[test]
+    fn ko_response_errors() {
+        let value = json!({"errors": [{"message": "unknown field"}, {"message": "denied"}]});
+
+        let error =
+            response_to_literal(value, response_info(), "flow", Interval::default()).unwrap_err();
+
+        assert_eq!(
+            error.message,
+            format!("{}: unknown field", ERROR_GRAPHQL_ERRORS)
+        );
+        let info = error.additional_info.unwrap();
+        assert_eq!(
+            info["errors"].primitive.to_json()[1]["message"],
+            json!("denied")
+        );
+        assert_eq!(info["status"].primitive.to_json(), json!(200));
+    }
+
+    #[test]
+    fn ko_response_data_and_errors() {
+        // a partial response is an error, the errors must not be ignored
+        let value = json!({
+            "data": {"user": null},
+            "errors": [{"message": "user not found", "path": ["user"]}],
+        });
+
+        let error =
+            response_to_literal(value, response_info(), "flow", Interval::default()).unwrap_err();
+
+        assert_eq!(
+            error.message,
+            format!("{}: user not found", ERROR_GRAPHQL_ERRORS)
+        );
+    }
+
+    #[test]
+    fn ko_response_no_data() {
+        for value in [
+            json!({}),
+            json!({"data": null}),
+            json!({"data": null, "errors": []}),
+        ] {
+            let error = response_to_literal(value, response_info(), "flow", Interval::default())
+                .unwrap_err();
+
+            assert_eq!(error.message, ERROR_GRAPHQL_NO_DATA);
+        }
+    }
+
+    #[test]
+    fn ok_format_request_error() {
+        let error = http_error(400, r#"{"errors": [{"message": "syntax error"}]}"#);
+
+        let error = format_request_error(error, "flow", Interval::default());
+
+        assert_eq!(
+            error.message,
+            format!("{}: syntax error", ERROR_GRAPHQL_ERRORS)
+        );
+        let info = error.additional_info.unwrap();
+        assert_eq!(info["status"].primitive.to_json(), json!(400));
+        assert_eq!(
+            info["errors"].primitive.to_json()[0]["message"],
+            json!("syntax error")
+        );
+        assert!(info.get("body").is_none());
+    }
+
+    #[test]
+    fn ok_format_request_error_without_errors() {
+        // the HTTP error is kept when the body has no GraphQL errors
+        for body in ["bad gateway", r#"{"data": null}"#, r#"{"errors": []}"#] {
+            let error = format_request_error(http_error(502, body), "flow", Interval::default());
+
+            assert_eq!(error.message, "HTTP request failed");
+            let info = error.additional_info.unwrap();
+            assert_eq!(info["body"].primitive.to_string(), body);
+        }
+
+        let error = gen_error_info(
+            Position::new(Interval::default(), "flow"),
+            "HTTP request failed".to_owned(),
+        );
+        let error = format_request_error(error, "flow", Interval::default());
+        assert_eq!(error.message, "HTTP request failed");
+    }
+}
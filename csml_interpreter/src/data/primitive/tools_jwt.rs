@@ -1,4 +1,9 @@
-use crate::data::{ast::Interval, position::Position, primitive::PrimitiveString, Literal};
+use crate::data::{
+    ast::Interval,
+    position::Position,
+    primitive::{PrimitiveString, PrimitiveType},
+    Literal,
+};
 use crate::error_format::*;
 use crate::interpreter::json_to_literal;
 
@@ -152,7 +157,7 @@ pub fn get_headers(
             "cty" => {
                 headers.cty = Some(
                     Literal::get_value::<String>(
-                        &lit.primitive,
+                        &value.primitive,
                         flow_name,
                         interval,
                         "JWT Headers 'cty' must be of type String".to_owned(),
@@ -163,7 +168,7 @@ pub fn get_headers(
             "jku" => {
                 headers.jku = Some(
                     Literal::get_value::<String>(
-                        &lit.primitive,
+                        &value.primitive,
                         flow_name,
                         interval,
                         "JWT Headers 'jku' must be of type String".to_owned(),
@@ -174,7 +179,7 @@ pub fn get_headers(
             "kid" => {
                 headers.kid = Some(
                     Literal::get_value::<String>(
-                        &lit.primitive,
+                        &value.primitive,
                         flow_name,
                         interval,
                         "JWT Headers 'kid' must be of type String".to_owned(),
@@ -185,7 +190,7 @@ pub fn get_headers(
             "x5u" => {
                 headers.x5u = Some(
                     Literal::get_value::<String>(
-                        &lit.primitive,
+                        &value.primitive,
                         flow_name,
                         interval,
                         "JWT Headers 'x5u' must be of type String".to_owned(),
@@ -196,7 +201,7 @@ pub fn get_headers(
             "x5t" => {
                 headers.x5t = Some(
                     Literal::get_value::<String>(
-                        &lit.primitive,
+                        &value.primitive,
                         flow_name,
                         interval,
                         "JWT Headers 'x5t' must be of type String".to_owned(),
@@ -211,6 +216,48 @@ pub fn get_headers(
     Ok(())
 }
 
+fn get_string_set(
+    lit: &Literal,
+    flow_name: &str,
+    interval: Interval,
+    error_message: &str,
+) -> Result<HashSet<String>, ErrorInfo> {
+    match lit.primitive.get_type() {
+        PrimitiveType::PrimitiveArray => {
+            let vec = Literal::get_value::<Vec<Literal>>(
+                &lit.primitive,
+                flow_name,
+                interval,
+                error_message.to_owned(),
+            )?;
+
+            let mut set = HashSet::new();
+            for value in vec.iter() {
+                let value = Literal::get_value::<String>(
+                    &value.primitive,
+                    flow_name,
+                    interval,
+                    error_message.to_owned(),
+                )?;
+
+                set.insert(value.to_owned());
+            }
+
+            Ok(set)
+        }
+        _ => {
+            let value = Literal::get_value::<String>(
+                &lit.primitive,
+                flow_name,
+                interval,
+                error_message.to_owned(),
+            )?;
+
+            Ok(HashSet::from_iter(vec![value.to_owned()]))
+        }
+    }
+}
+
 pub fn get_validation(
     lit: &Literal,
     flow_name: &str,
@@ -221,18 +268,26 @@ pub fn get_validation(
         &lit.primitive,
         flow_name,
         interval,
-        "JWT Headers wrong format".to_owned(),
+        "JWT Validation wrong format".to_owned(),
     )?;
     for (key, value) in map.iter() {
         match key.as_ref() {
             "leeway" => {
-                validation.leeway = Literal::get_value::<u64>(
+                let leeway = Literal::get_value::<i64>(
                     &value.primitive,
                     flow_name,
                     interval,
                     "JWT Validation 'leeway' must be of type Int".to_owned(),
-                )?
-                .to_owned()
+                )?;
+
+                if *leeway < 0 {
+                    return Err(gen_error_info(
+                        Position::new(interval, flow_name),
+                        "JWT Validation 'leeway' must be a positive Int".to_owned(),
+                    ));
+                }
+
+                validation.leeway = *leeway as u64
             }
             "validate_exp" => {
                 validation.validate_exp = Literal::get_value::<bool>(
@@ -253,28 +308,22 @@ pub fn get_validation(
                 .to_owned()
             }
             "aud" => {
-                let vec = Literal::get_value::<Vec<String>>(
-                    &value.primitive,
+                validation.aud = Some(get_string_set(
+                    value,
                     flow_name,
                     interval,
-                    "JWT Validation 'aud' must be of type Boolean".to_owned(),
-                )?;
-
-                validation.aud = Some(HashSet::from_iter(vec.iter().cloned()));
+                    "JWT Validation 'aud' must be of type String or Array of String",
+                )?);
+                validation.required_spec_claims.insert("aud".to_owned());
             }
             "iss" => {
-                let mut iss = HashSet::new();
-                let iss_value = Literal::get_value::<String>(
-                    &value.primitive,
+                validation.iss = Some(get_string_set(
+                    value,
                     flow_name,
                     interval,
-                    "JWT Validation 'validate_nbf' must be of type Boolean".to_owned(),
-                )?
-                .to_owned();
-
-                iss.insert(iss_value);
-
-                validation.iss = Some(iss)
+                    "JWT Validation 'iss' must be of type String or Array of String",
+                )?);
+                validation.required_spec_claims.insert("iss".to_owned());
             }
             "sub" => {
                 validation.sub = Some(
@@ -282,7 +331,7 @@ pub fn get_validation(
                         &value.primitive,
                         flow_name,
                         interval,
-                        "JWT Validation 'validate_nbf' must be of type Boolean".to_owned(),
+                        "JWT Validation 'sub' must be of type String".to_owned(),
                     )?
                     .to_owned(),
                 )
@@ -293,3 +342,410 @@ pub fn get_validation(
 
     Ok(())
 }
+
+////////////////////////////////////////////////////////////////////////////////
+// JWKS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn get_jwks_algorithm(
+    lit: &Literal,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<jsonwebtoken::Algorithm, ErrorInfo> {
+    let algo = Literal::get_value::<String>(
+        &lit.primitive,
+        flow_name,
+        interval,
+        ERROR_JWT_VALIDATION_ALGO.to_owned(),
+    )?;
+
+    jsonwebtoken::Algorithm::from_str(algo).map_err(|_| {
+        gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_JWT_VALIDATION_ALGO.to_string(),
+        )
+    })
+}
+
+/// Return the list of JWK if the literal is a JWKS, either as an Object
+/// or as its JSON String representation (as it is usually stored in `_env`).
+pub fn get_jwks(
+    lit: &Literal,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Option<Vec<serde_json::Value>>, ErrorInfo> {
+    let jwks = match lit.primitive.get_type() {
+        PrimitiveType::PrimitiveObject => lit.primitive.to_json(),
+        PrimitiveType::PrimitiveString => {
+            let value = Literal::get_value::<String>(
+                &lit.primitive,
+                flow_name,
+                interval,
+                ERROR_JWT_JWKS.to_owned(),
+            )?;
+
+            match serde_json::from_str::<serde_json::Value>(value) {
+                Ok(json) if json.is_object() => json,
+                _ => return Ok(None),
+            }
+        }
+        _ => return Ok(None),
+    };
+
+    match jwks.get("keys") {
+        Some(serde_json::Value::Array(keys)) => Ok(Some(keys.to_owned())),
+        Some(_) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_JWT_JWKS.to_string(),
+        )),
+        None => Ok(None),
+    }
+}
+
+fn decode_jwk_param(
+    jwk: &serde_json::Value,
+    param: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Vec<u8>, ErrorInfo> {
+    match jwk.get(param).and_then(|value| value.as_str()) {
+        Some(value) => base64::decode_config(value, base64::URL_SAFE_NO_PAD).map_err(|_| {
+            gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{}: '{}' is not valid base64url", ERROR_JWT_JWKS_KEY, param),
+            )
+        }),
+        None => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            format!("{}: missing '{}'", ERROR_JWT_JWKS_KEY, param),
+        )),
+    }
+}
+
+fn jwk_to_decoding_key(
+    jwk: &serde_json::Value,
+    algo: jsonwebtoken::Algorithm,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<jsonwebtoken::DecodingKey, ErrorInfo> {
+    use jsonwebtoken::Algorithm;
+
+    let kty = jwk.get("kty").and_then(|kty| kty.as_str());
+    let crv = jwk.get("crv").and_then(|crv| crv.as_str());
+
+    match (kty, crv, algo) {
+        (
+            Some("RSA"),
+            _,
+            Algorithm::RS256
+            | Algorithm::RS384
+            | Algorithm::RS512
+            | Algorithm::PS256
+            | Algorithm::PS384
+            | Algorithm::PS512,
+        ) => {
+            let n = decode_jwk_param(jwk, "n", flow_name, interval)?;
+            let e = decode_jwk_param(jwk, "e", flow_name, interval)?;
+
+            Ok(jsonwebtoken::DecodingKey::from_rsa_raw_components(&n, &e))
+        }
+        (Some("EC"), Some("P-256"), Algorithm::ES256)
+        | (Some("EC"), Some("P-384"), Algorithm::ES384) => {
+            // uncompressed SEC1 point: 0x04 || x || y
+            let mut point = vec![0x04];
+            point.append(&mut decode_jwk_param(jwk, "x", flow_name, interval)?);
+            point.append(&mut decode_jwk_param(jwk, "y", flow_name, interval)?);
+
+            Ok(jsonwebtoken::DecodingKey::from_ec_der(&point))
+        }
+        (Some("OKP"), Some("Ed25519"), Algorithm::EdDSA) => {
+            let x = decode_jwk_param(jwk, "x", flow_name, interval)?;
+
+            Ok(jsonwebtoken::DecodingKey::from_ed_der(&x))
+        }
+        (Some("oct"), _, Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512) => {
+            let k = decode_jwk_param(jwk, "k", flow_name, interval)?;
+
+            Ok(jsonwebtoken::DecodingKey::from_secret(&k))
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_JWT_JWKS_KEY.to_string(),
+        )),
+    }
+}
+
+/// Select the signing key of the token in the JWKS by its 'kid' and
+/// return it with the algorithm to use for the validation.
+/// If the token has no 'kid', the first signing key of the set is used.
+pub fn get_jwks_key(
+    keys: &[serde_json::Value],
+    token: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<(jsonwebtoken::DecodingKey, jsonwebtoken::Algorithm), ErrorInfo> {
+    let header = jsonwebtoken::decode_header(token).map_err(|e| {
+        gen_error_info(
+            Position::new(interval, flow_name),
+            format!("Invalid JWT verify {:?}", e.kind()),
+        )
+    })?;
+
+    let is_signing_key = |jwk: &&serde_json::Value| match jwk.get("use") {
+        Some(key_use) => key_use == "sig",
+        None => true,
+    };
+
+    let jwk = match &header.kid {
+        Some(kid) => keys
+            .iter()
+            .filter(is_signing_key)
+            .find(|jwk| jwk.get("kid").and_then(|value| value.as_str()) == Some(kid.as_str())),
+        None => keys.iter().find(is_signing_key),
+    };
+
+    let jwk = match jwk {
+        Some(jwk) => jwk,
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_JWT_JWKS_KID.to_string(),
+            ))
+        }
+    };
+
+    if let Some(alg) = jwk.get("alg").and_then(|alg| alg.as_str()) {
+        if alg != jwt_algorithm_to_str(&header.alg) {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_JWT_JWKS_ALGO.to_string(),
+            ));
+        }
+    }
+
+    let key = jwk_to_decoding_key(jwk, header.alg, flow_name, interval)?;
+
+    Ok((key, header.alg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::{Algorithm, EncodingKey, Header, Validation};
+    use openssl::bn::BigNumContext;
+    use openssl::ec::{EcGroup, EcKey, PointConversionForm};
+    use openssl::nid::Nid;
+    use openssl::pkey::PKey;
+    use openssl::rsa::Rsa;
+    use serde_json::json;
+
+    fn b64(bytes: &[u8]) -> String {
+        base64::encode_config(bytes, base64::URL_SAFE_NO_PAD)
+    }
+
+    fn rsa_fixture(kid: &str) -> (EncodingKey, serde_json::Value) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwk = json!({
+            "kty": "RSA",
+            "kid": kid,
+            "n": b64(&rsa.n().to_vec()),
+            "e": b64(&rsa.e().to_vec()),
+        });
+
+        let pem = rsa.private_key_to_pem().unwrap();
+        (EncodingKey::from_rsa_pem(&pem).unwrap(), jwk)
+    }
+
+    fn ec_fixture(kid: &str, nid: Nid, crv: &str) -> (EncodingKey, serde_json::Value) {
+        let group = EcGroup::from_curve_name(nid).unwrap();
+        let ec = EcKey::generate(&group).unwrap();
+
+        let mut ctx = BigNumContext::new().unwrap();
+        let point = ec
+            .public_key()
+            .to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut ctx)
+            .unwrap();
+        // 0x04 || x || y
+        let (x, y) = point[1..].split_at((point.len() - 1) / 2);
+        let jwk = json!({"kty": "EC", "kid": kid, "crv": crv, "x": b64(x), "y": b64(y)});
+
+        let pem = PKey::from_ec_key(ec)
+            .unwrap()
+            .private_key_to_pem_pkcs8()
+            .unwrap();
+        (EncodingKey::from_ec_pem(&pem).unwrap(), jwk)
+    }
+
+    fn ed_fixture(kid: &str) -> (EncodingKey, serde_json::Value) {
+        let key = PKey::generate_ed25519().unwrap();
+
+        // the raw public key is the end of its DER encoding
+        let der = key.public_key_to_der().unwrap();
+        let jwk =
+            json!({"kty": "OKP", "kid": kid, "crv": "Ed25519", "x": b64(&der[der.len() - 32..])});
+
+        let pem = key.private_key_to_pem_pkcs8().unwrap();
+        (EncodingKey::from_ed_pem(&pem).unwrap(), jwk)
+    }
+
+    fn sign(
+        algo: Algorithm,
+        kid: Option<&str>,
+        claims: serde_json::Value,
+        key: &EncodingKey,
+    ) -> String {
+        let mut header = Header::new(algo);
+        header.kid = kid.map(|kid| kid.to_owned());
+
+        jsonwebtoken::encode(&header, &claims, key).unwrap()
+    }
+
+    fn claims() -> serde_json::Value {
+        json!({"user": "csml", "exp": 4102444800_i64})
+    }
+
+    fn verify(keys: &[serde_json::Value], token: &str) -> Result<serde_json::Value, ErrorInfo> {
+        let (key, algo) = get_jwks_key(keys, token, "flow", Interval::default())?;
+
+        let mut validation = Validation::new(algo);
+        validation.validate_nbf = true;
+
+        jsonwebtoken::decode::<serde_json::Value>(token, &key, &validation)
+            .map(|data| data.claims)
+            .map_err(|e| {
+                gen_error_info(
+                    Position::new(Interval::default(), "flow"),
+                    format!("Invalid JWT verify {:?}", e.kind()),
+                )
+            })
+    }
+
+    #[test]
+    fn ok_jwks_rsa() {
+        let (key, jwk) = rsa_fixture("rsa");
+
+        for algo in [Algorithm::RS256, Algorithm::RS512, Algorithm::PS256] {
+            let token = sign(algo, Some("rsa"), claims(), &key);
+
+            assert_eq!(verify(&[jwk.clone()], &token).unwrap()["user"], "csml");
+        }
+    }
+
+    #[test]
+    fn ok_jwks_ec() {
+        let (key, jwk) = ec_fixture("p256", Nid::X9_62_PRIME256V1, "P-256");
+        let token = sign(Algorithm::ES256, Some("p256"), claims(), &key);
+        assert_eq!(verify(&[jwk], &token).unwrap()["user"], "csml");
+
+        let (key, jwk) = ec_fixture("p384", Nid::SECP384R1, "P-384");
+        let token = sign(Algorithm::ES384, Some("p384"), claims(), &key);
+        assert_eq!(verify(&[jwk], &token).unwrap()["user"], "csml");
+    }
+
+    #[test]
+    fn ok_jwks_eddsa() {
+        let (key, jwk) = ed_fixture("ed");
+        let token = sign(Algorithm::EdDSA, Some("ed"), claims(), &key);
+
+        assert_eq!(verify(&[jwk], &token).unwrap()["user"], "csml");
+    }
+
+    #[test]
+    fn ok_jwks_select_key() {
+        let (old_key, old_jwk) = ec_fixture("old", Nid::X9_62_PRIME256V1, "P-256");
+        let (new_key, new_jwk) = ed_fixture("new");
+        let mut enc_jwk = rsa_fixture("enc").1;
+        enc_jwk["use"] = json!("enc");
+        let keys = [enc_jwk, old_jwk, new_jwk];
+
+        let token = sign(Algorithm::EdDSA, Some("new"), claims(), &new_key);
+        assert!(verify(&keys, &token).is_ok());
+
+        // without 'kid', the first signing key is used
+        let token = sign(Algorithm::ES256, None, claims(), &old_key);
+        assert!(verify(&keys, &token).is_ok());
+    }
+
+    #[test]
+    fn ko_jwks_kid() {
+        let (key, mut jwk) = rsa_fixture("rsa");
+        let token = sign(Algorithm::RS256, Some("unknown"), claims(), &key);
+
+        let error = verify(&[jwk.clone()], &token).unwrap_err();
+        assert_eq!(error.message, ERROR_JWT_JWKS_KID);
+
+        // encryption keys are not used to verify signatures
+        jwk["use"] = json!("enc");
+        let token = sign(Algorithm::RS256, Some("rsa"), claims(), &key);
+        let error = verify(&[jwk], &token).unwrap_err();
+        assert_eq!(error.message, ERROR_JWT_JWKS_KID);
+    }
+
+    #[test]
+    fn ko_jwks_signature() {
+        let (key, _) = rsa_fixture("rsa");
+        let (_, other_jwk) = rsa_fixture("rsa");
+        let token = sign(Algorithm::RS256, Some("rsa"), claims(), &key);
+
+        let error = verify(&[other_jwk], &token).unwrap_err();
+        assert_eq!(error.message, "Invalid JWT verify InvalidSignature");
+    }
+
+    #[test]
+    fn ko_jwks_nbf() {
+        let (key, jwk) = ed_fixture("ed");
+        let claims = json!({"user": "csml", "exp": 4102444800_i64, "nbf": 4070908800_i64});
+        let token = sign(Algorithm::EdDSA, Some("ed"), claims, &key);
+
+        let error = verify(&[jwk], &token).unwrap_err();
+        assert_eq!(error.message, "Invalid JWT verify ImmatureSignature");
+    }
+
+    #[test]
+    fn ko_jwks_algorithm() {
+        let (key, mut jwk) = rsa_fixture("rsa");
+        jwk["alg"] = json!("RS512");
+        let token = sign(Algorithm::RS256, Some("rsa"), claims(), &key);
+
+        let error = verify(&[jwk], &token).unwrap_err();
+        assert_eq!(error.message, ERROR_JWT_JWKS_ALGO);
+    }
+
+    #[test]
+    fn ko_jwks_invalid_token() {
+        let (_, jwk) = rsa_fixture("rsa");
+
+        let error = verify(&[jwk], "not.a.token").unwrap_err();
+        assert!(error.message.starts_with("Invalid JWT verify"));
+    }
+
+    fn key_error(jwk: &serde_json::Value, algo: Algorithm) -> String {
+        match jwk_to_decoding_key(jwk, algo, "flow", Interval::default()) {
+            Ok(_) => panic!("the jwk {} must be rejected for {:?}", jwk, algo),
+            Err(error) => error.message,
+        }
+    }
+
+    #[test]
+    fn ko_jwk_to_decoding_key() {
+        let (_, rsa_jwk) = rsa_fixture("rsa");
+        let (_, ec_jwk) = ec_fixture("p256", Nid::X9_62_PRIME256V1, "P-256");
+
+        // the key type or the curve does not match the algorithm of the token
+        assert_eq!(key_error(&rsa_jwk, Algorithm::ES256), ERROR_JWT_JWKS_KEY);
+        assert_eq!(key_error(&ec_jwk, Algorithm::ES384), ERROR_JWT_JWKS_KEY);
+        assert_eq!(
+            key_error(&json!({"kty": "unknown"}), Algorithm::HS256),
+            ERROR_JWT_JWKS_KEY
+        );
+
+        assert_eq!(
+            key_error(&json!({"kty": "RSA", "e": "AQAB"}), Algorithm::RS256),
+            format!("{}: missing 'n'", ERROR_JWT_JWKS_KEY)
+        );
+        assert_eq!(
+            key_error(&json!({"kty": "oct", "k": "not base64!"}), Algorithm::HS256),
+            format!("{}: 'k' is not valid base64url", ERROR_JWT_JWKS_KEY)
+        );
+    }
+}
@@ -4,10 +4,18 @@ use crate::data::{
 };
 use crate::error_format::*;
 use lettre::{
-    message::{header, Mailbox, MultiPart, SinglePart},
+    message::{
+        header::{self, HeaderName, HeaderValue},
+        Attachment, Mailbox, MultiPart, SinglePart,
+    },
     transport::smtp::authentication::{Credentials, Mechanism},
 };
+use regex::{Captures, Regex};
 use std::collections::HashMap;
+use std::sync::OnceLock;
+
+// headers set from the other email parameters, they can not be overridden with 'headers'
+const RESERVED_HEADERS: [&str; 6] = ["from", "to", "cc", "bcc", "subject", "content-type"];
 ////////////////////////////////////////////////////////////////////////////////
 // PRIVATE FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
@@ -79,6 +87,246 @@ where
     }
 }
 
+fn format_email_list(
+    email: &HashMap<String, Literal>,
+    value: &str,
+    data: &Data,
+    interval: Interval,
+) -> Result<Vec<Mailbox>, ErrorInfo> {
+    let error_message = format!(
+        "email [{}] value need to be of type String or Array of String and a valid email",
+        value
+    );
+
+    let lit = match email.get(value) {
+        Some(lit) => lit,
+        None => return Ok(vec![]),
+    };
+
+    let addresses = match lit.primitive.get_type() {
+        PrimitiveType::PrimitiveString => vec![lit],
+        PrimitiveType::PrimitiveArray => Literal::get_value::<Vec<Literal>>(
+            &lit.primitive,
+            &data.context.flow,
+            lit.interval,
+            error_message.clone(),
+        )?
+        .iter()
+        .collect(),
+        _ => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                error_message,
+            ))
+        }
+    };
+
+    addresses
+        .iter()
+        .map(|lit| {
+            let address = Literal::get_value::<String>(
+                &lit.primitive,
+                &data.context.flow,
+                lit.interval,
+                error_message.clone(),
+            )?;
+
+            parse_email(address, data, interval)
+        })
+        .collect()
+}
+
+// replace every {{ key }} or {{ key.sub_key }} in the template by its value in 'vars',
+// unknown keys are replaced by an empty string
+fn render_template(template: &str, vars: &serde_json::Value, escape_html: bool) -> String {
+    static TEMPLATE_REGEX: OnceLock<Regex> = OnceLock::new();
+    let template_regex =
+        TEMPLATE_REGEX.get_or_init(|| Regex::new(r"\{\{\s*([\w.]+)\s*\}\}").unwrap());
+
+    template_regex
+        .replace_all(template, |caps: &Captures| {
+            let value = caps[1].split('.').fold(Some(vars), |value, key| {
+                value.and_then(|value| value.get(key))
+            });
+
+            let value = match value {
+                Some(serde_json::Value::String(string)) => string.to_owned(),
+                Some(serde_json::Value::Null) | None => String::new(),
+                Some(value) => value.to_string(),
+            };
+
+            match escape_html {
+                true => html_escape::encode_text(&value).to_string(),
+                false => value,
+            }
+        })
+        .to_string()
+}
+
+// plain text fallback for html only emails
+fn html_to_text(html: &str) -> String {
+    static LINE_BREAK_REGEX: OnceLock<Regex> = OnceLock::new();
+    static TAG_REGEX: OnceLock<Regex> = OnceLock::new();
+    static BLANK_LINES_REGEX: OnceLock<Regex> = OnceLock::new();
+
+    let line_break_regex = LINE_BREAK_REGEX
+        .get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</p>|</div>|</h[1-6]>|</li>|</tr>").unwrap());
+    let tag_regex = TAG_REGEX.get_or_init(|| Regex::new(r"(?s)<[^>]*>").unwrap());
+    let blank_lines_regex = BLANK_LINES_REGEX.get_or_init(|| Regex::new(r"\n\s*\n\s*\n+").unwrap());
+
+    let text = line_break_regex.replace_all(html, "\n");
+    let text = tag_regex.replace_all(&text, "");
+    let text = html_escape::decode_html_entities(&text);
+
+    blank_lines_regex
+        .replace_all(text.trim(), "\n\n")
+        .to_string()
+}
+
+fn get_template_value(
+    email: &HashMap<String, Literal>,
+    value: &str,
+    template_data: &Option<serde_json::Value>,
+    escape_html: bool,
+    data: &Data,
+    interval: Interval,
+) -> Result<Option<String>, ErrorInfo> {
+    let value = format_email_value(email, value, "", data, interval)?;
+
+    match (value, template_data) {
+        (Some(value), Some(vars)) => Ok(Some(render_template(value, vars, escape_html))),
+        (Some(value), None) => Ok(Some(value.to_owned())),
+        (None, _) => Ok(None),
+    }
+}
+
+fn format_attachment(
+    attachment: &Literal,
+    data: &Data,
+    interval: Interval,
+) -> Result<SinglePart, ErrorInfo> {
+    let usage = "email attachments need to be of type Array of Object {\"filename\": String, \"content\": String, \"content_type\": Optional<String>, \"encoding\": Optional<\"base64\" | \"text\">}";
+
+    let attachment = Literal::get_value::<HashMap<String, Literal>>(
+        &attachment.primitive,
+        &data.context.flow,
+        attachment.interval,
+        usage.to_owned(),
+    )?;
+
+    let filename =
+        get_value::<String>(attachment.get("filename"), data, usage.to_owned(), interval)?;
+
+    let content_type = match attachment.get("content_type") {
+        Some(_) => {
+            let content_type = get_value::<String>(
+                attachment.get("content_type"),
+                data,
+                usage.to_owned(),
+                interval,
+            )?;
+
+            match header::ContentType::parse(content_type) {
+                Ok(content_type) => content_type,
+                Err(_) => {
+                    return Err(gen_error_info(
+                        Position::new(interval, &data.context.flow),
+                        format!("invalid attachment content_type [{}]", content_type),
+                    ))
+                }
+            }
+        }
+        None => header::ContentType::parse("application/octet-stream").unwrap(),
+    };
+
+    let encoding = match attachment.get("encoding") {
+        Some(_) => {
+            get_value::<String>(attachment.get("encoding"), data, usage.to_owned(), interval)?
+                .to_owned()
+        }
+        None => "base64".to_owned(),
+    };
+
+    let body = match attachment.get("content") {
+        // content as a String is base64 encoded by default
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            let content = get_value::<String>(Some(lit), data, usage.to_owned(), interval)?;
+
+            match encoding.as_ref() {
+                "base64" => match base64::decode(content) {
+                    Ok(body) => body,
+                    Err(_) => {
+                        return Err(gen_error_info(
+                            Position::new(interval, &data.context.flow),
+                            format!("attachment [{}] content is not a valid base64", filename),
+                        ))
+                    }
+                },
+                "text" => content.as_bytes().to_vec(),
+                _ => {
+                    return Err(gen_error_info(
+                        Position::new(interval, &data.context.flow),
+                        usage.to_owned(),
+                    ))
+                }
+            }
+        }
+        // any other value (ex: a response fetched with HTTP) is attached as JSON
+        Some(lit) => lit.primitive.to_json().to_string().into_bytes(),
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                usage.to_owned(),
+            ))
+        }
+    };
+
+    Ok(Attachment::new(filename.to_owned()).body(body, content_type))
+}
+
+fn format_headers(
+    email: &HashMap<String, Literal>,
+    data: &Data,
+    interval: Interval,
+) -> Result<Vec<HeaderValue>, ErrorInfo> {
+    let usage = "email headers need to be of type Object {\"X-Header-Name\": String}";
+
+    let headers = match email.get("headers") {
+        Some(lit) => Literal::get_value::<HashMap<String, Literal>>(
+            &lit.primitive,
+            &data.context.flow,
+            lit.interval,
+            usage.to_owned(),
+        )?,
+        None => return Ok(vec![]),
+    };
+
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = get_value::<String>(Some(value), data, usage.to_owned(), interval)?;
+
+            if RESERVED_HEADERS.contains(&name.to_ascii_lowercase().as_str()) {
+                return Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!(
+                        "email header [{}] can not be set in headers, use the email parameter instead",
+                        name
+                    ),
+                ));
+            }
+
+            match HeaderName::new_from_ascii(name.to_owned()) {
+                Ok(name) => Ok(HeaderValue::new(name, value.to_owned())),
+                Err(_) => Err(gen_error_info(
+                    Position::new(interval, &data.context.flow),
+                    format!("invalid email header name [{}]", name),
+                )),
+            }
+        })
+        .collect()
+}
+
 // by default letter will use Mechanism::Plain and Mechanism::Login
 
 // PLAIN authentication mechanism, defined in
@@ -144,9 +392,7 @@ pub fn format_email(
         message_builder = message_builder.from(mbox);
     }
 
-    let to = format_email_value(&email, "to", "and a valid email", data, interval)?;
-    if let Some(to) = to {
-        let mbox = parse_email(to.as_ref(), data, interval)?;
+    for mbox in format_email_list(&email, "to", data, interval)? {
         message_builder = message_builder.to(mbox);
     }
 
@@ -156,50 +402,79 @@ pub fn format_email(
         message_builder = message_builder.reply_to(mbox);
     }
 
-    let bcc = format_email_value(&email, "bcc", "and a valid email", data, interval)?;
-    if let Some(bcc) = bcc {
-        let mbox = parse_email(bcc.as_ref(), data, interval)?;
+    for mbox in format_email_list(&email, "bcc", data, interval)? {
         message_builder = message_builder.bcc(mbox);
     }
 
-    let cc = format_email_value(&email, "cc", "and a valid email", data, interval)?;
-    if let Some(cc) = cc {
-        let mbox = parse_email(cc.as_ref(), data, interval)?;
+    for mbox in format_email_list(&email, "cc", data, interval)? {
         message_builder = message_builder.cc(mbox);
     }
 
-    let subject = format_email_value(&email, "subject", "", data, interval)?;
+    let template_data = email
+        .get("template_data")
+        .map(|lit| lit.primitive.to_json());
+
+    let subject = get_template_value(&email, "subject", &template_data, false, data, interval)?;
     if let Some(subject) = subject {
-        message_builder = message_builder.subject(subject.to_owned());
+        message_builder = message_builder.subject(subject);
     }
 
-    let text = format_email_value(&email, "text", "", data, interval)?;
-    let html = format_email_value(&email, "html", "", data, interval)?;
+    let text = get_template_value(&email, "text", &template_data, false, data, interval)?;
+    let html = get_template_value(&email, "html", &template_data, true, data, interval)?;
 
-    if text.is_none() && html.is_none() {
-        return Err(gen_error_info(
-            Position::new(interval, &data.context.flow),
-            "email text/html parameter is mandatory".to_owned(),
-        ));
-    }
+    let text = match (text, &html) {
+        (None, None) => {
+            return Err(gen_error_info(
+                Position::new(interval, &data.context.flow),
+                "email text/html parameter is mandatory".to_owned(),
+            ));
+        }
+        (None, Some(html)) => Some(html_to_text(html)),
+        (text, _) => text,
+    };
 
-    let mut multipart = MultiPart::alternative().build();
+    let mut body = MultiPart::alternative().build();
 
     if let Some(text) = text {
-        multipart = multipart.singlepart(
+        body = body.singlepart(
             SinglePart::builder()
                 .header(header::ContentType::TEXT_PLAIN)
-                .body(String::from(text)),
+                .body(text),
         );
     }
     if let Some(html) = html {
-        multipart = multipart.singlepart(
+        body = body.singlepart(
             SinglePart::builder()
                 .header(header::ContentType::TEXT_HTML)
-                .body(String::from(html)),
+                .body(html),
         );
     }
 
+    let attachments = match email.get("attachments") {
+        Some(lit) => Literal::get_value::<Vec<Literal>>(
+            &lit.primitive,
+            &data.context.flow,
+            lit.interval,
+            "email attachments need to be of type Array".to_owned(),
+        )?
+        .iter()
+        .map(|attachment| format_attachment(attachment, data, interval))
+        .collect::<Result<Vec<SinglePart>, ErrorInfo>>()?,
+        None => vec![],
+    };
+
+    let multipart = match attachments.is_empty() {
+        true => body,
+        false => attachments.into_iter().fold(
+            MultiPart::mixed().multipart(body),
+            |multipart, attachment| multipart.singlepart(attachment),
+        ),
+    };
+
+    for header in format_headers(&email, data, interval)? {
+        message_builder = message_builder.raw_header(header);
+    }
+
     match message_builder.multipart(multipart) {
         Ok(message) => Ok(message),
         Err(_) => Err(gen_error_info(
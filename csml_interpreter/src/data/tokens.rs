@@ -80,6 +80,8 @@ pub const HOLD: &str = "hold";
 pub const HOLD_SECURE: &str = "hold_secure";
 pub const GOTO: &str = "goto";
 pub const PREVIOUS: &str = "previous";
+pub const BOT: &str = "bot";
+pub const WITH: &str = "with";
 pub const MATCH: &str = "match";
 pub const NOT_MATCH: &str = "!match";
 pub const DEFAULT: &str = "default";
@@ -88,6 +90,7 @@ pub const FORGET: &str = "forget";
 pub const _METADATA: &str = "_metadata";
 pub const _MEMORY: &str = "_memory";
 pub const _ENV: &str = "_env";
+pub const _SWITCH: &str = "_switch";
 pub const BREAK: &str = "break";
 pub const CONTINUE: &str = "continue";
 pub const RETURN: &str = "return";
@@ -122,8 +125,8 @@ pub const TYPES: &[&str] = &[
 
 pub const RESERVED: &[&str] = &[
     FOREACH, WHILE, IF, ELSE, IMPORT, CONST, INSERT, AS, IN, DO, FROM, EVENT, FLOW, FILE, STEP,
-    SAY, USE, HOLD, GOTO, MATCH, _METADATA, _MEMORY, _ENV, DEFAULT, REMEMBER, FORGET, TRUE, FALSE,
-    NULL, BREAK, COMPONENT,
+    SAY, USE, HOLD, GOTO, MATCH, _METADATA, _MEMORY, _ENV, _SWITCH, DEFAULT, REMEMBER, FORGET,
+    TRUE, FALSE, NULL, BREAK, COMPONENT,
 ];
 
 pub const UTILISATION_RESERVED: &[&str] = &[
@@ -133,7 +136,7 @@ pub const UTILISATION_RESERVED: &[&str] = &[
 
 pub const ASSIGNATION_RESERVED: &[&str] = &[
     FOREACH, WHILE, IF, ELSE, IMPORT, AS, DO, EVENT, FLOW, STEP, SAY, USE, HOLD, GOTO, MATCH,
-    REMEMBER, FORGET, _METADATA, _MEMORY, _ENV, TRUE, FALSE, NULL, BREAK, COMPONENT,
+    REMEMBER, FORGET, _METADATA, _MEMORY, _ENV, _SWITCH, TRUE, FALSE, NULL, BREAK, COMPONENT,
 ];
 
 pub const TYPING: &str = "Typing";
@@ -167,12 +170,16 @@ pub const DEBUG: &str = "Debug";
 pub const UUID: &str = "UUID";
 pub const TIME: &str = "Time";
 pub const EXISTS: &str = "Exists";
+pub const OAUTH2: &str = "OAuth2";
+pub const GRAPHQL: &str = "GraphQL";
+pub const FORM: &str = "Form";
+pub const HANDOFF: &str = "Handoff";
 
 pub const OBJECT: &str = "Object";
 
 pub const BUILT_IN: &[&str] = &[
     ONE_OF, SHUFFLE, LENGTH, FIND, RANDOM, FLOOR, FN, APP, HTTP, OBJECT, DEBUG, UUID, BASE64, HEX,
-    JWT, CRYPTO, TIME, SMTP, EXISTS,
+    JWT, CRYPTO, TIME, SMTP, EXISTS, OAUTH2, GRAPHQL, FORM, HANDOFF,
 ];
 
 pub const OR_BUILT_IN: &str = "Or";
@@ -56,6 +56,8 @@ pub const ERROR_STEP_EXIST: &str = "step does not exist";
 pub const ERROR_INVALID_FLOW: &str = "invalid flow: ";
 pub const ERROR_START_INSTRUCTIONS: &str =
     "to start an action one of the following instructions is expected: [say, do, if, foreach, goto]";
+pub const ERROR_HOLD_TIMEOUT: &str =
+    "hold expects a timeout and an on_timeout step. Example: hold(timeout = \"10m\", on_timeout = \"reminder\")";
 pub const ERROR_FOREACH: &str =
     "foreach only accepts iterable elements like arrays and strings. Example: foreach(elem) in [1, 2, 3]";
 pub const ERROR_FIND_BY_INDEX: &str =
@@ -76,6 +78,7 @@ pub const ERROR_EVENT_CONTENT_TYPE: &str = "event can only be of ContentType::Ev
 
 // Goto
 pub const ERROR_GOTO_VAR: &str = "variables in goto need to resolve as strings";
+pub const ERROR_GOTO_PAYLOAD_SECURE: &str = "secure variables can not be sent to another bot";
 
 // Component
 pub const ERROR_COMPONENT_NAMESPACE: &str = "component must have a function applied";
@@ -155,6 +158,19 @@ pub const ERROR_JWT: &str = "JWT builtin expects payload as argument. Example: J
       })";
 pub const ERROR_SMTP: &str =
     "SMTP builtin expects SMTP Server Address. Example: SMTP(\"smtp.gmail.com\")";
+pub const ERROR_OAUTH2: &str = "OAuth2 builtin expects a token url, a client id and a client secret of type String. Example: OAuth2(\"https://auth.example.com/token\", client_id, client_secret, [\"read\"])";
+pub const ERROR_OAUTH2_SCOPES: &str = "OAuth2 scopes must be of type String or Array of String";
+pub const ERROR_OAUTH2_TOKEN: &str = "OAuth2 token request failed";
+pub const ERROR_OAUTH2_HTTP: &str =
+    "[http] takes one argument of type String. Usage: OAuth2(...).http(\"https://api.example.com\")";
+pub const ERROR_GRAPHQL: &str =
+    "GraphQL builtin expects one url of type string. Example: GraphQL(\"https://api.example.com/graphql\")";
+pub const ERROR_FORM: &str = "Form builtin expects an Array of fields. Example: Form([{\"name\": \"email\", \"prompt\": \"What is your email?\", \"validator\": \"email\"}])";
+pub const ERROR_FORM_FIELD: &str = "Form fields must be objects with a 'name' of type String, a 'prompt' and an optional 'max_retries' of type Int";
+pub const ERROR_FORM_VALIDATOR: &str = "Form field validator must be a Closure or one of [text, email, int, number, phone, date, boolean, url]";
+pub const ERROR_FORM_SCOPE: &str = "Form can not be used inside a function";
+pub const ERROR_FORM_MAX_RETRIES: &str = "Form field has no valid answer after its max_retries";
+pub const ERROR_HANDOFF: &str = "Handoff builtin expects an optional reason of type String and optional metadata of type Object. Example: Handoff(reason = \"billing\", metadata = {\"priority\": \"high\"})";
 pub const ERROR_CRYPTO: &str =
     "CRYPTO builtin expects one argument of type string. Example: CRYPTO(\"text\")";
 pub const ERROR_BUILTIN_UNKNOWN: &str = "Unknown builtin";
@@ -290,7 +306,16 @@ pub const ERROR_JWT_VALIDATION_CLAIMS: &str =
 pub const ERROR_JWT_VALIDATION_ALGO: &str =
     "JWT(jwt).verify(claims, algo, secret) expect second argument 'algo' of type String";
 pub const ERROR_JWT_VALIDATION_SECRETE: &str =
-    "JWT(jwt).verify(claims, algo, secret) expect third argument 'secrete' of type String";
+    "JWT(jwt).verify(claims, algo, secret) expect third argument 'secrete' of type String or JWKS";
+
+pub const ERROR_JWT_JWKS: &str =
+    "Invalid JWKS, expect an Object of the form {\"keys\": [...]} or its JSON String representation";
+pub const ERROR_JWT_JWKS_KID: &str =
+    "No key in the JWKS matches the 'kid' of the token header";
+pub const ERROR_JWT_JWKS_KEY: &str =
+    "Invalid JWK, supported key types are RSA, EC (P-256, P-384), OKP (Ed25519) and oct";
+pub const ERROR_JWT_JWKS_ALGO: &str =
+    "The 'alg' of the token header does not match the algorithm of the selected JWK";
 
 // #### HTTP OBJECT
 pub const ERROR_HTTP_SET: &str =
@@ -301,6 +326,11 @@ pub const ERROR_HTTP_QUERY: &str =
 pub const ERROR_HTTP_SEND: &str = "[send] HTTP Object is bad formatted read doc for correct usage";
 pub const ERROR_HTTP_UNKNOWN_METHOD: &str = "is not a method of HTTP";
 
+// #### GRAPHQL OBJECT
+pub const ERROR_GRAPHQL_QUERY: &str = "takes a document of type String and optional variables of type Object. Usage: GraphQL(...).query(\"{ user { id } }\", {\"key\": 42})";
+pub const ERROR_GRAPHQL_ERRORS: &str = "GraphQL request returned errors";
+pub const ERROR_GRAPHQL_NO_DATA: &str = "bad format: no 'data' in GraphQL response";
+
 // #### OBJECT
 pub const ERROR_OBJECT_TYPE: &str = "value must be of type Object";
 pub const ERROR_OBJECT_GET: &str = "key does not exist";
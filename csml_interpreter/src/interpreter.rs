@@ -10,12 +10,17 @@ pub use json_to_rust::{json_to_literal, memory_to_literal};
 use crate::data::error_info::ErrorInfo;
 use crate::data::position::Position;
 use crate::data::{
-    ast::*, warnings::DisplayWarnings, Data, Hold, IndexInfo, Literal, MessageData, MSG,
+    ast::*,
+    hold::{check_timeout_duration, parse_timeout_duration},
+    primitive::PrimitiveType,
+    warnings::DisplayWarnings,
+    Data, Hold, HoldTimeout, IndexInfo, Literal, MessageData, MSG,
 };
 use crate::error_format::*;
 use crate::interpreter::{
     ast_interpreter::{for_loop, match_actions, solve_if_statement, while_loop},
-    variable_handler::{expr_to_literal, interval::interval_from_expr},
+    builtins::form::is_form_pending,
+    variable_handler::{expr_to_literal, interval::interval_from_expr, resolve_fn_args},
 };
 use crate::parser::ExitCondition;
 
@@ -37,6 +42,71 @@ fn step_vars_to_json(map: HashMap<String, Literal>) -> serde_json::Value {
     serde_json::json!(json_map)
 }
 
+fn get_hold_timeout(
+    options: &Option<Box<Expr>>,
+    interval: Interval,
+    data: &mut Data,
+    message_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Option<HoldTimeout>, ErrorInfo> {
+    let options = match options {
+        Some(options) => options,
+        None => return Ok(None),
+    };
+
+    let args = resolve_fn_args(options, data, message_data, &DisplayWarnings::On, sender)?;
+
+    let duration = match args.get("timeout", 0) {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveInt => {
+            Literal::get_value::<i64>(
+                &lit.primitive,
+                &data.context.flow,
+                interval,
+                ERROR_HOLD_TIMEOUT.to_owned(),
+            )
+            .ok()
+            .copied()
+            .and_then(check_timeout_duration)
+        }
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            parse_timeout_duration(&lit.primitive.to_string())
+        }
+        _ => None,
+    };
+
+    let step = match args.get("on_timeout", 1) {
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Some(lit.primitive.to_string())
+        }
+        _ => None,
+    };
+
+    match (duration, step) {
+        (Some(duration), Some(step)) => Ok(Some(HoldTimeout { duration, step })),
+        _ => Err(gen_error_info(
+            Position::new(interval, &data.context.flow),
+            ERROR_HOLD_TIMEOUT.to_owned(),
+        )),
+    }
+}
+
+fn gen_hold(command_index: usize, data: &Data, secure: bool, timeout: Option<HoldTimeout>) -> Hold {
+    let map = data.step_vars.to_owned();
+
+    Hold::new(
+        IndexInfo {
+            command_index,
+            loop_index: data.loop_indexes.clone(),
+        },
+        step_vars_to_json(map),
+        data.context.step.get_step(),
+        data.context.flow.clone(),
+        data.previous_info.clone(),
+        secure,
+    )
+    .with_timeout(timeout)
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTION
 ////////////////////////////////////////////////////////////////////////////////
@@ -56,7 +126,11 @@ pub fn interpret_scope(
                 continue;
             } else if hold.index.command_index == instruction_info.index {
                 data.context.hold = None;
-                continue; // this command is the hold, we need to skip it in order to continue the conversation
+
+                // a pending form holds on its own instruction and needs the new event to continue
+                if !is_form_pending(data) {
+                    continue; // this command is the hold, we need to skip it in order to continue the conversation
+                }
             }
         }
 
@@ -88,21 +162,10 @@ pub fn interpret_scope(
 
                 return Ok(message_data);
             }
-            Expr::ObjectExpr(ObjectType::Hold(..)) => {
-                let index = instruction_info.index;
-                let map = data.step_vars.to_owned();
-
-                let hold = Hold::new(
-                    IndexInfo {
-                        command_index: index,
-                        loop_index: data.loop_indexes.clone(),
-                    },
-                    step_vars_to_json(map),
-                    data.context.step.get_step(),
-                    data.context.flow.clone(),
-                    data.previous_info.clone(),
-                    false,
-                );
+            Expr::ObjectExpr(ObjectType::Hold(options, interval)) => {
+                let timeout =
+                    get_hold_timeout(options, *interval, data, &mut message_data, sender)?;
+                let hold = gen_hold(instruction_info.index, data, false, timeout);
 
                 message_data.hold = Some(hold.to_owned());
 
@@ -110,21 +173,10 @@ pub fn interpret_scope(
                 message_data.exit_condition = Some(ExitCondition::Hold);
                 return Ok(message_data);
             }
-            Expr::ObjectExpr(ObjectType::HoldSecure(..)) => {
-                let index = instruction_info.index;
-                let map = data.step_vars.to_owned();
-
-                let hold = Hold::new(
-                    IndexInfo {
-                        command_index: index,
-                        loop_index: data.loop_indexes.clone(),
-                    },
-                    step_vars_to_json(map),
-                    data.context.step.get_step(),
-                    data.context.flow.clone(),
-                    data.previous_info.clone(),
-                    true,
-                );
+            Expr::ObjectExpr(ObjectType::HoldSecure(options, interval)) => {
+                let timeout =
+                    get_hold_timeout(options, *interval, data, &mut message_data, sender)?;
+                let hold = gen_hold(instruction_info.index, data, true, timeout);
 
                 message_data.hold = Some(hold.to_owned());
 
@@ -166,6 +218,16 @@ pub fn interpret_scope(
                 ));
             }
         };
+
+        if let Some(ExitCondition::HoldInstruction) = message_data.exit_condition {
+            let hold = gen_hold(instruction_info.index, data, false, None);
+
+            message_data.hold = Some(hold.to_owned());
+
+            MSG::send(&sender, MSG::Hold(hold));
+            message_data.exit_condition = Some(ExitCondition::Hold);
+            return Ok(message_data);
+        }
     }
 
     Ok(message_data)
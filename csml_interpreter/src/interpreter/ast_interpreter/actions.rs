@@ -4,11 +4,12 @@ use crate::data::warnings::DisplayWarnings;
 use crate::data::{
     ast::*,
     context::ContextStepInfo,
+    csml_logs::{csml_logger, CsmlLog, LogLvl},
     data::Data,
     literal::ContentType,
     message::*,
     primitive::{closure::capture_variables, PrimitiveNull, PrimitiveString},
-    Literal, Memory, MemoryType, MessageData, MSG,
+    Literal, Memory, MemoryType, MessageData, TargetBot, MSG,
 };
 use crate::error_format::*;
 use crate::interpreter::variable_handler::{
@@ -91,6 +92,78 @@ fn check_if_inserted_step<'a>(name: &str, interval: &Interval, data: &'a Data) -
     }
 }
 
+/**
+ * Payload sent to another bot, secure variables can not leave the current bot.
+ */
+fn get_switch_payload(
+    payload: &Option<Box<Expr>>,
+    msg_data: &mut MessageData,
+    data: &mut Data,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Option<serde_json::Value>, ErrorInfo> {
+    let expr = match payload {
+        Some(expr) => expr,
+        None => return Ok(None),
+    };
+
+    let literal = expr_to_literal(expr, &DisplayWarnings::On, None, data, msg_data, sender)?;
+
+    if literal.secure_variable {
+        return Err(gen_error_info(
+            Position::new(literal.interval, &data.context.flow),
+            ERROR_GOTO_PAYLOAD_SECURE.to_owned(),
+        ));
+    }
+
+    Ok(Some(literal.primitive.to_json()))
+}
+
+/**
+ * Go back to the bot that switched to the current one, at the flow and step it came from.
+ * Without a previous bot, the conversation ends.
+ */
+fn goto_previous_bot(
+    payload: &Option<Box<Expr>>,
+    mut msg_data: MessageData,
+    data: &mut Data,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<MessageData, ErrorInfo> {
+    let payload = get_switch_payload(payload, &mut msg_data, data, sender)?;
+
+    match data.context.previous_bot.clone() {
+        Some(previous_bot) => {
+            msg_data.exit_condition = Some(ExitCondition::End);
+
+            MSG::send(
+                &sender,
+                MSG::Next {
+                    flow: Some(previous_bot.flow),
+                    step: Some(ContextStepInfo::UnknownFlow(previous_bot.step)),
+                    bot: Some(TargetBot {
+                        bot: previous_bot.bot,
+                        payload,
+                    }),
+                },
+            );
+        }
+        None => {
+            data.context.step = ContextStepInfo::Normal("end".to_string());
+            msg_data.exit_condition = Some(ExitCondition::Goto);
+
+            MSG::send(
+                &sender,
+                MSG::Next {
+                    flow: None,
+                    step: Some(ContextStepInfo::Normal("end".to_owned())),
+                    bot: None,
+                },
+            );
+        }
+    }
+
+    Ok(msg_data)
+}
+
 pub fn match_actions(
     function: &ObjectType,
     mut msg_data: MessageData,
@@ -132,6 +205,17 @@ pub fn match_actions(
                 MSG::send_error_msg(&sender, &mut msg_data, Err(err));
                 Ok(msg_data)
             } else {
+                csml_logger(
+                    CsmlLog::new(
+                        None,
+                        Some(data.context.flow.to_owned()),
+                        Some(interval.start_line),
+                        lit.primitive.to_string(),
+                    )
+                    .with_event("csml_debug"),
+                    LogLvl::Debug,
+                );
+
                 let msg = Message::new(lit, &data.context.flow)?;
                 MSG::send(&sender, MSG::Message(msg.clone()));
                 Ok(Message::add_to_message(msg_data, MessageType::Msg(msg)))
@@ -398,6 +482,7 @@ pub fn match_actions(
                 step,
                 flow,
                 bot: None,
+                ..
             },
             interval,
         ) => {
@@ -463,6 +548,7 @@ pub fn match_actions(
                 step,
                 flow,
                 bot: Some(next_bot),
+                payload,
             },
             ..,
         ) => {
@@ -481,6 +567,7 @@ pub fn match_actions(
             };
 
             let bot = search_goto_var_memory(&next_bot, &mut msg_data, data, sender)?;
+            let payload = get_switch_payload(payload, &mut msg_data, data, sender)?;
 
             msg_data.exit_condition = Some(ExitCondition::End);
 
@@ -489,13 +576,17 @@ pub fn match_actions(
                 MSG::Next {
                     step: Some(step),
                     flow: flow,
-                    bot: Some(bot), // need to send previous flow / step / bot info
+                    bot: Some(TargetBot { bot, payload }), // need to send previous flow / step / bot info
                 },
             );
 
             Ok(msg_data)
         }
 
+        ObjectType::Previous(PreviousType::Bot(payload, _), _) => {
+            goto_previous_bot(payload, msg_data, data, sender)
+        }
+
         ObjectType::Previous(previous_type, _) => {
             let flow_opt;
             let mut step_opt = None;
@@ -526,7 +617,8 @@ pub fn match_actions(
                     data.context.flow = tmp_f;
                     data.context.step = tmp_s;
                 }
-                (_, None) => {
+                // nothing to go back to, end the conversation
+                _ => {
                     flow_opt = None;
                     step_opt = Some(ContextStepInfo::Normal("end".to_owned()));
 
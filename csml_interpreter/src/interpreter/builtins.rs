@@ -1,10 +1,14 @@
 pub mod api;
 pub mod crypto;
 pub mod exists;
+pub mod form;
 pub mod format;
 pub mod functions;
+pub mod graphql;
+pub mod handoff;
 pub mod http_builtin;
 pub mod jwt;
+pub mod oauth2;
 pub mod smtp;
 pub mod time;
 
@@ -20,10 +24,14 @@ use std::sync::mpsc;
 use api::api;
 use crypto::crypto;
 use exists::exists;
+use form::form;
 use format::*;
 use functions::*;
+use graphql::graphql;
+use handoff::handoff;
 use http_builtin::http;
 use jwt::jwt;
+use oauth2::oauth2;
 use smtp::smtp;
 use time::time;
 // use uri::*;
@@ -54,10 +62,13 @@ pub fn match_builtin(
 ) -> Result<Literal, ErrorInfo> {
     match name {
         HTTP => http(args, &data.context.flow, interval),
+        GRAPHQL => graphql(args, &data.context.flow, interval),
         SMTP => smtp(args, &data.context.flow, interval),
         BASE64 => base64(args, &data.context.flow, interval),
         HEX => hex(args, &data.context.flow, interval),
         FN | APP => api(args, interval, data, msg_data, sender),
+        FORM => form(args, interval, data, msg_data, sender),
+        HANDOFF => handoff(args, interval, data, msg_data, sender),
         ONE_OF => one_of(args, &data.context.flow, interval),
         OR_BUILT_IN => or(args, &data.context.flow, interval),
         SHUFFLE => shuffle(args, &data.context.flow, interval),
@@ -71,6 +82,7 @@ pub fn match_builtin(
         CRYPTO => crypto(args, &data.context.flow, interval),
         TIME => time(args, &data.context.flow, interval),
         EXISTS => exists(args, data, interval),
+        OAUTH2 => oauth2(args, interval, data, sender),
 
         //old builtin
         _object => object(args, &data.context.flow, interval),
@@ -0,0 +1,465 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{
+    PrimitiveArray, PrimitiveBoolean, PrimitiveClosure, PrimitiveFloat, PrimitiveInt,
+    PrimitiveNull, PrimitiveObject, PrimitiveString, PrimitiveType,
+};
+use crate::data::{
+    ast::Interval,
+    data::{init_child_context, init_child_scope},
+    ArgsType, Data, Literal, Message, MessageData, MSG,
+};
+use crate::error_format::*;
+use crate::interpreter::variable_handler::resolve_csml_object::{
+    exec_closure, insert_args_in_scope_memory, insert_memories_in_scope_memory,
+};
+use crate::parser::ExitCondition;
+use regex::Regex;
+use std::collections::HashMap;
+use std::sync::{mpsc, OnceLock};
+
+// prefix of the step variables holding the progress of each form between two holds
+pub const FORM_STATE: &str = "_form";
+
+const DEFAULT_MAX_RETRIES: i64 = 3;
+
+////////////////////////////////////////////////////////////////////////////////
+/// DATA STRUCTURE
+////////////////////////////////////////////////////////////////////////////////
+
+enum Validator {
+    Type(String),
+    Closure(PrimitiveClosure),
+}
+
+struct FormField {
+    name: String,
+    prompt: Literal,
+    retry_prompt: Option<Literal>,
+    validator: Validator,
+    max_retries: i64,
+}
+
+struct FormState {
+    field: usize,
+    retries: i64,
+    values: HashMap<String, Literal>,
+    // fields left empty after too many invalid answers
+    failed: Vec<String>,
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_field(
+    literal: &Literal,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<FormField, ErrorInfo> {
+    let field = Literal::get_value::<HashMap<String, Literal>>(
+        &literal.primitive,
+        flow_name,
+        interval,
+        ERROR_FORM_FIELD.to_owned(),
+    )?;
+
+    let name = match field.get("name") {
+        Some(name) if name.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            name.primitive.to_string()
+        }
+        _ => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_FORM_FIELD.to_owned(),
+            ))
+        }
+    };
+
+    let prompt = match field.get("prompt") {
+        Some(prompt) => prompt.to_owned(),
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_FORM_FIELD.to_owned(),
+            ))
+        }
+    };
+
+    let validator = match field.get("validator") {
+        None => Validator::Type("text".to_owned()),
+        Some(lit) if lit.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Validator::Type(lit.primitive.to_string())
+        }
+        Some(lit) => Validator::Closure(
+            Literal::get_value::<PrimitiveClosure>(
+                &lit.primitive,
+                flow_name,
+                interval,
+                ERROR_FORM_VALIDATOR.to_owned(),
+            )?
+            .to_owned(),
+        ),
+    };
+
+    let max_retries = match field.get("max_retries") {
+        Some(lit) => *Literal::get_value::<i64>(
+            &lit.primitive,
+            flow_name,
+            interval,
+            ERROR_FORM_FIELD.to_owned(),
+        )?,
+        None => DEFAULT_MAX_RETRIES,
+    };
+
+    Ok(FormField {
+        name,
+        prompt,
+        retry_prompt: field.get("retry_prompt").map(|lit| lit.to_owned()),
+        validator,
+        max_retries,
+    })
+}
+
+fn get_fields(
+    args: &ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Vec<FormField>, ErrorInfo> {
+    let fields = match args.get("fields", 0) {
+        Some(literal) => Literal::get_value::<Vec<Literal>>(
+            &literal.primitive,
+            flow_name,
+            interval,
+            ERROR_FORM.to_owned(),
+        )?,
+        None => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                ERROR_FORM.to_owned(),
+            ))
+        }
+    };
+
+    fields
+        .iter()
+        .map(|field| get_field(field, flow_name, interval))
+        .collect()
+}
+
+/**
+ * Each Form call of a step has its own state, keyed by its position in the flow
+ */
+fn get_state_key(interval: Interval) -> String {
+    format!(
+        "{}#{}:{}",
+        FORM_STATE, interval.start_line, interval.start_column
+    )
+}
+
+fn get_state(key: &str, data: &Data) -> Option<FormState> {
+    let state = data.step_vars.get(key)?;
+    let state = state.primitive.as_any().downcast_ref::<PrimitiveObject>()?;
+
+    let field = match state.value.get("field") {
+        Some(lit) => lit.primitive.as_any().downcast_ref::<PrimitiveInt>()?.value as usize,
+        None => 0,
+    };
+    let retries = match state.value.get("retries") {
+        Some(lit) => lit.primitive.as_any().downcast_ref::<PrimitiveInt>()?.value,
+        None => 0,
+    };
+    let values = match state.value.get("values") {
+        Some(lit) => lit
+            .primitive
+            .as_any()
+            .downcast_ref::<PrimitiveObject>()?
+            .value
+            .to_owned(),
+        None => HashMap::new(),
+    };
+    let failed = match state.value.get("failed") {
+        Some(lit) => lit
+            .primitive
+            .as_any()
+            .downcast_ref::<PrimitiveArray>()?
+            .value
+            .iter()
+            .map(|name| name.primitive.to_string())
+            .collect(),
+        None => vec![],
+    };
+
+    Some(FormState {
+        field,
+        retries,
+        values,
+        failed,
+    })
+}
+
+fn save_state(key: &str, state: &FormState, data: &mut Data, interval: Interval) {
+    let mut map = HashMap::new();
+
+    map.insert(
+        "field".to_owned(),
+        PrimitiveInt::get_literal(state.field as i64, interval),
+    );
+    map.insert(
+        "retries".to_owned(),
+        PrimitiveInt::get_literal(state.retries, interval),
+    );
+    map.insert(
+        "values".to_owned(),
+        PrimitiveObject::get_literal(&state.values, interval),
+    );
+    let failed: Vec<Literal> = state
+        .failed
+        .iter()
+        .map(|name| PrimitiveString::get_literal(name, interval))
+        .collect();
+    map.insert(
+        "failed".to_owned(),
+        PrimitiveArray::get_literal(&failed, interval),
+    );
+
+    data.step_vars
+        .insert(key.to_owned(), PrimitiveObject::get_literal(&map, interval));
+}
+
+fn get_pattern(pattern: &'static OnceLock<Regex>, regex: &str) -> &'static Regex {
+    pattern.get_or_init(|| Regex::new(regex).unwrap())
+}
+
+static EMAIL_PATTERN: OnceLock<Regex> = OnceLock::new();
+static PHONE_PATTERN: OnceLock<Regex> = OnceLock::new();
+static URL_PATTERN: OnceLock<Regex> = OnceLock::new();
+
+fn validate_type(
+    validator: &str,
+    value: &str,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Option<Literal>, ErrorInfo> {
+    let value = value.trim();
+
+    let literal = match validator {
+        "text" if !value.is_empty() => Some(PrimitiveString::get_literal(value, interval)),
+        "text" => None,
+        "email" => {
+            let regex = get_pattern(&EMAIL_PATTERN, r"^[^@\s]+@[^@\s]+\.[^@\s]+$");
+            match regex.is_match(value) {
+                true => Some(PrimitiveString::get_literal(value, interval)),
+                false => None,
+            }
+        }
+        "int" => match value.parse::<i64>() {
+            Ok(int) => Some(PrimitiveInt::get_literal(int, interval)),
+            Err(_) => None,
+        },
+        "number" => match (value.parse::<i64>(), value.replace(',', ".").parse::<f64>()) {
+            (Ok(int), _) => Some(PrimitiveInt::get_literal(int, interval)),
+            (_, Ok(float)) if float.is_finite() => {
+                Some(PrimitiveFloat::get_literal(float, interval))
+            }
+            _ => None,
+        },
+        "phone" => {
+            let phone: String = value
+                .chars()
+                .filter(|c| !matches!(c, ' ' | '-' | '.' | '(' | ')'))
+                .collect();
+            let regex = get_pattern(&PHONE_PATTERN, r"^\+?[0-9]{6,15}$");
+            match regex.is_match(&phone) {
+                true => Some(PrimitiveString::get_literal(&phone, interval)),
+                false => None,
+            }
+        }
+        "date" => ["%Y-%m-%d", "%d/%m/%Y", "%d.%m.%Y"]
+            .iter()
+            .find_map(|format| chrono::NaiveDate::parse_from_str(value, format).ok())
+            .map(|date| {
+                PrimitiveString::get_literal(&date.format("%Y-%m-%d").to_string(), interval)
+            }),
+        "boolean" => match value.to_lowercase().as_ref() {
+            "yes" | "y" | "true" | "oui" => Some(PrimitiveBoolean::get_literal(true, interval)),
+            "no" | "n" | "false" | "non" => Some(PrimitiveBoolean::get_literal(false, interval)),
+            _ => None,
+        },
+        "url" => {
+            let regex = get_pattern(&URL_PATTERN, r"^https?://[^\s/$.?#].[^\s]*$");
+            match regex.is_match(value) {
+                true => Some(PrimitiveString::get_literal(value, interval)),
+                false => None,
+            }
+        }
+        _ => {
+            return Err(gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{} [{}]", ERROR_FORM_VALIDATOR, validator),
+            ))
+        }
+    };
+
+    Ok(literal)
+}
+
+fn validate_closure(
+    closure: &PrimitiveClosure,
+    value: &str,
+    interval: Interval,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Option<Literal>, ErrorInfo> {
+    let mut context = init_child_context(data);
+    let mut step_count = *data.step_count;
+    let mut new_scope_data = init_child_scope(data, &mut context, &mut step_count);
+
+    if let Some(memories) = closure.enclosed_variables.clone() {
+        insert_memories_in_scope_memory(&mut new_scope_data, memories, msg_data, sender);
+    }
+
+    let mut map = HashMap::new();
+    map.insert(
+        "arg0".to_owned(),
+        PrimitiveString::get_literal(value, interval),
+    );
+
+    let args = ArgsType::Normal(map);
+    insert_args_in_scope_memory(&mut new_scope_data, &closure.args, &args, msg_data, sender);
+
+    let result = exec_closure(
+        &closure.func,
+        &closure.args,
+        args,
+        interval,
+        &mut new_scope_data,
+        msg_data,
+        sender,
+    )?;
+
+    // a boolean accepts or rejects the raw input, any other value replaces it
+    match result.primitive.get_type() {
+        PrimitiveType::PrimitiveBoolean if result.primitive.as_bool() => {
+            Ok(Some(PrimitiveString::get_literal(value, interval)))
+        }
+        PrimitiveType::PrimitiveBoolean | PrimitiveType::PrimitiveNull => Ok(None),
+        _ => Ok(Some(result)),
+    }
+}
+
+fn send_prompt(
+    prompt: &Literal,
+    data: &Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<(), ErrorInfo> {
+    let msg = Message::new(prompt.to_owned(), &data.context.flow)?;
+
+    MSG::send(sender, MSG::Message(msg.clone()));
+    msg_data.messages.push(msg);
+
+    Ok(())
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * A form is waiting for an answer when its state is saved in the step variables,
+ * the instruction holding the form needs to be executed again with the new event.
+ */
+pub fn is_form_pending(data: &Data) -> bool {
+    let prefix = format!("{}#", FORM_STATE);
+
+    data.step_vars.keys().any(|key| key.starts_with(&prefix))
+}
+
+pub fn form(
+    args: ArgsType,
+    interval: Interval,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let flow_name = data.context.flow.to_owned();
+    let fields = get_fields(&args, &flow_name, interval)?;
+
+    let key = get_state_key(interval);
+    let resumed = get_state(&key, data);
+    let is_resumed = resumed.is_some();
+    let mut state = resumed.unwrap_or(FormState {
+        field: 0,
+        retries: 0,
+        values: HashMap::new(),
+        failed: vec![],
+    });
+
+    // the current event is the answer to the last prompt
+    if is_resumed && state.field < fields.len() {
+        let field = &fields[state.field];
+        let value = data.event.content_value.to_owned();
+
+        let literal = match &field.validator {
+            Validator::Type(validator) => validate_type(validator, &value, &flow_name, interval)?,
+            Validator::Closure(closure) => {
+                validate_closure(closure, &value, interval, data, msg_data, sender)?
+            }
+        };
+
+        match literal {
+            Some(literal) => {
+                state.values.insert(field.name.to_owned(), literal);
+                state.field += 1;
+                state.retries = 0;
+            }
+            None if state.retries < field.max_retries => {
+                state.retries += 1;
+
+                let prompt = field.retry_prompt.as_ref().unwrap_or(&field.prompt);
+                send_prompt(prompt, data, msg_data, sender)?;
+                save_state(&key, &state, data, interval);
+
+                msg_data.exit_condition = Some(ExitCondition::HoldInstruction);
+                return Ok(PrimitiveNull::get_literal(interval));
+            }
+            // too many invalid answers, the field is left empty and reported in the result
+            None => {
+                state.failed.push(field.name.to_owned());
+                state.field += 1;
+                state.retries = 0;
+            }
+        }
+    }
+
+    if state.field < fields.len() {
+        send_prompt(&fields[state.field].prompt, data, msg_data, sender)?;
+        save_state(&key, &state, data, interval);
+
+        msg_data.exit_condition = Some(ExitCondition::HoldInstruction);
+        return Ok(PrimitiveNull::get_literal(interval));
+    }
+
+    data.step_vars.remove(&key);
+
+    // form.is_error() and form.email.is_error() are true if the email was never valid
+    for name in state.failed.iter() {
+        let mut literal = PrimitiveNull::get_literal(interval);
+        literal.add_error_to_info(&format!("{} [{}]", ERROR_FORM_MAX_RETRIES, name));
+
+        state.values.insert(name.to_owned(), literal);
+    }
+
+    let mut result = PrimitiveObject::get_literal(&state.values, interval);
+    if !state.failed.is_empty() {
+        result.add_error_to_info(&format!(
+            "{} [{}]",
+            ERROR_FORM_MAX_RETRIES,
+            state.failed.join(", ")
+        ));
+    }
+
+    Ok(result)
+}
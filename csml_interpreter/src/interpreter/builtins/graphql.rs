@@ -0,0 +1,36 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveObject, PrimitiveString, PrimitiveType};
+use crate::data::{ast::Interval, ArgsType, Literal};
+use crate::error_format::*;
+use crate::interpreter::builtins::http_builtin::get_http_object;
+
+////////////////////////////////////////////////////////////////////////////////
+/// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn graphql(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+    match args.get("url", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            let mut graphql = get_http_object(literal, interval);
+
+            // every GraphQL operation is sent as a POST request with a JSON body
+            graphql.insert(
+                "method".to_owned(),
+                PrimitiveString::get_literal("post", interval),
+            );
+
+            args.populate(&mut graphql, &["url", "header"], flow_name, interval)?;
+
+            let mut result = PrimitiveObject::get_literal(&graphql, interval);
+
+            result.set_content_type("graphql");
+
+            Ok(result)
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_GRAPHQL.to_owned(),
+        )),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveNull, PrimitiveType};
+use crate::data::{ast::Interval, ArgsType, Data, Literal, MessageData, MSG};
+use crate::error_format::*;
+use crate::parser::ExitCondition;
+use std::sync::mpsc;
+
+////////////////////////////////////////////////////////////////////////////////
+/// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_reason(
+    args: &ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<Option<String>, ErrorInfo> {
+    match args.get("reason", 0) {
+        None => Ok(None),
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Ok(Some(literal.primitive.to_string()))
+        }
+        Some(_) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_HANDOFF.to_owned(),
+        )),
+    }
+}
+
+fn get_metadata(
+    args: &ArgsType,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<serde_json::Value, ErrorInfo> {
+    match args.get("metadata", 1) {
+        None => Ok(serde_json::json!({})),
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveObject => {
+            Ok(literal.primitive.to_json())
+        }
+        Some(_) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_HANDOFF.to_owned(),
+        )),
+    }
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+/**
+ * Hand the conversation over to a human agent.
+ * The engine stops answering the user until the bot is resumed,
+ * so nothing after the handoff is executed in the current step.
+ */
+pub fn handoff(
+    args: ArgsType,
+    interval: Interval,
+    data: &mut Data,
+    msg_data: &mut MessageData,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let reason = get_reason(&args, &data.context.flow, interval)?;
+    let metadata = get_metadata(&args, &data.context.flow, interval)?;
+
+    MSG::send(sender, MSG::Handoff { reason, metadata });
+
+    msg_data.exit_condition = Some(ExitCondition::End);
+
+    Ok(PrimitiveNull::get_literal(interval))
+}
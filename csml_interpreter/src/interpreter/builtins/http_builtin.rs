@@ -1,7 +1,7 @@
 use crate::data::error_info::ErrorInfo;
 use crate::data::position::Position;
 use crate::data::primitive::{PrimitiveInt, PrimitiveObject, PrimitiveString, PrimitiveType};
-use crate::data::{ast::Interval, csml_logs::*, ArgsType, Literal};
+use crate::data::{ast::Interval, csml_logs::*, csml_metrics, csml_tracing, ArgsType, Literal};
 use crate::error_format::*;
 use std::collections::HashMap;
 use std::env;
@@ -173,7 +173,7 @@ fn get_no_certificate_verifier_agent() -> ureq::Agent {
         .build()
 }
 
-fn get_http_request(
+pub(crate) fn get_http_request(
     method: &str,
     url: &str,
     flow_name: &str,
@@ -242,6 +242,21 @@ pub fn http_request(
 
     let mut request = get_http_request(method, &url, flow_name, interval, is_ssl_disable)?;
 
+    let kind = match is_app_call {
+        true => "app",
+        false => "http",
+    };
+    let mut span = csml_tracing::Span::start(
+        &format!("{}_call", kind),
+        vec![
+            ("flow", flow_name.to_owned()),
+            ("http.method", method.to_uppercase()),
+        ],
+    );
+
+    // propagate the trace to the called service, unless the bot sets its own traceparent
+    request = request.set("traceparent", &span.context().to_traceparent());
+
     for key in header.keys() {
         let value = match header.get(key) {
             Some(val) => val.primitive.to_string(),
@@ -275,11 +290,34 @@ pub fn http_request(
         LogLvl::Debug,
     );
 
+    let timer = csml_metrics::Timer::new(
+        "csml_http_call_duration_seconds",
+        vec![("method", method.to_owned()), ("kind", kind.to_owned())],
+    );
+
     let response = match object.get("body") {
         Some(body) => request.send_json(body.primitive.to_json()),
         None => request.call(),
     };
 
+    drop(timer);
+    let status = match &response {
+        Ok(response) => response.status().to_string(),
+        Err(ureq::Error::Status(code, _)) => code.to_string(),
+        Err(_) => "error".to_owned(),
+    };
+    csml_metrics::increment_counter(
+        "csml_http_calls_total",
+        &[("method", method), ("kind", kind), ("status", &status)],
+    );
+    span.set_attribute("http.status_code", status.to_owned());
+    match &response {
+        // the apps_endpoint is not exposed, as in the error messages
+        Err(_) if is_app_call => span.set_error(format!("Apps service: status {}", status)),
+        Err(err) => span.set_error(err.to_string()),
+        Ok(_) => {}
+    }
+
     match response {
         Ok(response) => {
             let response_info = get_request_info(&response, interval);
@@ -365,33 +403,39 @@ pub fn http_request(
     }
 }
 
-pub fn http(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+pub fn get_http_object(url: &Literal, interval: Interval) -> HashMap<String, Literal> {
     let mut http: HashMap<String, Literal> = HashMap::new();
     let mut header = HashMap::new();
 
-    match args.get("url", 0) {
-        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
-            header.insert(
-                "Content-Type".to_owned(),
-                PrimitiveString::get_literal("application/json", interval),
-            );
-            header.insert(
-                "Accept".to_owned(),
-                PrimitiveString::get_literal("application/json,text/*", interval),
-            );
-            header.insert(
-                "User-Agent".to_owned(),
-                PrimitiveString::get_literal("csml/v1", interval),
-            );
+    header.insert(
+        "Content-Type".to_owned(),
+        PrimitiveString::get_literal("application/json", interval),
+    );
+    header.insert(
+        "Accept".to_owned(),
+        PrimitiveString::get_literal("application/json,text/*", interval),
+    );
+    header.insert(
+        "User-Agent".to_owned(),
+        PrimitiveString::get_literal("csml/v1", interval),
+    );
 
-            http.insert("url".to_owned(), literal.to_owned());
-            http.insert(
-                "method".to_owned(),
-                PrimitiveString::get_literal("get", interval),
-            );
+    http.insert("url".to_owned(), url.to_owned());
+    http.insert(
+        "method".to_owned(),
+        PrimitiveString::get_literal("get", interval),
+    );
 
-            let lit_header = PrimitiveObject::get_literal(&header, interval);
-            http.insert("header".to_owned(), lit_header);
+    let lit_header = PrimitiveObject::get_literal(&header, interval);
+    http.insert("header".to_owned(), lit_header);
+
+    http
+}
+
+pub fn http(args: ArgsType, flow_name: &str, interval: Interval) -> Result<Literal, ErrorInfo> {
+    match args.get("url", 0) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            let mut http = get_http_object(literal, interval);
 
             args.populate(
                 &mut http,
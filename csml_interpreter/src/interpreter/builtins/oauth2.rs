@@ -0,0 +1,330 @@
+use crate::data::error_info::ErrorInfo;
+use crate::data::position::Position;
+use crate::data::primitive::{PrimitiveInt, PrimitiveObject, PrimitiveString, PrimitiveType};
+use crate::data::{ast::Interval, csml_logs::*, ArgsType, Data, Literal, MSG};
+use crate::error_format::*;
+use crate::interpreter::builtins::http_builtin::get_http_request;
+use std::collections::HashMap;
+use std::sync::mpsc;
+
+// tokens are refreshed this many seconds before they actually expire
+const EXPIRATION_MARGIN: i64 = 30;
+
+////////////////////////////////////////////////////////////////////////////////
+/// PRIVATE FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+fn get_string_arg(
+    args: &ArgsType,
+    name: &str,
+    index: usize,
+    flow_name: &str,
+    interval: Interval,
+) -> Result<String, ErrorInfo> {
+    match args.get(name, index) {
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Ok(literal.primitive.to_string())
+        }
+        _ => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            ERROR_OAUTH2.to_owned(),
+        )),
+    }
+}
+
+fn get_scopes(args: &ArgsType, flow_name: &str, interval: Interval) -> Result<String, ErrorInfo> {
+    match args.get("scopes", 3) {
+        None => Ok(String::new()),
+        Some(literal) if literal.primitive.get_type() == PrimitiveType::PrimitiveString => {
+            Ok(literal.primitive.to_string())
+        }
+        Some(literal) => {
+            let scopes = Literal::get_value::<Vec<Literal>>(
+                &literal.primitive,
+                flow_name,
+                interval,
+                ERROR_OAUTH2_SCOPES.to_owned(),
+            )?;
+
+            let mut list = vec![];
+            for scope in scopes.iter() {
+                if scope.primitive.get_type() != PrimitiveType::PrimitiveString {
+                    return Err(gen_error_info(
+                        Position::new(interval, flow_name),
+                        ERROR_OAUTH2_SCOPES.to_owned(),
+                    ));
+                }
+                list.push(scope.primitive.to_string());
+            }
+
+            Ok(list.join(" "))
+        }
+    }
+}
+
+fn get_cache_key(token_url: &str, client_id: &str, scopes: &str) -> String {
+    let key = format!("{}|{}|{}", token_url, client_id, scopes);
+
+    match openssl::hash::hash(openssl::hash::MessageDigest::sha256(), key.as_bytes()) {
+        Ok(digest) => hex::encode(digest),
+        Err(_) => key,
+    }
+}
+
+fn get_cached_token(key: &str, sender: &Option<mpsc::Sender<MSG>>) -> Option<serde_json::Value> {
+    let sender = sender.as_ref()?;
+    let (reply, receiver) = mpsc::channel();
+
+    sender
+        .send(MSG::GetCachedToken {
+            key: key.to_owned(),
+            reply,
+        })
+        .ok()?;
+
+    receiver.recv().ok().flatten()
+}
+
+fn is_token_valid(token: &serde_json::Value, now: i64) -> bool {
+    match token["expires_at"].as_i64() {
+        Some(expires_at) => expires_at - EXPIRATION_MARGIN > now,
+        // tokens without expiration are valid until the server refuses them
+        None => token["access_token"].is_string(),
+    }
+}
+
+fn request_token(
+    token_url: &str,
+    client_id: &str,
+    client_secret: &str,
+    auth_method: &str,
+    params: &[(&str, &str)],
+    flow_name: &str,
+    interval: Interval,
+) -> Result<serde_json::Value, ErrorInfo> {
+    let mut request = get_http_request("post", token_url, flow_name, interval, false)?
+        .set("Accept", "application/json")
+        .set("User-Agent", "csml/v1");
+
+    let mut form = params.to_vec();
+
+    match auth_method {
+        "post" => {
+            form.push(("client_id", client_id));
+            form.push(("client_secret", client_secret));
+        }
+        _ => {
+            let credentials = format!("{}:{}", client_id, client_secret);
+            let authorization = format!("Basic {}", base64::encode(credentials.as_bytes()));
+            request = request.set("Authorization", &authorization);
+        }
+    }
+
+    csml_logger(
+        CsmlLog::new(
+            None,
+            Some(flow_name.to_string()),
+            Some(interval.start_line),
+            format!("Request OAuth2 token from {}", token_url),
+        ),
+        LogLvl::Info,
+    );
+
+    match request.send_form(&form) {
+        Ok(response) => match response.into_json::<serde_json::Value>() {
+            Ok(value) if value["access_token"].is_string() => Ok(value),
+            _ => Err(gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{}: {}", ERROR_OAUTH2_TOKEN, ERROR_FAIL_RESPONSE_JSON),
+            )),
+        },
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+
+            csml_logger(
+                CsmlLog::new(
+                    None,
+                    Some(flow_name.to_string()),
+                    Some(interval.start_line),
+                    format!("OAuth2 token request failed: status {}", code),
+                ),
+                LogLvl::Error,
+            );
+
+            let mut error = gen_error_info(
+                Position::new(interval, flow_name),
+                format!("{}: status code {}", ERROR_OAUTH2_TOKEN, code),
+            );
+            error.add_info("status", PrimitiveInt::get_literal(code as i64, interval));
+            error.add_info("body", PrimitiveString::get_literal(&body, interval));
+
+            Err(error)
+        }
+        Err(err) => Err(gen_error_info(
+            Position::new(interval, flow_name),
+            format!("{}: {}", ERROR_OAUTH2_TOKEN, err),
+        )),
+    }
+}
+
+fn format_token(
+    response: serde_json::Value,
+    previous: Option<&serde_json::Value>,
+    now: i64,
+) -> (serde_json::Value, i64) {
+    let expires_in = response["expires_in"].as_i64();
+
+    let mut token = serde_json::json!({
+        "access_token": response["access_token"],
+        "token_type": response["token_type"].as_str().unwrap_or("Bearer"),
+    });
+
+    if let Some(expires_in) = expires_in {
+        token["expires_at"] = serde_json::json!(now + expires_in);
+    }
+    if let Some(scope) = response["scope"].as_str() {
+        token["scope"] = serde_json::json!(scope);
+    }
+
+    // some servers do not send the refresh_token back on refresh, keep the previous one
+    match (response.get("refresh_token"), previous) {
+        (Some(refresh_token), _) if refresh_token.is_string() => {
+            token["refresh_token"] = refresh_token.to_owned();
+        }
+        (_, Some(previous)) if previous["refresh_token"].is_string() => {
+            token["refresh_token"] = previous["refresh_token"].to_owned();
+        }
+        _ => {}
+    }
+
+    // without expiration information the token is cached for one hour
+    (token, expires_in.unwrap_or(3600))
+}
+
+fn token_to_literal(
+    token: &serde_json::Value,
+    token_url: &str,
+    interval: Interval,
+) -> Result<Literal, ErrorInfo> {
+    let mut map: HashMap<String, Literal> = HashMap::new();
+
+    map.insert(
+        "token_url".to_owned(),
+        PrimitiveString::get_literal(token_url, interval),
+    );
+
+    let mut access_token =
+        PrimitiveString::get_literal(token["access_token"].as_str().unwrap_or(""), interval);
+    access_token.secure_variable = true;
+    add_secret(token["access_token"].as_str().unwrap_or(""));
+    map.insert("access_token".to_owned(), access_token);
+
+    map.insert(
+        "token_type".to_owned(),
+        PrimitiveString::get_literal(token["token_type"].as_str().unwrap_or("Bearer"), interval),
+    );
+
+    if let Some(expires_at) = token["expires_at"].as_i64() {
+        map.insert(
+            "expires_at".to_owned(),
+            PrimitiveInt::get_literal(expires_at, interval),
+        );
+    }
+    if let Some(scope) = token["scope"].as_str() {
+        map.insert(
+            "scope".to_owned(),
+            PrimitiveString::get_literal(scope, interval),
+        );
+    }
+
+    let mut result = PrimitiveObject::get_literal(&map, interval);
+    result.set_content_type("oauth2");
+
+    Ok(result)
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// PUBLIC FUNCTIONS
+////////////////////////////////////////////////////////////////////////////////
+
+pub fn oauth2(
+    args: ArgsType,
+    interval: Interval,
+    data: &mut Data,
+    sender: &Option<mpsc::Sender<MSG>>,
+) -> Result<Literal, ErrorInfo> {
+    let flow_name = data.context.flow.to_owned();
+
+    let token_url = get_string_arg(&args, "token_url", 0, &flow_name, interval)?;
+    let client_id = get_string_arg(&args, "client_id", 1, &flow_name, interval)?;
+    let client_secret = get_string_arg(&args, "client_secret", 2, &flow_name, interval)?;
+    let scopes = get_scopes(&args, &flow_name, interval)?;
+    let auth_method = match args.get("auth_method", 4) {
+        Some(literal) => literal.primitive.to_string(),
+        None => "basic".to_owned(),
+    };
+
+    let now = chrono::Utc::now().timestamp();
+    let key = get_cache_key(&token_url, &client_id, &scopes);
+    let cached = get_cached_token(&key, sender);
+
+    if let Some(token) = &cached {
+        if is_token_valid(token, now) {
+            return token_to_literal(token, &token_url, interval);
+        }
+    }
+
+    // try to refresh the expired token first, fall back on a new client_credentials grant
+    let refreshed = match cached
+        .as_ref()
+        .and_then(|token| token["refresh_token"].as_str())
+    {
+        Some(refresh_token) => request_token(
+            &token_url,
+            &client_id,
+            &client_secret,
+            &auth_method,
+            &[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ],
+            &flow_name,
+            interval,
+        )
+        .ok(),
+        None => None,
+    };
+
+    let response = match refreshed {
+        Some(response) => response,
+        None => {
+            let mut params = vec![("grant_type", "client_credentials")];
+            if !scopes.is_empty() {
+                params.push(("scope", &scopes));
+            }
+
+            request_token(
+                &token_url,
+                &client_id,
+                &client_secret,
+                &auth_method,
+                &params,
+                &flow_name,
+                interval,
+            )?
+        }
+    };
+
+    let (token, expires_in) = format_token(response, cached.as_ref(), now);
+
+    MSG::send(
+        sender,
+        MSG::CacheToken {
+            key,
+            token: token.clone(),
+            expires_in,
+        },
+    );
+
+    token_to_literal(&token, &token_url, interval)
+}
@@ -56,8 +56,15 @@ fn interpret_function_scope(
             }
         };
 
-        if let Some(ExitCondition::Return(_)) = &message_data.exit_condition {
-            return Ok(message_data);
+        match &message_data.exit_condition {
+            Some(ExitCondition::Return(_)) => return Ok(message_data),
+            Some(ExitCondition::HoldInstruction) => {
+                return Err(gen_error_info(
+                    Position::new(interval_from_expr(action), &data.context.flow),
+                    ERROR_FORM_SCOPE.to_owned(),
+                ))
+            }
+            _ => {}
         }
     }
 
@@ -19,11 +19,12 @@ use crate::data::primitive::{
 use crate::data::{
     ast::{Expr, Function, GotoValueType, Identifier, Interval, PathLiteral, PathState},
     data::Data,
-    tokens::{COMPONENT, EVENT, _ENV, _MEMORY, _METADATA},
+    tokens::{COMPONENT, EVENT, _ENV, _MEMORY, _METADATA, _SWITCH},
     warnings::DisplayWarnings,
     ArgsType, Literal, MemoryType, MessageData, MSG,
 };
 use crate::error_format::*;
+use crate::interpreter::json_to_rust::json_to_literal;
 use crate::interpreter::variable_handler::{
     gen_literal::gen_literal_from_component,
     gen_literal::gen_literal_from_event,
@@ -505,6 +506,37 @@ fn get_flow_context(data: &mut Data, interval: Interval) -> HashMap<String, Lite
     flow_context
 }
 
+/**
+ * `_switch` holds the bot that switched to the current one and the payload it sent,
+ * or null if the conversation did not come from another bot.
+ */
+fn get_switch_literal(data: &Data, interval: Interval) -> Result<Literal, ErrorInfo> {
+    let previous_bot = match &data.context.previous_bot {
+        Some(previous_bot) => previous_bot,
+        None => return Ok(PrimitiveNull::get_literal(interval)),
+    };
+
+    let mut switch = HashMap::new();
+    switch.insert(
+        "bot".to_owned(),
+        PrimitiveString::get_literal(&previous_bot.bot, interval),
+    );
+    switch.insert(
+        "flow".to_owned(),
+        PrimitiveString::get_literal(&previous_bot.flow, interval),
+    );
+    switch.insert(
+        "step".to_owned(),
+        PrimitiveString::get_literal(&previous_bot.step, interval),
+    );
+    switch.insert(
+        "payload".to_owned(),
+        json_to_literal(&previous_bot.payload, interval, &data.context.flow)?,
+    );
+
+    Ok(PrimitiveObject::get_literal(&switch, interval))
+}
+
 pub fn get_metadata_context_literal(
     path: &[(Interval, PathLiteral)],
     interval: Interval,
@@ -648,6 +680,30 @@ pub fn get_var(
                 Ok(PrimitiveObject::get_literal(&metadata, interval.to_owned()))
             }
         },
+        name if name == _SWITCH => {
+            let mut lit = get_switch_literal(data, *interval)?;
+
+            match path {
+                Some(path) => {
+                    let path = resolve_path(path, dis_warnings, data, msg_data, sender)?;
+                    let content_type = ContentType::get(&lit);
+                    let (lit, _tmp_mem_update) = exec_path_actions(
+                        &mut lit,
+                        dis_warnings,
+                        &MemoryType::Constant,
+                        None,
+                        &Some(path),
+                        &content_type,
+                        data,
+                        msg_data,
+                        sender,
+                    )?;
+
+                    Ok(lit)
+                }
+                None => Ok(lit),
+            }
+        }
         name if name == _MEMORY => {
             let memory: HashMap<String, Literal> = data.get_all_memories();
             let mut lit = PrimitiveObject::get_literal(&memory, var.interval);
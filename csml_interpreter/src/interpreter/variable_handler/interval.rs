@@ -47,8 +47,8 @@ pub fn interval_from_reserved_fn(reserved_fn: &ObjectType) -> Interval {
         ObjectType::Assign(_assign, ident, ..) => interval_from_expr(ident),
         ObjectType::As(ident, ..) => ident.interval.to_owned(),
         ObjectType::BuiltIn(Function { interval, .. }) => interval.to_owned(),
-        ObjectType::Hold(interval) => interval.to_owned(),
-        ObjectType::HoldSecure(interval) => interval.to_owned(),
+        ObjectType::Hold(_, interval) => interval.to_owned(),
+        ObjectType::HoldSecure(_, interval) => interval.to_owned(),
         ObjectType::Break(interval) => interval.to_owned(),
         ObjectType::Continue(interval) => interval.to_owned(),
     }
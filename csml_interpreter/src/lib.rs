@@ -6,6 +6,8 @@ pub mod linter;
 pub mod parser;
 
 pub use data::csml_logs;
+pub use data::csml_metrics;
+pub use data::csml_tracing;
 pub use interpreter::components::load_components;
 pub use parser::step_checksum::get_step;
 
@@ -35,23 +37,45 @@ use std::sync::mpsc;
 // PRIVATE FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////
 
+/**
+ * Errors are rare enough to be counted per bot, flow and step
+ */
+fn count_step_error(bot_id: &str, flow: &str, step: &str) {
+    csml_metrics::increment_counter(
+        "csml_interpreter_errors_total",
+        &[("bot_id", bot_id), ("flow", flow), ("step", step)],
+    );
+}
+
 fn execute_step(
     step: &str,
     flow: &Flow,
     mut data: &mut Data,
+    bot_id: &str,
     sender: &Option<mpsc::Sender<MSG>>,
 ) -> MessageData {
+    let mut span = csml_tracing::Span::start(
+        "execute_step",
+        vec![
+            ("bot_id", bot_id.to_owned()),
+            ("flow", data.context.flow.to_owned()),
+            ("step", step.to_owned()),
+        ],
+    );
+
     // stop execution if step_count >= STEP_LIMIT in order to avoid infinite loops
     if *data.step_count >= data.step_limit {
-        let msg_data = Err(gen_error_info(
+        let error = gen_error_info(
             Position::new(
                 Interval::new_as_u32(0, 0, 0, None, None),
                 &data.context.flow,
             ),
             format!("{}, stop at step {}", ERROR_STEP_LIMIT, step),
-        ));
+        );
 
-        return MessageData::error_to_message(msg_data, sender);
+        count_step_error(bot_id, &data.context.flow, step);
+        span.set_error(error.message.to_owned());
+        return MessageData::error_to_message(Err(error), sender);
     }
 
     let mut msg_data = match flow
@@ -71,6 +95,11 @@ fn execute_step(
         )),
     };
 
+    if let Err(err) = &msg_data {
+        count_step_error(bot_id, &data.context.flow, step);
+        span.set_error(err.message.to_owned());
+    }
+
     if let Ok(msg_data) = &mut msg_data {
         match &mut msg_data.exit_condition {
             Some(condition) if *condition == ExitCondition::Goto => {
@@ -446,6 +475,13 @@ pub fn interpret(
     sender: Option<mpsc::Sender<MSG>>,
 ) -> MessageData {
     csml_logs::init_logger();
+    let _timer = csml_metrics::Timer::new("csml_interpreter_duration_seconds", vec![]);
+
+    // the secrets added while interpreting are removed from the logs of the request
+    let _log_context = csml_logs::enter_log_context(csml_logs::get_log_context());
+    if event.secure {
+        csml_logs::add_secret(&event.content_value);
+    }
 
     let mut msg_data = MessageData::default();
 
@@ -519,9 +555,10 @@ pub fn interpret(
 
         msg_data = match inserted_ast {
             Some(inserted_ast) => {
-                msg_data + execute_step(&step.get_step(), &inserted_ast, &mut data, &sender)
+                msg_data
+                    + execute_step(&step.get_step(), &inserted_ast, &mut data, &bot.id, &sender)
             }
-            None => msg_data + execute_step(&step.get_step(), &ast, &mut data, &sender),
+            None => msg_data + execute_step(&step.get_step(), &ast, &mut data, &bot.id, &sender),
         };
 
         previous_info = data.previous_info.clone();
@@ -532,5 +569,7 @@ pub fn interpret(
         step_vars = HashMap::new();
     }
 
+    csml_metrics::add_counter("csml_interpreter_steps_total", &[], step_count as u64);
+
     msg_data
 }
@@ -692,6 +692,7 @@ fn validate_scope(
                         step: Some(GotoValueType::Name(step)),
                         flow: Some(GotoValueType::Name(flow)),
                         bot: None,
+                        ..
                     } => {
                         register_flow_breaker(
                             step_breakers,
@@ -715,6 +716,7 @@ fn validate_scope(
                         step: None,
                         flow: Some(GotoValueType::Name(flow)),
                         bot: None,
+                        ..
                     } => {
                         register_flow_breaker(
                             step_breakers,
@@ -738,6 +740,7 @@ fn validate_scope(
                         step: Some(GotoValueType::Name(step)),
                         flow: None,
                         bot: None,
+                        ..
                     } => {
                         register_flow_breaker(
                             step_breakers,
@@ -786,7 +789,7 @@ fn validate_scope(
                 }
             }
 
-            Expr::ObjectExpr(ObjectType::Hold(interval)) => {
+            Expr::ObjectExpr(ObjectType::Hold(_, interval)) => {
                 register_flow_breaker(step_breakers, StepBreakers::HOLD(interval.clone()));
 
                 if state.in_function > 0 {
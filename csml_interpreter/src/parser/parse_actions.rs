@@ -11,7 +11,7 @@ use crate::parser::{
     parse_if::parse_if,
     parse_path::parse_path,
     parse_previous::parse_previous,
-    parse_var_types::parse_r_bracket,
+    parse_var_types::{parse_expr_list, parse_r_bracket},
     parse_while_loop::parse_while,
     tools::{get_interval, get_string, get_tag},
 };
@@ -19,7 +19,7 @@ use crate::parser::{
 use nom::{
     branch::alt,
     bytes::complete::{tag, take_while1},
-    combinator::opt,
+    combinator::{opt, peek},
     error::{ContextError, ErrorKind, ParseError},
     multi::separated_list0,
     sequence::{preceded, terminated, tuple},
@@ -348,6 +348,16 @@ where
     Ok((s, Expr::ObjectExpr(ObjectType::Use(Box::new(expr)))))
 }
 
+// hold(timeout = "10m", on_timeout = "step"), the options must directly follow the keyword
+fn parse_hold_options<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Option<Box<Expr>>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, options) = opt(preceded(peek(tag(L_PAREN)), parse_expr_list))(s)?;
+
+    Ok((s, options.map(Box::new)))
+}
+
 fn parse_hold<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
@@ -356,8 +366,9 @@ where
     let (s, name) = get_string(s)?;
 
     let (s, ..) = get_tag(name, HOLD)(s)?;
+    let (s, options) = parse_hold_options(s)?;
 
-    Ok((s, Expr::ObjectExpr(ObjectType::Hold(inter))))
+    Ok((s, Expr::ObjectExpr(ObjectType::Hold(options, inter))))
 }
 
 fn parse_hold_secure<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
@@ -368,8 +379,9 @@ where
     let (s, name) = get_string(s)?;
 
     let (s, ..) = get_tag(name, HOLD_SECURE)(s)?;
+    let (s, options) = parse_hold_options(s)?;
 
-    Ok((s, Expr::ObjectExpr(ObjectType::HoldSecure(inter))))
+    Ok((s, Expr::ObjectExpr(ObjectType::HoldSecure(options, inter))))
 }
 
 fn parse_break<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
@@ -1,9 +1,10 @@
 use crate::data::{ast::*, tokens::*};
 use crate::error_format::{gen_nom_failure, ERROR_GOTO_STEP};
 use crate::parser::{
-    get_interval, parse_comments::comment, parse_idents::parse_string_assignation,
-    parse_path::parse_path, parse_var_types::parse_idents_expr_usage, tools::get_string,
-    tools::get_tag, GotoType, GotoValueType,
+    get_interval, operator::parse_operator, parse_comments::comment,
+    parse_idents::parse_string_assignation, parse_path::parse_path,
+    parse_var_types::parse_idents_expr_usage, tools::get_string, tools::get_tag, GotoType,
+    GotoValueType,
 };
 
 use nom::{branch::alt, bytes::complete::tag, combinator::opt, error::*, sequence::preceded, *};
@@ -92,13 +93,39 @@ where
         return Err(gen_nom_failure(s, ERROR_GOTO_STEP));
     }
 
-    Ok((s, GotoType::StepFlow { step, flow, bot }))
+    // a payload can only be sent to another bot
+    let (s, payload) = match bot {
+        Some(_) => opt(parse_with_payload)(s)?,
+        None => (s, None),
+    };
+
+    Ok((
+        s,
+        GotoType::StepFlow {
+            step,
+            flow,
+            bot,
+            payload,
+        },
+    ))
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 // PUBLIC FUNCTION
 ////////////////////////////////////////////////////////////////////////////////
 
+pub fn parse_with_payload<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Box<Expr>, E>
+where
+    E: ParseError<Span<'a>> + ContextError<Span<'a>>,
+{
+    let (s, name) = preceded(comment, get_string)(s)?;
+    let (s, ..) = get_tag(name, WITH)(s)?;
+
+    let (s, payload) = preceded(comment, parse_operator)(s)?;
+
+    Ok((s, Box::new(payload)))
+}
+
 pub fn parse_goto<'a, E>(s: Span<'a>) -> IResult<Span<'a>, Expr, E>
 where
     E: ParseError<Span<'a>> + ContextError<Span<'a>>,
@@ -1,7 +1,10 @@
 use crate::data::{ast::*, tokens::*};
-use crate::parser::{get_interval, parse_comments::comment, tools::get_string, tools::get_tag};
+use crate::parser::{
+    get_interval, parse_comments::comment, parse_goto::parse_with_payload, tools::get_string,
+    tools::get_tag,
+};
 
-use nom::{error::*, sequence::preceded, *};
+use nom::{combinator::opt, error::*, sequence::preceded, *};
 
 fn get_previous<I, E: ParseError<I>>(
     var: String,
@@ -12,6 +15,8 @@ fn get_previous<I, E: ParseError<I>>(
             Ok((input, PreviousType::Flow(interval)))
         } else if var == STEP {
             Ok((input, PreviousType::Step(interval)))
+        } else if var == BOT {
+            Ok((input, PreviousType::Bot(None, interval)))
         } else {
             Err(Err::Error(E::from_error_kind(input, ErrorKind::Tag)))
         }
@@ -30,6 +35,15 @@ where
     let (s, name) = get_string(s)?;
     let (s, previous) = get_previous(name, inter)(s)?;
 
+    // going back to the previous bot can send a payload as well
+    let (s, previous) = match previous {
+        PreviousType::Bot(_, interval) => {
+            let (s, payload) = opt(parse_with_payload)(s)?;
+            (s, PreviousType::Bot(payload, interval))
+        }
+        previous => (s, previous),
+    };
+
     Ok((
         s,
         Expr::ObjectExpr(ObjectType::Previous(previous, interval)),
@@ -12,6 +12,8 @@ pub enum ExitCondition {
     Break,
     Continue,
     Hold,
+    // a builtin asks to hold on the instruction currently executed
+    HoldInstruction,
     Return(Literal),
 }
 
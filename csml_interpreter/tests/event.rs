@@ -352,6 +352,39 @@ fn event_step_9() {
     assert_eq!(v1, v2)
 }
 
+#[test]
+fn event_location() {
+    let data = r#"{
+        "memories":[
+        ],
+        "messages":[
+            {"content":{"text": "48.85 2.35 Paris"}, "content_type":"text"},
+            {"content":{"text": "48.85,2.35"}, "content_type":"text"}
+        ]}"#;
+    let msg = format_message(
+        Event::new(
+            "location",
+            "48.85,2.35",
+            serde_json::json!({"latitude": 48.85, "longitude": 2.35, "name": "Paris"}),
+        ),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "location",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/event.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
 #[test]
 fn event_types() {
     let context = Context::new(
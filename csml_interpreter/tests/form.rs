@@ -0,0 +1,178 @@
+mod support;
+
+use csml_interpreter::data::event::Event;
+use csml_interpreter::data::hold::{Hold, IndexInfo};
+use csml_interpreter::data::Context;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn form_context(form_state: Value) -> Context {
+    hold_context("start", "_form#2:15", form_state)
+}
+
+fn hold_context(step: &str, key: &str, form_state: Value) -> Context {
+    Context::new(
+        HashMap::new(),
+        HashMap::new(),
+        None,
+        Some(Hold::new(
+            IndexInfo {
+                command_index: 0,
+                loop_index: vec![],
+            },
+            serde_json::json!({ key: form_state }),
+            step.to_owned(),
+            "flow".to_owned(),
+            None,
+            false,
+        )),
+        step,
+        "flow",
+        None,
+    )
+}
+
+#[test]
+fn form_first_prompt() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"What is your name?"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_next_field() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"What is your email?"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "John", serde_json::json!({"text": "John"})),
+        form_context(serde_json::json!({"field": 0, "retries": 0, "values": {}})),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_retry_prompt() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"This email is not valid"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "john", serde_json::json!({"text": "john"})),
+        form_context(serde_json::json!({"field": 1, "retries": 0, "values": {"name": "John"}})),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_max_retries() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"Your promo code?"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "john", serde_json::json!({"text": "john"})),
+        form_context(serde_json::json!({"field": 1, "retries": 1, "values": {"name": "John"}})),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_filled() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"John john@csml.dev CSML"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "CSML", serde_json::json!({"text": "CSML"})),
+        form_context(serde_json::json!({
+            "field": 2,
+            "retries": 0,
+            "values": {"name": "John", "email": "john@csml.dev"}
+        })),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_state_of_another_form() {
+    // the state of another form of the step does not resume this one
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"What is your name?"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "John", serde_json::json!({"text": "John"})),
+        hold_context(
+            "start",
+            "_form#42:15",
+            serde_json::json!({"field": 1, "retries": 0, "values": {"name": "Jane"}}),
+        ),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn form_failed_field() {
+    let data = r#"{"memories":[], "messages":[
+        {"content":{"text":"true true"}, "content_type":"text"},
+        {"content":{"text":"Form field has no valid answer after its max_retries [age]"}, "content_type":"text"}
+    ]}"#;
+    let msg = format_message(
+        Event::new("text", "old", serde_json::json!({"text": "old"})),
+        hold_context(
+            "failed_field",
+            "_form#23:15",
+            serde_json::json!({"field": 0, "retries": 0, "values": {}}),
+        ),
+        "CSML/basic_test/built-in/form.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
@@ -0,0 +1,74 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn graphql_invalid_url() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/graphql.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn graphql_invalid_query() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "step_0",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/graphql.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn graphql_set_header() {
+    let data = r#"{"messages":[
+        {"content":{ "text": "post" },"content_type":"text"},
+        {"content":{ "text": "Bearer token" },"content_type":"text"}
+    ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "step_1",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/graphql.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
@@ -1,10 +1,11 @@
 mod support;
 
 use csml_interpreter::data::event::Event;
-use csml_interpreter::data::hold::{Hold, IndexInfo};
+use csml_interpreter::data::hold::{parse_timeout_duration, Hold, IndexInfo};
 use csml_interpreter::data::Context;
 use std::collections::HashMap;
 
+use crate::support::tools::format_holds;
 use crate::support::tools::format_message;
 use crate::support::tools::message_to_json_value;
 
@@ -348,3 +349,77 @@ fn hold_test_step_4_ok() {
 
     assert_eq!(v1, v2)
 }
+
+#[test]
+fn hold_timeout_ok() {
+    let data = r#"{"memories":[], "messages":[{"content":{"text":"OK"}, "content_type":"text"}] }"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            Some(Hold::new(
+                IndexInfo {
+                    command_index: 1,
+                    loop_index: vec![],
+                },
+                serde_json::json!({}),
+                "".to_owned(),
+                "".to_owned(),
+                None,
+                false,
+            )),
+            "hold_timeout_ok",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/hold.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn hold_timeout_duration() {
+    assert_eq!(parse_timeout_duration("45"), Some(45));
+    assert_eq!(parse_timeout_duration("30s"), Some(30));
+    assert_eq!(parse_timeout_duration("10m"), Some(600));
+    assert_eq!(parse_timeout_duration("2h"), Some(7200));
+    assert_eq!(parse_timeout_duration("1d"), Some(86400));
+    assert_eq!(parse_timeout_duration("0"), None);
+    assert_eq!(parse_timeout_duration("10y"), None);
+    assert_eq!(parse_timeout_duration("soon"), None);
+    assert_eq!(parse_timeout_duration("-5m"), None);
+    assert_eq!(parse_timeout_duration("9223372036854775807"), None);
+    assert_eq!(parse_timeout_duration("9223372036854775807d"), None);
+    assert_eq!(parse_timeout_duration("153722867280912931m"), None);
+}
+
+#[test]
+fn hold_timeout_saved() {
+    let holds = format_holds(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "hold_timeout_ok",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/hold.csml",
+    );
+
+    assert_eq!(holds.len(), 1);
+    let hold = &holds[0];
+    let timeout = hold.timeout.as_ref().expect("the hold should have a timeout");
+
+    assert_eq!(hold.index.command_index, 1);
+    assert_eq!(timeout.duration, 600);
+    assert_eq!(timeout.step, "hold_4_ok");
+}
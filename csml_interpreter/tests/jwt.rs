@@ -0,0 +1,92 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn jwt_verify_jwks() {
+    let data = r#"{"memories":[], "messages":[ {"content":{"text": "csml"},"content_type":"text"}  ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/jwt.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn jwt_verify_jwks_with_algo() {
+    let data = r#"{"memories":[], "messages":[ {"content":{"text": "new-key"},"content_type":"text"}  ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "verify_with_algo",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/jwt.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+fn jwt_error(step: &str) -> String {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            step,
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/jwt.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+
+    assert_eq!(v1["messages"][0]["content_type"], "error");
+    v1["messages"][0]["content"]["error"]
+        .as_str()
+        .unwrap()
+        .to_owned()
+}
+
+#[test]
+fn jwt_verify_jwks_nbf() {
+    // the token is not valid before 2099
+    assert!(jwt_error("verify_nbf").starts_with("Invalid JWT verify ImmatureSignature"));
+}
+
+#[test]
+fn jwt_verify_jwks_unknown_kid() {
+    assert!(jwt_error("verify_unknown_kid")
+        .starts_with("No key in the JWKS matches the 'kid' of the token header"));
+}
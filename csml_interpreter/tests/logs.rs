@@ -0,0 +1,89 @@
+use csml_interpreter::csml_logs::{
+    add_redactor, add_secret, enter_log_context, get_log_context, redact, set_conversation_id,
+    LogContext,
+};
+use csml_interpreter::data::Client;
+
+#[test]
+fn ok_redact_credentials() {
+    assert_eq!(
+        redact("request headers: [authorization: Bearer eyJhbGciOi.abc, accept: */*]"),
+        "request headers: [authorization: [REDACTED], accept: */*]"
+    );
+    assert_eq!(
+        redact(r#"{"username": "john", "password": "hunter2"}"#),
+        r#"{"username": "john", "password": "[REDACTED]"}"#
+    );
+    assert_eq!(
+        redact("token_url: https://auth.csml.dev"),
+        "token_url: https://auth.csml.dev"
+    );
+}
+
+#[test]
+fn ok_redact_secrets() {
+    {
+        let _log_context = enter_log_context(LogContext::default());
+        add_secret("4242 4242 4242 4242");
+
+        assert_eq!(
+            redact("event received: 4242 4242 4242 4242"),
+            "event received: [REDACTED]"
+        );
+    }
+
+    // the secrets are removed with the log context
+    assert_eq!(
+        redact("event received: 4242 4242 4242 4242"),
+        "event received: 4242 4242 4242 4242"
+    );
+}
+
+#[test]
+fn ok_redact_secrets_of_other_threads() {
+    let _log_context = enter_log_context(LogContext::default());
+
+    // the secrets added by the interpreter thread are redacted in the logs of the request
+    let log_context = get_log_context();
+    std::thread::spawn(move || {
+        let _log_context = enter_log_context(log_context);
+        add_secret("access-token-42");
+    })
+    .join()
+    .unwrap();
+
+    assert_eq!(
+        redact("token refreshed: access-token-42"),
+        "token refreshed: [REDACTED]"
+    );
+}
+
+#[test]
+fn ok_redactor() {
+    add_redactor(Box::new(|message| message.replace("ssn-123-45", "[SSN]")));
+
+    assert_eq!(redact("user ssn-123-45"), "user [SSN]");
+}
+
+#[test]
+fn ok_log_context() {
+    {
+        let _log_context = enter_log_context(LogContext {
+            request_id: Some("request_id".to_owned()),
+            client: Some(Client::new(
+                "bot_id".to_owned(),
+                "channel_id".to_owned(),
+                "user_id".to_owned(),
+            )),
+            ..Default::default()
+        });
+        set_conversation_id("conversation_id");
+
+        let context = get_log_context();
+        assert_eq!(context.request_id.as_deref(), Some("request_id"));
+        assert_eq!(context.conversation_id.as_deref(), Some("conversation_id"));
+        assert_eq!(context.client.unwrap().bot_id, "bot_id");
+    }
+
+    assert!(get_log_context().request_id.is_none());
+}
@@ -0,0 +1,86 @@
+mod support;
+
+use csml_interpreter::csml_metrics::{self, MetricsRecorder, Registry};
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+
+fn run_step(step: &str) {
+    format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            step,
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/numerical_operation/addition.csml",
+    );
+}
+
+#[test]
+fn ok_interpreter_metrics() {
+    let registry = csml_metrics::registry();
+    let steps = registry.get_counter("csml_interpreter_steps_total", &[]);
+
+    run_step("start");
+
+    assert!(registry.get_counter("csml_interpreter_steps_total", &[]) > steps);
+
+    let duration = registry
+        .get_histogram("csml_interpreter_duration_seconds", &[])
+        .unwrap();
+    assert!(duration.count >= 1);
+}
+
+#[test]
+fn ok_interpreter_error_metrics() {
+    let labels = [
+        ("bot_id", "id"),
+        ("flow", "flow"),
+        ("step", "metrics_unknown_step"),
+    ];
+    let registry = csml_metrics::registry();
+    let errors = registry.get_counter("csml_interpreter_errors_total", &labels);
+
+    run_step("metrics_unknown_step");
+
+    assert_eq!(
+        registry.get_counter("csml_interpreter_errors_total", &labels),
+        errors + 1
+    );
+}
+
+#[test]
+fn ok_render_metrics() {
+    let registry = Registry::default();
+
+    registry.increment_counter(
+        "requests_total",
+        &[("route", "/run"), ("method", "POST")],
+        2,
+    );
+    registry.increment_counter(
+        "requests_total",
+        &[("method", "POST"), ("route", "/run")],
+        1,
+    );
+    registry.observe_histogram("duration_seconds", &[], 0.2);
+    registry.observe_histogram("duration_seconds", &[], 3.0);
+
+    let output = registry.render();
+
+    assert!(output.contains("# TYPE requests_total counter\n"));
+    assert!(output.contains("requests_total{method=\"POST\",route=\"/run\"} 3\n"));
+    assert!(output.contains("# TYPE duration_seconds histogram\n"));
+    assert!(output.contains("duration_seconds_bucket{le=\"0.1\"} 0\n"));
+    assert!(output.contains("duration_seconds_bucket{le=\"0.25\"} 1\n"));
+    assert!(output.contains("duration_seconds_bucket{le=\"+Inf\"} 2\n"));
+    assert!(output.contains("duration_seconds_sum 3.2\n"));
+    assert!(output.contains("duration_seconds_count 2\n"));
+}
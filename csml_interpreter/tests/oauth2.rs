@@ -0,0 +1,283 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::{event::Event, primitive::PrimitiveString, Interval};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::mpsc;
+use std::thread;
+
+use crate::support::tools::{format_message, format_oauth2};
+
+/**
+ * Token endpoint answering the client_credentials grant with a new token,
+ * and the refresh_token grant with `refresh_status`. The form of each request is sent back.
+ */
+fn token_server(refresh_status: u16) -> (String, mpsc::Receiver<String>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/token", listener.local_addr().unwrap());
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+
+            let mut content_length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                }
+            }
+
+            let mut form = vec![0; content_length];
+            reader.read_exact(&mut form).unwrap();
+            let form = String::from_utf8(form).unwrap();
+
+            let (status, body) = match form.contains("grant_type=refresh_token") {
+                true if refresh_status == 200 => (
+                    200,
+                    json!({"access_token": "refreshed_token", "expires_in": 60}),
+                ),
+                true => (refresh_status, json!({"error": "invalid_grant"})),
+                false => (
+                    200,
+                    json!({"access_token": "new_token", "token_type": "bearer", "expires_in": 3600}),
+                ),
+            };
+            sender.send(form).unwrap();
+
+            let body = body.to_string();
+            write!(
+                stream,
+                "HTTP/1.1 {} OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+    });
+
+    (url, receiver)
+}
+
+fn get_context(step: &str, token_url: &str) -> Context {
+    let mut metadata = HashMap::new();
+    metadata.insert(
+        "token_url".to_owned(),
+        PrimitiveString::get_literal(token_url, Interval::default()),
+    );
+
+    Context::new(HashMap::new(), metadata, None, None, step, "flow", None)
+}
+
+fn get_texts(msg: &csml_interpreter::data::message_data::MessageData) -> Vec<Value> {
+    msg.messages
+        .iter()
+        .map(|message| message.content["text"].to_owned())
+        .collect()
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+#[test]
+fn oauth2_missing_secret() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/oauth2.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn oauth2_invalid_scopes() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "step_0",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/oauth2.csml",
+    );
+
+    assert_eq!(msg.messages[0].content_type, "error")
+}
+
+#[test]
+fn oauth2_new_token() {
+    let (token_url, requests) = token_server(200);
+
+    let (msg, tokens) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("token", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        None,
+    );
+
+    let form = requests.try_recv().unwrap();
+    assert!(form.contains("grant_type=client_credentials"));
+    assert!(form.contains("scope=read+write"));
+
+    // token_type is given as sent by the server, the token is cached for expires_in
+    let texts = get_texts(&msg);
+    assert_eq!(texts[0], json!("bearer"));
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].0["access_token"], json!("new_token"));
+    assert_eq!(tokens[0].1, 3600);
+
+    let expires_at = tokens[0].0["expires_at"].as_i64().unwrap();
+    assert!((expires_at - now() - 3600).abs() <= 5);
+    assert_eq!(texts[1], json!(expires_at.to_string()));
+}
+
+#[test]
+fn oauth2_cached_token() {
+    let (token_url, requests) = token_server(200);
+    let expires_at = now() + 600;
+
+    let (msg, tokens) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("token", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        Some(
+            json!({"access_token": "cached_token", "token_type": "Bearer", "expires_at": expires_at}),
+        ),
+    );
+
+    // a valid cached token is used without requesting the server
+    assert!(requests.try_recv().is_err());
+    assert!(tokens.is_empty());
+    assert_eq!(
+        get_texts(&msg),
+        vec![json!("Bearer"), json!(expires_at.to_string())]
+    );
+}
+
+#[test]
+fn oauth2_expired_token() {
+    let (token_url, requests) = token_server(200);
+
+    // tokens are renewed shortly before they actually expire
+    let (_, tokens) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("token", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        Some(
+            json!({"access_token": "cached_token", "token_type": "Bearer", "expires_at": now() + 10}),
+        ),
+    );
+
+    assert!(requests
+        .try_recv()
+        .unwrap()
+        .contains("grant_type=client_credentials"));
+    assert_eq!(tokens[0].0["access_token"], json!("new_token"));
+}
+
+#[test]
+fn oauth2_refresh_token() {
+    let (token_url, requests) = token_server(200);
+
+    let (_, tokens) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("token", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        Some(json!({
+            "access_token": "cached_token",
+            "token_type": "Bearer",
+            "expires_at": now() - 10,
+            "refresh_token": "refresh_token"
+        })),
+    );
+
+    let form = requests.try_recv().unwrap();
+    assert!(form.contains("grant_type=refresh_token"));
+    assert!(form.contains("refresh_token=refresh_token"));
+    assert!(requests.try_recv().is_err());
+
+    // the server did not send a new refresh_token, the previous one is kept
+    assert_eq!(tokens[0].0["access_token"], json!("refreshed_token"));
+    assert_eq!(tokens[0].0["token_type"], json!("Bearer"));
+    assert_eq!(tokens[0].0["refresh_token"], json!("refresh_token"));
+    assert_eq!(tokens[0].1, 60);
+}
+
+#[test]
+fn oauth2_refresh_token_fallback() {
+    let (token_url, requests) = token_server(400);
+
+    let (msg, tokens) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("token", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        Some(json!({
+            "access_token": "cached_token",
+            "token_type": "Bearer",
+            "expires_at": now() - 10,
+            "refresh_token": "refresh_token"
+        })),
+    );
+
+    // a refused refresh falls back on a new client_credentials grant
+    assert!(requests
+        .try_recv()
+        .unwrap()
+        .contains("grant_type=refresh_token"));
+    assert!(requests
+        .try_recv()
+        .unwrap()
+        .contains("grant_type=client_credentials"));
+    assert_eq!(get_texts(&msg)[0], json!("bearer"));
+    assert_eq!(tokens[0].0["access_token"], json!("new_token"));
+    assert_eq!(tokens[0].0["refresh_token"], json!("refresh_token"));
+}
+
+#[test]
+fn oauth2_http() {
+    let (token_url, _requests) = token_server(200);
+
+    let (msg, _) = format_oauth2(
+        Event::new("payload", "", json!({})),
+        get_context("http", &token_url),
+        "CSML/basic_test/built-in/oauth2.csml",
+        None,
+    );
+
+    // the Authorization header holds the access token and can not be displayed
+    assert_eq!(
+        msg.messages[0].content["text"],
+        json!("https://api.example.com/users")
+    );
+    assert_eq!(msg.messages[1].content_type, "error");
+    assert!(msg.messages[1].content["error"]
+        .as_str()
+        .unwrap()
+        .starts_with("Secure variable can not be displayed"));
+}
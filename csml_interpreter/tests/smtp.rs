@@ -0,0 +1,67 @@
+mod support;
+
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+#[test]
+fn smtp_dry_run() {
+    let data = r#"{
+        "memories":[],
+        "messages":[
+            {"content":{"text": "true"}, "content_type":"text"},
+            {"content":{"text": "true"}, "content_type":"text"},
+            {"content":{"text": "true"}, "content_type":"text"},
+            {"content":{"text": "true"}, "content_type":"text"},
+            {"content":{"text": "true"}, "content_type":"text"},
+            {"content":{"text": "true"}, "content_type":"text"}
+        ]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/smtp.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn smtp_reserved_header() {
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "reserved_header",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/built-in/smtp.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+
+    assert_eq!(v1["messages"][0]["content_type"], "error");
+    assert_eq!(
+        v1["messages"][0]["content"]["error"],
+        "email header [Bcc] can not be set in headers, use the email parameter instead at line 14, column 42 at flow [flow]"
+    );
+}
@@ -2,12 +2,14 @@ use csml_interpreter::data::csml_bot::CsmlBot;
 use csml_interpreter::data::csml_flow::CsmlFlow;
 use csml_interpreter::data::event::Event;
 use csml_interpreter::data::message_data::MessageData;
-use csml_interpreter::data::Context;
+use csml_interpreter::data::{Context, Hold, MSG};
 use csml_interpreter::{interpret, load_components};
 use serde_json::{json, map::Map, Value};
 
 use std::fs::File;
 use std::io::prelude::*;
+use std::sync::mpsc;
+use std::thread;
 
 ////////////////////////////////////////////////////////////////////////////////
 /// PUBLIC FUNCTIONS
@@ -47,6 +49,88 @@ pub fn format_message(event: Event, context: Context, filepath: &str) -> Message
     interpret(bot, context, event, None)
 }
 
+#[allow(dead_code)]
+pub fn format_holds(event: Event, context: Context, filepath: &str) -> Vec<Hold> {
+    let content = read_file(filepath.to_string()).unwrap();
+
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+    let native_component = load_components().unwrap();
+
+    let bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        Some(native_component),
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let (sender, receiver) = mpsc::channel::<MSG>();
+    interpret(bot, context, event, Some(sender));
+
+    receiver
+        .try_iter()
+        .filter_map(|msg| match msg {
+            MSG::Hold(hold) => Some(hold),
+            _ => None,
+        })
+        .collect()
+}
+
+/**
+ * Interpret the flow with `cached` as the bot token cache,
+ * returns the messages and the tokens saved in the cache with their duration.
+ */
+#[allow(dead_code)]
+pub fn format_oauth2(
+    event: Event,
+    context: Context,
+    filepath: &str,
+    cached: Option<Value>,
+) -> (MessageData, Vec<(Value, i64)>) {
+    let content = read_file(filepath.to_string()).unwrap();
+
+    let flow = CsmlFlow::new("id", "flow", &content, Vec::default());
+    let native_component = load_components().unwrap();
+
+    let bot = CsmlBot::new(
+        "id",
+        "bot",
+        None,
+        vec![flow],
+        Some(native_component),
+        None,
+        "flow",
+        None,
+        None,
+        None,
+        None,
+        None,
+    );
+
+    let (sender, receiver) = mpsc::channel::<MSG>();
+    let interpreter = thread::spawn(move || interpret(bot, context, event, Some(sender)));
+
+    let mut tokens = vec![];
+    for msg in receiver {
+        match msg {
+            MSG::GetCachedToken { reply, .. } => reply.send(cached.clone()).unwrap(),
+            MSG::CacheToken {
+                token, expires_in, ..
+            } => tokens.push((token, expires_in)),
+            _ => {}
+        }
+    }
+
+    (interpreter.join().unwrap(), tokens)
+}
+
 #[allow(dead_code)]
 pub fn message_to_json_value(result: MessageData) -> Value {
     let mut message: Map<String, Value> = Map::new();
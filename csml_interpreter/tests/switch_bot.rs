@@ -0,0 +1,92 @@
+mod support;
+
+use csml_interpreter::data::context::{Context, PreviousBot};
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+
+use crate::support::tools::format_message;
+use crate::support::tools::message_to_json_value;
+
+use serde_json::Value;
+
+fn previous_bot() -> Option<PreviousBot> {
+    Some(PreviousBot {
+        bot: "support".to_owned(),
+        flow: "Default".to_owned(),
+        step: "ask".to_owned(),
+        payload: serde_json::json!({"email": "john@doe.com"}),
+    })
+}
+
+////////////////////////////////////////////////////////////////////////////////
+/// VALID SYNTAX
+////////////////////////////////////////////////////////////////////////////////
+
+#[test]
+fn ok_switch_payload() {
+    let data = r#"{"messages":[ {"content":{"text": "support Default ask"},"content_type":"text"}, {"content":{"text": "john@doe.com"},"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "start",
+            "flow",
+            previous_bot(),
+        ),
+        "CSML/basic_test/switch_bot.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_no_switch() {
+    let data = r#"{"messages":[ {"content":{"text": "no switch"},"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "no_switch",
+            "flow",
+            None,
+        ),
+        "CSML/basic_test/switch_bot.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
+
+#[test]
+fn ok_previous_bot() {
+    let data = r#"{"messages":[ {"content":{"text": "back to the previous bot"},"content_type":"text"} ],"memories":[]}"#;
+    let msg = format_message(
+        Event::new("payload", "", serde_json::json!({})),
+        Context::new(
+            HashMap::new(),
+            HashMap::new(),
+            None,
+            None,
+            "previous_bot",
+            "flow",
+            previous_bot(),
+        ),
+        "CSML/basic_test/switch_bot.csml",
+    );
+
+    let v1: Value = message_to_json_value(msg);
+    let v2: Value = serde_json::from_str(data).unwrap();
+
+    assert_eq!(v1, v2)
+}
@@ -0,0 +1,139 @@
+mod support;
+
+use csml_interpreter::csml_tracing::{self, Span, SpanContext, SpanData, SpanExporter};
+use csml_interpreter::data::context::Context;
+use csml_interpreter::data::event::Event;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::support::tools::format_message;
+
+static SPANS: Mutex<Vec<SpanData>> = Mutex::new(Vec::new());
+
+struct TestExporter;
+
+impl SpanExporter for TestExporter {
+    fn export(&self, span: SpanData) {
+        SPANS.lock().unwrap().push(span);
+    }
+}
+
+fn get_spans(trace_id: &str) -> Vec<SpanData> {
+    let _ = csml_tracing::set_exporter(Box::new(TestExporter));
+
+    SPANS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|span| span.context.trace_id == trace_id)
+        .cloned()
+        .collect()
+}
+
+fn remote_parent(trace_id: &str) -> Option<SpanContext> {
+    SpanContext::from_traceparent(&format!("00-{}-00f067aa0ba902b7-01", trace_id))
+}
+
+#[test]
+fn ok_traceparent() {
+    let context =
+        SpanContext::from_traceparent("00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01")
+            .unwrap();
+
+    assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+    assert_eq!(context.span_id, "00f067aa0ba902b7");
+    assert!(context.sampled);
+    assert_eq!(
+        context.to_traceparent(),
+        "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+    );
+}
+
+#[test]
+fn ko_traceparent() {
+    assert!(SpanContext::from_traceparent("").is_none());
+    assert!(SpanContext::from_traceparent("00-4bf92f3577b34da6-00f067aa0ba902b7-01").is_none());
+    assert!(SpanContext::from_traceparent(
+        "00-00000000000000000000000000000000-00f067aa0ba902b7-01"
+    )
+    .is_none());
+    assert!(SpanContext::from_traceparent(
+        "ff-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01"
+    )
+    .is_none());
+}
+
+#[test]
+fn ok_nested_spans() {
+    let trace_id = "11111111111111111111111111111111";
+    get_spans(trace_id);
+
+    {
+        let _parent = csml_tracing::enter(remote_parent(trace_id));
+        let parent = Span::start("parent", vec![]);
+
+        {
+            let _child = Span::start("child", vec![("key", "value".to_owned())]);
+            assert_eq!(
+                csml_tracing::current_context().unwrap().trace_id,
+                parent.context().trace_id
+            );
+        }
+
+        assert_eq!(
+            csml_tracing::current_context().as_ref(),
+            Some(parent.context())
+        );
+    }
+
+    assert!(csml_tracing::current_context().is_none());
+
+    let spans = get_spans(trace_id);
+    let parent = spans.iter().find(|span| span.name == "parent").unwrap();
+    let child = spans.iter().find(|span| span.name == "child").unwrap();
+
+    assert_eq!(parent.parent_span_id.as_deref(), Some("00f067aa0ba902b7"));
+    assert_eq!(
+        child.parent_span_id.as_deref(),
+        Some(parent.context.span_id.as_str())
+    );
+    assert_eq!(
+        child.attributes,
+        vec![("key".to_owned(), "value".to_owned())]
+    );
+}
+
+#[test]
+fn ok_step_spans() {
+    let trace_id = "22222222222222222222222222222222";
+    get_spans(trace_id);
+
+    {
+        let _parent = csml_tracing::enter(remote_parent(trace_id));
+
+        format_message(
+            Event::new("payload", "", serde_json::json!({})),
+            Context::new(
+                HashMap::new(),
+                HashMap::new(),
+                None,
+                None,
+                "tracing_unknown_step",
+                "flow",
+                None,
+            ),
+            "CSML/basic_test/numerical_operation/addition.csml",
+        );
+    }
+
+    let spans = get_spans(trace_id);
+    let step = spans
+        .iter()
+        .find(|span| span.name == "execute_step")
+        .unwrap();
+
+    assert!(step
+        .attributes
+        .contains(&("step".to_owned(), "tracing_unknown_step".to_owned())));
+    assert!(step.error.is_some());
+}
@@ -1,16 +1,49 @@
 use actix_cors::Cors;
 use actix_files as fs;
-use actix_web::{http::header, middleware, web, App, HttpServer};
+use actix_web::{dev::Service, http::header, middleware, web, App, HttpServer};
 use csml_engine::make_migrations;
 use csml_interpreter::csml_logs::init_logger;
 
 mod routes;
 
 const MAX_BODY_SIZE: usize = 8_388_608; // 8MB
+const DEFAULT_TIMEOUTS_INTERVAL: u64 = 60; // seconds
+
+/**
+ * Periodically send the timeout events of expired holds, dispatch the due schedules
+ * and deliver the pending messages of the outbox.
+ * The interval is set with ENGINE_HOLD_TIMEOUTS_INTERVAL (in seconds), 0 disables the worker
+ * when the POST /data/timeouts, /schedules/run and /outbox/run routes are called by an external scheduler.
+ */
+fn start_scheduler_worker() {
+    let interval = match std::env::var("ENGINE_HOLD_TIMEOUTS_INTERVAL") {
+        Ok(val) => val.parse::<u64>().unwrap_or(DEFAULT_TIMEOUTS_INTERVAL),
+        Err(_) => DEFAULT_TIMEOUTS_INTERVAL,
+    };
+
+    if interval == 0 {
+        return;
+    }
+
+    std::thread::spawn(move || loop {
+        std::thread::sleep(std::time::Duration::from_secs(interval));
+
+        if let Err(err) = csml_engine::process_due_timeouts() {
+            eprintln!("EngineError: {:?}", err);
+        }
+        if let Err(err) = csml_engine::process_due_schedules() {
+            eprintln!("EngineError: {:?}", err);
+        }
+        if let Err(err) = csml_engine::process_outbox() {
+            eprintln!("EngineError: {:?}", err);
+        }
+    });
+}
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     init_logger();
+    csml_engine::init_tracing();
 
     let server_port: String = match std::env::var("ENGINE_SERVER_PORT") {
         Ok(val) => val,
@@ -24,6 +57,8 @@ async fn main() -> std::io::Result<()> {
         Err(err) => panic!("PgSQL Migration ERROR: {:?}", err),
     };
 
+    start_scheduler_worker();
+
     HttpServer::new(|| {
         App::new()
             .wrap(
@@ -38,13 +73,42 @@ async fn main() -> std::io::Result<()> {
                     .max_age(86_400), //24h
             )
             .wrap(middleware::Logger::default())
+            .wrap_fn(|req, srv| {
+                let start = std::time::Instant::now();
+                let route = req
+                    .match_pattern()
+                    .unwrap_or_else(|| "unmatched".to_owned());
+                let method = req.method().to_string();
+
+                let fut = srv.call(req);
+                async move {
+                    let res = fut.await;
+                    let status = match &res {
+                        Ok(res) => res.status(),
+                        Err(err) => err.as_response_error().status_code(),
+                    };
+
+                    routes::metrics::record_request(
+                        &route,
+                        &method,
+                        status.as_u16(),
+                        start.elapsed(),
+                    );
+                    res
+                }
+            })
             .app_data(web::JsonConfig::default().limit(MAX_BODY_SIZE))
             .service(fs::Files::new("/static", "./static").use_last_modified(true))
             .service(routes::index::home)
             .service(routes::validate::handler)
             .service(routes::status::get_status)
+            .service(routes::metrics::handler)
             .service(routes::run::handler)
+            .service(routes::run::stream_handler)
+            .service(routes::ws::handler)
             .service(routes::sns::handler)
+            .service(routes::channels::telegram::handler)
+            .service(routes::channels::slack::handler)
             .service(routes::bot_versions::add_bot_version)
             .service(routes::bot_versions::get_bot_version)
             .service(routes::bot_versions::get_bot_latest_version)
@@ -54,15 +118,29 @@ async fn main() -> std::io::Result<()> {
             .service(routes::conversations::get_open)
             .service(routes::conversations::close_user_conversations)
             .service(routes::conversations::get_client_conversations)
+            .service(routes::conversations::send_agent_message)
+            .service(routes::conversations::resume_conversation)
             .service(routes::memories::create_client_memory)
             .service(routes::memories::get_memories)
             .service(routes::memories::get_memory)
             .service(routes::memories::delete_memories)
             .service(routes::memories::delete_memory)
             .service(routes::messages::get_client_messages)
+            .service(routes::analytics::get_step_analytics)
+            .service(routes::analytics::get_funnel)
+            .service(routes::schedules::process_due_schedules)
+            .service(routes::schedules::create_schedule)
+            .service(routes::schedules::get_client_schedules)
+            .service(routes::schedules::cancel_schedule)
+            .service(routes::outbox::process_outbox)
+            .service(routes::outbox::get_dead_letters)
+            .service(routes::outbox::replay_dead_letters)
+            .service(routes::outbox::delete_dead_letter)
             .service(routes::state::get_client_current_state)
             .service(routes::data::delete_expired_data)
+            .service(routes::data::process_due_timeouts)
             .service(routes::data::delete_bot)
+            .service(routes::data::export_client_data)
             .service(routes::data::delete_client)
     })
     .bind(format!("0.0.0.0:{}", server_port))?
@@ -2,12 +2,18 @@ pub mod index;
 pub mod validate;
 pub mod run;
 pub mod sns;
+pub mod channels;
+pub mod ws;
 pub mod conversations;
 pub mod data;
 pub mod memories;
 pub mod messages;
+pub mod outbox;
+pub mod schedules;
 pub mod state;
 pub mod status;
+pub mod metrics;
+pub mod analytics;
 
 pub mod bot_versions;
 
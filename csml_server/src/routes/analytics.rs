@@ -0,0 +1,193 @@
+use crate::routes::tools::authorize;
+use actix_web::{get, web, HttpResponse};
+use csml_engine::{auth::Scope, data::EngineError};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnalyticsQuery {
+    bot_id: String,
+    channel_id: Option<String>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FunnelQuery {
+    bot_id: String,
+    channel_id: Option<String>,
+    from_date: Option<i64>,
+    to_date: Option<i64>,
+    // flow:step,flow:step
+    steps: String,
+}
+
+fn check_dates(from_date: Option<i64>, to_date: Option<i64>) -> Result<(), HttpResponse> {
+    match csml_engine::analytics::parse_date_range(from_date, to_date) {
+        Some(_) => Ok(()),
+        None => Err(HttpResponse::BadRequest().body(
+            "from_date and to_date must be unix timestamps in seconds, from_date before to_date",
+        )),
+    }
+}
+
+fn analytics_error(err: EngineError) -> HttpResponse {
+    match err {
+        // too many messages in the date range
+        EngineError::Format(err) => HttpResponse::BadRequest().body(err),
+        err => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Number of conversations that visited each step of a bot, ended there,
+ * the median time spent in the step and the transitions between the steps
+ *
+ * {"statusCode": 200, "body": {"conversations": 42, "steps": [...], "transitions": [...]}}
+ *
+ */
+#[get("/analytics/steps")]
+pub async fn get_step_analytics(
+    query: web::Query<AnalyticsQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&query.bot_id)).await {
+        return resp;
+    }
+
+    if let Err(resp) = check_dates(query.from_date, query.to_date) {
+        return resp;
+    }
+
+    let query = query.into_inner();
+
+    let res = web::block(move || {
+        csml_engine::get_step_analytics(
+            &query.bot_id,
+            query.channel_id.as_deref(),
+            query.from_date,
+            query.to_date,
+        )
+    })
+    .await;
+
+    match res {
+        Ok(Ok(analytics)) => HttpResponse::Ok().json(analytics),
+        Ok(Err(err)) => analytics_error(err),
+        Err(err) => {
+            eprintln!("BlockingError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Number of conversations that visited the given steps in order, and the drop-off between them
+ *
+ * {"statusCode": 200, "body": [{"flow_id": "Default", "step_id": "start", "conversations": 42, "drop_off_rate": 0.0}, ...]}
+ *
+ */
+#[get("/analytics/funnel")]
+pub async fn get_funnel(
+    query: web::Query<FunnelQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&query.bot_id)).await {
+        return resp;
+    }
+
+    if let Err(resp) = check_dates(query.from_date, query.to_date) {
+        return resp;
+    }
+
+    let funnel = match csml_engine::analytics::parse_funnel(&query.steps) {
+        Some(funnel) => funnel,
+        None => {
+            return HttpResponse::BadRequest()
+                .body("steps must be a list of flow:step separated by commas")
+        }
+    };
+
+    let query = query.into_inner();
+
+    let res = web::block(move || {
+        csml_engine::get_funnel(
+            &query.bot_id,
+            query.channel_id.as_deref(),
+            query.from_date,
+            query.to_date,
+            &funnel,
+        )
+    })
+    .await;
+
+    match res {
+        Ok(Ok(funnel)) => HttpResponse::Ok().json(funnel),
+        Ok(Err(err)) => analytics_error(err),
+        Err(err) => {
+            eprintln!("BlockingError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_step_analytics() {
+        let mut app = test::init_service(App::new().service(get_step_analytics)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/steps?bot_id=botid&channel_id=analytics-channel")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/steps?bot_id=botid&from_date=-1")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/steps?bot_id=botid&from_date=120&to_date=60")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[actix_rt::test]
+    async fn test_funnel() {
+        let mut app = test::init_service(App::new().service(get_funnel)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/funnel?bot_id=botid&steps=Default:start,Default:end")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/funnel?bot_id=botid&steps=start")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+
+        let resp = test::TestRequest::get()
+            .uri("/analytics/funnel?bot_id=botid&steps=Default:start&to_date=9223372036854775807")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+}
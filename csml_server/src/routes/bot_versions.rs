@@ -1,5 +1,6 @@
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
 use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::auth::Scope;
 use csml_engine::{
     create_bot_version, delete_all_bot_versions, delete_bot_version_id, fold_bot,
     get_bot_by_version_id, get_bot_versions, get_last_bot_version,
@@ -18,9 +19,8 @@ use std::thread;
 pub async fn make_bot_fold(body: web::Json<CsmlBot>, req: actix_web::HttpRequest) -> HttpResponse {
     let bot = body.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot.id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || fold_bot(bot)).join().unwrap();
@@ -47,9 +47,8 @@ pub async fn add_bot_version(
 ) -> HttpResponse {
     let bot = body.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot.id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || create_bot_version(bot))
@@ -98,9 +97,8 @@ pub async fn get_bot_latest_version(
 ) -> HttpResponse {
     let bot_id = path.bot_id.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || get_last_bot_version(&bot_id))
@@ -129,9 +127,8 @@ pub async fn delete_bot_versions(
 ) -> HttpResponse {
     let bot_id = path.bot_id.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || delete_all_bot_versions(&bot_id))
@@ -177,9 +174,8 @@ pub async fn get_bot_latest_versions(
         None => None,
     };
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || get_bot_versions(&bot_id, limit, pagination_key))
@@ -224,9 +220,8 @@ pub async fn get_bot_version(
     let bot_id = path.bot_id.to_owned();
     let version_id = path.version_id.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || get_bot_by_version_id(&version_id, &bot_id))
@@ -256,9 +251,8 @@ pub async fn delete_bot_version(
     let bot_id = path.bot_id.to_owned();
     let version_id = path.version_id.to_owned();
 
-    if let Some(value) = validate_api_key(&req) {
-        eprintln!("AuthError: {:?}", value);
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || delete_bot_version_id(&version_id, &bot_id))
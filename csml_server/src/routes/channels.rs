@@ -0,0 +1,117 @@
+/**
+ * Inbound webhooks of the messaging channels.
+ *
+ * Each adapter verifies the signature of the channel, converts its webhook payload
+ * into a CsmlRequest and replies with the messages rendered for this channel.
+ * Channel secrets are read from env vars, suffixed with the uppercase bot_id
+ * to configure several bots on the same server (e.g. `TELEGRAM_BOT_TOKEN_MYBOT`),
+ * the unsuffixed var being used as default.
+ */
+pub mod slack;
+pub mod telegram;
+
+use actix_web::web;
+use csml_engine::data::{BotOpt, CsmlRequest};
+use csml_engine::start_conversation;
+use csml_interpreter::data::Client;
+use openssl::{hash::MessageDigest, memcmp, pkey::PKey, sign::Signer};
+use serde_json::{json, Value};
+
+pub fn get_channel_config(name: &str, bot_id: &str) -> Option<String> {
+    let bot_var = format!(
+        "{}_{}",
+        name,
+        bot_id
+            .to_ascii_uppercase()
+            .replace(|c: char| !c.is_ascii_alphanumeric(), "_")
+    );
+
+    match std::env::var(bot_var) {
+        Ok(val) if !val.is_empty() => Some(val),
+        _ => match std::env::var(name) {
+            Ok(val) if !val.is_empty() => Some(val),
+            _ => None,
+        },
+    }
+}
+
+/**
+ * Compare two secrets in constant time
+ */
+pub fn secure_compare(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && memcmp::eq(a, b)
+}
+
+pub fn hmac_sha256_hex(secret: &str, data: &[u8]) -> Option<String> {
+    let key = PKey::hmac(secret.as_bytes()).ok()?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key).ok()?;
+    signer.update(data).ok()?;
+
+    Some(hex::encode(signer.sign_to_vec().ok()?))
+}
+
+pub fn init_request(
+    request_id: String,
+    client: Client,
+    payload: Value,
+    metadata: Value,
+    channel: &str,
+) -> CsmlRequest {
+    CsmlRequest {
+        request_id,
+        client,
+        callback_url: None,
+        payload,
+        metadata,
+        step_limit: None,
+        ttl_duration: None,
+        low_data_mode: None,
+        renderer: Some(channel.to_owned()),
+        traceparent: None,
+    }
+}
+
+/**
+ * Run the bot and return the messages rendered for the channel of the request.
+ * The bot is loaded from its latest version in db.
+ */
+pub async fn run_conversation(request: CsmlRequest) -> Vec<Value> {
+    let bot_opt = BotOpt::BotId {
+        bot_id: request.client.bot_id.to_owned(),
+        apps_endpoint: std::env::var("ENGINE_APPS_ENDPOINT").ok(),
+        multibot: None,
+    };
+
+    let res = web::block(move || start_conversation(request, bot_opt)).await;
+
+    let data = match res {
+        Ok(Ok(data)) => data,
+        Ok(Err(err)) => {
+            eprintln!("EngineError: {:?}", err);
+            return vec![];
+        }
+        Err(err) => {
+            eprintln!("BlockingError: {:?}", err);
+            return vec![];
+        }
+    };
+
+    match data.get("messages") {
+        Some(Value::Array(messages)) => messages
+            .iter()
+            .map(|message| message["rendered"].to_owned())
+            .filter(|rendered| !rendered.is_null())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/**
+ * Event content of a channel event
+ */
+pub fn event(content_type: &str, content: Value) -> Value {
+    json!({
+        "content_type": content_type,
+        "content": content,
+    })
+}
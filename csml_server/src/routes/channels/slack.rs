@@ -0,0 +1,318 @@
+use super::{
+    event, get_channel_config, hmac_sha256_hex, init_request, run_conversation, secure_compare,
+};
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use awc::Client as HttpClient;
+use csml_engine::data::CsmlRequest;
+use csml_interpreter::data::Client;
+use serde_json::{json, Value};
+
+const SLACK_POST_MESSAGE: &str = "https://slack.com/api/chat.postMessage";
+// requests older than 5 minutes are rejected to prevent replay attacks
+const MAX_REQUEST_AGE: i64 = 60 * 5;
+
+/**
+ * Slack signature: `v0=` + hex(HMAC-SHA256(signing_secret, "v0:{timestamp}:{body}"))
+ */
+pub fn verify_signature(secret: &str, timestamp: &str, body: &[u8], signature: &str) -> bool {
+    let mut base = format!("v0:{}:", timestamp).into_bytes();
+    base.extend_from_slice(body);
+
+    match hmac_sha256_hex(secret, &base) {
+        Some(expected) => {
+            secure_compare(format!("v0={}", expected).as_bytes(), signature.as_bytes())
+        }
+        None => false,
+    }
+}
+
+fn verify_request(req: &HttpRequest, body: &[u8], bot_id: &str) -> Result<(), String> {
+    let secret = match get_channel_config("SLACK_SIGNING_SECRET", bot_id) {
+        Some(secret) => secret,
+        None => return Err("SLACK_SIGNING_SECRET is not configured".to_owned()),
+    };
+
+    let header = |name: &str| {
+        req.headers()
+            .get(name)
+            .and_then(|val| val.to_str().ok())
+            .map(|val| val.to_owned())
+    };
+
+    let (timestamp, signature) = match (
+        header("X-Slack-Request-Timestamp"),
+        header("X-Slack-Signature"),
+    ) {
+        (Some(timestamp), Some(signature)) => (timestamp, signature),
+        _ => {
+            return Err(
+                "Missing X-Slack-Request-Timestamp or X-Slack-Signature in header".to_owned(),
+            )
+        }
+    };
+
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|duration| duration.as_secs() as i64)
+        .unwrap_or_default();
+
+    match timestamp.parse::<i64>() {
+        Ok(time) if (now - time).abs() <= MAX_REQUEST_AGE => {}
+        _ => {
+            return Err(format!(
+                "Invalid X-Slack-Request-Timestamp value [{}]",
+                timestamp
+            ))
+        }
+    }
+
+    match verify_signature(&secret, &timestamp, body, &signature) {
+        true => Ok(()),
+        false => Err("Invalid X-Slack-Signature value".to_owned()),
+    }
+}
+
+/**
+ * Interactive components (buttons) are sent form-encoded, in a `payload` field
+ */
+fn parse_body(body: &[u8]) -> Option<Value> {
+    if body.starts_with(b"payload=") {
+        let payload = url::form_urlencoded::parse(body)
+            .find(|(key, _)| key == "payload")
+            .map(|(_, value)| value.into_owned())?;
+
+        return serde_json::from_str(&payload).ok();
+    }
+
+    serde_json::from_slice(body).ok()
+}
+
+/**
+ * Convert a Slack event or interaction into a CsmlRequest,
+ * along with the Slack channel to reply to.
+ * Messages of bots (including this one) and unsupported events are ignored.
+ */
+pub fn normalize_event(bot_id: &str, body: &Value) -> Option<(CsmlRequest, String)> {
+    let (request_id, user, channel, payload) = match body["type"].as_str()? {
+        "event_callback" => {
+            let event_body = &body["event"];
+
+            match event_body["type"].as_str()? {
+                "message"
+                    if event_body.get("bot_id").is_none()
+                        && event_body.get("subtype").is_none() =>
+                {
+                    (
+                        body["event_id"].as_str()?,
+                        event_body["user"].as_str()?,
+                        event_body["channel"].as_str()?,
+                        event("text", json!({ "text": event_body["text"].as_str()? })),
+                    )
+                }
+                reaction @ "reaction_added" | reaction @ "reaction_removed" => (
+                    body["event_id"].as_str()?,
+                    event_body["user"].as_str()?,
+                    event_body["item"]["channel"].as_str()?,
+                    event(
+                        "reaction",
+                        json!({
+                            "emoji": event_body["reaction"].as_str()?,
+                            "message_id": event_body["item"]["ts"].as_str()?,
+                            "action": if reaction == "reaction_added" { "add" } else { "remove" },
+                        }),
+                    ),
+                ),
+                _ => return None,
+            }
+        }
+        "block_actions" => {
+            let action = &body["actions"][0];
+
+            (
+                action["action_ts"].as_str()?,
+                body["user"]["id"].as_str()?,
+                body["channel"]["id"].as_str()?,
+                event("payload", json!({ "payload": action["value"].as_str()? })),
+            )
+        }
+        _ => return None,
+    };
+
+    let client = Client::new(bot_id.to_owned(), "slack".to_owned(), user.to_owned());
+    let metadata = json!({
+        "slack": {
+            "team_id": body["team_id"].as_str().or_else(|| body["team"]["id"].as_str()),
+            "channel": channel,
+        }
+    });
+
+    let request = init_request(
+        format!("slack-{}", request_id),
+        client,
+        payload,
+        metadata,
+        "slack",
+    );
+
+    Some((request, channel.to_owned()))
+}
+
+async fn reply(token: String, channel: String, messages: Vec<Value>) {
+    let http = HttpClient::default();
+
+    for mut message in messages {
+        message["channel"] = json!(channel);
+
+        let res = http
+            .post(SLACK_POST_MESSAGE)
+            .bearer_auth(&token)
+            .send_json(&message)
+            .await;
+
+        match res {
+            Ok(res) if res.status().is_success() => {}
+            Ok(res) => eprintln!("Slack chat.postMessage error: {}", res.status()),
+            Err(err) => eprintln!("Slack chat.postMessage error: {:?}", err),
+        }
+    }
+}
+
+/**
+ * Slack Events API and interactivity request URL.
+ * Events are acknowledged right away and the messages of the bot
+ * are sent with chat.postMessage.
+ */
+#[post("/channels/slack/{bot_id}")]
+pub async fn handler(path: web::Path<String>, body: web::Bytes, req: HttpRequest) -> HttpResponse {
+    let bot_id = path.into_inner();
+
+    if let Err(err) = verify_request(&req, &body, &bot_id) {
+        eprintln!("AuthError: {:?}", err);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    // events are retried when not acknowledged in 3 seconds, the first try is still running
+    if req.headers().contains_key("X-Slack-Retry-Num") {
+        return HttpResponse::Ok().finish();
+    }
+
+    let body = match parse_body(&body) {
+        Some(body) => body,
+        None => return HttpResponse::BadRequest().body("Request body can not be properly parsed"),
+    };
+
+    if body["type"] == "url_verification" {
+        return HttpResponse::Ok().json(json!({ "challenge": body["challenge"] }));
+    }
+
+    let (request, channel) = match normalize_event(&bot_id, &body) {
+        Some(res) => res,
+        None => return HttpResponse::Ok().finish(),
+    };
+
+    let token = match get_channel_config("SLACK_BOT_TOKEN", &bot_id) {
+        Some(token) => token,
+        None => {
+            eprintln!("ChannelError: SLACK_BOT_TOKEN is not configured");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    actix_rt::spawn(async move {
+        let messages = run_conversation(request).await;
+
+        reply(token, channel, messages).await;
+    });
+
+    HttpResponse::Ok().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::App;
+
+    fn fixture(name: &str) -> String {
+        std::fs::read_to_string(format!("fixtures/channels/slack/{}", name)).unwrap()
+    }
+
+    fn json_fixture(name: &str) -> Value {
+        serde_json::from_str(&fixture(&format!("{}.json", name))).unwrap()
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        // example request of the Slack documentation
+        let body = fixture("slash_command.txt");
+        let secret = "8f742231b10e8888abcd99yyyzzz85a5";
+        let signature = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+
+        assert!(verify_signature(
+            secret,
+            "1531420618",
+            body.as_bytes(),
+            signature
+        ));
+        assert!(!verify_signature(
+            secret,
+            "1531420619",
+            body.as_bytes(),
+            signature
+        ));
+    }
+
+    #[test]
+    fn test_normalize_message() {
+        let (request, channel) = normalize_event("bot", &json_fixture("message")).unwrap();
+
+        assert_eq!(channel, "D0PNCRP9N");
+        assert_eq!(request.request_id, "slack-Ev0PV52K25");
+        assert_eq!(request.client.channel_id, "slack");
+        assert_eq!(request.client.user_id, "U2147483697");
+        assert_eq!(request.payload, event("text", json!({"text": "hello"})));
+        assert_eq!(request.metadata["slack"]["team_id"], "T061EG9RZ");
+    }
+
+    #[test]
+    fn test_normalize_bot_message() {
+        assert!(normalize_event("bot", &json_fixture("bot_message")).is_none());
+    }
+
+    #[test]
+    fn test_normalize_reaction() {
+        let (request, channel) = normalize_event("bot", &json_fixture("reaction_added")).unwrap();
+
+        assert_eq!(channel, "C0G9QF9GZ");
+        assert_eq!(
+            request.payload,
+            event(
+                "reaction",
+                json!({"emoji": "thumbsup", "message_id": "1360782400.498405", "action": "add"})
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_block_actions() {
+        let body = parse_body(fixture("block_actions.txt").as_bytes()).unwrap();
+        let (request, channel) = normalize_event("bot", &body).unwrap();
+
+        assert_eq!(channel, "D0PNCRP9N");
+        assert_eq!(request.client.user_id, "U2147483697");
+        assert_eq!(request.payload, event("payload", json!({"payload": "yes"})));
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_signature() {
+        let mut app = actix_web::test::init_service(App::new().service(handler)).await;
+
+        let resp = actix_web::test::TestRequest::post()
+            .uri("/channels/slack/bot")
+            .set_json(&json_fixture("url_verification"))
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -0,0 +1,283 @@
+use super::{event, get_channel_config, init_request, run_conversation, secure_compare};
+use actix_web::{post, web, HttpRequest, HttpResponse};
+use awc::Client as HttpClient;
+use csml_engine::data::CsmlRequest;
+use csml_interpreter::data::Client;
+use serde_json::{json, Value};
+
+const TELEGRAM_API: &str = "https://api.telegram.org";
+
+/**
+ * Telegram sends the secret_token given to setWebhook in this header
+ */
+fn verify_secret_token(req: &HttpRequest, bot_id: &str) -> Result<(), String> {
+    let secret = match get_channel_config("TELEGRAM_SECRET_TOKEN", bot_id) {
+        Some(secret) => secret,
+        None => return Err("TELEGRAM_SECRET_TOKEN is not configured".to_owned()),
+    };
+
+    match req.headers().get("X-Telegram-Bot-Api-Secret-Token") {
+        Some(val) if secure_compare(val.as_bytes(), secret.as_bytes()) => Ok(()),
+        Some(_) => Err("Invalid X-Telegram-Bot-Api-Secret-Token value".to_owned()),
+        None => Err("Missing X-Telegram-Bot-Api-Secret-Token in header".to_owned()),
+    }
+}
+
+/**
+ * Convert a Telegram update into a CsmlRequest, along with the chat_id to reply to
+ * and the id of the callback query to answer.
+ * Updates without any supported content are ignored.
+ */
+pub fn normalize_update(
+    bot_id: &str,
+    update: &Value,
+) -> Option<(CsmlRequest, String, Option<String>)> {
+    let (chat, payload, callback_query_id) = if let Some(message) = update.get("message") {
+        let payload = if let Some(text) = message["text"].as_str() {
+            event("text", json!({ "text": text }))
+        } else if let Some(location) = message.get("location") {
+            event(
+                "location",
+                json!({
+                    "latitude": location["latitude"],
+                    "longitude": location["longitude"],
+                }),
+            )
+        } else if let Some(contact) = message.get("contact") {
+            let mut content = json!({ "phone_number": contact["phone_number"] });
+            for key in ["first_name", "last_name"].iter() {
+                if let Some(value) = contact[*key].as_str() {
+                    content[*key] = json!(value);
+                }
+            }
+            if let Some(user_id) = contact["user_id"].as_i64() {
+                content["user_id"] = json!(user_id.to_string());
+            }
+
+            event("contact", content)
+        } else {
+            return None;
+        };
+
+        (&message["chat"], payload, None)
+    } else if let Some(callback_query) = update.get("callback_query") {
+        let payload = event(
+            "payload",
+            json!({ "payload": callback_query["data"].as_str()? }),
+        );
+        let callback_query_id = callback_query["id"].as_str().map(|id| id.to_owned());
+
+        (
+            &callback_query["message"]["chat"],
+            payload,
+            callback_query_id,
+        )
+    } else if let Some(reaction) = update.get("message_reaction") {
+        let (emoji, action) = match (
+            reaction["new_reaction"][0]["emoji"].as_str(),
+            reaction["old_reaction"][0]["emoji"].as_str(),
+        ) {
+            (Some(emoji), _) => (emoji, "add"),
+            (None, Some(emoji)) => (emoji, "remove"),
+            (None, None) => return None,
+        };
+
+        let payload = event(
+            "reaction",
+            json!({
+                "emoji": emoji,
+                "message_id": reaction["message_id"].to_string(),
+                "action": action,
+            }),
+        );
+
+        (&reaction["chat"], payload, None)
+    } else {
+        return None;
+    };
+
+    let chat_id = chat["id"].as_i64()?.to_string();
+    let client = Client::new(bot_id.to_owned(), "telegram".to_owned(), chat_id.to_owned());
+
+    let request = init_request(
+        format!("telegram-{}", update["update_id"]),
+        client,
+        payload,
+        json!({ "telegram": { "chat": chat } }),
+        "telegram",
+    );
+
+    Some((request, chat_id, callback_query_id))
+}
+
+async fn call_api(http: &HttpClient, token: &str, method: &str, params: &Value) {
+    let url = format!("{}/bot{}/{}", TELEGRAM_API, token, method);
+
+    match http.post(url).send_json(params).await {
+        Ok(res) if res.status().is_success() => {}
+        Ok(res) => eprintln!("Telegram {} error: {}", method, res.status()),
+        Err(err) => eprintln!("Telegram {} error: {:?}", method, err),
+    }
+}
+
+async fn reply(
+    token: String,
+    chat_id: String,
+    callback_query_id: Option<String>,
+    messages: Vec<Value>,
+) {
+    let http = HttpClient::default();
+
+    if let Some(callback_query_id) = callback_query_id {
+        let params = json!({ "callback_query_id": callback_query_id });
+        call_api(&http, &token, "answerCallbackQuery", &params).await;
+    }
+
+    for mut message in messages {
+        let method = match message["method"].as_str() {
+            Some(method) => method.to_owned(),
+            None => continue,
+        };
+
+        if let Some(params) = message.as_object_mut() {
+            params.remove("method");
+            params.insert("chat_id".to_owned(), json!(chat_id));
+        }
+
+        call_api(&http, &token, &method, &message).await;
+    }
+}
+
+/**
+ * Telegram webhook, to be set with setWebhook and a secret_token.
+ * The update is acknowledged right away and the messages of the bot
+ * are sent with the Telegram Bot API.
+ */
+#[post("/channels/telegram/{bot_id}")]
+pub async fn handler(path: web::Path<String>, body: web::Bytes, req: HttpRequest) -> HttpResponse {
+    let bot_id = path.into_inner();
+
+    if let Err(err) = verify_secret_token(&req, &bot_id) {
+        eprintln!("AuthError: {:?}", err);
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let update: Value = match serde_json::from_slice(&body) {
+        Ok(update) => update,
+        Err(_) => {
+            return HttpResponse::BadRequest().body("Request body can not be properly parsed")
+        }
+    };
+
+    let (request, chat_id, callback_query_id) = match normalize_update(&bot_id, &update) {
+        Some(res) => res,
+        // nothing to answer, Telegram must not send this update again
+        None => return HttpResponse::Ok().finish(),
+    };
+
+    let token = match get_channel_config("TELEGRAM_BOT_TOKEN", &bot_id) {
+        Some(token) => token,
+        None => {
+            eprintln!("ChannelError: TELEGRAM_BOT_TOKEN is not configured");
+            return HttpResponse::InternalServerError().finish();
+        }
+    };
+
+    actix_rt::spawn(async move {
+        let messages = run_conversation(request).await;
+
+        reply(token, chat_id, callback_query_id, messages).await;
+    });
+
+    HttpResponse::Ok().finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::App;
+
+    fn fixture(name: &str) -> Value {
+        let path = format!("fixtures/channels/telegram/{}.json", name);
+        let content = std::fs::read_to_string(path).unwrap();
+
+        serde_json::from_str(&content).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_text() {
+        let (request, chat_id, callback_query_id) =
+            normalize_update("bot", &fixture("message_text")).unwrap();
+
+        assert_eq!(chat_id, "111222333");
+        assert_eq!(callback_query_id, None);
+        assert_eq!(request.client.channel_id, "telegram");
+        assert_eq!(request.client.user_id, "111222333");
+        assert_eq!(request.payload, event("text", json!({"text": "hello"})));
+        assert_eq!(request.renderer, Some("telegram".to_owned()));
+    }
+
+    #[test]
+    fn test_normalize_location() {
+        let (request, _, _) = normalize_update("bot", &fixture("message_location")).unwrap();
+
+        assert_eq!(
+            request.payload,
+            event(
+                "location",
+                json!({"latitude": 48.858844, "longitude": 2.294351})
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_contact() {
+        let (request, _, _) = normalize_update("bot", &fixture("message_contact")).unwrap();
+
+        assert_eq!(request.payload["content_type"], "contact");
+        assert_eq!(request.payload["content"]["phone_number"], "+33612345678");
+        assert_eq!(request.payload["content"]["user_id"], "111222333");
+    }
+
+    #[test]
+    fn test_normalize_callback_query() {
+        let (request, chat_id, callback_query_id) =
+            normalize_update("bot", &fixture("callback_query")).unwrap();
+
+        assert_eq!(chat_id, "111222333");
+        assert_eq!(callback_query_id, Some("4382bfdwdsb323b2d9".to_owned()));
+        assert_eq!(request.payload, event("payload", json!({"payload": "yes"})));
+    }
+
+    #[test]
+    fn test_normalize_reaction() {
+        let (request, _, _) = normalize_update("bot", &fixture("message_reaction")).unwrap();
+
+        assert_eq!(
+            request.payload,
+            event(
+                "reaction",
+                json!({"emoji": "👍", "message_id": "42", "action": "add"})
+            )
+        );
+    }
+
+    #[test]
+    fn test_normalize_unsupported() {
+        assert!(normalize_update("bot", &fixture("message_sticker")).is_none());
+    }
+
+    #[actix_rt::test]
+    async fn test_missing_secret_token() {
+        let mut app = actix_web::test::init_service(App::new().service(handler)).await;
+
+        let resp = actix_web::test::TestRequest::post()
+            .uri("/channels/telegram/bot")
+            .set_json(&fixture("message_text"))
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -1,8 +1,10 @@
 use actix_web::{get, post, web, HttpResponse};
 use csml_engine::{user_close_all_conversations, get_open_conversation, Client};
+use csml_engine::data::{AgentMessageRequest, EngineError, ResumeRequest};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
+use csml_engine::auth::Scope;
 
 
 /**
@@ -12,9 +14,8 @@ use crate::routes::tools::validate_api_key;
 #[post("/conversations/open")]
 pub async fn get_open(body: web::Json<Client>, req: actix_web::HttpRequest) -> HttpResponse {
 
-  if let Some(value) = validate_api_key(&req) {
-    eprintln!("AuthError: {:?}", value);
-    return HttpResponse::Forbidden().finish()
+  if let Err(resp) = authorize(&req, Scope::ReadData, Some(&body.bot_id)).await {
+    return resp
   }
 
   let res = thread::spawn(move || {
@@ -38,9 +39,8 @@ pub async fn get_open(body: web::Json<Client>, req: actix_web::HttpRequest) -> H
 #[post("/conversations/close")]
 pub async fn close_user_conversations(body: web::Json<Client>, req: actix_web::HttpRequest) -> HttpResponse {
 
-  if let Some(value) = validate_api_key(&req) {
-    eprintln!("AuthError: {:?}", value);
-    return HttpResponse::Forbidden().finish()
+  if let Err(resp) = authorize(&req, Scope::Run, Some(&body.bot_id)).await {
+    return resp
   }
 
   let res = thread::spawn(move || {
@@ -56,6 +56,58 @@ pub async fn close_user_conversations(body: web::Json<Client>, req: actix_web::H
   }
 }
 
+/**
+ * Send a message of a human agent to a user whose conversation is handed over to an agent
+ */
+#[post("/conversations/handoff/messages")]
+pub async fn send_agent_message(body: web::Json<AgentMessageRequest>, req: actix_web::HttpRequest) -> HttpResponse {
+
+  if let Err(resp) = authorize(&req, Scope::Run, Some(&body.client.bot_id)).await {
+    return resp
+  }
+
+  let request = body.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::send_agent_message(request)
+  }).join().unwrap();
+
+  match res {
+    Ok(data) => HttpResponse::Ok().json(data),
+    Err(EngineError::Format(err)) => HttpResponse::BadRequest().body(err),
+    Err(err) => {
+      eprintln!("EngineError: {:?}", err);
+      HttpResponse::InternalServerError().finish()
+    }
+  }
+}
+
+/**
+ * Give a conversation handed over to a human agent back to the bot, at a given flow and step
+ */
+#[post("/conversations/handoff/resume")]
+pub async fn resume_conversation(body: web::Json<ResumeRequest>, req: actix_web::HttpRequest) -> HttpResponse {
+
+  if let Err(resp) = authorize(&req, Scope::Run, Some(&body.client.bot_id)).await {
+    return resp
+  }
+
+  let request = body.into_inner();
+
+  let res = thread::spawn(move || {
+    csml_engine::resume_conversation(request)
+  }).join().unwrap();
+
+  match res {
+    Ok(data) => HttpResponse::Ok().json(data),
+    Err(EngineError::Format(err)) => HttpResponse::BadRequest().body(err),
+    Err(err) => {
+      eprintln!("EngineError: {:?}", err);
+      HttpResponse::InternalServerError().finish()
+    }
+  }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetClientInfoQuery {
   user_id: String,
@@ -71,9 +123,8 @@ pub struct GetClientInfoQuery {
 #[get("/conversations")]
 pub async fn get_client_conversations(query: web::Query<GetClientInfoQuery>, req: actix_web::HttpRequest) -> HttpResponse {
 
-  if let Some(value) = validate_api_key(&req) {
-    eprintln!("AuthError: {:?}", value);
-    return HttpResponse::Forbidden().finish()
+  if let Err(resp) = authorize(&req, Scope::ReadData, Some(&query.bot_id)).await {
+    return resp
   }
 
   let client = Client {
@@ -150,6 +201,30 @@ mod tests {
         assert_eq!(resp.status(), StatusCode::OK);
     }
 
+    #[actix_rt::test]
+    async fn test_resume_without_handoff() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(resume_conversation)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "resume-conversation-channel", "botid");
+
+        let resp = test::TestRequest::post()
+                    .uri(&format!("/conversations/handoff/resume"))
+                    .set_json(&serde_json::json!({
+                      "client": {
+                        "user_id": user_id,
+                        "channel_id": channel_id,
+                        "bot_id": bot_id
+                      },
+                      "flow_id": "Default"
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
     #[actix_rt::test]
     async fn test_get_conversations() {
         let mut app = test::init_service(
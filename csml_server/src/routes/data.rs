@@ -1,8 +1,9 @@
-use actix_web::{delete, post, web, HttpResponse};
+use actix_web::{delete, get, post, web, HttpResponse};
 use csml_interpreter::data::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
+use csml_engine::auth::Scope;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientQuery {
@@ -30,8 +31,8 @@ pub async fn delete_client(query: web::Query<ClientQuery>, req: actix_web::HttpR
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish()
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&client.bot_id)).await {
+        return resp
     }
 
     let res = thread::spawn(move || {
@@ -47,6 +48,37 @@ pub async fn delete_client(query: web::Query<ClientQuery>, req: actix_web::HttpR
     }
 }
 
+/*
+* Export all data stored for a given Client: conversations, messages, memories, state, schedules and outbox
+*
+* {"statusCode": 200, "body": {"client": {...}, "conversations": [...], "messages": [...], "memories": [...], "state": [...], "schedules": [...], "outbox": [...], "dead_letters": [...]}}
+*
+*/
+#[get("/data/clients")]
+pub async fn export_client_data(query: web::Query<ClientQuery>, req: actix_web::HttpRequest) -> HttpResponse {
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+    };
+
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp
+    }
+
+    let res = thread::spawn(move || {
+        csml_engine::export_client_data(&client)
+    }).join().unwrap();
+
+    match res {
+        Ok(data) => HttpResponse::Ok().json(data),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
 /**
  * Delete all bot data
  *
@@ -56,8 +88,8 @@ pub async fn delete_client(query: web::Query<ClientQuery>, req: actix_web::HttpR
 #[delete("/data/bots/{bot_id}")]
 pub async fn delete_bot(path: web::Path<BotIdPath>, req: actix_web::HttpRequest) -> HttpResponse {
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish()
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&path.bot_id)).await {
+        return resp
     }
 
     let res = thread::spawn(move || {
@@ -81,7 +113,11 @@ pub async fn delete_bot(path: web::Path<BotIdPath>, req: actix_web::HttpRequest)
  *
  */
 #[post("/data/cleanup")]
-pub async fn delete_expired_data() -> HttpResponse {
+pub async fn delete_expired_data(req: actix_web::HttpRequest) -> HttpResponse {
+
+    if let Err(resp) = authorize(&req, Scope::DeleteData, None).await {
+        return resp
+    }
 
     let res = thread::spawn(move || {
         csml_engine::delete_expired_data()
@@ -95,3 +131,56 @@ pub async fn delete_expired_data() -> HttpResponse {
         }
    }
 }
+
+/**
+ * Send the timeout events of every hold whose deadline is reached
+ *
+ * {"statusCode": 200, "body": [...]}
+ *
+ */
+#[post("/data/timeouts")]
+pub async fn process_due_timeouts(req: actix_web::HttpRequest) -> HttpResponse {
+
+    if let Err(resp) = authorize(&req, Scope::Run, None).await {
+        return resp
+    }
+
+    let res = thread::spawn(move || {
+        csml_engine::process_due_timeouts()
+    }).join().unwrap();
+
+    match res {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+   }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+    use actix_web::http::{StatusCode};
+
+    #[actix_rt::test]
+    async fn test_export_client_data() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(export_client_data)
+        ).await;
+
+        let (user_id, channel_id, bot_id) = ("test", "export-channel", "botid");
+
+        let resp = test::TestRequest::get()
+                    .uri(&format!("/data/clients?user_id={}&channel_id={}&bot_id={}", user_id, channel_id, bot_id))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body: serde_json::Value = test::read_body_json(resp).await;
+        assert_eq!(body["client"]["channel_id"], "export-channel");
+        assert!(body["messages"].is_array());
+    }
+}
@@ -1,5 +1,6 @@
 use actix_web::{get, HttpResponse};
 
+// public route
 #[get("/")]
 pub async fn home() -> HttpResponse {
   HttpResponse::Ok()
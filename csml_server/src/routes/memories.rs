@@ -1,5 +1,6 @@
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
 use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::auth::Scope;
 use csml_interpreter::data::Client;
 use serde::{Deserialize, Serialize};
 use std::thread;
@@ -40,8 +41,8 @@ pub async fn create_client_memory(
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::Run, Some(&client.bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || {
@@ -79,8 +80,8 @@ pub async fn delete_memory(
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&client.bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || csml_engine::delete_client_memory(&client, &memory_key))
@@ -113,8 +114,8 @@ pub async fn delete_memories(
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&client.bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || csml_engine::delete_client_memories(&client))
@@ -148,8 +149,8 @@ pub async fn get_memory(
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || csml_engine::get_client_memory(&client, &memory_key))
@@ -180,8 +181,8 @@ pub async fn get_memories(
         bot_id: query.bot_id.clone(),
     };
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish();
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp;
     }
 
     let res = thread::spawn(move || csml_engine::get_client_memories(&client))
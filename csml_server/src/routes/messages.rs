@@ -2,7 +2,8 @@ use actix_web::{get, web, HttpResponse};
 use csml_interpreter::data::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
+use csml_engine::auth::Scope;
 
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -43,8 +44,8 @@ pub async fn get_client_messages(query: web::Query<GetClientInfoQuery>, req: act
     let from_date = query.limit.to_owned();
     let to_date = query.limit.to_owned();
 
-    if let Some(_value) = validate_api_key(&req) {
-        return HttpResponse::Forbidden().finish()
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp
     }
 
     let res = thread::spawn(move || {
@@ -0,0 +1,78 @@
+use crate::routes::tools::authorize;
+use actix_web::{get, HttpRequest, HttpResponse};
+use csml_engine::auth::Scope;
+use csml_engine::metrics;
+use std::time::Duration;
+
+/**
+ * Set ENGINE_SERVER_METRICS_PUBLIC=true to let Prometheus scrape the metrics
+ * without credentials, when the route is only reachable from a private network
+ */
+fn is_public() -> bool {
+    match std::env::var("ENGINE_SERVER_METRICS_PUBLIC") {
+        Ok(val) => val == "true",
+        Err(_) => false,
+    }
+}
+
+/**
+ * Metrics of the server, the engine and the interpreter in the Prometheus text format.
+ * The error counters are labelled with the bot ids, so the route requires the read-data scope.
+ */
+#[get("/metrics")]
+pub async fn handler(req: HttpRequest) -> HttpResponse {
+    if !is_public() {
+        if let Err(resp) = authorize(&req, Scope::ReadData, None).await {
+            return resp;
+        }
+    }
+
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(metrics::render())
+}
+
+/**
+ * Called by the middleware of the server after each request.
+ * The route is the matched pattern (ex: /bots/{bot_id}) to keep a bounded number of series.
+ */
+pub fn record_request(route: &str, method: &str, status: u16, duration: Duration) {
+    let status = status.to_string();
+
+    metrics::increment_counter(
+        "csml_server_requests_total",
+        &[("route", route), ("method", method), ("status", &status)],
+    );
+    metrics::observe_histogram(
+        "csml_server_request_duration_seconds",
+        &[("route", route), ("method", method)],
+        duration.as_secs_f64(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::{test, App};
+
+    #[actix_rt::test]
+    async fn test_metrics() {
+        record_request("/test/{id}", "GET", 200, Duration::from_millis(20));
+
+        let mut app = test::init_service(App::new().service(handler)).await;
+
+        let resp = test::TestRequest::get()
+            .uri("/metrics")
+            .send_request(&mut app)
+            .await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+
+        let body = String::from_utf8(test::read_body(resp).await.to_vec()).unwrap();
+        assert!(body.contains(
+            "csml_server_requests_total{method=\"GET\",route=\"/test/{id}\",status=\"200\"} 1"
+        ));
+        assert!(body.contains("# TYPE csml_server_request_duration_seconds histogram"));
+    }
+}
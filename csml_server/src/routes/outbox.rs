@@ -0,0 +1,150 @@
+use crate::routes::tools::authorize;
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::auth::Scope;
+use csml_interpreter::data::Client;
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientQuery {
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeadLetterIdPath {
+    id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplayRequest {
+    pub client: Client,
+    // replay only these dead letters, all of them if not set
+    pub ids: Option<Vec<String>>,
+}
+
+/**
+ * Deliver the pending messages of the outbox, for deployments
+ * triggering them from an external scheduler
+ *
+ * {"statusCode": 200, "body": {"delivered": 3, "dead": 0}}
+ *
+ */
+#[post("/outbox/run")]
+pub async fn process_outbox(req: actix_web::HttpRequest) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::Run, None).await {
+        return resp;
+    }
+
+    let res = thread::spawn(csml_engine::process_outbox).join().unwrap();
+
+    match res {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * List the messages of a client that could not be delivered to their callback_url
+ *
+ * {"statusCode": 200, "body": [...]}
+ *
+ */
+#[get("/outbox/dead-letters")]
+pub async fn get_dead_letters(
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+    };
+
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp;
+    }
+
+    let res = thread::spawn(move || csml_engine::get_dead_letters(&client))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(messages) => HttpResponse::Ok().json(messages),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Put the dead letters of a client back in the outbox
+ *
+ * {"statusCode": 200, "body": {"replayed": 2}}
+ *
+ */
+#[post("/outbox/dead-letters/replay")]
+pub async fn replay_dead_letters(
+    body: web::Json<ReplayRequest>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::Run, Some(&body.client.bot_id)).await {
+        return resp;
+    }
+
+    let ReplayRequest { client, ids } = body.into_inner();
+
+    let res = thread::spawn(move || csml_engine::replay_dead_letters(&client, ids))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(count) => HttpResponse::Ok().json(serde_json::json!({ "replayed": count })),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Remove a dead letter without delivering it
+ *
+ * {"statusCode": 204}
+ *
+ */
+#[delete("/outbox/dead-letters/{id}")]
+pub async fn delete_dead_letter(
+    path: web::Path<DeadLetterIdPath>,
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let id = path.id.to_owned();
+
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+    };
+
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&client.bot_id)).await {
+        return resp;
+    }
+
+    let res = thread::spawn(move || csml_engine::delete_dead_letter(&client, &id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
@@ -1,17 +1,19 @@
 use actix_web::{post, web, HttpResponse};
-use csml_engine::{start_conversation};
-use csml_engine::data::{RunRequest};
+use bytes::Bytes;
+use csml_engine::{start_conversation, start_conversation_stream};
+use csml_engine::data::{EngineError, RunRequest};
+use futures::channel::{mpsc::unbounded, oneshot};
+use futures::{stream, StreamExt};
 use serde_json::{Value, json};
-use std::thread;
-use crate::routes::tools::validate_api_key;
+use std::{sync::mpsc, thread};
+use crate::routes::tools::{authorize_run, check_request_rate_limits, get_api_key, get_traceparent, too_many_requests};
 
 #[post("/run")]
 pub async fn handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -> HttpResponse {
   let mut request = body.event.to_owned();
 
-  if let Some(value) = validate_api_key(&req) {
-    eprintln!("AuthError: {:?}", value);
-    return HttpResponse::Forbidden().finish()
+  if let Err(resp) = authorize_run(&req, &body).await {
+    return resp
   }
 
   let bot_opt = match body.get_bot_opt() {
@@ -22,18 +24,28 @@ pub async fn handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -
     }
   };
 
+  if let Some(retry_after) = check_request_rate_limits(get_api_key(&req), &request.client).await {
+    return too_many_requests(retry_after)
+  }
+
   // request metadata should be an empty object by default
   request.metadata = match request.metadata {
     Value::Null => json!({}),
     val => val,
   };
 
+  if let Some(traceparent) = get_traceparent(&req) {
+    request.traceparent = Some(traceparent);
+  }
+
   let res = thread::spawn(move || {
     start_conversation(request, bot_opt)
   }).join().unwrap();
 
   match res {
     Ok(data) => HttpResponse::Ok().json(data),
+    // ENGINE_LOCK_MODE=reject, or the lock timed out
+    Err(EngineError::Locked(err)) => HttpResponse::Conflict().body(err),
     Err(err) => {
       eprintln!("EngineError: {:?}", err);
       HttpResponse::InternalServerError().finish()
@@ -41,6 +53,94 @@ pub async fn handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -
   }
 }
 
+fn sse_event(event: &str, data: &Value) -> Result<Bytes, std::convert::Infallible> {
+  Ok(Bytes::from(format!("event: {}\ndata: {}\n\n", event, data)))
+}
+
+/**
+ * Same as /run, as a text/event-stream response: a `message` event is sent for
+ * each message as soon as it is emitted, then an `end` event with the /run response.
+ */
+#[post("/run/stream")]
+pub async fn stream_handler(body: web::Json<RunRequest>, req: actix_web::HttpRequest) -> HttpResponse {
+  let mut request = body.event.to_owned();
+
+  if let Err(resp) = authorize_run(&req, &body).await {
+    return resp
+  }
+
+  let bot_opt = match body.get_bot_opt() {
+    Ok(bot_opt) => bot_opt,
+    Err(err) => {
+      eprintln!("EngineError: {:?}", err);
+      return HttpResponse::BadRequest().finish()
+    }
+  };
+
+  if let Some(retry_after) = check_request_rate_limits(get_api_key(&req), &request.client).await {
+    return too_many_requests(retry_after)
+  }
+
+  // request metadata should be an empty object by default
+  request.metadata = match request.metadata {
+    Value::Null => json!({}),
+    val => val,
+  };
+
+  if let Some(traceparent) = get_traceparent(&req) {
+    request.traceparent = Some(traceparent);
+  }
+
+  let (events, mut response) = unbounded();
+  let (locked_sender, locked) = oneshot::channel();
+
+  thread::spawn(move || {
+    let (sender, receiver) = mpsc::channel();
+
+    let conversation = thread::spawn(move || {
+      start_conversation_stream(request, bot_opt, sender)
+    });
+
+    // the stream ends when the conversation is done and the sender dropped
+    for messages in receiver {
+      if let Some(messages) = messages["messages"].as_array() {
+        for message in messages {
+          let _ = events.unbounded_send(sse_event("message", message));
+        }
+      }
+    }
+
+    let end = match conversation.join() {
+      Ok(Ok(data)) => sse_event("end", &Value::Object(data)),
+      // the lock is taken before any message is emitted, the stream has no events yet
+      Ok(Err(EngineError::Locked(err))) => {
+        let _ = locked_sender.send(err);
+        return
+      }
+      Ok(Err(err)) => {
+        eprintln!("EngineError: {:?}", err);
+        sse_event("error", &json!({"error": "the request could not be run"}))
+      }
+      Err(_) => sse_event("error", &json!({"error": "the request could not be run"})),
+    };
+    let _ = events.unbounded_send(end);
+  });
+
+  // the response status is known once the first event is received
+  let first = match response.next().await {
+    Some(event) => event,
+    None => return match locked.await {
+      Ok(err) => HttpResponse::Conflict().body(err),
+      Err(_) => HttpResponse::InternalServerError().finish(),
+    },
+  };
+
+  HttpResponse::Ok()
+    .content_type("text/event-stream")
+    .insert_header(("Cache-Control", "no-cache"))
+    .streaming(stream::once(async move { first }).chain(response))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -90,4 +190,86 @@ mod tests {
 
         assert_eq!(resp.status(), StatusCode::OK);
     }
+
+    #[actix_rt::test]
+    async fn test_run_stream() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(stream_handler)
+        ).await;
+
+        let resp = test::TestRequest::post()
+                    .uri(&format!("/run/stream"))
+                    .set_json(&serde_json::json!({
+                        "bot": {
+                            "id": "test_run_stream",
+                            "name": "test_run_stream",
+                            "flows": [
+                              {
+                                "id": "Default",
+                                "name": "Default",
+                                "content": "start: say \"Hello\" say \"World\" goto end",
+                                "commands": [],
+                              }
+                            ],
+                            "default_flow": "Default",
+                        },
+                        "event": {
+                            "request_id": "request_id",
+                            "client": {
+                                "user_id": "user_id",
+                                "channel_id": "channel_id",
+                                "bot_id": "test_run_stream"
+                            },
+                            "payload": {
+                              "content_type": "text" ,
+                              "content": {
+                                "text": "toto"
+                              }
+                            },
+                            "metadata": Value::Null,
+                        },
+                    }))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers().get("Content-Type").unwrap(), "text/event-stream");
+
+        let body = test::read_body(resp).await;
+        let events: Vec<(&str, Value)> = std::str::from_utf8(&body).unwrap()
+            .split_terminator("\n\n")
+            .map(|event| {
+                let (name, data) = event.split_once("\ndata: ").unwrap();
+                (name.trim_start_matches("event: "), serde_json::from_str(data).unwrap())
+            })
+            .collect();
+
+        // a message event per message, then the end event with the /run response
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].0, "message");
+        assert_eq!(events[0].1["payload"]["content"]["text"], "Hello");
+        assert_eq!(events[1].0, "message");
+        assert_eq!(events[1].1["payload"]["content"]["text"], "World");
+
+        let (name, end) = &events[2];
+        assert_eq!(*name, "end");
+        assert_eq!(end["request_id"], "request_id");
+        assert_eq!(end["conversation_end"], true);
+        assert_eq!(end["messages"].as_array().unwrap().len(), 2);
+    }
+
+    #[actix_rt::test]
+    async fn test_run_stream_invalid_body() {
+        let mut app = test::init_service(
+            App::new()
+                    .service(stream_handler)
+        ).await;
+
+        let resp = test::TestRequest::post()
+                    .uri(&format!("/run/stream"))
+                    .set_json(&serde_json::json!({"event": {}}))
+                    .send_request(&mut app).await;
+
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
 }
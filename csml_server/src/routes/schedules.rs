@@ -0,0 +1,148 @@
+use crate::routes::tools::authorize;
+use actix_web::{delete, get, post, web, HttpResponse};
+use csml_engine::auth::Scope;
+use csml_engine::data::ScheduleRequest;
+use csml_interpreter::data::Client;
+use serde::{Deserialize, Serialize};
+use std::thread;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClientQuery {
+    pub bot_id: String,
+    pub channel_id: String,
+    pub user_id: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ScheduleIdPath {
+    id: String,
+}
+
+/**
+ * Schedule a flow_trigger for a client, once (run_at) or on a cron expression
+ *
+ * {"statusCode": 201, "body": {"id": "...", "next_run_at": "...", ...}}
+ *
+ */
+#[post("/schedules")]
+pub async fn create_schedule(
+    body: web::Json<ScheduleRequest>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::Run, Some(&body.client.bot_id)).await {
+        return resp;
+    }
+
+    let request = body.into_inner();
+
+    let res = thread::spawn(move || csml_engine::create_schedule(request))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(schedule) => HttpResponse::Created().json(schedule),
+        Err(csml_engine::data::EngineError::Format(err)) => HttpResponse::BadRequest().body(err),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * List the schedules of a client
+ *
+ * {"statusCode": 200, "body": [...]}
+ *
+ */
+#[get("/schedules")]
+pub async fn get_client_schedules(
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+    };
+
+    if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+        return resp;
+    }
+
+    let res = thread::spawn(move || csml_engine::get_client_schedules(&client))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(schedules) => HttpResponse::Ok().json(schedules),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Cancel a schedule
+ *
+ * {"statusCode": 204}
+ * {"statusCode": 404} if the client has no schedule with this id
+ *
+ */
+#[delete("/schedules/{id}")]
+pub async fn cancel_schedule(
+    path: web::Path<ScheduleIdPath>,
+    query: web::Query<ClientQuery>,
+    req: actix_web::HttpRequest,
+) -> HttpResponse {
+    let id = path.id.to_owned();
+
+    let client = Client {
+        user_id: query.user_id.clone(),
+        channel_id: query.channel_id.clone(),
+        bot_id: query.bot_id.clone(),
+    };
+
+    if let Err(resp) = authorize(&req, Scope::DeleteData, Some(&client.bot_id)).await {
+        return resp;
+    }
+
+    let res = thread::spawn(move || csml_engine::cancel_schedule(&client, &id))
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().finish(),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+/**
+ * Dispatch the due schedules, for deployments triggering them from an external scheduler
+ *
+ * {"statusCode": 200, "body": [...]}
+ *
+ */
+#[post("/schedules/run")]
+pub async fn process_due_schedules(req: actix_web::HttpRequest) -> HttpResponse {
+    if let Err(resp) = authorize(&req, Scope::Run, None).await {
+        return resp;
+    }
+
+    let res = thread::spawn(csml_engine::process_due_schedules)
+        .join()
+        .unwrap();
+
+    match res {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
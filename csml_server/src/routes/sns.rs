@@ -1,6 +1,6 @@
 use actix_web::{post, web, HttpRequest, HttpResponse};
 use awc::Client;
-use csml_engine::data::RunRequest;
+use csml_engine::data::{EngineError, RunRequest};
 use csml_engine::start_conversation;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -79,6 +79,8 @@ async fn handle_notification(body: &str) -> HttpResponse {
 
     match res {
         Ok(data) => HttpResponse::Ok().json(data),
+        // SNS retries the delivery of the message
+        Err(EngineError::Locked(err)) => HttpResponse::Conflict().body(err),
         Err(err) => {
             eprintln!("EngineError: {:?}", err);
             HttpResponse::InternalServerError().finish()
@@ -92,6 +94,8 @@ async fn handle_notification(body: &str) -> HttpResponse {
  * HTTP/HTTPS endpoint subscription requests.
  * No message will be sent to the endpoint until the subscription
  * has been properly confirmed.
+ * Public route: SNS can not send the X-Api-Key header, so this endpoint should
+ * only be reachable by the SNS topic.
  */
 #[post("/sns")]
 pub async fn handler(req: HttpRequest, body: web::Bytes) -> HttpResponse {
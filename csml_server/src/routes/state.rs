@@ -2,7 +2,8 @@ use actix_web::{get, web, HttpResponse};
 use csml_engine::{Client};
 use serde::{Deserialize, Serialize};
 use std::thread;
-use crate::routes::tools::validate_api_key;
+use crate::routes::tools::authorize;
+use csml_engine::auth::Scope;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ClientQuery {
@@ -20,9 +21,8 @@ pub async fn get_client_current_state(query: web::Query<ClientQuery>, req: actix
     user_id: query.user_id.to_owned()
   };
 
-  if let Some(value) = validate_api_key(&req) {
-    eprintln!("AuthError: {:?}", value);
-    return HttpResponse::Forbidden().finish()
+  if let Err(resp) = authorize(&req, Scope::ReadData, Some(&client.bot_id)).await {
+    return resp
   }
 
   let res = thread::spawn(move || {
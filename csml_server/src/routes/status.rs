@@ -1,4 +1,7 @@
 use actix_web::{get, HttpResponse};
+use csml_engine::rate_limit;
+use crate::routes::tools::{get_daily_quota, get_rate_limit_store};
+use serde_json::json;
 use std::thread;
 
 /*
@@ -6,12 +9,27 @@ use std::thread;
 *
 * {"statusCode": 200}
 *
+* When ENGINE_SERVER_DAILY_QUOTA is set, the interactions of each bot today are listed:
+* {"daily_quota": {"limit": 1000, "interactions": {"bot_id": 42}}}
+*
+* Public route, so that health checks do not need credentials
 */
 #[get("/status")]
 pub async fn get_status() -> HttpResponse {
 
     let res = thread::spawn(move || {
-        csml_engine::get_status()
+        let mut status = csml_engine::get_status()?;
+
+        if let Some(limit) = get_daily_quota() {
+            let interactions = rate_limit::get_daily_interactions(get_rate_limit_store())?;
+
+            status["daily_quota"] = json!({
+                "limit": limit,
+                "interactions": interactions,
+            });
+        }
+
+        Ok::<_, csml_engine::data::EngineError>(status)
     }).join().unwrap();
 
     match res {
@@ -21,4 +39,4 @@ pub async fn get_status() -> HttpResponse {
             HttpResponse::InternalServerError().finish()
         }
     }
-}
\ No newline at end of file
+}
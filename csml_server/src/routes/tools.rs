@@ -1,22 +1,253 @@
+use actix_web::{web, HttpResponse};
+use csml_engine::auth::{self, Principal, Scope};
+use csml_engine::data::{EngineError, RunRequest};
+use csml_engine::rate_limit::{self, RateLimit, RateLimitStore};
+use csml_interpreter::data::Client;
+use serde_json::json;
+use std::time::Duration;
 
-pub fn validate_api_key(req: &actix_web::HttpRequest) -> Option<String> {
-    let api_keys = match std::env::var("ENGINE_SERVER_API_KEYS") {
-      Ok(val) if !val.is_empty() => val,
-      _ => return None
+/**
+ * Authenticate the request with its X-Api-Key or Authorization header.
+ * The api keys stored in db are looked up on the blocking thread pool.
+ */
+pub async fn authenticate(req: &actix_web::HttpRequest) -> Result<Principal, HttpResponse> {
+    let api_key = get_api_key(req);
+    let authorization = match req.headers().get("Authorization") {
+        Some(val) => val.to_str().ok().map(|val| val.to_owned()),
+        None => None,
     };
 
-    let vec = api_keys.split(',').collect::<Vec<&str>>();
+    if !auth::is_db_enabled() {
+        return auth::authenticate(api_key.as_deref(), authorization.as_deref())
+            .map_err(auth_error_response);
+    }
+
+    let authenticated =
+        web::block(move || auth::authenticate(api_key.as_deref(), authorization.as_deref()));
+
+    match authenticated.await {
+        Ok(res) => res.map_err(auth_error_response),
+        Err(err) => {
+            eprintln!("AuthError: {:?}", err);
+            Err(HttpResponse::InternalServerError().finish())
+        }
+    }
+}
+
+/**
+ * Authenticate the request, then check that it has the scope of the route and
+ * access to `bot_id`. bot_id is None for the routes that are not bound to a bot.
+ */
+pub async fn authorize(
+    req: &actix_web::HttpRequest,
+    scope: Scope,
+    bot_id: Option<&str>,
+) -> Result<Principal, HttpResponse> {
+    let principal = authenticate(req).await?;
+
+    principal
+        .authorize(scope, bot_id)
+        .map_err(auth_error_response)?;
+
+    Ok(principal)
+}
+
+pub async fn authorize_run(
+    req: &actix_web::HttpRequest,
+    body: &RunRequest,
+) -> Result<Principal, HttpResponse> {
+    let principal = authenticate(req).await?;
+
+    principal.authorize_run(body).map_err(auth_error_response)?;
+
+    Ok(principal)
+}
 
+pub fn auth_error_response(err: EngineError) -> HttpResponse {
+    match err {
+        EngineError::Unauthorized(err) => {
+            eprintln!("AuthError: {}", err);
+            HttpResponse::Unauthorized().finish()
+        }
+        EngineError::Forbidden(err) => {
+            eprintln!("AuthError: {}", err);
+            HttpResponse::Forbidden().finish()
+        }
+        err => {
+            eprintln!("AuthError: {:?}", err);
+            HttpResponse::InternalServerError().finish()
+        }
+    }
+}
+
+pub fn get_api_key(req: &actix_web::HttpRequest) -> Option<String> {
     match req.headers().get("X-Api-Key") {
-      Some(val) => {
-        let val = val.to_str().unwrap_or("");
-        if val.is_empty() || !vec.contains(&val) {
-          return Some(format!("Invalid X-Api-Key value [{}]", val))
-        }
-        None
-      },
-      None => {
-        Some("Missing X-Api-Key in header".to_owned())
-      }
+        Some(val) => val.to_str().ok().map(|val| val.to_owned()),
+        None => None,
+    }
+}
+
+/**
+ * W3C traceparent header of the caller, the spans of the request continue its trace
+ */
+pub fn get_traceparent(req: &actix_web::HttpRequest) -> Option<String> {
+    match req.headers().get("traceparent") {
+        Some(val) => val.to_str().ok().map(|val| val.to_owned()),
+        None => None,
+    }
+}
+
+fn get_rate_limit(name: &str) -> Option<RateLimit> {
+    match std::env::var(name) {
+        Ok(val) => RateLimit::parse(&val),
+        Err(_) => None,
+    }
+}
+
+pub fn get_rate_limit_store() -> RateLimitStore {
+    match std::env::var("ENGINE_SERVER_RATE_LIMIT_STORE") {
+        Ok(val) => RateLimitStore::from_name(&val),
+        Err(_) => RateLimitStore::Memory,
+    }
+}
+
+pub fn get_daily_quota() -> Option<i64> {
+    match std::env::var("ENGINE_SERVER_DAILY_QUOTA") {
+        Ok(val) => val.parse::<i64>().ok(),
+        Err(_) => None,
+    }
+}
+
+/**
+ * Take a token from the buckets of the api key, the bot and the client of a request,
+ * then count the interaction in the daily quota of the bot.
+ * Returns the time to wait before retrying if one of the limits is reached, in which
+ * case the tokens already taken are put back.
+ * Limits are not applied if their store fails, so that the bots keep answering.
+ */
+pub fn check_rate_limits(api_key: Option<&str>, client: &Client) -> Option<Duration> {
+    let store = get_rate_limit_store();
+    let client_key = format!(
+        "bot_id:{}#channel_id:{}#user_id:{}",
+        client.bot_id, client.channel_id, client.user_id
+    );
+
+    let buckets = [
+        ("api_key", "ENGINE_SERVER_RATE_LIMIT_API_KEY", api_key),
+        ("bot", "ENGINE_SERVER_RATE_LIMIT_BOT", Some(client.bot_id.as_str())),
+        ("client", "ENGINE_SERVER_RATE_LIMIT_CLIENT", Some(client_key.as_str())),
+    ];
+
+    let mut taken = vec![];
+    let mut retry_after = None;
+    for (scope, env_name, key) in buckets.iter() {
+        let (limit, key) = match (get_rate_limit(env_name), key) {
+            (Some(limit), Some(key)) => (limit, key),
+            _ => continue,
+        };
+
+        match rate_limit::take_token(store, scope, key, &limit) {
+            Ok(Ok(())) => taken.push((scope, key, limit)),
+            Ok(Err(delay)) => {
+                retry_after = Some(delay);
+                break;
+            }
+            Err(err) => eprintln!("RateLimitError: {:?}", err),
+        }
+    }
+
+    if retry_after.is_none() {
+        if let Some(quota) = get_daily_quota() {
+            match rate_limit::take_daily_interaction(store, &client.bot_id, quota) {
+                Ok(true) => {}
+                Ok(false) => retry_after = Some(rate_limit::get_quota_reset_delay()),
+                Err(err) => eprintln!("RateLimitError: {:?}", err),
+            }
+        }
+    }
+
+    if retry_after.is_some() {
+        for (scope, key, limit) in taken {
+            if let Err(err) = rate_limit::return_token(store, scope, key, &limit) {
+                eprintln!("RateLimitError: {:?}", err);
+            }
+        }
+    }
+
+    retry_after
+}
+
+/**
+ * check_rate_limits for the async handlers: the db store is called on the blocking
+ * thread pool, so that the workers keep serving the other requests
+ */
+pub async fn check_request_rate_limits(
+    api_key: Option<String>,
+    client: &Client,
+) -> Option<Duration> {
+    if get_rate_limit_store() == RateLimitStore::Memory {
+        return check_rate_limits(api_key.as_deref(), client);
+    }
+
+    let client = client.to_owned();
+    match web::block(move || check_rate_limits(api_key.as_deref(), &client)).await {
+        Ok(retry_after) => retry_after,
+        Err(err) => {
+            eprintln!("RateLimitError: {:?}", err);
+            None
+        }
+    }
+}
+
+/**
+ * Retry-After is in seconds, rounded up so that the client does not retry too early
+ */
+pub fn get_retry_after_seconds(retry_after: Duration) -> u64 {
+    let seconds = retry_after.as_secs();
+
+    match retry_after.subsec_nanos() {
+        0 => seconds,
+        _ => seconds + 1,
+    }
+}
+
+pub fn too_many_requests(retry_after: Duration) -> HttpResponse {
+    let seconds = get_retry_after_seconds(retry_after);
+
+    HttpResponse::TooManyRequests()
+        .insert_header(("Retry-After", seconds.to_string()))
+        .json(json!({
+            "error": "too many requests",
+            "retry_after": seconds,
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_after_seconds() {
+        assert_eq!(get_retry_after_seconds(Duration::from_millis(0)), 0);
+        assert_eq!(get_retry_after_seconds(Duration::from_millis(200)), 1);
+        assert_eq!(get_retry_after_seconds(Duration::from_millis(2000)), 2);
+        assert_eq!(get_retry_after_seconds(Duration::from_millis(2001)), 3);
+    }
+
+    #[test]
+    fn test_auth_error_response() {
+        let resp = auth_error_response(EngineError::Unauthorized("missing key".to_owned()));
+        assert_eq!(resp.status(), actix_web::http::StatusCode::UNAUTHORIZED);
+
+        let resp = auth_error_response(EngineError::Forbidden("missing scope".to_owned()));
+        assert_eq!(resp.status(), actix_web::http::StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_too_many_requests() {
+        let resp = too_many_requests(Duration::from_millis(1500));
+
+        assert_eq!(resp.status(), actix_web::http::StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(resp.headers().get("Retry-After").unwrap(), "2");
     }
 }
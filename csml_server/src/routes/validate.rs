@@ -2,6 +2,8 @@ use actix_web::{post, web, HttpResponse};
 use csml_engine::{validate_bot, CsmlResult};
 use csml_interpreter::data::csml_bot::CsmlBot;
 use serde::{Deserialize, Serialize};
+use crate::routes::tools::authorize;
+use csml_engine::auth::Scope;
 
 #[derive(Debug, Serialize, Deserialize)]
 struct ValidateBotResponse {
@@ -29,7 +31,11 @@ struct ValidationError {
 }
 
 #[post("/validate")]
-pub async fn handler(body: web::Json<CsmlBot>) -> HttpResponse {
+pub async fn handler(body: web::Json<CsmlBot>, req: actix_web::HttpRequest) -> HttpResponse {
+  if let Err(resp) = authorize(&req, Scope::ManageVersions, Some(&body.id)).await {
+    return resp
+  }
+
   let response = match validate_bot(body.clone()) {
 
     CsmlResult {
@@ -0,0 +1,297 @@
+use crate::routes::tools::{authenticate, check_rate_limits, get_api_key, get_retry_after_seconds};
+use actix::{Actor, ActorContext, Addr, AsyncContext, Handler, Message, StreamHandler};
+use actix_web::{get, web, HttpRequest, HttpResponse};
+use actix_web_actors::ws;
+use csml_engine::auth::{Principal, Scope};
+use csml_engine::data::{EngineError, RunRequest};
+use csml_engine::start_conversation_stream;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+const DEFAULT_MAX_IN_FLIGHT: usize = 4;
+
+/**
+ * Frame sent to the client of the socket
+ */
+#[derive(Message)]
+#[rtype(result = "()")]
+struct Frame(Value);
+
+struct WsSession {
+    // the rate limits of the api key apply to each request of the socket
+    api_key: Option<String>,
+    // the bots of each request are checked against the credentials of the socket
+    principal: Principal,
+    // requests of the socket being run, each one holds a thread
+    in_flight: Arc<AtomicUsize>,
+}
+
+/**
+ * Slot of a request in the requests of the socket, freed when the request is done
+ */
+struct InFlight(Arc<AtomicUsize>);
+
+impl InFlight {
+    fn acquire(in_flight: &Arc<AtomicUsize>) -> Option<Self> {
+        let max = match std::env::var("ENGINE_SERVER_WS_MAX_IN_FLIGHT") {
+            Ok(val) => val.parse::<usize>().unwrap_or(DEFAULT_MAX_IN_FLIGHT),
+            Err(_) => DEFAULT_MAX_IN_FLIGHT,
+        };
+
+        in_flight
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |count| {
+                (count < max).then(|| count + 1)
+            })
+            .ok()
+            .map(|_| InFlight(in_flight.clone()))
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Actor for WsSession {
+    type Context = ws::WebsocketContext<Self>;
+}
+
+impl Handler<Frame> for WsSession {
+    type Result = ();
+
+    fn handle(&mut self, frame: Frame, ctx: &mut Self::Context) {
+        ctx.text(frame.0.to_string());
+    }
+}
+
+/**
+ * Typing and wait components are sent as hints, so that the client
+ * can display an indicator or delay the next messages.
+ */
+fn message_frame(message: &Value, request_id: &Value) -> Value {
+    let payload = &message["payload"];
+
+    match payload["content_type"].as_str() {
+        Some(hint) if hint == "typing" || hint == "wait" => json!({
+            "type": hint,
+            "request_id": request_id,
+            "duration": payload["content"]["duration"],
+        }),
+        _ => json!({
+            "type": "message",
+            "request_id": request_id,
+            "message": message,
+        }),
+    }
+}
+
+fn error_frame(request_id: &Value, error: &str) -> Value {
+    json!({
+        "type": "error",
+        "request_id": request_id,
+        "error": error,
+    })
+}
+
+/**
+ * Run the request in a thread: each message is sent as a frame as soon as the
+ * interpreter emits it, followed by a `conversation_end` frame with the result
+ * of the request, as returned by /run.
+ */
+fn run(
+    body: RunRequest,
+    api_key: Option<String>,
+    principal: &Principal,
+    in_flight: &Arc<AtomicUsize>,
+    addr: Addr<WsSession>,
+) {
+    let mut request = body.event.to_owned();
+    let request_id = json!(request.request_id);
+
+    // the client must wait for the end of its previous requests
+    let slot = match InFlight::acquire(in_flight) {
+        Some(slot) => slot,
+        None => {
+            let mut frame = error_frame(&request_id, "too many requests in flight");
+            frame["status"] = json!(429);
+            addr.do_send(Frame(frame));
+            return;
+        }
+    };
+
+    if let Err(err) = principal.authorize_run(&body) {
+        eprintln!("AuthError: {:?}", err);
+        addr.do_send(Frame(error_frame(&request_id, "forbidden")));
+        return;
+    }
+
+    let bot_opt = match body.get_bot_opt() {
+        Ok(bot_opt) => bot_opt,
+        Err(err) => {
+            eprintln!("EngineError: {:?}", err);
+            addr.do_send(Frame(error_frame(&request_id, "invalid bot option")));
+            return;
+        }
+    };
+
+    // request metadata should be an empty object by default
+    request.metadata = match request.metadata {
+        Value::Null => json!({}),
+        val => val,
+    };
+
+    thread::spawn(move || {
+        let _slot = slot;
+
+        if let Some(retry_after) = check_rate_limits(api_key.as_deref(), &request.client) {
+            let mut frame = error_frame(&request_id, "too many requests");
+            frame["retry_after"] = json!(get_retry_after_seconds(retry_after));
+            addr.do_send(Frame(frame));
+            return;
+        }
+
+        let (sender, receiver) = mpsc::channel();
+
+        let conversation =
+            thread::spawn(move || start_conversation_stream(request, bot_opt, sender));
+
+        // the stream ends when the conversation is done and the sender dropped
+        for messages in receiver {
+            if let Some(messages) = messages["messages"].as_array() {
+                for message in messages {
+                    addr.do_send(Frame(message_frame(message, &request_id)));
+                }
+            }
+        }
+
+        match conversation.join() {
+            Ok(Ok(mut data)) => {
+                data.insert("type".to_owned(), json!("conversation_end"));
+                addr.do_send(Frame(Value::Object(data)));
+            }
+            // ENGINE_LOCK_MODE=reject, or the lock timed out
+            Ok(Err(EngineError::Locked(err))) => {
+                let mut frame = error_frame(&request_id, &err);
+                frame["status"] = json!(409);
+                addr.do_send(Frame(frame));
+            }
+            Ok(Err(err)) => {
+                eprintln!("EngineError: {:?}", err);
+                addr.do_send(Frame(error_frame(
+                    &request_id,
+                    "the request could not be run",
+                )));
+            }
+            Err(_) => {
+                addr.do_send(Frame(error_frame(
+                    &request_id,
+                    "the request could not be run",
+                )));
+            }
+        }
+    });
+}
+
+impl StreamHandler<Result<ws::Message, ws::ProtocolError>> for WsSession {
+    fn handle(&mut self, msg: Result<ws::Message, ws::ProtocolError>, ctx: &mut Self::Context) {
+        match msg {
+            Ok(ws::Message::Ping(msg)) => ctx.pong(&msg),
+            Ok(ws::Message::Text(text)) => match serde_json::from_str::<RunRequest>(&text) {
+                Ok(body) => run(
+                    body,
+                    self.api_key.clone(),
+                    &self.principal,
+                    &self.in_flight,
+                    ctx.address(),
+                ),
+                Err(_) => ctx.text(error_frame(&Value::Null, "invalid CSML request").to_string()),
+            },
+            Ok(ws::Message::Close(reason)) => {
+                ctx.close(reason);
+                ctx.stop();
+            }
+            Err(_) => ctx.stop(),
+            _ => {}
+        }
+    }
+}
+
+/**
+ * Run CSML requests over a WebSocket: the client sends the same requests as
+ * for /run (one per text frame) and receives each message as it is sent.
+ */
+#[get("/ws")]
+pub async fn handler(req: HttpRequest, stream: web::Payload) -> HttpResponse {
+    let principal = match authenticate(&req).await {
+        Ok(principal) => principal,
+        Err(resp) => return resp,
+    };
+
+    // the bots are only known with the requests sent on the socket
+    if !principal.has_scope(Scope::Run) {
+        eprintln!(
+            "AuthError: {} is missing the scope [run]",
+            principal.subject
+        );
+        return HttpResponse::Forbidden().finish();
+    }
+
+    let session = WsSession {
+        api_key: get_api_key(&req),
+        principal,
+        in_flight: Arc::new(AtomicUsize::new(0)),
+    };
+
+    match ws::start(session, &req, stream) {
+        Ok(res) => res,
+        Err(err) => {
+            eprintln!("WsError: {:?}", err);
+            HttpResponse::BadRequest().finish()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_frame() {
+        let request_id = json!("request_id");
+
+        let message = json!({
+            "payload": {"content_type": "text", "content": {"text": "hello"}},
+            "interaction_order": 0,
+            "conversation_id": "conversation_id",
+            "direction": "SEND",
+        });
+        let frame = message_frame(&message, &request_id);
+        assert_eq!(frame["type"], "message");
+        assert_eq!(frame["message"], message);
+
+        let typing = json!({
+            "payload": {"content_type": "typing", "content": {"duration": 1000}},
+        });
+        let frame = message_frame(&typing, &request_id);
+        assert_eq!(frame["type"], "typing");
+        assert_eq!(frame["duration"], 1000);
+    }
+
+    #[test]
+    fn test_in_flight() {
+        let in_flight = Arc::new(AtomicUsize::new(0));
+
+        let slots: Vec<InFlight> = (0..DEFAULT_MAX_IN_FLIGHT)
+            .map(|_| InFlight::acquire(&in_flight).unwrap())
+            .collect();
+        assert!(InFlight::acquire(&in_flight).is_none());
+
+        // a slot is freed when its request is done
+        drop(slots);
+        assert_eq!(in_flight.load(Ordering::SeqCst), 0);
+        assert!(InFlight::acquire(&in_flight).is_some());
+    }
+}